@@ -1,18 +1,24 @@
 use coreum_wasm_sdk::core::{CoreumMsg, CoreumQueries};
 use cosmwasm_std::{
     attr, entry_point, from_json, to_json_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut,
-    Env, MessageInfo, Order, Reply, ReplyOn, StdError, StdResult, WasmMsg,
+    Env, MessageInfo, Order, Reply, ReplyOn, StdError, StdResult, Storage, SubMsgResult, Uint128,
+    WasmMsg,
 };
 use cw2::{ensure_from_older_version, set_contract_version};
 use cw20::Cw20ReceiveMsg;
+use cw_storage_plus::Bound;
+use semver::Version;
 
 use dex::{
-    asset::{addr_opt_validate, Asset, AssetInfo},
+    asset::{
+        addr_opt_validate, Asset, AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated,
+    },
     common::{claim_ownership, drop_ownership_proposal, propose_new_owner, validate_addresses},
     factory::{
-        ConfigResponse, DistributionFlow, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg,
-        PartialDefaultStakeConfig, PartialStakeConfig, PoolConfig, PoolType, PoolsResponse,
-        QueryMsg, ReceiveMsg, ROUTE,
+        ConfigResponse, CreatorStatsResponse, DistributionFlow, ExecuteMsg, FeeInfoResponse,
+        InstantiateMsg, MigrateMsg, PartialDefaultStakeConfig, PartialStakeConfig, PoolConfig,
+        PoolType, PoolValue, PoolsResponse, QueryMsg, ReceiveMsg, RouteInfo, RoutesResponse,
+        TotalValueLockedResponse, MAX_TRADING_STARTS_DELAY, ROUTE,
     },
     fee_config::FeeConfig,
     pool::{ExecuteMsg as PoolExecuteMsg, InstantiateMsg as PoolInstantiateMsg, PairInfo},
@@ -22,10 +28,15 @@ use dex_stake::msg::ExecuteMsg as StakeExecuteMsg;
 
 use crate::{
     error::ContractError,
-    querier::query_pair_info,
+    querier::{
+        query_distribution_data, query_pair_info, query_pool_factory, query_pool_reserves,
+        query_spot_price, query_staking_denom, query_trading_started,
+    },
     state::{
-        check_asset_infos, pair_key, read_pairs, Config, TmpPoolInfo, CONFIG, OWNERSHIP_PROPOSAL,
-        PAIRS, PAIRS_TO_MIGRATE, PAIR_CONFIGS, STAKING_ADDRESSES, TMP_PAIR_INFO,
+        check_asset_infos, pair_key, read_pairs, Config, TmpPoolInfo, COLLECTED_FEES, CONFIG,
+        CREATOR_POOL_COUNT, DEFAULT_LIMIT, DEPRECATED_PAIRS, MAX_LIMIT, OWNERSHIP_PROPOSAL, PAIRS,
+        PAIRS_TO_MIGRATE, PAIR_CONFIGS, POOL_CODE_IDS, POOL_COUNT, POOL_CREATORS, POOL_TYPES,
+        STAKING_ADDRESSES, STAKING_TO_POOL, TMP_PAIR_INFO,
     },
 };
 
@@ -39,13 +50,11 @@ pub type SubMsg = cosmwasm_std::SubMsg<CoreumMsg>;
 const CONTRACT_NAME: &str = "dex-factory";
 /// Contract version that is used for migration.
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// First contract version that tracks the collected pool creation fees.
+const COLLECTED_FEES_VERSION: Version = Version::new(1, 2, 0);
 /// A `reply` call code ID used in a sub-message.
 const INSTANTIATE_PAIR_REPLY_ID: u64 = 1;
 
-const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
-/// The maximum amount of seconds that the trading can be delayed when the contract is instantiated.
-const MAX_TRADING_STARTS_DELAY: u64 = 60 * SECONDS_PER_DAY;
-
 /// Creates a new contract with the specified parameters packed in the `msg` variable.
 ///
 /// * **msg**  is message which contains the parameters used for creating the contract.
@@ -74,11 +83,16 @@ pub fn instantiate(
     let config = Config {
         owner: deps.api.addr_validate(&msg.owner)?,
         fee_address: addr_opt_validate(deps.api, &msg.fee_address)?,
+        creation_fee_address: addr_opt_validate(deps.api, &msg.creation_fee_address)?,
         max_referral_commission: msg.max_referral_commission,
         default_stake_config: msg.default_stake_config,
         only_owner_can_create_pools: false,
         pool_creation_fee: msg.pool_creation_fee,
         trading_starts: msg.trading_starts,
+        require_trading_starts: msg.require_trading_starts,
+        guardian: None,
+        pool_creation_paused: false,
+        max_pools: None,
     };
 
     let config_set: HashSet<String> = msg
@@ -99,6 +113,7 @@ pub fn instantiate(
         PAIR_CONFIGS.save(deps.storage, pc.pool_type.to_string(), pc)?;
     }
     CONFIG.save(deps.storage, &config)?;
+    POOL_COUNT.save(deps.storage, &0)?;
 
     Ok(Response::new())
 }
@@ -107,10 +122,16 @@ pub fn instantiate(
 pub struct UpdateConfig {
     /// Contract address to send governance fees to (the Protocol)
     fee_address: Option<String>,
+    /// Address to send the collected pool creation fees to
+    creation_fee_address: Option<String>,
     /// Whether only the owner or anyone can create new pairs
     only_owner_can_create_pools: Option<bool>,
     /// The default configuration for the staking contracts of new pairs
     default_stake_config: Option<PartialDefaultStakeConfig>,
+    /// Whether new pairs must have a trading start in the future
+    require_trading_starts: Option<bool>,
+    /// The maximum referral commission
+    max_referral_commission: Option<Decimal>,
 }
 
 /// Exposes all the execute functions available in the contract.
@@ -119,6 +140,7 @@ pub struct UpdateConfig {
 /// ## Variants
 /// * **ExecuteMsg::UpdateConfig {
 ///             fee_address,
+///             creation_fee_address,
 ///         }** Updates general contract parameters.
 ///
 /// * **ExecuteMsg::UpdatePoolConfig { config }** Updates a pair type
@@ -133,6 +155,18 @@ pub struct UpdateConfig {
 /// * **ExecuteMsg::Deregister { asset_infos }** Removes an existing pair from the factory.
 /// * The asset information is for the assets that are traded in the pair.
 ///
+/// * **ExecuteMsg::ReRegisterPool { pool_addr }** Adds a previously deregistered pair back to the factory.
+///
+/// * **ExecuteMsg::SetPoolDeprecated { asset_infos, deprecated }** Hides or shows an existing pair in the pairs listing.
+///
+/// * **ExecuteMsg::SetGuardian { guardian }** Sets or removes the guardian.
+///
+/// * **ExecuteMsg::SetPoolCreationPaused { paused }** Stops or resumes the creation of new pairs.
+///
+/// * **ExecuteMsg::SetMaxPools { max_pools }** Sets or removes the maximum number of pairs.
+///
+/// * **ExecuteMsg::SetPoolTypeDisabled { pool_type, disabled }** Disables or enables a pair type.
+///
 /// * **ExecuteMsg::ProposeNewOwner { owner, expires_in }** Creates a request to change contract ownership.
 ///
 /// * **ExecuteMsg::DropOwnershipProposal {}** Removes a request to change contract ownership.
@@ -140,6 +174,15 @@ pub struct UpdateConfig {
 /// * **ExecuteMsg::ClaimOwnership {}** Claims contract ownership.
 ///
 /// * **ExecuteMsg::MarkAsMigrated {}** Mark pairs as migrated.
+///
+/// * **ExecuteMsg::FreezeAllPools { frozen, start_after, limit }** Freezes or unfreezes a page of registered pools.
+///
+/// * **ExecuteMsg::UpdateTradingStarts { new_start, pools, start_after, limit }** Moves the trading start of the factory
+/// and of the given or a page of registered pools that have not started trading yet.
+///
+/// * **ExecuteMsg::WithdrawPoolCreationFees {}** Sends the collected pool creation fees out of the factory.
+///
+/// * **ExecuteMsg::SweepStuck { denom, recipient }** Sends stuck tokens of a native denom out of the factory.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<CoreumQueries>,
@@ -150,15 +193,21 @@ pub fn execute(
     match msg {
         ExecuteMsg::UpdateConfig {
             fee_address,
+            creation_fee_address,
             only_owner_can_create_pools,
             default_stake_config,
+            require_trading_starts,
+            max_referral_commission,
         } => execute_update_config(
             deps,
             info,
             UpdateConfig {
                 fee_address,
+                creation_fee_address,
                 only_owner_can_create_pools,
                 default_stake_config,
+                require_trading_starts,
+                max_referral_commission,
             },
         ),
         ExecuteMsg::UpdatePoolFees {
@@ -172,20 +221,44 @@ pub fn execute(
             init_params,
             total_fee_bps,
             staking_config,
-        } => execute_create_pair(
-            deps,
-            info,
-            env,
-            pool_type,
-            asset_infos,
-            init_params,
-            total_fee_bps,
-            staking_config,
-            Vec::new(),
-        ),
+            existing_staking_addr,
+            trading_starts,
+        } => {
+            let deposit = sent_pool_creation_fee(deps.as_ref(), &info)?;
+            execute_create_pair(
+                deps,
+                env,
+                info.sender,
+                deposit,
+                pool_type,
+                asset_infos,
+                init_params,
+                total_fee_bps,
+                staking_config,
+                existing_staking_addr,
+                Vec::new(),
+                trading_starts,
+            )
+        }
         ExecuteMsg::Deregister { asset_infos } => {
             deregister_pool_and_staking(deps, info, asset_infos)
         }
+        ExecuteMsg::ReRegisterPool { pool_addr } => {
+            execute_reregister_pool(deps, env, info, pool_addr)
+        }
+        ExecuteMsg::SetPoolDeprecated {
+            asset_infos,
+            deprecated,
+        } => execute_set_pool_deprecated(deps, info, asset_infos, deprecated),
+        ExecuteMsg::SetGuardian { guardian } => execute_set_guardian(deps, info, guardian),
+        ExecuteMsg::SetPoolCreationPaused { paused } => {
+            execute_set_pool_creation_paused(deps, info, paused)
+        }
+        ExecuteMsg::SetMaxPools { max_pools } => execute_set_max_pools(deps, info, max_pools),
+        ExecuteMsg::SetPoolTypeDisabled {
+            pool_type,
+            disabled,
+        } => execute_set_pool_type_disabled(deps, info, pool_type, disabled),
         ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
             let config = CONFIG.load(deps.storage)?;
 
@@ -232,23 +305,74 @@ pub fn execute(
             total_fee_bps,
             staking_config,
             distribution_flows,
-        } => execute_create_pair(
-            deps,
-            info,
-            env,
-            pool_type,
-            asset_infos,
-            init_params,
-            total_fee_bps,
-            staking_config,
-            distribution_flows,
-        ),
+            trading_starts,
+        } => {
+            let deposit = sent_pool_creation_fee(deps.as_ref(), &info)?;
+            execute_create_pair(
+                deps,
+                env,
+                info.sender,
+                deposit,
+                pool_type,
+                asset_infos,
+                init_params,
+                total_fee_bps,
+                staking_config,
+                None,
+                distribution_flows,
+                trading_starts,
+            )
+        }
         ExecuteMsg::CreateDistributionFlow {
             asset_infos,
             asset,
             rewards,
         } => execute_create_distribution_flow(deps, env, info, asset_infos, asset, rewards),
-        ExecuteMsg::WithdrawPoolCreationFees {} => execute_withdraw_pool_creation_fees(deps, env),
+        ExecuteMsg::UpdateStakingManager {
+            asset_infos,
+            new_manager,
+        } => execute_update_staking_manager(deps, info, asset_infos, new_manager),
+        ExecuteMsg::UpdatePoolStakingAddr {
+            asset_infos,
+            new_staking_addr,
+        } => execute_update_pool_staking_addr(deps, info, asset_infos, new_staking_addr),
+        ExecuteMsg::MigratePoolsOfType {
+            pool_type,
+            new_code_id,
+            migrate_msg,
+            start_after,
+            limit,
+        } => execute_migrate_pools_of_type(
+            deps,
+            env,
+            info,
+            pool_type,
+            new_code_id,
+            migrate_msg,
+            start_after,
+            limit,
+        ),
+        ExecuteMsg::UpdateFeesForType {
+            pool_type,
+            fee_config,
+            start_after,
+            limit,
+        } => execute_update_fees_for_type(deps, info, pool_type, fee_config, start_after, limit),
+        ExecuteMsg::FreezeAllPools {
+            frozen,
+            start_after,
+            limit,
+        } => execute_freeze_all_pools(deps, info, frozen, start_after, limit),
+        ExecuteMsg::UpdateTradingStarts {
+            new_start,
+            pools,
+            start_after,
+            limit,
+        } => execute_update_trading_starts(deps, env, info, new_start, pools, start_after, limit),
+        ExecuteMsg::WithdrawPoolCreationFees {} => execute_withdraw_pool_creation_fees(deps, info),
+        ExecuteMsg::SweepStuck { denom, recipient } => {
+            execute_sweep_stuck(deps, env, info, denom, recipient)
+        }
         ExecuteMsg::Receive(msg) => receive_cw20_message(deps, env, info, msg),
     }
 }
@@ -271,6 +395,9 @@ fn receive_cw20_message(
             required_deposit.info.to_string(),
         ));
     }
+    // the pool is created on behalf of the sender of the cw20 tokens
+    let sender = deps.api.addr_validate(&msg.sender)?;
+    let deposit = deposit.validate(deps.api)?;
 
     match from_json(&msg.msg)? {
         ReceiveMsg::CreatePool {
@@ -279,16 +406,21 @@ fn receive_cw20_message(
             init_params,
             total_fee_bps,
             staking_config,
+            existing_staking_addr,
+            trading_starts,
         } => execute_create_pair(
             deps,
-            info,
             env,
+            sender,
+            Some(deposit),
             pool_type,
             asset_infos,
             init_params,
             total_fee_bps,
             staking_config,
+            existing_staking_addr,
             Vec::new(),
+            trading_starts,
         ),
         ReceiveMsg::CreatePoolAndDistributionFlows {
             pool_type,
@@ -297,16 +429,20 @@ fn receive_cw20_message(
             total_fee_bps,
             staking_config,
             distribution_flows,
+            trading_starts,
         } => execute_create_pair(
             deps,
-            info,
             env,
+            sender,
+            Some(deposit),
             pool_type,
             asset_infos,
             init_params,
             total_fee_bps,
             staking_config,
+            None,
             distribution_flows,
+            trading_starts,
         ),
     }
 }
@@ -372,12 +508,334 @@ fn execute_create_distribution_flow(
                 manager: env.contract.address.to_string(), // use factory as manager for now
                 asset,
                 rewards,
+                rewards_locked_until_unbond: false,
             })?,
             funds: vec![],
         }))),
     )
 }
 
+/// Forwards the change of the distribution flow manager to the correct LP token staking contract.
+///
+/// * **asset_infos** is the pair of assets whose LP token staking contract should be updated.
+///
+/// * **new_manager** is the address that will manage all distribution flows of the staking contract.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_update_staking_manager(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    new_manager: String,
+) -> Result<Response, ContractError> {
+    // check permission
+    if info.sender != CONFIG.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_manager = deps.api.addr_validate(&new_manager)?;
+    let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
+    let pair = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+    let staking = query_pair_info(&deps.querier, pair)?.staking_addr;
+
+    let messages = query_distribution_data(&deps.querier, &staking)?
+        .distributions
+        .into_iter()
+        .map(|(asset, _)| {
+            Ok(WasmMsg::Execute {
+                contract_addr: staking.to_string(),
+                msg: to_json_binary(&StakeExecuteMsg::UpdateDistributionManager {
+                    asset: asset.into(),
+                    manager: new_manager.to_string(),
+                })?,
+                funds: vec![],
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "update_staking_manager"),
+        attr("staking_contract", staking),
+        attr("new_manager", new_manager),
+    ]))
+}
+
+/// Replaces the staking contract of a pool and keeps track of the new one.
+///
+/// * **asset_infos** is the pair of assets whose staking contract should be replaced.
+///
+/// * **new_staking_addr** is the address of the new staking contract.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_update_pool_staking_addr(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    new_staking_addr: String,
+) -> Result<Response, ContractError> {
+    // check permission
+    if info.sender != CONFIG.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
+    let pair = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+    let new_staking_addr = validate_unused_staking_addr(deps.as_ref(), &new_staking_addr)?;
+
+    let pair_info = query_pair_info(&deps.querier, &pair)?;
+    if query_staking_denom(&deps.querier, &new_staking_addr)? != pair_info.liquidity_token {
+        return Err(ContractError::StakingDenomMismatch {});
+    }
+
+    STAKING_ADDRESSES.remove(deps.storage, &pair_info.staking_addr);
+    STAKING_TO_POOL.remove(deps.storage, &pair_info.staking_addr);
+    STAKING_ADDRESSES.save(deps.storage, &new_staking_addr, &())?;
+    STAKING_TO_POOL.save(deps.storage, &new_staking_addr, &pair)?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_json_binary(&PoolExecuteMsg::UpdateStakingAddr {
+                new_addr: new_staking_addr.to_string(),
+            })?,
+            funds: vec![],
+        })
+        .add_attributes(vec![
+            attr("action", "update_pool_staking_addr"),
+            attr("pair", pair),
+            attr("old_staking_addr", pair_info.staking_addr),
+            attr("new_staking_addr", new_staking_addr),
+        ]))
+}
+
+/// Validates that `addr` belongs to a contract which is not yet used as the staking contract of a pool.
+fn validate_unused_staking_addr(
+    deps: Deps<CoreumQueries>,
+    addr: &str,
+) -> Result<Addr, ContractError> {
+    let addr = deps.api.addr_validate(addr)?;
+    // make sure the address belongs to a contract
+    deps.querier.query_wasm_contract_info(&addr)?;
+    // a staking contract can only serve a single pool
+    if STAKING_ADDRESSES.has(deps.storage, &addr) || STAKING_TO_POOL.has(deps.storage, &addr) {
+        return Err(ContractError::StakingAddressInUse {});
+    }
+    Ok(addr)
+}
+
+/// Sets a new code id for the given pool type and migrates a page of its existing pools to it.
+///
+/// * **pool_type** is the type of the pools to migrate.
+///
+/// * **new_code_id** is the code id that new and existing pools of this type will use.
+///
+/// * **migrate_msg** is the message passed to the `migrate` entry point of each pool.
+///
+/// * **start_after** and **limit** select the page of pools to go through.
+///
+/// Pools whose contract admin is not the factory are skipped and counted in the `skipped_pools` attribute.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_migrate_pools_of_type(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    pool_type: PoolType,
+    new_code_id: u64,
+    migrate_msg: Binary,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    // check permission
+    if info.sender != CONFIG.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // new pools of this type are created with the new code id
+    PAIR_CONFIGS.update(deps.storage, pool_type.to_string(), |config| match config {
+        Some(mut config) => {
+            config.code_id = new_code_id;
+            Ok(config)
+        }
+        None => Err(ContractError::PoolConfigNotFound {}),
+    })?;
+
+    let mut messages = vec![];
+    let mut skipped_pools = 0u32;
+    for pair_addr in read_pairs(deps.as_ref(), start_after, limit, true)? {
+        if query_pair_info(&deps.querier, &pair_addr)?.pool_type == pool_type {
+            // pools created before the factory became their admin cannot be migrated by it
+            let admin = deps.querier.query_wasm_contract_info(&pair_addr)?.admin;
+            if admin.as_deref() != Some(env.contract.address.as_str()) {
+                skipped_pools += 1;
+                continue;
+            }
+            messages.push(WasmMsg::Migrate {
+                contract_addr: pair_addr.to_string(),
+                new_code_id,
+                msg: migrate_msg.clone(),
+            });
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "migrate_pools_of_type"),
+            attr("pool_type", pool_type.to_string()),
+            attr("new_code_id", new_code_id.to_string()),
+            attr("migrated_pools", messages.len().to_string()),
+            attr("skipped_pools", skipped_pools.to_string()),
+        ])
+        .add_messages(messages))
+}
+
+/// Sends the new fee config to a page of the existing pools of the given type.
+///
+/// * **pool_type** is the type of the pools to update.
+///
+/// * **fee_config** is the new fee config of the pools.
+///
+/// * **start_after** and **limit** select the page of pools to go through.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_update_fees_for_type(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    pool_type: PoolType,
+    fee_config: FeeConfig,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    // check permission
+    if info.sender != CONFIG.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !fee_config.valid_fee_bps() {
+        return Err(ContractError::PoolConfigInvalidFeeBps {});
+    }
+
+    let mut messages = vec![];
+    for pair_addr in read_pairs(deps.as_ref(), start_after, limit, true)? {
+        if query_pair_info(&deps.querier, &pair_addr)?.pool_type == pool_type {
+            messages.push(WasmMsg::Execute {
+                contract_addr: pair_addr.to_string(),
+                msg: to_json_binary(&PoolExecuteMsg::UpdateFees {
+                    fee_config: fee_config.clone(),
+                })?,
+                funds: vec![],
+            });
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_fees_for_type"),
+            attr("pool_type", pool_type.to_string()),
+            attr("updated_pools", messages.len().to_string()),
+        ])
+        .add_messages(messages))
+}
+
+/// Freezes or unfreezes the registered pairs, processed in pages.
+/// The owner can execute this, the guardian only to freeze.
+fn execute_freeze_all_pools(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    frozen: bool,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner_or_guardian(&config, &info.sender, frozen)?;
+
+    let messages = read_pairs(deps.as_ref(), start_after, limit, true)?
+        .into_iter()
+        .map(|pair_addr| -> StdResult<WasmMsg> {
+            Ok(WasmMsg::Execute {
+                contract_addr: pair_addr.to_string(),
+                msg: to_json_binary(&PoolExecuteMsg::Freeze { frozen })?,
+                funds: vec![],
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "freeze_all_pools"),
+            attr("frozen", frozen.to_string()),
+            attr("updated_pools", messages.len().to_string()),
+        ])
+        .add_messages(messages))
+}
+
+/// Sets the factory's trading start and sends it to the registered pools that have not started trading yet.
+///
+/// * **new_start** is the new block time until which trading is disabled.
+///
+/// * **pools** are the assets of the pools to update. If not given, a page of all registered pools is updated.
+///
+/// * **start_after** and **limit** select the page of pools to go through.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_update_trading_starts(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    new_start: u64,
+    pools: Option<Vec<Vec<AssetInfo>>>,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let block_time = env.block.time.seconds();
+    if new_start < block_time || new_start > block_time + MAX_TRADING_STARTS_DELAY {
+        return Err(ContractError::InvalidTradingStart {});
+    }
+
+    config.trading_starts = Some(new_start);
+    CONFIG.save(deps.storage, &config)?;
+
+    let pair_addrs = match pools {
+        Some(pools) => pools
+            .iter()
+            .map(|asset_infos| -> Result<_, ContractError> {
+                let asset_infos = check_asset_infos(deps.api, asset_infos)?;
+                Ok(PAIRS.load(deps.storage, &pair_key(&asset_infos))?)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => read_pairs(deps.as_ref(), start_after, limit, true)?,
+    };
+
+    let mut messages = vec![];
+    for pair_addr in pair_addrs {
+        if !query_trading_started(&deps.querier, &pair_addr)? {
+            messages.push(WasmMsg::Execute {
+                contract_addr: pair_addr.to_string(),
+                msg: to_json_binary(&PoolExecuteMsg::UpdateTradingStarts { new_start })?,
+                funds: vec![],
+            });
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_trading_starts"),
+            attr("trading_starts", new_start.to_string()),
+            attr("updated_pools", messages.len().to_string()),
+        ])
+        .add_messages(messages))
+}
+
 /// Updates general contract settings.
 ///
 /// * **param** is an object of type [`UpdateConfig`] that contains the parameters to update.
@@ -401,6 +859,10 @@ pub fn execute_update_config(
         config.fee_address = Some(deps.api.addr_validate(&fee_address)?);
     }
 
+    if let Some(creation_fee_address) = param.creation_fee_address {
+        config.creation_fee_address = Some(deps.api.addr_validate(&creation_fee_address)?);
+    }
+
     if let Some(only_owner) = param.only_owner_can_create_pools {
         config.only_owner_can_create_pools = only_owner;
     }
@@ -409,6 +871,19 @@ pub fn execute_update_config(
         config.default_stake_config.update(default_stake_config);
     }
 
+    if let Some(require_trading_starts) = param.require_trading_starts {
+        config.require_trading_starts = require_trading_starts;
+    }
+
+    if let Some(max_referral_commission) = param.max_referral_commission {
+        if max_referral_commission > Decimal::one() {
+            return Err(ContractError::InvalidReferralCommission(
+                max_referral_commission,
+            ));
+        }
+        config.max_referral_commission = max_referral_commission;
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -448,6 +923,10 @@ pub fn execute_update_pair_config(
 
 /// Creates a new pair of `pool_type` with the assets specified in `asset_infos`.
 ///
+/// * **creator** is the address creating the pair.
+///
+/// * **deposit** is the pool creation fee paid by the creator, if any.
+///
 /// * **pool_type** is the pair type of the newly created pair.
 ///
 /// * **asset_infos** is a vector with assets for which we create a pair.
@@ -457,32 +936,70 @@ pub fn execute_update_pair_config(
 /// * **staking_config** is the configuration for the staking contract. Overrides the default staking config.
 ///
 /// * **distribution_flows** is a vector of distribution flows to be created for the pair's staking contract.
+///
+/// * **trading_starts** is the block time until which trading is disabled. Overrides the factory's trading start.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_create_pair(
     deps: DepsMut<CoreumQueries>,
-    info: MessageInfo,
     env: Env,
+    creator: Addr,
+    deposit: Option<AssetValidated>,
     pool_type: PoolType,
     asset_infos: Vec<AssetInfo>,
     init_params: Option<Binary>,
     total_fee_bps: Option<u16>,
     staking_config: PartialStakeConfig,
+    existing_staking_addr: Option<String>,
     distribution_flows: Vec<DistributionFlow>,
+    trading_starts: Option<u64>,
 ) -> Result<Response, ContractError> {
     let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
 
     let config = CONFIG.load(deps.storage)?;
 
-    if config.only_owner_can_create_pools && info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
+    if config.pool_creation_paused {
+        return Err(ContractError::PoolCreationPaused {});
+    }
+
+    let block_time = env.block.time.seconds();
+    if let Some(trading_starts) = trading_starts {
+        if trading_starts < block_time || trading_starts > block_time + MAX_TRADING_STARTS_DELAY {
+            return Err(ContractError::InvalidTradingStart {});
+        }
+    }
+    // a pool's own trading start takes precedence over the factory's
+    let trading_starts = trading_starts.or(config.trading_starts);
+    if config.require_trading_starts && !matches!(trading_starts, Some(start) if start > block_time)
+    {
+        return Err(ContractError::TradingStartsRequired {});
     }
 
-    if !config.only_owner_can_create_pools && !permissionless_fee_sent(&deps, &info) {
-        return Err(ContractError::PermissionlessRequiresDeposit {});
+    if config.only_owner_can_create_pools && creator != config.owner {
+        return Err(ContractError::Unauthorized {});
     }
 
+    let deposit = if !config.only_owner_can_create_pools {
+        let fee = deposit.ok_or(ContractError::PermissionlessRequiresDeposit {})?;
+        COLLECTED_FEES.update(deps.storage, &fee.info, |collected| -> StdResult<_> {
+            Ok(collected.unwrap_or_default() + fee.amount)
+        })?;
+        Some(fee)
+    } else {
+        None
+    };
+
     // pool is verified if it's created by the admin/owner of the contract
-    let verified = info.sender == config.owner;
+    let verified = creator == config.owner;
+
+    // only the owner can bind a pool to an already existing staking contract
+    let existing_staking_addr = existing_staking_addr
+        .map(|addr| -> Result<_, ContractError> {
+            if creator != config.owner {
+                return Err(ContractError::Unauthorized {});
+            }
+            validate_unused_staking_addr(deps.as_ref(), &addr)
+        })
+        .transpose()?;
 
     if PAIRS.has(deps.storage, &pair_key(&asset_infos)) {
         return Err(ContractError::PoolWasCreated {});
@@ -498,6 +1015,16 @@ pub fn execute_create_pair(
         return Err(ContractError::PoolConfigDisabled {});
     }
 
+    let pool_count = POOL_COUNT.load(deps.storage)?;
+    if matches!(config.max_pools, Some(max_pools) if pool_count >= max_pools) {
+        return Err(ContractError::PoolLimitReached {});
+    }
+    POOL_COUNT.save(deps.storage, &(pool_count + 1))?;
+
+    CREATOR_POOL_COUNT.update(deps.storage, &creator, |count| -> StdResult<_> {
+        Ok(count.unwrap_or_default() + 1)
+    })?;
+
     let pair_key = pair_key(&asset_infos);
     TMP_PAIR_INFO.save(
         deps.storage,
@@ -505,38 +1032,39 @@ pub fn execute_create_pair(
             pair_key,
             asset_infos: asset_infos.clone(),
             distribution_flows,
+            code_id: pair_config.code_id,
+            creator,
+            deposit,
+            existing_staking_addr: existing_staking_addr.clone(),
         },
     )?;
 
     let sub_msg: Vec<SubMsg> = vec![SubMsg {
         id: INSTANTIATE_PAIR_REPLY_ID,
         msg: WasmMsg::Instantiate {
-            admin: Some(config.owner.to_string()),
+            admin: Some(env.contract.address.to_string()),
             code_id: pair_config.code_id,
             msg: to_json_binary(&PoolInstantiateMsg {
-                asset_infos: asset_infos.iter().cloned().map(Into::into).collect(),
-                factory_addr: env.contract.address.to_string(),
-                init_params,
-                staking_config: config
-                    .default_stake_config
-                    .combine_with(staking_config)
-                    .to_stake_config(),
-                trading_starts: config
-                    .trading_starts
-                    .unwrap_or_else(|| env.block.time.seconds()),
-                fee_config: FeeConfig {
-                    total_fee_bps: total_fee_bps.unwrap_or(pair_config.fee_config.total_fee_bps),
-                    protocol_fee_bps: pair_config.fee_config.protocol_fee_bps,
-                },
                 verified,
-                circuit_breaker: None,
+                existing_staking_addr: existing_staking_addr.map(String::from),
+                trading_starts: trading_starts.unwrap_or(block_time),
+                ..pool_instantiate_msg(
+                    &env,
+                    &config,
+                    &pair_config,
+                    &asset_infos,
+                    init_params,
+                    total_fee_bps,
+                    staking_config,
+                )
             })?,
             funds: vec![],
             label: "Dex pair".to_string(),
         }
         .into(),
         gas_limit: None,
-        reply_on: ReplyOn::Success,
+        // also reply on failure, so the pool creation fee can be refunded
+        reply_on: ReplyOn::Always,
     }];
 
     Ok(Response::new()
@@ -547,6 +1075,46 @@ pub fn execute_create_pair(
         ]))
 }
 
+/// Assembles the [`PoolInstantiateMsg`] for a new pool with the given parameters.
+/// The pool is not marked as verified and does not use an existing staking contract.
+fn pool_instantiate_msg(
+    env: &Env,
+    config: &Config,
+    pair_config: &PoolConfig,
+    asset_infos: &[AssetInfoValidated],
+    init_params: Option<Binary>,
+    total_fee_bps: Option<u16>,
+    staking_config: PartialStakeConfig,
+) -> PoolInstantiateMsg {
+    PoolInstantiateMsg {
+        asset_infos: asset_infos.iter().cloned().map(Into::into).collect(),
+        factory_addr: env.contract.address.to_string(),
+        init_params,
+        staking_config: config
+            .default_stake_config
+            .clone()
+            .combine_with(
+                pair_config
+                    .stake_config_override
+                    .clone()
+                    .unwrap_or_default(),
+            )
+            .combine_with(staking_config)
+            .to_stake_config(),
+        trading_starts: config
+            .trading_starts
+            .unwrap_or_else(|| env.block.time.seconds()),
+        fee_config: FeeConfig {
+            total_fee_bps: total_fee_bps.unwrap_or(pair_config.fee_config.total_fee_bps),
+            protocol_fee_bps: pair_config.fee_config.protocol_fee_bps,
+        },
+        verified: false,
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+    }
+}
+
 /// Marks specified pairs as migrated to the new admin.
 ///
 /// * **pairs** is a vector of pairs which should be marked as transferred.
@@ -580,6 +1148,10 @@ pub fn reply(
     env: Env,
     msg: Reply,
 ) -> Result<Response, ContractError> {
+    if let SubMsgResult::Err(err) = &msg.result {
+        return reply::instantiate_pair_failed(deps, err);
+    }
+
     // parse the reply
     let res = cw_utils::parse_reply_instantiate_data(msg).map_err(|_| {
         StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
@@ -588,19 +1160,29 @@ pub fn reply(
     reply::instantiate_pair(deps, env, res)
 }
 
-fn permissionless_fee_sent(deps: &DepsMut<CoreumQueries>, info: &MessageInfo) -> bool {
-    let deposit_required = CONFIG.load(deps.storage).unwrap().pool_creation_fee;
+/// Returns the pool creation fee sent along with the message,
+/// or `None` if not enough was sent.
+fn sent_pool_creation_fee(
+    deps: Deps<CoreumQueries>,
+    info: &MessageInfo,
+) -> StdResult<Option<AssetValidated>> {
+    let deposit_required = CONFIG.load(deps.storage)?.pool_creation_fee;
 
-    info.funds.iter().any(|coin| {
-        coin.amount >= deposit_required.amount && coin.denom == deposit_required.info.to_string()
-    })
+    Ok(info
+        .funds
+        .iter()
+        .find(|coin| {
+            coin.amount >= deposit_required.amount
+                && coin.denom == deposit_required.info.to_string()
+        })
+        .map(|coin| AssetInfoValidated::SmartToken(coin.denom.clone()).with_balance(coin.amount)))
 }
 
 pub mod reply {
     use cosmwasm_std::wasm_execute;
     use cw_utils::MsgInstantiateContractResponse;
 
-    use crate::state::STAKING_ADDRESSES;
+    use crate::state::{POOL_CODE_IDS, POOL_CREATORS, STAKING_ADDRESSES, STAKING_TO_POOL};
 
     use super::*;
 
@@ -617,29 +1199,23 @@ pub mod reply {
         let pair_contract = deps.api.addr_validate(&res.contract_address)?;
 
         PAIRS.save(deps.storage, &tmp.pair_key, &pair_contract)?;
+        POOL_CODE_IDS.save(deps.storage, &pair_contract, &tmp.code_id)?;
+        POOL_CREATORS.save(deps.storage, &pair_contract, &tmp.creator)?;
 
-        for asset_info in &tmp.asset_infos {
-            for asset_info_2 in &tmp.asset_infos {
-                if asset_info != asset_info_2 {
-                    ROUTE.update::<_, StdError>(
-                        deps.storage,
-                        (asset_info.to_string(), asset_info_2.to_string()),
-                        |maybe_contracts| {
-                            if let Some(mut contracts) = maybe_contracts {
-                                contracts.push(pair_contract.clone());
-                                Ok(contracts)
-                            } else {
-                                Ok(vec![pair_contract.clone()])
-                            }
-                        },
-                    )?;
-                }
-            }
-        }
+        add_pair_to_routes(deps.storage, &tmp.asset_infos, &pair_contract)?;
 
         // keep track of staking address
         let pair_info = query_pair_info(&deps.querier, &pair_contract)?;
+        // an existing staking contract has to stake the LP shares of the new pair
+        if tmp.existing_staking_addr.is_some()
+            && query_staking_denom(&deps.querier, &pair_info.staking_addr)?
+                != pair_info.liquidity_token
+        {
+            return Err(ContractError::StakingDenomMismatch {});
+        }
         STAKING_ADDRESSES.save(deps.storage, &pair_info.staking_addr, &())?;
+        STAKING_TO_POOL.save(deps.storage, &pair_info.staking_addr, &pair_contract)?;
+        POOL_TYPES.save(deps.storage, &pair_contract, &pair_info.pool_type)?;
 
         Ok(Response::new()
             // create distribution flows
@@ -651,6 +1227,7 @@ pub mod reply {
                             manager: env.contract.address.to_string(),
                             asset: flow.asset,
                             rewards: flow.rewards,
+                            rewards_locked_until_unbond: false,
                         },
                         vec![],
                     )
@@ -662,6 +1239,38 @@ pub mod reply {
                 attr("pair_contract_addr", pair_contract),
             ]))
     }
+
+    /// Handles a failed pair instantiation. If the creator paid the pool creation fee, it is refunded
+    /// in the asset it was paid in (with a cw20 transfer for cw20 fees) and the creation is undone.
+    /// Otherwise, the error is returned, failing the whole transaction.
+    pub fn instantiate_pair_failed(
+        deps: DepsMut<CoreumQueries>,
+        err: &str,
+    ) -> Result<Response, ContractError> {
+        let tmp = TMP_PAIR_INFO.load(deps.storage)?;
+        let deposit = tmp
+            .deposit
+            .ok_or_else(|| StdError::generic_err(err.to_string()))?;
+
+        COLLECTED_FEES.update(deps.storage, &deposit.info, |collected| -> StdResult<_> {
+            Ok(collected.unwrap_or_default().checked_sub(deposit.amount)?)
+        })?;
+        CREATOR_POOL_COUNT.update(deps.storage, &tmp.creator, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default().saturating_sub(1))
+        })?;
+        POOL_COUNT.update(deps.storage, |count| -> StdResult<_> {
+            Ok(count.saturating_sub(1))
+        })?;
+
+        Ok(Response::new()
+            .add_message(deposit.into_msg(&tmp.creator)?)
+            .add_attributes(vec![
+                attr("action", "refund_pool_creation_fee"),
+                attr("recipient", tmp.creator),
+                attr("amount", deposit.to_string()),
+                attr("error", err),
+            ]))
+    }
 }
 
 /// Removes an existing pair from the factory.
@@ -690,9 +1299,15 @@ pub fn deregister_pool_and_staking(
 
     let pair_addr = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
     PAIRS.remove(deps.storage, &pair_key(&asset_infos));
+    DEPRECATED_PAIRS.remove(deps.storage, &pair_key(&asset_infos));
+    POOL_COUNT.update(deps.storage, |count| -> StdResult<_> {
+        Ok(count.saturating_sub(1))
+    })?;
     // keep track of staking address
     let pair_info = query_pair_info(&deps.querier, &pair_addr)?;
     STAKING_ADDRESSES.remove(deps.storage, &pair_info.staking_addr);
+    STAKING_TO_POOL.remove(deps.storage, &pair_info.staking_addr);
+    POOL_TYPES.remove(deps.storage, &pair_addr);
 
     for asset_info1 in &asset_infos {
         for asset_info2 in &asset_infos {
@@ -719,23 +1334,318 @@ pub fn deregister_pool_and_staking(
     ]))
 }
 
-pub fn execute_withdraw_pool_creation_fees(
+/// Adds a pair that was removed with [`deregister_pool_and_staking`] back to the factory.
+///
+/// * **pool_addr** is the address of the still deployed pair contract.
+/// The assets, pair type and LP Staking Contract are read from the pair itself.
+///
+/// ## Executor
+/// Only the owner can execute this.
+pub fn execute_reregister_pool(
     deps: DepsMut<CoreumQueries>,
     env: Env,
+    info: MessageInfo,
+    pool_addr: String,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let pool_fee_creation_asset = config.pool_creation_fee.info;
-    // This is called 'query_pool' but it actually just do the balance query...
-    let balance = pool_fee_creation_asset.query_pool(&deps.querier, env.contract.address)?;
-
-    Ok(Response::new().add_message(
-        Asset {
-            info: pool_fee_creation_asset,
-            amount: balance,
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_addr = deps.api.addr_validate(&pool_addr)?;
+    if query_pool_factory(&deps.querier, &pair_addr)? != env.contract.address {
+        return Err(ContractError::ForeignPool {});
+    }
+    let pair_info = query_pair_info(&deps.querier, &pair_addr)?;
+    let pair_key = pair_key(&pair_info.asset_infos);
+    if PAIRS.has(deps.storage, &pair_key) {
+        return Err(ContractError::PoolWasRegistered {});
+    }
+
+    let pool_count = POOL_COUNT.load(deps.storage)?;
+    if matches!(config.max_pools, Some(max_pools) if pool_count >= max_pools) {
+        return Err(ContractError::PoolLimitReached {});
+    }
+    POOL_COUNT.save(deps.storage, &(pool_count + 1))?;
+
+    PAIRS.save(deps.storage, &pair_key, &pair_addr)?;
+    add_pair_to_routes(deps.storage, &pair_info.asset_infos, &pair_addr)?;
+    STAKING_ADDRESSES.save(deps.storage, &pair_info.staking_addr, &())?;
+    STAKING_TO_POOL.save(deps.storage, &pair_info.staking_addr, &pair_addr)?;
+    POOL_TYPES.save(deps.storage, &pair_addr, &pair_info.pool_type)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "re_register"),
+        attr("pair_contract_addr", pair_addr),
+    ]))
+}
+
+/// Adds the pair to the routes between each combination of its assets.
+fn add_pair_to_routes(
+    storage: &mut dyn Storage,
+    asset_infos: &[AssetInfoValidated],
+    pair_contract: &Addr,
+) -> StdResult<()> {
+    for asset_info in asset_infos {
+        for asset_info_2 in asset_infos {
+            if asset_info != asset_info_2 {
+                ROUTE.update::<_, StdError>(
+                    storage,
+                    (asset_info.to_string(), asset_info_2.to_string()),
+                    |maybe_contracts| {
+                        if let Some(mut contracts) = maybe_contracts {
+                            contracts.push(pair_contract.clone());
+                            Ok(contracts)
+                        } else {
+                            Ok(vec![pair_contract.clone()])
+                        }
+                    },
+                )?;
+            }
         }
-        .validate(deps.api)?
-        .into_msg(config.owner)?,
-    ))
+    }
+    Ok(())
+}
+
+/// Marks a pair as deprecated, which hides it from the pairs listing by default.
+/// The pair itself keeps working and can still be queried directly.
+/// Only the owner can execute this.
+pub fn execute_set_pool_deprecated(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    deprecated: bool,
+) -> Result<Response, ContractError> {
+    let asset_infos = asset_infos
+        .into_iter()
+        .map(|a| a.validate(deps.api))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if info.sender != CONFIG.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_key = pair_key(&asset_infos);
+    let pair_addr = PAIRS.load(deps.storage, &pair_key)?;
+    if deprecated {
+        DEPRECATED_PAIRS.save(deps.storage, &pair_key, &())?;
+    } else {
+        DEPRECATED_PAIRS.remove(deps.storage, &pair_key);
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_pool_deprecated"),
+        attr("pair_contract_addr", pair_addr),
+        attr("deprecated", deprecated.to_string()),
+    ]))
+}
+
+/// Sets or removes the guardian.
+/// Only the owner can execute this.
+pub fn execute_set_guardian(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.guardian = addr_opt_validate(deps.api, &guardian)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_guardian"),
+        attr(
+            "guardian",
+            config
+                .guardian
+                .map(String::from)
+                .unwrap_or_else(|| "none".to_string()),
+        ),
+    ]))
+}
+
+/// Sets or removes the maximum number of pairs that can be registered in the factory.
+/// Pairs that are already registered are not affected.
+/// Only the owner can execute this.
+pub fn execute_set_max_pools(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    max_pools: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.max_pools = max_pools;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_max_pools"),
+        attr(
+            "max_pools",
+            max_pools
+                .map(|max_pools| max_pools.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+        ),
+    ]))
+}
+
+/// Checks that the sender may make an emergency change.
+/// The owner can make any change, the guardian only those that make the factory more restrictive.
+fn assert_owner_or_guardian(
+    config: &Config,
+    sender: &Addr,
+    restrictive: bool,
+) -> Result<(), ContractError> {
+    if *sender == config.owner || (restrictive && config.guardian.as_ref() == Some(sender)) {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+/// Stops or resumes the creation of new pairs.
+/// The owner can execute this, the guardian only to pause.
+pub fn execute_set_pool_creation_paused(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner_or_guardian(&config, &info.sender, paused)?;
+
+    config.pool_creation_paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_pool_creation_paused"),
+        attr("paused", paused.to_string()),
+    ]))
+}
+
+/// Disables or enables the creation of new pairs of the given type.
+/// The owner can execute this, the guardian only to disable.
+pub fn execute_set_pool_type_disabled(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    pool_type: PoolType,
+    disabled: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner_or_guardian(&config, &info.sender, disabled)?;
+
+    PAIR_CONFIGS.update(
+        deps.storage,
+        pool_type.to_string(),
+        |pair_config| match pair_config {
+            Some(mut pair_config) => {
+                pair_config.is_disabled = disabled;
+                Ok(pair_config)
+            }
+            None => Err(ContractError::PoolConfigNotFound {}),
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_pool_type_disabled"),
+        attr("pool_type", pool_type.to_string()),
+        attr("disabled", disabled.to_string()),
+    ]))
+}
+
+/// Sends the collected pool creation fees of every fee asset to the `creation_fee_address`,
+/// or to the owner if none is configured.
+/// Only the owner can execute this.
+pub fn execute_withdraw_pool_creation_fees(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collected = COLLECTED_FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(info, amount)| info.with_balance(amount)))
+        .filter(|item| !matches!(item, Ok(fee) if fee.amount.is_zero()))
+        .collect::<StdResult<Vec<_>>>()?;
+    if collected.is_empty() {
+        return Err(ContractError::NoCollectedFees {});
+    }
+    for fee in &collected {
+        COLLECTED_FEES.remove(deps.storage, &fee.info);
+    }
+
+    let recipient = config.creation_fee_address.unwrap_or(config.owner);
+    Ok(Response::new()
+        .add_messages(
+            collected
+                .iter()
+                .map(|fee| fee.into_msg(recipient.clone()))
+                .collect::<StdResult<Vec<_>>>()?,
+        )
+        .add_attributes(vec![
+            attr("action", "withdraw_pool_creation_fees"),
+            attr("amount", collected.iter().join(",")),
+            attr("recipient", recipient),
+        ]))
+}
+
+/// Sends the balance of `denom` to `recipient`, or to the owner if none is given.
+/// Only the owner can execute this. The collected pool creation fees are never touched,
+/// since those are withdrawn with [`execute_withdraw_pool_creation_fees`].
+pub fn execute_sweep_stuck(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let asset_info = AssetInfo::SmartToken(denom);
+    let recipient = addr_opt_validate(deps.api, &recipient)?.unwrap_or(config.owner);
+
+    // only what exceeds the collected pool creation fees can be swept
+    let collected = COLLECTED_FEES
+        .may_load(deps.storage, &asset_info.validate(deps.api)?)?
+        .unwrap_or_default();
+    let balance = asset_info
+        .query_pool(&deps.querier, env.contract.address)?
+        .saturating_sub(collected);
+    if balance.is_zero() {
+        return Err(if collected.is_zero() {
+            ContractError::NothingToSweep(asset_info.to_string())
+        } else {
+            ContractError::CannotSweepCreationFees {}
+        });
+    }
+
+    Ok(Response::new()
+        .add_message(
+            Asset {
+                info: asset_info.clone(),
+                amount: balance,
+            }
+            .validate(deps.api)?
+            .into_msg(recipient.clone())?,
+        )
+        .add_attributes(vec![
+            attr("action", "sweep_stuck"),
+            attr("asset", asset_info.to_string()),
+            attr("amount", balance),
+            attr("recipient", recipient),
+        ]))
 }
 
 /// Exposes all the queries available in the contract.
@@ -745,8 +1655,19 @@ pub fn execute_withdraw_pool_creation_fees(
 ///
 /// * **QueryMsg::Pool { asset_infos }** Returns a [`PoolInfo`] object with information about a specific Dex pair.
 ///
-/// * **QueryMsg::Pools { start_after, limit }** Returns an array that contains items of type [`PoolInfo`].
-/// This returns information about multiple Dex pairs
+/// * **QueryMsg::StakingAddress { asset_infos }** Returns the address of the staking contract of a specific Dex pair.
+///
+/// * **QueryMsg::PoolAddresses { asset_infos }** Returns the addresses of multiple Dex pairs.
+///
+/// * **QueryMsg::Pools { start_after, limit, include_deprecated }** Returns an array that contains items of type [`PoolInfo`].
+/// This returns information about multiple Dex pairs. Deprecated pairs are skipped unless `include_deprecated` is set.
+///
+/// * **QueryMsg::Route { from, to }** Returns the addresses of all pools that can swap `from` into `to`.
+///
+/// * **QueryMsg::Routes { start_after, limit }** Returns all routes in an object of type [`RoutesResponse`].
+///
+/// * **QueryMsg::PoolsByType { pool_type, start_after, limit }** Returns the non-deprecated pools
+/// of the given type in an object of type [`PoolsResponse`].
 ///
 /// * **QueryMsg::FeeInfo { pool_type }** Returns the fee structure (total and protocol fees) for a specific pair type.
 ///
@@ -755,14 +1676,41 @@ pub fn execute_withdraw_pool_creation_fees(
 /// * **QueryMsg::PoolsToMigrate {}** Returns a vector that contains pair addresses that are not migrated.
 ///
 /// * **QueryMsg::PoolsType { address }** Returns boolean.`true` if the pool is verified, `false` if non-verified
+///
+/// * **QueryMsg::PoolOfStaking { staking_addr }** Returns the address of the pool the given staking contract belongs to.
+///
+/// * **QueryMsg::PoolCodeId { pool_addr }** Returns the code id the given pool was instantiated with.
+///
+/// * **QueryMsg::TotalValueLocked { reference, start_after, limit }** Returns the value of the
+/// reserves of a page of pools in terms of the `reference` asset.
+///
+/// * **QueryMsg::SimulateCreatePool { .. }** Returns the [`PoolInstantiateMsg`] that would be used
+/// to create a pool with the given parameters.
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<CoreumQueries>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
         QueryMsg::Pool { asset_infos } => to_json_binary(&query_pair(deps, asset_infos)?),
-        QueryMsg::Pools { start_after, limit } => {
-            to_json_binary(&query_pairs(deps, start_after, limit)?)
+        QueryMsg::StakingAddress { asset_infos } => {
+            to_json_binary(&query_pair(deps, asset_infos)?.staking_addr)
+        }
+        QueryMsg::PoolAddresses { asset_infos } => {
+            to_json_binary(&query_pair_addresses(deps, asset_infos)?)
+        }
+        QueryMsg::Pools {
+            start_after,
+            limit,
+            include_deprecated,
+        } => to_json_binary(&query_pairs(deps, start_after, limit, include_deprecated)?),
+        QueryMsg::Route { from, to } => to_json_binary(&query_route(deps, from, to)?),
+        QueryMsg::Routes { start_after, limit } => {
+            to_json_binary(&query_routes(deps, start_after, limit)?)
         }
+        QueryMsg::PoolsByType {
+            pool_type,
+            start_after,
+            limit,
+        } => to_json_binary(&query_pairs_by_type(deps, pool_type, start_after, limit)?),
         QueryMsg::FeeInfo { pool_type } => to_json_binary(&query_fee_info(deps, pool_type)?),
         QueryMsg::BlacklistedPoolTypes {} => to_json_binary(&query_blacklisted_pool_types(deps)?),
         QueryMsg::PoolsToMigrate {} => {
@@ -771,9 +1719,192 @@ pub fn query(deps: Deps<CoreumQueries>, _env: Env, msg: QueryMsg) -> StdResult<B
         QueryMsg::ValidateStakingAddress { address } => {
             to_json_binary(&STAKING_ADDRESSES.has(deps.storage, &deps.api.addr_validate(&address)?))
         }
+        QueryMsg::PoolOfStaking { staking_addr } => to_json_binary(
+            &STAKING_TO_POOL.may_load(deps.storage, &deps.api.addr_validate(&staking_addr)?)?,
+        ),
+        QueryMsg::PoolCodeId { pool_addr } => to_json_binary(
+            &POOL_CODE_IDS.may_load(deps.storage, &deps.api.addr_validate(&pool_addr)?)?,
+        ),
+        QueryMsg::PoolCreator { pool_address } => to_json_binary(
+            &POOL_CREATORS.may_load(deps.storage, &deps.api.addr_validate(&pool_address)?)?,
+        ),
+        QueryMsg::CreatorStats { creator } => to_json_binary(&query_creator_stats(deps, creator)?),
+        QueryMsg::TotalValueLocked {
+            reference,
+            start_after,
+            limit,
+        } => to_json_binary(&query_total_value_locked(
+            deps,
+            reference,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SimulateCreatePool {
+            pool_type,
+            asset_infos,
+            init_params,
+            total_fee_bps,
+            staking_config,
+        } => to_json_binary(&query_simulate_create_pool(
+            deps,
+            env,
+            pool_type,
+            asset_infos,
+            init_params,
+            total_fee_bps,
+            staking_config,
+        )?),
     }
 }
 
+/// Returns the number of pools created by the given address.
+pub fn query_creator_stats(
+    deps: Deps<CoreumQueries>,
+    creator: String,
+) -> StdResult<CreatorStatsResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let pools_created = CREATOR_POOL_COUNT
+        .may_load(deps.storage, &creator)?
+        .unwrap_or_default();
+    Ok(CreatorStatsResponse {
+        creator,
+        pools_created,
+    })
+}
+
+/// Returns the value of the reserves of the registered pairs in terms of the `reference` asset.
+/// Querying starts after the pair `start_after` and goes through at most `limit` pairs.
+/// Fails if a value does not fit into a [`Uint128`].
+pub fn query_total_value_locked(
+    deps: Deps<CoreumQueries>,
+    reference: AssetInfo,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<TotalValueLockedResponse> {
+    let reference = reference.validate(deps.api)?;
+
+    let mut total_value = Uint128::zero();
+    let mut pools = vec![];
+    for pair_addr in read_pairs(deps, start_after, limit, true)? {
+        let asset_infos = query_pair_info(&deps.querier, &pair_addr)?.asset_infos;
+        let reserves = query_pool_reserves(&deps.querier, &pair_addr)?;
+
+        let value = reserves_value(deps, &pair_addr, &asset_infos, &reserves, &reference)?;
+        if let Some(value) = value {
+            total_value = total_value.checked_add(value)?;
+        }
+
+        pools.push(PoolValue {
+            contract_addr: pair_addr,
+            asset_infos,
+            value,
+        });
+    }
+
+    Ok(TotalValueLockedResponse {
+        reference,
+        total_value,
+        pools,
+    })
+}
+
+/// Returns the value of the `reserves` of the pool `pair_addr` in terms of `reference`,
+/// or `None` if one of the assets cannot be priced.
+fn reserves_value(
+    deps: Deps<CoreumQueries>,
+    pair_addr: &Addr,
+    asset_infos: &[AssetInfoValidated],
+    reserves: &[AssetValidated],
+    reference: &AssetInfoValidated,
+) -> StdResult<Option<Uint128>> {
+    let mut value = Uint128::zero();
+    for reserve in reserves {
+        let Some(price) = reference_price(deps, pair_addr, asset_infos, &reserve.info, reference)
+        else {
+            return Ok(None);
+        };
+        let reserve_value = reserve
+            .amount
+            .checked_mul_floor(price)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+        value = value.checked_add(reserve_value)?;
+    }
+    Ok(Some(value))
+}
+
+/// Returns the price of `asset` in terms of `reference`, using the spot price of the pool `pair_addr`
+/// if it contains the reference asset, otherwise of the first pool routing `asset` to the reference asset,
+/// possibly via another of the pool's `asset_infos`. Returns `None` if no price can be found.
+fn reference_price(
+    deps: Deps<CoreumQueries>,
+    pair_addr: &Addr,
+    asset_infos: &[AssetInfoValidated],
+    asset: &AssetInfoValidated,
+    reference: &AssetInfoValidated,
+) -> Option<Decimal> {
+    if asset == reference {
+        return Some(Decimal::one());
+    }
+    if asset_infos.contains(reference) {
+        return query_spot_price(&deps.querier, pair_addr, asset, reference).ok();
+    }
+
+    let route_price = |from: &AssetInfoValidated| -> Option<Decimal> {
+        let pool = ROUTE
+            .may_load(deps.storage, (from.to_string(), reference.to_string()))
+            .ok()??
+            .into_iter()
+            .next()?;
+        query_spot_price(&deps.querier, pool, from, reference).ok()
+    };
+
+    route_price(asset).or_else(|| {
+        asset_infos
+            .iter()
+            .filter(|other| *other != asset)
+            .find_map(|other| {
+                let price = query_spot_price(&deps.querier, pair_addr, asset, other).ok()?;
+                price.checked_mul(route_price(other)?).ok()
+            })
+    })
+}
+
+/// Returns the [`PoolInstantiateMsg`] that the factory would use to create a pool with the given parameters.
+/// The result is not marked as verified, since only pools created by the owner are.
+pub fn query_simulate_create_pool(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    pool_type: PoolType,
+    asset_infos: Vec<AssetInfo>,
+    init_params: Option<Binary>,
+    total_fee_bps: Option<u16>,
+    staking_config: PartialStakeConfig,
+) -> StdResult<PoolInstantiateMsg> {
+    let asset_infos = check_asset_infos(deps.api, &asset_infos)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let pair_config = PAIR_CONFIGS
+        .load(deps.storage, pool_type.to_string())
+        .map_err(|_| StdError::generic_err(ContractError::PoolConfigNotFound {}.to_string()))?;
+    if pair_config.is_disabled {
+        return Err(StdError::generic_err(
+            ContractError::PoolConfigDisabled {}.to_string(),
+        ));
+    }
+
+    Ok(pool_instantiate_msg(
+        &env,
+        &config,
+        &pair_config,
+        &asset_infos,
+        init_params,
+        total_fee_bps,
+        staking_config,
+    ))
+}
+
 /// Returns a vector that contains blacklisted pair types
 pub fn query_blacklisted_pool_types(deps: Deps<CoreumQueries>) -> StdResult<Vec<PoolType>> {
     PAIR_CONFIGS
@@ -801,9 +1932,16 @@ pub fn query_config(deps: Deps<CoreumQueries>) -> StdResult<ConfigResponse> {
             .map(|item| Ok(item?.1))
             .collect::<StdResult<Vec<_>>>()?,
         fee_address: config.fee_address,
+        creation_fee_address: config.creation_fee_address,
         max_referral_commission: config.max_referral_commission,
+        default_stake_config: config.default_stake_config,
         only_owner_can_create_pools: config.only_owner_can_create_pools,
+        pool_creation_fee: config.pool_creation_fee,
         trading_starts: config.trading_starts,
+        require_trading_starts: config.require_trading_starts,
+        guardian: config.guardian,
+        pool_creation_paused: config.pool_creation_paused,
+        max_pools: config.max_pools,
     };
 
     Ok(resp)
@@ -820,6 +1958,24 @@ pub fn query_pair(deps: Deps<CoreumQueries>, asset_infos: Vec<AssetInfo>) -> Std
     query_pair_info(&deps.querier, pair_addr)
 }
 
+/// Returns the addresses of the pairs trading the given assets, in the same order as `asset_infos`.
+/// * **asset_infos** is a vector with the assets traded in each pair.
+pub fn query_pair_addresses(
+    deps: Deps<CoreumQueries>,
+    asset_infos: Vec<Vec<AssetInfo>>,
+) -> StdResult<Vec<Addr>> {
+    asset_infos
+        .into_iter()
+        .map(|asset_infos| {
+            let asset_infos = asset_infos
+                .into_iter()
+                .map(|a| a.validate(deps.api))
+                .collect::<StdResult<Vec<_>>>()?;
+            PAIRS.load(deps.storage, &pair_key(&asset_infos))
+        })
+        .collect()
+}
+
 /// Returns a vector with pair data that contains items of type [`PoolInfo`]. Querying starts at `start_after` and returns `limit` pairs.
 /// * **start_after** is a field which accepts a vector with items of type [`AssetInfo`].
 /// This is the pair from which we start a query.
@@ -829,8 +1985,9 @@ pub fn query_pairs(
     deps: Deps<CoreumQueries>,
     start_after: Option<Vec<AssetInfo>>,
     limit: Option<u32>,
+    include_deprecated: bool,
 ) -> StdResult<PoolsResponse> {
-    let pools = read_pairs(deps, start_after, limit)?
+    let pools = read_pairs(deps, start_after, limit, include_deprecated)?
         .iter()
         .map(|pair_addr| query_pair_info(&deps.querier, pair_addr))
         .collect::<StdResult<Vec<_>>>()?;
@@ -838,6 +1995,78 @@ pub fn query_pairs(
     Ok(PoolsResponse { pools })
 }
 
+/// Returns the addresses of all pools that can swap `from` into `to`, in the order they were registered.
+pub fn query_route(
+    deps: Deps<CoreumQueries>,
+    from: AssetInfo,
+    to: AssetInfo,
+) -> StdResult<Vec<Addr>> {
+    let from = from.validate(deps.api)?;
+    let to = to.validate(deps.api)?;
+    Ok(ROUTE
+        .may_load(deps.storage, (from.to_string(), to.to_string()))?
+        .unwrap_or_default())
+}
+
+/// Returns all routes that still have at least one pool. Querying starts after the route
+/// `start_after` and returns at most `limit` routes.
+pub fn query_routes(
+    deps: Deps<CoreumQueries>,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<RoutesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let routes = ROUTE
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        // routes of deregistered pools are left empty
+        .filter(|item| !matches!(item, Ok((_, pools)) if pools.is_empty()))
+        .take(limit)
+        .map(|item| {
+            let ((from, to), pools) = item?;
+            Ok(RouteInfo { from, to, pools })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RoutesResponse { routes })
+}
+
+/// Returns information about the non-deprecated pools of the given type using a [`PoolsResponse`] object.
+///
+/// * **pool_type** is the type of the pools to return.
+///
+/// * **start_after** is an optional field that specifies after which pair to start reading data.
+///
+/// * **limit** is the maximum number of pairs to go through. Pools of other types are skipped,
+/// so fewer pools may be returned.
+pub fn query_pairs_by_type(
+    deps: Deps<CoreumQueries>,
+    pool_type: PoolType,
+    start_after: Option<Vec<AssetInfo>>,
+    limit: Option<u32>,
+) -> StdResult<PoolsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let mut pools = vec![];
+    for pair_addr in read_pairs(deps, start_after, Some(limit), false)? {
+        let pool_type_of_pair = match POOL_TYPES.may_load(deps.storage, &pair_addr)? {
+            Some(pool_type) => pool_type,
+            // pools created before the type was recorded
+            None => query_pair_info(&deps.querier, &pair_addr)?.pool_type,
+        };
+        if pool_type_of_pair == pool_type {
+            pools.push(query_pair_info(&deps.querier, &pair_addr)?);
+        }
+    }
+
+    Ok(PoolsResponse { pools })
+}
+
 /// Returns the fee setup for a specific pair type using a [`FeeInfoResponse`] struct.
 /// * **pool_type** is a struct that represents the fee information (total and protocol fees) for a specific pair type.
 pub fn query_fee_info(
@@ -858,12 +2087,31 @@ pub fn query_fee_info(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut<CoreumQueries>,
-    _env: Env,
+    env: Env,
     msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
     match msg {
         MigrateMsg::Update() => {
-            ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+            let old_version =
+                ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+            // fees collected before they were tracked are still held by the factory
+            if old_version < COLLECTED_FEES_VERSION {
+                let config = CONFIG.load(deps.storage)?;
+                let fee_info = config.pool_creation_fee.info.validate(deps.api)?;
+                let collected = fee_info.query_balance(&deps.querier, env.contract.address)?;
+                if !collected.is_zero() {
+                    COLLECTED_FEES.save(deps.storage, &fee_info, &collected)?;
+                }
+            }
+
+            // pools registered before they were counted
+            if !POOL_COUNT.exists(deps.storage) {
+                let pool_count = PAIRS
+                    .keys_raw(deps.storage, None, None, Order::Ascending)
+                    .count();
+                POOL_COUNT.save(deps.storage, &(pool_count as u64))?;
+            }
         }
         MigrateMsg::AddPermissionlessPoolDeposit(asset) => {
             CONFIG.update(deps.storage, |old_config| -> StdResult<_> {
@@ -10,6 +10,9 @@ pub enum ContractError {
     #[error("Invalid value for trading start")]
     InvalidTradingStart {},
 
+    #[error("New pools require a trading start in the future")]
+    TradingStartsRequired {},
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -19,6 +22,9 @@ pub enum ContractError {
     #[error("Pair was already registered")]
     PoolWasRegistered {},
 
+    #[error("The pool was not created by this factory")]
+    ForeignPool {},
+
     #[error("Duplicate of pair configs")]
     PoolConfigDuplicate {},
 
@@ -31,6 +37,12 @@ pub enum ContractError {
     #[error("Pool config disabled")]
     PoolConfigDisabled {},
 
+    #[error("Pool creation is paused")]
+    PoolCreationPaused {},
+
+    #[error("The maximum number of pools has been reached")]
+    PoolLimitReached {},
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
@@ -48,4 +60,19 @@ pub enum ContractError {
 
     #[error("Factory is in permissionless mode: deposit must be sent to create new pair")]
     PermissionlessRequiresDeposit {},
+
+    #[error("Pool creation fees cannot be swept, withdraw them instead")]
+    CannotSweepCreationFees {},
+
+    #[error("No {0} to sweep")]
+    NothingToSweep(String),
+
+    #[error("No pool creation fees were collected")]
+    NoCollectedFees {},
+
+    #[error("Staking contract is already used by another pool")]
+    StakingAddressInUse {},
+
+    #[error("Staking contract does not stake the LP share denom of the pool")]
+    StakingDenomMismatch {},
 }
@@ -105,4 +105,9 @@ impl WasmMockQuerier {
     pub fn with_dex_pairs(&mut self, pairs: &[(&String, &PairInfo)]) {
         self.dex_pair_querier = DexPairQuerier::new(pairs);
     }
+
+    // Configure the native balance of an address
+    pub fn with_balance(&mut self, addr: &str, balance: Vec<Coin>) {
+        self.base.update_balance(addr, balance);
+    }
 }
@@ -1,7 +1,13 @@
 use coreum_wasm_sdk::core::CoreumQueries;
-use cosmwasm_std::{QuerierWrapper, StdResult};
+use cosmwasm_std::{Addr, Decimal, QuerierWrapper, StdError, StdResult};
 
-use dex::pool::{PairInfo, QueryMsg};
+use dex::asset::{AssetInfoValidated, AssetValidated};
+use dex::pool::{
+    ConfigResponse, PairInfo, PoolResponse, QueryMsg, SpotPriceResponse, TradingStartedResponse,
+};
+use dex_stake::msg::{
+    BondingInfoResponse, DistributionDataResponse, QueryMsg as StakeQueryMsg, StakedResponse,
+};
 
 /// Returns information about a pair (using the [`PoolInfo`] struct).
 ///
@@ -12,3 +18,92 @@ pub fn query_pair_info(
 ) -> StdResult<PairInfo> {
     querier.query_wasm_smart(pool_contract, &QueryMsg::Pair {})
 }
+
+/// Returns the factory that created a pool.
+///
+/// `pool_contract` is the pool for which to retrieve the factory.
+pub fn query_pool_factory(
+    querier: &QuerierWrapper<CoreumQueries>,
+    pool_contract: impl Into<String>,
+) -> StdResult<Addr> {
+    let res: ConfigResponse = querier.query_wasm_smart(pool_contract, &QueryMsg::Config {})?;
+    Ok(res.factory_addr)
+}
+
+/// Returns whether trading in a pool has started.
+///
+/// `pool_contract` is the pool to check.
+pub fn query_trading_started(
+    querier: &QuerierWrapper<CoreumQueries>,
+    pool_contract: impl Into<String>,
+) -> StdResult<bool> {
+    let res: TradingStartedResponse =
+        querier.query_wasm_smart(pool_contract, &QueryMsg::TradingStarted {})?;
+    Ok(res.started)
+}
+
+/// Returns the reserves of a pool.
+///
+/// `pool_contract` is the pool for which to retrieve the reserves.
+pub fn query_pool_reserves(
+    querier: &QuerierWrapper<CoreumQueries>,
+    pool_contract: impl Into<String>,
+) -> StdResult<Vec<AssetValidated>> {
+    let res: PoolResponse = querier.query_wasm_smart(pool_contract, &QueryMsg::Pool {})?;
+    Ok(res.assets)
+}
+
+/// Returns the spot price of `offer` in terms of `ask` in a pool.
+///
+/// `pool_contract` is the pool for which to retrieve the price.
+pub fn query_spot_price(
+    querier: &QuerierWrapper<CoreumQueries>,
+    pool_contract: impl Into<String>,
+    offer: &AssetInfoValidated,
+    ask: &AssetInfoValidated,
+) -> StdResult<Decimal> {
+    let res: SpotPriceResponse = querier.query_wasm_smart(
+        pool_contract,
+        &QueryMsg::SpotPrice {
+            offer: offer.clone().into(),
+            ask: ask.clone().into(),
+        },
+    )?;
+    Ok(res.price)
+}
+
+/// Returns the distribution flows of a staking contract.
+///
+/// `staking_contract` is the staking contract for which to retrieve the distribution flows.
+pub fn query_distribution_data(
+    querier: &QuerierWrapper<CoreumQueries>,
+    staking_contract: impl Into<String>,
+) -> StdResult<DistributionDataResponse> {
+    querier.query_wasm_smart(staking_contract, &StakeQueryMsg::DistributionData {})
+}
+
+/// Returns the LP share denom staked by a staking contract.
+///
+/// `staking_contract` is the staking contract for which to retrieve the denom.
+pub fn query_staking_denom(
+    querier: &QuerierWrapper<CoreumQueries>,
+    staking_contract: impl Into<String>,
+) -> StdResult<String> {
+    let staking_contract = staking_contract.into();
+    let bonding: BondingInfoResponse =
+        querier.query_wasm_smart(&staking_contract, &StakeQueryMsg::BondingInfo {})?;
+    let unbonding_period = bonding
+        .bonding
+        .first()
+        .ok_or_else(|| StdError::generic_err("Staking contract has no unbonding periods"))?
+        .unbonding_period;
+    // the staked amount of the contract itself does not matter, only the denom is needed
+    let staked: StakedResponse = querier.query_wasm_smart(
+        &staking_contract,
+        &StakeQueryMsg::Staked {
+            address: staking_contract.clone(),
+            unbonding_period,
+        },
+    )?;
+    Ok(staked.lp_share_denom)
+}
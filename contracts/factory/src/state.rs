@@ -1,13 +1,13 @@
 use coreum_wasm_sdk::core::CoreumQueries;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Api, Decimal, Deps, Order, StdResult};
+use cosmwasm_std::{Addr, Api, Decimal, Deps, Order, StdResult, Uint128};
 use cw_storage_plus::{Bound, Item, Map};
 
 use crate::error::ContractError;
 use dex::{
-    asset::{Asset, AssetInfo, AssetInfoValidated},
+    asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated},
     common::OwnershipProposal,
-    factory::{DefaultStakeConfig, DistributionFlow, PoolConfig},
+    factory::{DefaultStakeConfig, DistributionFlow, PoolConfig, PoolType},
 };
 
 use itertools::Itertools;
@@ -20,6 +20,9 @@ pub struct Config {
     pub owner: Addr,
     /// Contract address to send governance fees to (the protocol)
     pub fee_address: Option<Addr>,
+    /// Address to send the collected pool creation fees to. Falls back to the owner.
+    #[serde(default)]
+    pub creation_fee_address: Option<Addr>,
     /// Maximum referral commission
     pub max_referral_commission: Decimal,
     /// Default values for lp token staking contracts
@@ -30,6 +33,18 @@ pub struct Config {
     pub pool_creation_fee: Asset,
     /// The block time until which trading is disabled
     pub trading_starts: Option<u64>,
+    /// When this is set to `true`, new pools need a trading start in the future
+    #[serde(default)]
+    pub require_trading_starts: bool,
+    /// Address that can pause pool creation and disable pool types, but change nothing else
+    #[serde(default)]
+    pub guardian: Option<Addr>,
+    /// When this is set to `true`, no new pools can be created
+    #[serde(default)]
+    pub pool_creation_paused: bool,
+    /// The maximum number of pools that can be registered in the factory
+    #[serde(default)]
+    pub max_pools: Option<u64>,
 }
 
 /// This is an intermediate structure for storing a pair's key. It is used in a submessage response.
@@ -38,6 +53,14 @@ pub struct TmpPoolInfo {
     pub pair_key: Vec<u8>,
     pub asset_infos: Vec<AssetInfoValidated>,
     pub distribution_flows: Vec<DistributionFlow>,
+    /// The code id the pair is instantiated with
+    pub code_id: u64,
+    /// The address creating the pair
+    pub creator: Addr,
+    /// The pool creation fee paid by the creator, refunded if the pair instantiation fails
+    pub deposit: Option<AssetValidated>,
+    /// The existing staking contract the pair is bound to, if any
+    pub existing_staking_addr: Option<Addr>,
 }
 
 /// Saves a pair's key
@@ -49,9 +72,30 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Saves created pairs (from olders to latest)
 pub const PAIRS: Map<&[u8], Addr> = Map::new("pair_info");
 
+/// Set of pair keys of deprecated pairs, which are hidden from the pairs listing by default
+pub const DEPRECATED_PAIRS: Map<&[u8], ()> = Map::new("deprecated_pairs");
+
 /// Set of all staking addresses
 pub const STAKING_ADDRESSES: Map<&Addr, ()> = Map::new("staking_addresses");
 
+/// Maps each staking address to the pool it belongs to
+pub const STAKING_TO_POOL: Map<&Addr, Addr> = Map::new("staking_to_pool");
+
+/// Maps each pool address to the code id it was instantiated with
+pub const POOL_CODE_IDS: Map<&Addr, u64> = Map::new("pool_code_ids");
+
+/// Maps each pool address to its pool type, so pools can be filtered by type without querying them
+pub const POOL_TYPES: Map<&Addr, PoolType> = Map::new("pool_types");
+
+/// Maps each pool address to the address that created it
+pub const POOL_CREATORS: Map<&Addr, Addr> = Map::new("pool_creators");
+
+/// Number of pools created by each address
+pub const CREATOR_POOL_COUNT: Map<&Addr, u64> = Map::new("creator_pool_count");
+
+/// Number of pools registered in the factory, including a pool that is currently being instantiated
+pub const POOL_COUNT: Item<u64> = Item::new("pool_count");
+
 /// Calculates a pair key from the specified parameters in the `asset_infos` variable.
 ///
 /// `asset_infos` is an array with multiple items of type [`AssetInfo`].
@@ -70,7 +114,9 @@ pub const PAIR_CONFIGS: Map<String, PoolConfig> = Map::new("pair_configs");
 
 /// ## Pagination settings
 /// The default limit for reading pairs from [`PAIRS`]
-const DEFAULT_LIMIT: u32 = 10;
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
+/// The maximum limit for reading pairs from [`PAIRS`]
+pub(crate) const MAX_LIMIT: u32 = 30;
 
 /// Reads pairs from the [`PAIRS`] vector according to the `start_after` and `limit` variables.
 /// Otherwise, it returns the default number of pairs, starting from the oldest one.
@@ -78,10 +124,14 @@ const DEFAULT_LIMIT: u32 = 10;
 /// `start_after` is the pair from which the function starts to fetch results.
 ///
 /// `limit` is the number of items to retrieve.
+///
+/// `include_deprecated` determines whether deprecated pairs are returned as well.
+/// Deprecated pairs are still in use, so actions applied to all pairs should include them.
 pub fn read_pairs(
     deps: Deps<CoreumQueries>,
     start_after: Option<Vec<AssetInfo>>,
     limit: Option<u32>,
+    include_deprecated: bool,
 ) -> StdResult<Vec<Addr>> {
     let start_after = start_after
         .map(|a| {
@@ -92,30 +142,24 @@ pub fn read_pairs(
         .transpose()?;
     let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
 
-    if let Some(start) = calc_range_start(start_after) {
-        PAIRS
-            .range(
-                deps.storage,
-                Some(Bound::exclusive(start.as_slice())),
-                None,
-                Order::Ascending,
-            )
-            .take(limit)
-            .map(|item| {
-                let (_, pair_addr) = item?;
-                Ok(pair_addr)
-            })
-            .collect()
-    } else {
-        PAIRS
-            .range(deps.storage, None, None, Order::Ascending)
-            .take(limit)
-            .map(|item| {
-                let (_, pair_addr) = item?;
-                Ok(pair_addr)
-            })
-            .collect()
-    }
+    let start = calc_range_start(start_after);
+    PAIRS
+        .range(
+            deps.storage,
+            start.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| match item {
+            Ok((key, _)) => include_deprecated || !DEPRECATED_PAIRS.has(deps.storage, key),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (_, pair_addr) = item?;
+            Ok(pair_addr)
+        })
+        .collect()
 }
 
 /// Calculates the key of a pair from which to start reading data.
@@ -151,6 +195,9 @@ pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_pro
 /// Stores pairs to migrate
 pub const PAIRS_TO_MIGRATE: Item<Vec<Addr>> = Item::new("pairs_to_migrate");
 
+/// Amount of pool creation fees collected since they were last withdrawn, per fee asset
+pub const COLLECTED_FEES: Map<&AssetInfoValidated, Uint128> = Map::new("collected_fees_by_asset");
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::MockApi;
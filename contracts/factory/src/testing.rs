@@ -1,15 +1,16 @@
+use coreum_wasm_sdk::core::CoreumQueries;
 use cosmwasm_std::{
-    attr, from_json,
+    attr, coin, coins, from_json,
     testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
-    to_json_binary, Addr, Coin, Decimal, ReplyOn, SubMsg, Uint128, WasmMsg,
+    to_json_binary, Addr, BankMsg, Coin, Decimal, Deps, DepsMut, ReplyOn, SubMsg, Uint128, WasmMsg,
 };
 use cw_utils::MsgInstantiateContractResponse;
 
 use dex::{
     asset::{Asset, AssetInfo},
     factory::{
-        ConfigResponse, DefaultStakeConfig, ExecuteMsg, InstantiateMsg, PartialStakeConfig,
-        PoolConfig, PoolType, PoolsResponse, QueryMsg,
+        ConfigResponse, DefaultStakeConfig, ExecuteMsg, FeeInfoResponse, InstantiateMsg,
+        PartialStakeConfig, PoolConfig, PoolType, PoolsResponse, QueryMsg,
     },
     fee_config::FeeConfig,
     pool::{InstantiateMsg as PoolInstantiateMsg, PairInfo},
@@ -19,7 +20,6 @@ use crate::{
     contract::{execute, instantiate, query, reply},
     error::ContractError,
     mock_querier::mock_dependencies,
-    state::CONFIG,
 };
 
 fn default_stake_config() -> DefaultStakeConfig {
@@ -29,6 +29,7 @@ fn default_stake_config() -> DefaultStakeConfig {
         min_bond: Uint128::new(1000),
         unbonding_periods: vec![1],
         max_distributions: 6,
+        auto_claim_on_delegate: false,
     }
 }
 
@@ -54,6 +55,7 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                stake_config_override: None,
             },
             PoolConfig {
                 code_id: 325u64,
@@ -63,9 +65,11 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                stake_config_override: None,
             },
         ],
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.clone(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -74,6 +78,7 @@ fn proper_initialization() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -91,8 +96,10 @@ fn proper_initialization() {
                 protocol_fee_bps: 10,
             },
             is_disabled: false,
+            stake_config_override: None,
         }],
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.clone(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -101,6 +108,7 @@ fn proper_initialization() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -121,6 +129,7 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                stake_config_override: None,
             },
             PoolConfig {
                 code_id: 325u64,
@@ -130,9 +139,11 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                stake_config_override: None,
             },
         ],
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.clone(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -141,6 +152,7 @@ fn proper_initialization() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -165,6 +177,7 @@ fn trading_starts_validation() {
     let mut msg = InstantiateMsg {
         pool_configs: vec![],
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -173,6 +186,7 @@ fn trading_starts_validation() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     // in the past
@@ -208,11 +222,13 @@ fn update_config() {
             protocol_fee_bps: 166,
         },
         is_disabled: false,
+        stake_config_override: None,
     }];
 
     let msg = InstantiateMsg {
         pool_configs,
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -221,6 +237,7 @@ fn update_config() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -234,29 +251,51 @@ fn update_config() {
     let info = mock_info(owner, &[]);
     let msg = ExecuteMsg::UpdateConfig {
         fee_address: Some(String::from("new_fee_addr")),
+        creation_fee_address: None,
         only_owner_can_create_pools: Some(true),
         default_stake_config: None,
+        require_trading_starts: None,
+        max_referral_commission: Some(Decimal::percent(5)),
     };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
     // It worked, let's query the state
-    let query_res = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
+    let query_res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_json(query_res).unwrap();
     assert_eq!(owner, config_res.owner);
     assert_eq!(
         String::from("new_fee_addr"),
         config_res.fee_address.unwrap()
     );
+    assert_eq!(config_res.max_referral_commission, Decimal::percent(5));
+
+    // The max referral commission cannot be more than 100%
+    let msg = ExecuteMsg::UpdateConfig {
+        fee_address: None,
+        creation_fee_address: None,
+        only_owner_can_create_pools: None,
+        default_stake_config: None,
+        require_trading_starts: None,
+        max_referral_commission: Some(Decimal::percent(101)),
+    };
+    let res = execute(deps.as_mut(), env, mock_info(owner, &[]), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::InvalidReferralCommission(Decimal::percent(101))
+    );
 
     // Unauthorized err
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
         fee_address: None,
+        creation_fee_address: None,
         only_owner_can_create_pools: None,
         default_stake_config: None,
+        require_trading_starts: None,
+        max_referral_commission: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
@@ -271,6 +310,7 @@ fn update_owner() {
     let msg = InstantiateMsg {
         pool_configs: vec![],
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -279,6 +319,7 @@ fn update_owner() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -345,6 +386,209 @@ fn update_owner() {
     assert_eq!(new_owner, config.owner);
 }
 
+#[test]
+fn guardian_can_only_pause() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+    let guardian = "guardian0000";
+
+    let pair_config = PoolConfig {
+        code_id: 11,
+        pool_type: PoolType::Xyk {},
+        fee_config: FeeConfig {
+            total_fee_bps: 100,
+            protocol_fee_bps: 10,
+        },
+        is_disabled: false,
+        stake_config_override: None,
+    };
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![pair_config],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::Cw20Token("coreum".to_string()),
+            amount: Uint128::new(3_000),
+        },
+        require_trading_starts: false,
+    };
+
+    let env = mock_env();
+    instantiate(deps.as_mut(), env.clone(), mock_info(owner, &[]), msg).unwrap();
+
+    // Only the owner can set the guardian
+    let set_guardian = ExecuteMsg::SetGuardian {
+        guardian: Some(guardian.to_string()),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        set_guardian.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        set_guardian,
+    )
+    .unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.guardian, Some(Addr::unchecked(guardian)));
+
+    // Guardian pauses pool creation and disables the pool type
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::SetPoolCreationPaused { paused: true },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::SetPoolTypeDisabled {
+            pool_type: PoolType::Xyk {},
+            disabled: true,
+        },
+    )
+    .unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(config.pool_creation_paused);
+    assert!(config.pool_configs[0].is_disabled);
+
+    // Guardian cannot undo the pause or re-enable the pool type
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::SetPoolCreationPaused { paused: false },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::SetPoolTypeDisabled {
+            pool_type: PoolType::Xyk {},
+            disabled: false,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Guardian cannot change fees, config or ownership
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::UpdatePoolFees {
+            asset_infos: vec![
+                AssetInfo::Cw20Token("asset0000".to_string()),
+                AssetInfo::Cw20Token("asset0001".to_string()),
+            ],
+            fee_config: FeeConfig {
+                total_fee_bps: 0,
+                protocol_fee_bps: 0,
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::UpdateConfig {
+            fee_address: Some(guardian.to_string()),
+            creation_fee_address: None,
+            only_owner_can_create_pools: None,
+            default_stake_config: None,
+            require_trading_starts: None,
+            max_referral_commission: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(guardian, &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: guardian.to_string(),
+            expires_in: 100,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Unauthorized");
+
+    // No pools can be created while paused
+    let create_pool = ExecuteMsg::CreatePool {
+        pool_type: PoolType::Xyk {},
+        asset_infos: vec![
+            AssetInfo::Cw20Token("asset0000".to_string()),
+            AssetInfo::Cw20Token("asset0001".to_string()),
+        ],
+        init_params: None,
+        total_fee_bps: None,
+        staking_config: PartialStakeConfig::default(),
+        existing_staking_addr: None,
+        trading_starts: None,
+    };
+    let deposit = mock_info(owner, &[Coin::new(3_000, "coreum")]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        deposit.clone(),
+        create_pool.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PoolCreationPaused {});
+
+    // Owner resumes pool creation, but the pool type is still disabled
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::SetPoolCreationPaused { paused: false },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        deposit.clone(),
+        create_pool.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PoolConfigDisabled {});
+
+    // Owner re-enables the pool type
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::SetPoolTypeDisabled {
+            pool_type: PoolType::Xyk {},
+            disabled: false,
+        },
+    )
+    .unwrap();
+    execute(deps.as_mut(), env, deposit, create_pool).unwrap();
+}
+
 #[test]
 fn update_pair_config() {
     let mut deps = mock_dependencies(&[]);
@@ -357,11 +601,13 @@ fn update_pair_config() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        stake_config_override: None,
     }];
 
     let msg = InstantiateMsg {
         pool_configs: pool_configs.clone(),
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -370,6 +616,7 @@ fn update_pair_config() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -392,6 +639,7 @@ fn update_pair_config() {
             protocol_fee_bps: 2,
         },
         is_disabled: false,
+        stake_config_override: None,
     };
 
     // Unauthorized err
@@ -416,6 +664,7 @@ fn update_pair_config() {
                 protocol_fee_bps: 10_001,
             },
             is_disabled: false,
+            stake_config_override: None,
         },
     };
 
@@ -444,6 +693,7 @@ fn update_pair_config() {
             protocol_fee_bps: 20,
         },
         is_disabled: false,
+        stake_config_override: None,
     };
 
     let info = mock_info(owner, &[]);
@@ -474,11 +724,13 @@ fn create_pair() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        stake_config_override: None,
     };
 
     let msg = InstantiateMsg {
         pool_configs: vec![pair_config.clone()],
         fee_address: None,
+        creation_fee_address: None,
         owner: "owner0000".to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -487,6 +739,7 @@ fn create_pair() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -500,7 +753,6 @@ fn create_pair() {
         AssetInfo::Cw20Token("asset0001".to_string()),
     ];
 
-    let config = CONFIG.load(&deps.storage);
     let env = mock_env();
     let info = mock_info("owner0000", &[Coin::new(3_000, "coreum")]);
 
@@ -515,6 +767,8 @@ fn create_pair() {
             init_params: None,
             total_fee_bps: None,
             staking_config: PartialStakeConfig::default(),
+            existing_staking_addr: None,
+            trading_starts: None,
         },
     )
     .unwrap_err();
@@ -530,6 +784,8 @@ fn create_pair() {
             init_params: None,
             total_fee_bps: None,
             staking_config: PartialStakeConfig::default(),
+            existing_staking_addr: None,
+            trading_starts: None,
         },
     )
     .unwrap();
@@ -553,22 +809,120 @@ fn create_pair() {
                     trading_starts: mock_env().block.time.seconds(),
                     fee_config: pair_config.fee_config,
                     circuit_breaker: None,
+                    existing_staking_addr: None,
+                    initial_oracle_price: None,
                     verified: true,
                 })
                 .unwrap(),
                 code_id: pair_config.code_id,
                 funds: vec![],
-                admin: Some(config.unwrap().owner.to_string()),
+                admin: Some(String::from(MOCK_CONTRACT_ADDR)),
                 label: String::from("Dex pair"),
             }
             .into(),
             id: 1,
             gas_limit: None,
-            reply_on: ReplyOn::Success
+            reply_on: ReplyOn::Always
         }]
     );
 }
 
+#[test]
+fn create_pair_require_trading_starts() {
+    let mut deps = mock_dependencies(&[]);
+
+    let pair_config = PoolConfig {
+        code_id: 11,
+        pool_type: PoolType::Xyk {},
+        fee_config: FeeConfig {
+            total_fee_bps: 100,
+            protocol_fee_bps: 10,
+        },
+        is_disabled: false,
+        stake_config_override: None,
+    };
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![pair_config.clone()],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::Cw20Token("coreum".to_string()),
+            amount: Uint128::new(3_000),
+        },
+        require_trading_starts: true,
+    };
+
+    let env = mock_env();
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::Cw20Token("asset0000".to_string()),
+        AssetInfo::Cw20Token("asset0001".to_string()),
+    ];
+    let info = mock_info("owner0000", &[Coin::new(3_000, "coreum")]);
+    let create_msg = |trading_starts| ExecuteMsg::CreatePool {
+        pool_type: PoolType::Xyk {},
+        asset_infos: asset_infos.clone(),
+        init_params: None,
+        total_fee_bps: None,
+        staking_config: PartialStakeConfig::default(),
+        existing_staking_addr: None,
+        trading_starts,
+    };
+
+    // no trading start at all
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), create_msg(None)).unwrap_err();
+    assert_eq!(err, ContractError::TradingStartsRequired {});
+
+    // trading starting right now is not enough
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        create_msg(Some(env.block.time.seconds())),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::TradingStartsRequired {});
+
+    // a start in the future is accepted and passed on to the pool
+    let trading_starts = env.block.time.seconds() + 3600;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        create_msg(Some(trading_starts)),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        WasmMsg::Instantiate {
+            msg: to_json_binary(&PoolInstantiateMsg {
+                factory_addr: String::from(MOCK_CONTRACT_ADDR),
+                asset_infos: asset_infos.clone(),
+                init_params: None,
+                staking_config: default_stake_config().to_stake_config(),
+                trading_starts,
+                fee_config: pair_config.fee_config,
+                circuit_breaker: None,
+                existing_staking_addr: None,
+                initial_oracle_price: None,
+                verified: true,
+            })
+            .unwrap(),
+            code_id: pair_config.code_id,
+            funds: vec![],
+            admin: Some(String::from(MOCK_CONTRACT_ADDR)),
+            label: String::from("Dex pair"),
+        }
+        .into()
+    );
+}
+
 #[test]
 fn create_permissionless_pair() {
     let mut deps = mock_dependencies(&[]);
@@ -581,11 +935,13 @@ fn create_permissionless_pair() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        stake_config_override: None,
     };
 
     let msg = InstantiateMsg {
         pool_configs: vec![pair_config.clone()],
         fee_address: None,
+        creation_fee_address: None,
         owner: "owner0000".to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -594,6 +950,7 @@ fn create_permissionless_pair() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000u128),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -607,7 +964,6 @@ fn create_permissionless_pair() {
         AssetInfo::Cw20Token("asset0001".to_string()),
     ];
 
-    let config = CONFIG.load(&deps.storage);
     let env = mock_env();
     let info = mock_info(
         "user0000",
@@ -627,6 +983,8 @@ fn create_permissionless_pair() {
             init_params: None,
             total_fee_bps: None,
             staking_config: PartialStakeConfig::default(),
+            existing_staking_addr: None,
+            trading_starts: None,
         },
     )
     .unwrap();
@@ -650,24 +1008,26 @@ fn create_permissionless_pair() {
                     trading_starts: mock_env().block.time.seconds(),
                     fee_config: pair_config.fee_config,
                     circuit_breaker: None,
+                    existing_staking_addr: None,
+                    initial_oracle_price: None,
                     verified: false,
                 })
                 .unwrap(),
                 code_id: pair_config.code_id,
                 funds: vec![],
-                admin: Some(config.unwrap().owner.to_string()),
+                admin: Some(String::from(MOCK_CONTRACT_ADDR)),
                 label: String::from("Dex pair"),
             }
             .into(),
             id: 1,
             gas_limit: None,
-            reply_on: ReplyOn::Success
+            reply_on: ReplyOn::Always
         }]
     );
 }
 
 #[test]
-fn create_permissionless_pair_too_small_deposit() {
+fn simulate_create_pool() {
     let mut deps = mock_dependencies(&[]);
 
     let pair_config = PoolConfig {
@@ -678,11 +1038,13 @@ fn create_permissionless_pair_too_small_deposit() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        stake_config_override: None,
     };
 
     let msg = InstantiateMsg {
         pool_configs: vec![pair_config.clone()],
         fee_address: None,
+        creation_fee_address: None,
         owner: "owner0000".to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -691,60 +1053,301 @@ fn create_permissionless_pair_too_small_deposit() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000u128),
         },
+        require_trading_starts: false,
     };
-
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-
-    // instantiating the factory
-    let _ = instantiate(deps.as_mut(), env, info, msg.clone()).unwrap();
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
 
     let asset_infos = vec![
         AssetInfo::Cw20Token("asset0000".to_string()),
         AssetInfo::Cw20Token("asset0001".to_string()),
     ];
+    let staking_config = PartialStakeConfig {
+        min_bond: Some(Uint128::new(5000)),
+        unbonding_periods: Some(vec![60, 120]),
+        ..Default::default()
+    };
 
-    let env = mock_env();
-    let info = mock_info(
-        "user0000",
-        &[Coin {
-            denom: "coreum".to_string(),
-            // 1_000 tokens less then required
-            amount: Uint128::new(2_000),
-        }],
+    // unknown pool types are rejected
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateCreatePool {
+            pool_type: PoolType::Stable {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            total_fee_bps: None,
+            staking_config: staking_config.clone(),
+        },
+    )
+    .unwrap_err();
+
+    let simulated: PoolInstantiateMsg = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateCreatePool {
+                pool_type: PoolType::Xyk {},
+                asset_infos: asset_infos.clone(),
+                init_params: None,
+                total_fee_bps: Some(50),
+                staking_config: staking_config.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        simulated,
+        PoolInstantiateMsg {
+            factory_addr: String::from(MOCK_CONTRACT_ADDR),
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            staking_config: DefaultStakeConfig {
+                min_bond: Uint128::new(5000),
+                unbonding_periods: vec![60, 120],
+                ..default_stake_config()
+            }
+            .to_stake_config(),
+            trading_starts: mock_env().block.time.seconds(),
+            fee_config: FeeConfig {
+                total_fee_bps: 50,
+                protocol_fee_bps: 10,
+            },
+            circuit_breaker: None,
+            existing_staking_addr: None,
+            initial_oracle_price: None,
+            verified: false,
+        }
     );
 
-    let err = execute(
+    // creating the pool uses exactly the simulated message
+    let res = execute(
         deps.as_mut(),
-        env,
-        info,
+        mock_env(),
+        mock_info("user0000", &[Coin::new(3_000, "coreum")]),
         ExecuteMsg::CreatePool {
             pool_type: PoolType::Xyk {},
-            asset_infos: asset_infos.clone(),
+            asset_infos,
             init_params: None,
-            total_fee_bps: None,
-            staking_config: PartialStakeConfig::default(),
+            total_fee_bps: Some(50),
+            staking_config,
+            existing_staking_addr: None,
+            trading_starts: None,
         },
     )
-    .unwrap_err();
-    assert_eq!(err, ContractError::PermissionlessRequiresDeposit {});
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        WasmMsg::Instantiate {
+            msg: to_json_binary(&simulated).unwrap(),
+            code_id: pair_config.code_id,
+            funds: vec![],
+            admin: Some(String::from(MOCK_CONTRACT_ADDR)),
+            label: String::from("Dex pair"),
+        }
+        .into()
+    );
 }
+
 #[test]
-fn register() {
+fn pool_type_stake_config_override() {
     let mut deps = mock_dependencies(&[]);
-    let owner = "owner0000";
 
+    let fee_config = FeeConfig {
+        total_fee_bps: 100,
+        protocol_fee_bps: 10,
+    };
     let msg = InstantiateMsg {
-        pool_configs: vec![PoolConfig {
-            code_id: 123u64,
-            pool_type: PoolType::Xyk {},
-            fee_config: FeeConfig {
-                total_fee_bps: 100,
+        pool_configs: vec![
+            PoolConfig {
+                code_id: 42,
+                pool_type: PoolType::Xyk {},
+                fee_config: fee_config.clone(),
+                is_disabled: false,
+                stake_config_override: None,
+            },
+            PoolConfig {
+                code_id: 43,
+                pool_type: PoolType::Stable {},
+                fee_config,
+                is_disabled: false,
+                stake_config_override: Some(PartialStakeConfig {
+                    unbonding_periods: Some(vec![3600, 7200]),
+                    min_bond: Some(Uint128::new(2000)),
+                    ..Default::default()
+                }),
+            },
+        ],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::Cw20Token("coreum".to_string()),
+            amount: Uint128::new(3_000u128),
+        },
+        require_trading_starts: false,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let simulate = |pool_type: PoolType, staking_config: PartialStakeConfig| {
+        let simulated: PoolInstantiateMsg = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateCreatePool {
+                    pool_type,
+                    asset_infos: vec![
+                        AssetInfo::Cw20Token("asset0000".to_string()),
+                        AssetInfo::Cw20Token("asset0001".to_string()),
+                    ],
+                    init_params: None,
+                    total_fee_bps: None,
+                    staking_config,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        simulated.staking_config
+    };
+
+    // xyk pools use the global default
+    assert_eq!(
+        simulate(PoolType::Xyk {}, PartialStakeConfig::default()),
+        default_stake_config().to_stake_config()
+    );
+    // stable pools use the override on top of the global default
+    assert_eq!(
+        simulate(PoolType::Stable {}, PartialStakeConfig::default()),
+        DefaultStakeConfig {
+            min_bond: Uint128::new(2000),
+            unbonding_periods: vec![3600, 7200],
+            ..default_stake_config()
+        }
+        .to_stake_config()
+    );
+    // the staking config given on creation takes precedence over the override
+    assert_eq!(
+        simulate(
+            PoolType::Stable {},
+            PartialStakeConfig {
+                min_bond: Some(Uint128::new(5000)),
+                ..Default::default()
+            }
+        ),
+        DefaultStakeConfig {
+            min_bond: Uint128::new(5000),
+            unbonding_periods: vec![3600, 7200],
+            ..default_stake_config()
+        }
+        .to_stake_config()
+    );
+    // claims can be paid out on delegation for a single pool
+    assert_eq!(
+        simulate(
+            PoolType::Xyk {},
+            PartialStakeConfig {
+                auto_claim_on_delegate: Some(true),
+                ..Default::default()
+            }
+        ),
+        DefaultStakeConfig {
+            auto_claim_on_delegate: true,
+            ..default_stake_config()
+        }
+        .to_stake_config()
+    );
+}
+
+#[test]
+fn create_permissionless_pair_too_small_deposit() {
+    let mut deps = mock_dependencies(&[]);
+
+    let pair_config = PoolConfig {
+        code_id: 42,
+        pool_type: PoolType::Xyk {},
+        fee_config: FeeConfig {
+            total_fee_bps: 100,
+            protocol_fee_bps: 10,
+        },
+        is_disabled: false,
+        stake_config_override: None,
+    };
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![pair_config.clone()],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::Cw20Token("coreum".to_string()),
+            amount: Uint128::new(3_000u128),
+        },
+        require_trading_starts: false,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // instantiating the factory
+    let _ = instantiate(deps.as_mut(), env, info, msg.clone()).unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::Cw20Token("asset0000".to_string()),
+        AssetInfo::Cw20Token("asset0001".to_string()),
+    ];
+
+    let env = mock_env();
+    let info = mock_info(
+        "user0000",
+        &[Coin {
+            denom: "coreum".to_string(),
+            // 1_000 tokens less then required
+            amount: Uint128::new(2_000),
+        }],
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::CreatePool {
+            pool_type: PoolType::Xyk {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            total_fee_bps: None,
+            staking_config: PartialStakeConfig::default(),
+            existing_staking_addr: None,
+            trading_starts: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PermissionlessRequiresDeposit {});
+}
+#[test]
+fn register() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![PoolConfig {
+            code_id: 123u64,
+            pool_type: PoolType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
                 protocol_fee_bps: 10,
             },
             is_disabled: false,
+            stake_config_override: None,
         }],
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -753,6 +1356,7 @@ fn register() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let env = mock_env();
@@ -769,7 +1373,9 @@ fn register() {
         asset_infos: asset_infos.clone(),
         init_params: None,
         staking_config: PartialStakeConfig::default(),
+        existing_staking_addr: None,
         total_fee_bps: None,
+        trading_starts: None,
     };
 
     let env = mock_env();
@@ -853,7 +1459,9 @@ fn register() {
         asset_infos: asset_infos_2.clone(),
         init_params: None,
         staking_config: PartialStakeConfig::default(),
+        existing_staking_addr: None,
         total_fee_bps: None,
+        trading_starts: None,
     };
 
     let env = mock_env();
@@ -889,6 +1497,7 @@ fn register() {
     let query_msg = QueryMsg::Pools {
         start_after: None,
         limit: None,
+        include_deprecated: false,
     };
 
     let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
@@ -926,6 +1535,7 @@ fn register() {
     let query_msg = QueryMsg::Pools {
         start_after: None,
         limit: Some(1),
+        include_deprecated: false,
     };
 
     let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
@@ -949,6 +1559,7 @@ fn register() {
     let query_msg = QueryMsg::Pools {
         start_after: Some(asset_infos),
         limit: None,
+        include_deprecated: false,
     };
 
     let res = query(deps.as_ref(), env, query_msg).unwrap();
@@ -1002,6 +1613,7 @@ fn register() {
     let query_msg = QueryMsg::Pools {
         start_after: None,
         limit: None,
+        include_deprecated: false,
     };
 
     let res = query(deps.as_ref(), env, query_msg).unwrap();
@@ -1022,3 +1634,529 @@ fn register() {
         },]
     );
 }
+
+#[test]
+fn fee_streams_go_to_configured_addresses() {
+    let mut deps = mock_dependencies(&[coin(6_000, "ucore")]);
+    let owner = "owner0000";
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![PoolConfig {
+            code_id: 123u64,
+            pool_type: PoolType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 10,
+            },
+            is_disabled: false,
+            stake_config_override: None,
+        }],
+        fee_address: Some("protocol_treasury".to_string()),
+        creation_fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::SmartToken("ucore".to_string()),
+            amount: Uint128::new(3_000),
+        },
+        require_trading_starts: false,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+    for assets in [["asset0000", "asset0001"], ["asset0000", "asset0002"]] {
+        create_permissionless_pool(deps.as_mut(), assets);
+    }
+
+    // without a creation fee address, the owner gets the creation fees
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: coins(6_000, "ucore"),
+        })]
+    );
+
+    // only the owner can set the creation fee address
+    let update_msg = ExecuteMsg::UpdateConfig {
+        fee_address: None,
+        creation_fee_address: Some("creation_treasury".to_string()),
+        only_owner_can_create_pools: None,
+        default_stake_config: None,
+        require_trading_starts: None,
+        max_referral_commission: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        update_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), update_msg).unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config.creation_fee_address,
+        Some(Addr::unchecked("creation_treasury"))
+    );
+
+    // creation fees go to the creation fee address...
+    create_permissionless_pool(deps.as_mut(), ["asset0001", "asset0002"]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "creation_treasury".to_string(),
+            amount: coins(3_000, "ucore"),
+        })]
+    );
+
+    // ...while the pools still send protocol fees to the fee address
+    let fee_info: FeeInfoResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::FeeInfo {
+                pool_type: PoolType::Xyk {},
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        fee_info.fee_address,
+        Some(Addr::unchecked("protocol_treasury"))
+    );
+}
+
+#[test]
+fn sweep_stuck_tokens() {
+    // the factory holds two pool creation fees and some stray tokens
+    let mut deps = mock_dependencies(&[coin(6_400, "ucore"), coin(500, "stray")]);
+    let owner = "owner0000";
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![PoolConfig {
+            code_id: 123u64,
+            pool_type: PoolType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 10,
+            },
+            is_disabled: false,
+            stake_config_override: None,
+        }],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::SmartToken("ucore".to_string()),
+            amount: Uint128::new(3_000),
+        },
+        require_trading_starts: false,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+    for assets in [["asset0000", "asset0001"], ["asset0000", "asset0002"]] {
+        create_permissionless_pool(deps.as_mut(), assets);
+    }
+
+    let sweep = |denom: &str, recipient: Option<&str>| ExecuteMsg::SweepStuck {
+        denom: denom.to_string(),
+        recipient: recipient.map(str::to_string),
+    };
+
+    // only the owner can sweep
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        sweep("stray", None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // the stray token goes to the owner by default...
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        sweep("stray", None),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: coins(500, "stray"),
+        })]
+    );
+
+    // ...or to the given recipient
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        sweep("stray", Some("rescuer")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "rescuer".to_string(),
+            amount: coins(500, "stray"),
+        })]
+    );
+
+    // there is nothing to sweep of tokens the factory doesn't hold
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        sweep("uatom", None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToSweep("uatom".to_string()));
+
+    // only what exceeds the pool creation fees is swept...
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        sweep("ucore", Some("rescuer")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "rescuer".to_string(),
+            amount: coins(400, "ucore"),
+        })]
+    );
+
+    // ...the pool creation fees themselves cannot be swept...
+    deps.querier
+        .with_balance(MOCK_CONTRACT_ADDR, vec![coin(6_000, "ucore")]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        sweep("ucore", Some("rescuer")),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CannotSweepCreationFees {});
+
+    // ...and are still withdrawn in full
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: coins(6_000, "ucore"),
+        })]
+    );
+}
+
+/// Creates a pool of the given cw20 tokens, paying the 3_000ucore pool creation fee
+fn create_permissionless_pool(deps: DepsMut<CoreumQueries>, assets: [&str; 2]) {
+    execute(
+        deps,
+        mock_env(),
+        mock_info("creator", &coins(3_000, "ucore")),
+        ExecuteMsg::CreatePool {
+            pool_type: PoolType::Xyk {},
+            asset_infos: assets
+                .iter()
+                .map(|asset| AssetInfo::Cw20Token(asset.to_string()))
+                .collect(),
+            init_params: None,
+            total_fee_bps: None,
+            staking_config: PartialStakeConfig::default(),
+            existing_staking_addr: None,
+            trading_starts: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn withdraw_pool_creation_fees() {
+    // the factory also holds some stray ucore that was not paid as a creation fee
+    let mut deps = mock_dependencies(&[coin(10_000, "ucore")]);
+    let owner = "owner0000";
+
+    let msg = InstantiateMsg {
+        pool_configs: vec![PoolConfig {
+            code_id: 123u64,
+            pool_type: PoolType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 10,
+            },
+            is_disabled: false,
+            stake_config_override: None,
+        }],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::SmartToken("ucore".to_string()),
+            amount: Uint128::new(3_000),
+        },
+        require_trading_starts: false,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+
+    // nothing was collected yet
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoCollectedFees {});
+
+    for assets in [
+        ["asset0000", "asset0001"],
+        ["asset0000", "asset0002"],
+        ["asset0001", "asset0002"],
+    ] {
+        create_permissionless_pool(deps.as_mut(), assets);
+    }
+
+    // only the owner can withdraw the fees
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // exactly the collected fees are withdrawn
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: coins(9_000, "ucore"),
+        })]
+    );
+
+    // and they can only be withdrawn once
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::WithdrawPoolCreationFees {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoCollectedFees {});
+}
+
+fn query_pools_by_type_helper(
+    deps: Deps<CoreumQueries>,
+    pool_type: PoolType,
+    start_after: Option<&PairInfo>,
+    limit: Option<u32>,
+) -> Vec<PairInfo> {
+    let res = query(
+        deps,
+        mock_env(),
+        QueryMsg::PoolsByType {
+            pool_type,
+            start_after: start_after
+                .map(|pool| pool.asset_infos.iter().cloned().map(Into::into).collect()),
+            limit,
+        },
+    )
+    .unwrap();
+    from_json::<PoolsResponse>(res).unwrap().pools
+}
+
+#[test]
+fn query_pools_by_type() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+
+    let pool_config = |pool_type| PoolConfig {
+        code_id: 123u64,
+        pool_type,
+        fee_config: FeeConfig {
+            total_fee_bps: 100,
+            protocol_fee_bps: 10,
+        },
+        is_disabled: false,
+        stake_config_override: None,
+    };
+    let msg = InstantiateMsg {
+        pool_configs: vec![
+            pool_config(PoolType::Xyk {}),
+            pool_config(PoolType::Stable {}),
+        ],
+        fee_address: None,
+        creation_fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        pool_creation_fee: Asset {
+            info: AssetInfo::Cw20Token("coreum".to_string()),
+            amount: Uint128::new(3_000),
+        },
+        require_trading_starts: false,
+    };
+    let info = mock_info("addr0000", &[Coin::new(3_000u128, "coreum")]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // create an xyk, a stable and another xyk pool
+    let pool_types = [PoolType::Xyk {}, PoolType::Stable {}, PoolType::Xyk {}];
+    let mut pools = vec![];
+    for (i, pool_type) in pool_types.into_iter().enumerate() {
+        let asset_infos = vec![
+            AssetInfo::Cw20Token("asset0000".to_string()),
+            AssetInfo::Cw20Token(format!("asset000{}", i + 1)),
+        ];
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner, &[Coin::new(3_000, "coreum")]),
+            ExecuteMsg::CreatePool {
+                pool_type: pool_type.clone(),
+                asset_infos: asset_infos.clone(),
+                init_params: None,
+                staking_config: PartialStakeConfig::default(),
+                existing_staking_addr: None,
+                total_fee_bps: None,
+                trading_starts: None,
+            },
+        )
+        .unwrap();
+
+        let pair_addr = format!("pair000{i}");
+        pools.push(PairInfo {
+            asset_infos: asset_infos
+                .iter()
+                .map(|a| a.validate(&deps.api).unwrap())
+                .collect(),
+            contract_addr: Addr::unchecked(&pair_addr),
+            staking_addr: Addr::unchecked(format!("stake000{i}")),
+            liquidity_token: format!("liquidity000{i}"),
+            pool_type,
+            fee_config: FeeConfig {
+                total_fee_bps: 0,
+                protocol_fee_bps: 0,
+            },
+            verified: true,
+        });
+        let pair_addrs: Vec<_> = pools.iter().map(|p| p.contract_addr.to_string()).collect();
+        deps.querier.with_dex_pairs(
+            &pair_addrs
+                .iter()
+                .zip(&pools)
+                .collect::<Vec<(&String, &PairInfo)>>(),
+        );
+        reply::instantiate_pair(
+            deps.as_mut(),
+            mock_env(),
+            MsgInstantiateContractResponse {
+                contract_address: pair_addr,
+                data: None,
+            },
+        )
+        .unwrap();
+    }
+
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Xyk {}, None, None),
+        vec![pools[0].clone(), pools[2].clone()]
+    );
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Stable {}, None, None),
+        vec![pools[1].clone()]
+    );
+    assert_eq!(
+        query_pools_by_type_helper(
+            deps.as_ref(),
+            PoolType::Custom("custom".to_string()),
+            None,
+            None
+        ),
+        vec![]
+    );
+
+    // paginated
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Xyk {}, None, Some(1)),
+        vec![pools[0].clone()]
+    );
+    // the page only covers the stable pool
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Xyk {}, Some(&pools[0]), Some(1)),
+        vec![]
+    );
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Xyk {}, Some(&pools[1]), Some(1)),
+        vec![pools[2].clone()]
+    );
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Xyk {}, Some(&pools[2]), None),
+        vec![]
+    );
+
+    // deprecated pools are skipped
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(owner, &[]),
+        ExecuteMsg::SetPoolDeprecated {
+            asset_infos: pools[0]
+                .asset_infos
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect(),
+            deprecated: true,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_pools_by_type_helper(deps.as_ref(), PoolType::Xyk {}, None, None),
+        vec![pools[2].clone()]
+    );
+}
@@ -1,7 +1,7 @@
 use anyhow::Result as AnyResult;
 
 use bindings_test::CoreumApp;
-use cosmwasm_std::{Addr, Binary, Coin, Decimal, Uint128};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, Decimal, Uint128};
 use cw20::MinterResponse;
 use cw_multi_test::{AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
 
@@ -112,8 +112,10 @@ impl FactoryHelper {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                stake_config_override: None,
             }],
             fee_address: None,
+            creation_fee_address: None,
             owner: owner.to_string(),
             max_referral_commission: Decimal::one(),
             default_stake_config: DefaultStakeConfig {
@@ -122,12 +124,14 @@ impl FactoryHelper {
                 min_bond: Uint128::new(1000),
                 unbonding_periods: vec![1, 2, 3],
                 max_distributions: 6,
+                auto_claim_on_delegate: false,
             },
             trading_starts: None,
             pool_creation_fee: Asset {
                 info: AssetInfo::Cw20Token("coreum".to_string()),
                 amount: Uint128::new(3_000),
             },
+            require_trading_starts: false,
         };
 
         let factory = router
@@ -159,8 +163,11 @@ impl FactoryHelper {
     ) -> AnyResult<AppResponse> {
         let msg = dex::factory::ExecuteMsg::UpdateConfig {
             fee_address,
+            creation_fee_address: None,
             only_owner_can_create_pools,
             default_stake_config,
+            require_trading_starts: None,
+            max_referral_commission: None,
         };
 
         router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
@@ -185,7 +192,38 @@ impl FactoryHelper {
             asset_infos,
             init_params,
             staking_config: staking_config.unwrap_or_default(),
+            existing_staking_addr: None,
             total_fee_bps: None,
+            trading_starts: None,
+        };
+
+        router.execute_contract(
+            sender.clone(),
+            self.factory.clone(),
+            &msg,
+            &[Coin::new(3_000, "coreum")],
+        )
+    }
+
+    #[allow(dead_code)]
+    pub fn create_pair_with_existing_staking(
+        &mut self,
+        router: &mut CoreumApp,
+        sender: &Addr,
+        tokens: [&str; 2],
+        existing_staking_addr: &str,
+    ) -> AnyResult<AppResponse> {
+        let msg = dex::factory::ExecuteMsg::CreatePool {
+            pool_type: PoolType::Xyk {},
+            asset_infos: vec![
+                AssetInfo::SmartToken(tokens[0].to_owned()),
+                AssetInfo::SmartToken(tokens[1].to_owned()),
+            ],
+            init_params: None,
+            staking_config: PartialStakeConfig::default(),
+            existing_staking_addr: Some(existing_staking_addr.to_owned()),
+            total_fee_bps: None,
+            trading_starts: None,
         };
 
         router.execute_contract(
@@ -208,6 +246,20 @@ impl FactoryHelper {
         router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
     }
 
+    #[allow(dead_code)]
+    pub fn re_register_pool(
+        &mut self,
+        router: &mut CoreumApp,
+        sender: &Addr,
+        pool_addr: &Addr,
+    ) -> AnyResult<AppResponse> {
+        let msg = dex::factory::ExecuteMsg::ReRegisterPool {
+            pool_addr: pool_addr.to_string(),
+        };
+
+        router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
+    }
+
     pub fn create_pair_with_addr(
         &mut self,
         router: &mut CoreumApp,
@@ -245,6 +297,65 @@ impl FactoryHelper {
 
         router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
     }
+
+    #[allow(dead_code)]
+    pub fn update_fees_for_type(
+        &mut self,
+        router: &mut CoreumApp,
+        sender: &Addr,
+        pool_type: PoolType,
+        fee_config: FeeConfig,
+        start_after: Option<Vec<AssetInfo>>,
+        limit: Option<u32>,
+    ) -> AnyResult<AppResponse> {
+        let msg = dex::factory::ExecuteMsg::UpdateFeesForType {
+            pool_type,
+            fee_config,
+            start_after,
+            limit,
+        };
+
+        router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
+    }
+
+    #[allow(dead_code)]
+    pub fn update_staking_manager(
+        &mut self,
+        router: &mut CoreumApp,
+        sender: &Addr,
+        asset_infos: Vec<AssetInfo>,
+        new_manager: &str,
+    ) -> AnyResult<AppResponse> {
+        let msg = dex::factory::ExecuteMsg::UpdateStakingManager {
+            asset_infos,
+            new_manager: new_manager.to_owned(),
+        };
+
+        router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
+    }
+
+    #[allow(dead_code)]
+    pub fn migrate_pools_of_type(
+        &mut self,
+        router: &mut CoreumApp,
+        sender: &Addr,
+        new_code_id: u64,
+        start_after: Option<Vec<AssetInfo>>,
+        limit: Option<u32>,
+    ) -> AnyResult<AppResponse> {
+        let msg = dex::factory::ExecuteMsg::MigratePoolsOfType {
+            pool_type: PoolType::Xyk {},
+            new_code_id,
+            migrate_msg: to_json_binary(&dex::pool::MigrateMsg::UpdateFreeze {
+                frozen: false,
+                circuit_breaker: None,
+            })?,
+            start_after,
+            limit,
+        };
+
+        router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
+    }
 }
 
 pub fn instantiate_token(
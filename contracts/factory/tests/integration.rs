@@ -1,15 +1,23 @@
 mod factory_helper;
 
 use bindings_test::CoreumApp;
-use cosmwasm_std::{attr, from_json, Addr, Coin, Decimal, StdError, Uint128};
+use coreum_wasm_sdk::core::{CoreumMsg, CoreumQueries};
+use cosmwasm_std::{
+    attr, to_json_binary, Addr, Coin, Decimal, DepsMut, Env, Event, MessageInfo, Response,
+    StdError, StdResult, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
 use dex::asset::{Asset, AssetInfo};
 use dex::factory::{
-    ConfigResponse, DefaultStakeConfig, ExecuteMsg, FeeInfoResponse, InstantiateMsg,
-    PartialDefaultStakeConfig, PoolConfig, PoolType, QueryMsg,
+    ConfigResponse, CreatorStatsResponse, DefaultStakeConfig, ExecuteMsg, FeeInfoResponse,
+    InstantiateMsg, MigrateMsg, PartialDefaultStakeConfig, PoolConfig, PoolType, PoolsResponse,
+    QueryMsg, ReceiveMsg, RouteInfo, RoutesResponse, TotalValueLockedResponse,
 };
 use dex::fee_config::FeeConfig;
-use dex::pool::PairInfo;
-use dex_factory::state::Config;
+use dex::pool::{InstantiateMsg as PoolInstantiateMsg, PairInfo};
+use dex_stake::msg::{
+    DistributionDataResponse, ExecuteMsg as StakeExecuteMsg, QueryMsg as StakeQueryMsg,
+};
 
 use crate::factory_helper::{instantiate_token, FactoryHelper};
 use cw_multi_test::{ContractWrapper, Executor};
@@ -40,6 +48,7 @@ fn default_stake_config() -> DefaultStakeConfig {
         min_bond: Uint128::new(1000),
         unbonding_periods: vec![1],
         max_distributions: 6,
+        auto_claim_on_delegate: false,
     }
 }
 
@@ -59,11 +68,13 @@ fn proper_initialization() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        stake_config_override: None,
     }];
 
     let msg = InstantiateMsg {
         pool_configs: pool_configs.clone(),
         fee_address: None,
+        creation_fee_address: None,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
@@ -72,6 +83,7 @@ fn proper_initialization() {
             info: AssetInfo::Cw20Token("coreum".to_string()),
             amount: Uint128::new(3_000),
         },
+        require_trading_starts: false,
     };
 
     let factory_instance = app
@@ -88,8 +100,28 @@ fn proper_initialization() {
     let msg = QueryMsg::Config {};
     let config_res: ConfigResponse = app.wrap().query_wasm_smart(factory_instance, &msg).unwrap();
 
-    assert_eq!(pool_configs, config_res.pool_configs);
-    assert_eq!(owner, config_res.owner);
+    // every config field is part of the response
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner,
+            pool_configs,
+            fee_address: None,
+            creation_fee_address: None,
+            max_referral_commission: Decimal::one(),
+            default_stake_config: default_stake_config(),
+            only_owner_can_create_pools: false,
+            pool_creation_fee: Asset {
+                info: AssetInfo::Cw20Token("coreum".to_string()),
+                amount: Uint128::new(3_000),
+            },
+            trading_starts: None,
+            require_trading_starts: false,
+            guardian: None,
+            pool_creation_paused: false,
+            max_pools: None,
+        }
+    );
 }
 
 #[test]
@@ -111,6 +143,7 @@ fn update_config() {
                 min_bond: Some(10000u128.into()),
                 unbonding_periods: None,
                 max_distributions: Some(u32::MAX),
+                auto_claim_on_delegate: None,
             }),
         )
         .unwrap();
@@ -121,15 +154,6 @@ fn update_config() {
         .unwrap();
 
     assert_eq!("fee", config_res.fee_address.unwrap().to_string());
-
-    // query config raw to get default stake config
-    let raw_config: Config = from_json(
-        app.wrap()
-            .query_wasm_raw(&helper.factory, "config".as_bytes())
-            .unwrap()
-            .unwrap(),
-    )
-    .unwrap();
     assert_eq!(
         DefaultStakeConfig {
             staking_code_id: 12345,
@@ -137,8 +161,9 @@ fn update_config() {
             min_bond: Uint128::new(10_000),
             unbonding_periods: vec![1, 2, 3], // same as before
             max_distributions: u32::MAX,
+            auto_claim_on_delegate: false,
         },
-        raw_config.default_stake_config
+        config_res.default_stake_config
     );
 
     // Unauthorized err
@@ -248,7 +273,7 @@ fn test_create_then_deregister_pair() {
 }
 
 #[test]
-fn test_valid_staking() {
+fn pool_of_staking() {
     let mut app = mock_app();
     let owner = Addr::unchecked("owner");
     let mut helper = FactoryHelper::init(&mut app, &owner);
@@ -267,34 +292,12 @@ fn test_valid_staking() {
         "tokenY",
         Some(18),
     );
+    let asset_infos = vec![
+        AssetInfo::Cw20Token(token1.to_string()),
+        AssetInfo::Cw20Token(token2.to_string()),
+    ];
 
-    // Verify the pair now exists, we don't need to check the bool result here as non existence returns an Error
-    let is_valid: bool = app
-        .wrap()
-        .query_wasm_smart(
-            helper.factory.clone(),
-            &QueryMsg::ValidateStakingAddress {
-                address: "contract6".to_string(),
-            },
-        )
-        .unwrap();
-
-    assert!(!is_valid);
-
-    app.init_modules(|router, _, storage| {
-        router.bank.init_balance(
-            storage,
-            &owner,
-            vec![Coin {
-                denom: "coreum".to_string(),
-                amount: Uint128::new(3_000),
-            }],
-        )
-    })
-    .unwrap();
-
-    // Create the pair which we will later delete
-    let res = helper
+    helper
         .create_pair(
             &mut app,
             &owner,
@@ -305,50 +308,59 @@ fn test_valid_staking() {
         )
         .unwrap();
 
-    assert_eq!(res.events[1].attributes[1], attr("action", "create_pair"));
-    assert_eq!(
-        res.events[1].attributes[2],
-        attr("pair", format!("{}-{}", token1.as_str(), token2.as_str()))
-    );
-
-    // Verify the pair now exists, we don't need to check the bool result here as non existence returns an Error
-    let _is_valid: bool = app
+    let pair: PairInfo = app
         .wrap()
         .query_wasm_smart(
             helper.factory.clone(),
-            &QueryMsg::ValidateStakingAddress {
-                address: "contract6".to_string(),
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
             },
         )
         .unwrap();
-    // assert!(is_valid);
-    // Deregsiter the pair, which removes the Pair addr and the staking contract addr from Storage
-    helper
-        .deregister_pool_and_staking(
-            &mut app,
-            &owner,
-            vec![
-                AssetInfo::Cw20Token(token1.to_string()),
-                AssetInfo::Cw20Token(token2.to_string()),
-            ],
+
+    // The pool can be resolved from its staking contract
+    let pool: Option<Addr> = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::PoolOfStaking {
+                staking_addr: pair.staking_addr.to_string(),
+            },
         )
         .unwrap();
+    assert_eq!(pool, Some(pair.contract_addr.clone()));
 
-    let is_valid: bool = app
+    // Other addresses do not resolve to a pool
+    let pool: Option<Addr> = app
         .wrap()
         .query_wasm_smart(
             helper.factory.clone(),
-            &QueryMsg::ValidateStakingAddress {
-                address: "contract6".to_string(),
+            &QueryMsg::PoolOfStaking {
+                staking_addr: pair.contract_addr.to_string(),
             },
         )
         .unwrap();
+    assert_eq!(pool, None);
 
-    assert!(!is_valid);
+    helper
+        .deregister_pool_and_staking(&mut app, &owner, asset_infos)
+        .unwrap();
+
+    // After deregistering, the staking contract no longer resolves
+    let pool: Option<Addr> = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::PoolOfStaking {
+                staking_addr: pair.staking_addr.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pool, None);
 }
 
 #[test]
-fn test_create_pair() {
+fn staking_address() {
     let mut app = mock_app();
     let owner = Addr::unchecked("owner");
     let mut helper = FactoryHelper::init(&mut app, &owner);
@@ -367,36 +379,22 @@ fn test_create_pair() {
         "tokenY",
         Some(18),
     );
-    // TODO: this test requires 6_000 tokens, because we try to initialize pool twice.
-    app.init_modules(|router, _, storage| {
-        router.bank.init_balance(
-            storage,
-            &owner,
-            vec![Coin {
-                denom: "coreum".to_string(),
-                amount: Uint128::new(6_000),
-            }],
-        )
-    })
-    .unwrap();
-    //  factory_helper.rs:164-167 we set one of the tokens as SmartToken, the other
-    //  as Cw20Token, hence it's two different tokens and the below fails to unwrap_err
-    let err = helper
-        .create_pair(
-            &mut app,
-            &owner,
-            PoolType::Xyk {},
-            [token1.as_str(), token1.as_str()],
-            None,
-            None,
+    let asset_infos = vec![
+        AssetInfo::Cw20Token(token1.to_string()),
+        AssetInfo::Cw20Token(token2.to_string()),
+    ];
+
+    // The pool does not exist yet
+    app.wrap()
+        .query_wasm_smart::<Addr>(
+            helper.factory.clone(),
+            &QueryMsg::StakingAddress {
+                asset_infos: asset_infos.clone(),
+            },
         )
         .unwrap_err();
-    assert_eq!(
-        err.root_cause().to_string(),
-        "Doubling assets in asset infos"
-    );
 
-    let res = helper
+    helper
         .create_pair(
             &mut app,
             &owner,
@@ -407,117 +405,1371 @@ fn test_create_pair() {
         )
         .unwrap();
 
-    let err = helper
-        .create_pair(
-            &mut app,
-            &owner,
-            PoolType::Xyk {},
-            [token1.as_str(), token2.as_str()],
-            None,
-            None,
+    let pair: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
+    let staking_addr: Addr = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::StakingAddress { asset_infos },
+        )
+        .unwrap();
+    assert_eq!(staking_addr, pair.staking_addr);
+}
+
+#[test]
+fn max_pools() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens = ["tokenX", "tokenY", "tokenZ"]
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)));
+    let pairs = [
+        [tokens[0].as_str(), tokens[1].as_str()],
+        [tokens[0].as_str(), tokens[2].as_str()],
+        [tokens[1].as_str(), tokens[2].as_str()],
+    ];
+
+    // only the owner can set the limit
+    let set_max_pools = ExecuteMsg::SetMaxPools { max_pools: Some(2) };
+    let err = app
+        .execute_contract(
+            Addr::unchecked("user"),
+            helper.factory.clone(),
+            &set_max_pools,
+            &[],
         )
         .unwrap_err();
-    assert_eq!(err.root_cause().to_string(), "Pair was already created");
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+    app.execute_contract(owner.clone(), helper.factory.clone(), &set_max_pools, &[])
+        .unwrap();
 
-    assert_eq!(res.events[1].attributes[1], attr("action", "create_pair"));
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(helper.factory.clone(), &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.max_pools, Some(2));
+
+    // pools can be created up to the limit
+    for tokens in &pairs[..2] {
+        helper
+            .create_pair(&mut app, &owner, PoolType::Xyk {}, *tokens, None, None)
+            .unwrap();
+    }
+    let err = helper
+        .create_pair(&mut app, &owner, PoolType::Xyk {}, pairs[2], None, None)
+        .unwrap_err();
     assert_eq!(
-        res.events[1].attributes[2],
-        attr("pair", format!("{}-{}", token1.as_str(), token2.as_str()))
+        err.root_cause().to_string(),
+        "The maximum number of pools has been reached"
     );
 
-    let res: PairInfo = app
+    // deregistering a pool frees up a slot
+    let asset_infos = vec![
+        AssetInfo::SmartToken(tokens[0].to_string()),
+        AssetInfo::SmartToken(tokens[1].to_string()),
+    ];
+    let deregistered: PairInfo = app
         .wrap()
         .query_wasm_smart(
             helper.factory.clone(),
             &QueryMsg::Pool {
-                asset_infos: vec![
-                    AssetInfo::Cw20Token(token1.to_string()),
-                    AssetInfo::Cw20Token(token2.to_string()),
-                ],
+                asset_infos: asset_infos.clone(),
             },
         )
         .unwrap();
+    helper
+        .deregister_pool_and_staking(&mut app, &owner, asset_infos)
+        .unwrap();
+    helper
+        .create_pair(&mut app, &owner, PoolType::Xyk {}, pairs[2], None, None)
+        .unwrap();
+    let err = helper
+        .create_pair(&mut app, &owner, PoolType::Xyk {}, pairs[0], None, None)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "The maximum number of pools has been reached"
+    );
 
-    // In multitest, contract names are counted in the order in which contracts are created
-    assert_eq!("contract1", helper.factory.to_string());
-    assert_eq!("contract4", res.contract_addr.to_string());
-    assert_eq!("ucontcontlp-contract4", res.liquidity_token.to_string());
+    // re-registering a pool also counts towards the limit
+    let err = helper
+        .re_register_pool(&mut app, &owner, &deregistered.contract_addr)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "The maximum number of pools has been reached"
+    );
 
-    // Create disabled pair type
+    // removing the limit allows creating pools again
     app.execute_contract(
         owner.clone(),
         helper.factory.clone(),
-        &ExecuteMsg::UpdatePoolConfig {
-            config: PoolConfig {
-                code_id: 0,
-                pool_type: PoolType::Custom("Custom".to_string()),
-                fee_config: FeeConfig {
-                    total_fee_bps: 100,
-                    protocol_fee_bps: 40,
-                },
-                is_disabled: true,
-            },
-        },
+        &ExecuteMsg::SetMaxPools { max_pools: None },
         &[],
     )
     .unwrap();
-
-    let token3 = instantiate_token(
-        &mut app,
-        helper.cw20_token_code_id,
-        &owner,
-        "tokenY",
-        Some(18),
-    );
-
-    let err = helper
-        .create_pair(
-            &mut app,
-            &owner,
-            PoolType::Custom("Custom".to_string()),
-            [token1.as_str(), token3.as_str()],
-            None,
-            None,
-        )
-        .unwrap_err();
-    assert_eq!(err.root_cause().to_string(), "Pool config disabled");
-
-    // Query fee info
-    let fee_info: FeeInfoResponse = app
-        .wrap()
-        .query_wasm_smart(
-            &helper.factory,
-            &QueryMsg::FeeInfo {
-                pool_type: PoolType::Custom("Custom".to_string()),
-            },
-        )
-        .unwrap();
-    assert_eq!(100, fee_info.total_fee_bps);
-    assert_eq!(40, fee_info.protocol_fee_bps);
-
-    // query blacklisted pairs
-    let pair_types: Vec<PoolType> = app
-        .wrap()
-        .query_wasm_smart(&helper.factory, &QueryMsg::BlacklistedPoolTypes {})
+    helper
+        .create_pair(&mut app, &owner, PoolType::Xyk {}, pairs[0], None, None)
         .unwrap();
-    assert_eq!(pair_types, vec![PoolType::Custom("Custom".to_string())]);
 }
 
 #[test]
-#[ignore = "all our pools are created by default with `only_owner_can_create_pools` set to false"]
-fn test_create_pair_permissions() {
+fn routes() {
     let mut app = mock_app();
     let owner = Addr::unchecked("owner");
-    let user = Addr::unchecked("user");
     let mut helper = FactoryHelper::init(&mut app, &owner);
 
-    let token1 = instantiate_token(
-        &mut app,
-        helper.cw20_token_code_id,
-        &owner,
-        "tokenX",
-        Some(18),
-    );
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(6_000),
+            }],
+        )
+    })
+    .unwrap();
+
+    let factory = helper.factory.clone();
+    let token = |denom: &str| AssetInfo::SmartToken(denom.to_string());
+    let pool_addr_of = |app: &CoreumApp, tokens: [&str; 2]| -> Addr {
+        app.wrap()
+            .query_wasm_smart::<PairInfo>(
+                &factory,
+                &QueryMsg::Pool {
+                    asset_infos: vec![token(tokens[0]), token(tokens[1])],
+                },
+            )
+            .unwrap()
+            .contract_addr
+    };
+    let route = |app: &CoreumApp, from: &str, to: &str| -> Vec<Addr> {
+        app.wrap()
+            .query_wasm_smart(
+                &factory,
+                &QueryMsg::Route {
+                    from: token(from),
+                    to: token(to),
+                },
+            )
+            .unwrap()
+    };
+
+    for tokens in [["tokenX", "tokenY"], ["tokenX", "tokenZ"]] {
+        helper
+            .create_pair(&mut app, &owner, PoolType::Xyk {}, tokens, None, None)
+            .unwrap();
+    }
+    let pool_xy = pool_addr_of(&app, ["tokenX", "tokenY"]);
+    let pool_xz = pool_addr_of(&app, ["tokenX", "tokenZ"]);
+
+    // routes work in both directions
+    assert_eq!(route(&app, "tokenX", "tokenY"), vec![pool_xy.clone()]);
+    assert_eq!(route(&app, "tokenY", "tokenX"), vec![pool_xy.clone()]);
+    assert_eq!(route(&app, "tokenZ", "tokenX"), vec![pool_xz.clone()]);
+    // there is no direct route between Y and Z
+    assert_eq!(route(&app, "tokenY", "tokenZ"), Vec::<Addr>::new());
+
+    let routes = |app: &CoreumApp, start_after: Option<[&str; 2]>, limit: Option<u32>| {
+        app.wrap()
+            .query_wasm_smart::<RoutesResponse>(
+                &factory,
+                &QueryMsg::Routes {
+                    start_after: start_after.map(|[from, to]| (from.to_string(), to.to_string())),
+                    limit,
+                },
+            )
+            .unwrap()
+            .routes
+    };
+    let route_info = |from: &str, to: &str, pools: &[&Addr]| RouteInfo {
+        from: from.to_string(),
+        to: to.to_string(),
+        pools: pools.iter().map(|&pool| pool.clone()).collect(),
+    };
+    assert_eq!(
+        routes(&app, None, None),
+        vec![
+            route_info("tokenX", "tokenY", &[&pool_xy]),
+            route_info("tokenX", "tokenZ", &[&pool_xz]),
+            route_info("tokenY", "tokenX", &[&pool_xy]),
+            route_info("tokenZ", "tokenX", &[&pool_xz]),
+        ]
+    );
+    assert_eq!(
+        routes(&app, Some(["tokenX", "tokenY"]), Some(2)),
+        vec![
+            route_info("tokenX", "tokenZ", &[&pool_xz]),
+            route_info("tokenY", "tokenX", &[&pool_xy]),
+        ]
+    );
+
+    // deregistered pools are removed from their routes
+    helper
+        .deregister_pool_and_staking(&mut app, &owner, vec![token("tokenX"), token("tokenY")])
+        .unwrap();
+    assert_eq!(route(&app, "tokenX", "tokenY"), Vec::<Addr>::new());
+    assert_eq!(
+        routes(&app, None, None),
+        vec![
+            route_info("tokenX", "tokenZ", &[&pool_xz]),
+            route_info("tokenZ", "tokenX", &[&pool_xz]),
+        ]
+    );
+}
+
+#[test]
+fn re_register_deregistered_pool() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+    let asset_infos = vec![
+        AssetInfo::Cw20Token(token1.to_string()),
+        AssetInfo::Cw20Token(token2.to_string()),
+    ];
+
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+
+    let pair: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
+
+    // a registered pool cannot be registered again
+    let err = helper
+        .re_register_pool(&mut app, &owner, &pair.contract_addr)
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Pair was already registered");
+
+    helper
+        .deregister_pool_and_staking(&mut app, &owner, asset_infos.clone())
+        .unwrap();
+    let res: StdResult<PairInfo> = app.wrap().query_wasm_smart(
+        helper.factory.clone(),
+        &QueryMsg::Pool {
+            asset_infos: asset_infos.clone(),
+        },
+    );
+    res.unwrap_err();
+
+    // only the owner can re-register
+    let err = helper
+        .re_register_pool(&mut app, &Addr::unchecked("random"), &pair.contract_addr)
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // a pool created by another factory cannot be registered
+    let mut other_helper = FactoryHelper::init(&mut app, &owner);
+    other_helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+    let foreign_pair: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            other_helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
+    let err = helper
+        .re_register_pool(&mut app, &owner, &foreign_pair.contract_addr)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "The pool was not created by this factory"
+    );
+
+    helper
+        .re_register_pool(&mut app, &owner, &pair.contract_addr)
+        .unwrap();
+
+    // all queries work again
+    let res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res, pair);
+
+    let res: PoolsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::PoolsByType {
+                pool_type: PoolType::Xyk {},
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.pools, vec![pair.clone()]);
+
+    let pool: Option<Addr> = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::PoolOfStaking {
+                staking_addr: pair.staking_addr.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pool, Some(pair.contract_addr));
+}
+
+#[test]
+fn deprecated_pools_are_hidden() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens = ["tokenX", "tokenY", "tokenZ"]
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)));
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![Coin::new(6_000, "coreum")])
+    })
+    .unwrap();
+
+    for token in [&tokens[1], &tokens[2]] {
+        helper
+            .create_pair(
+                &mut app,
+                &owner,
+                PoolType::Xyk {},
+                [tokens[0].as_str(), token.as_str()],
+                None,
+                None,
+            )
+            .unwrap();
+    }
+    let deprecated_assets = vec![
+        AssetInfo::SmartToken(tokens[0].to_string()),
+        AssetInfo::SmartToken(tokens[1].to_string()),
+    ];
+    let deprecated_pool: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: deprecated_assets.clone(),
+            },
+        )
+        .unwrap();
+
+    let factory = helper.factory.clone();
+    let query_pools = |app: &CoreumApp, include_deprecated: bool| {
+        app.wrap()
+            .query_wasm_smart::<PoolsResponse>(
+                factory.clone(),
+                &QueryMsg::Pools {
+                    start_after: None,
+                    limit: None,
+                    include_deprecated,
+                },
+            )
+            .unwrap()
+            .pools
+            .into_iter()
+            .map(|pool| pool.contract_addr)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(query_pools(&app, false).len(), 2);
+
+    let set_deprecated = |deprecated| ExecuteMsg::SetPoolDeprecated {
+        asset_infos: deprecated_assets.clone(),
+        deprecated,
+    };
+
+    // only the owner can deprecate a pool
+    let err = app
+        .execute_contract(
+            Addr::unchecked("not_owner"),
+            helper.factory.clone(),
+            &set_deprecated(true),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &set_deprecated(true),
+        &[],
+    )
+    .unwrap();
+
+    // hidden by default, but still listed on request
+    let pools = query_pools(&app, false);
+    assert_eq!(pools.len(), 1);
+    assert!(!pools.contains(&deprecated_pool.contract_addr));
+    let pools = query_pools(&app, true);
+    assert_eq!(pools.len(), 2);
+    assert!(pools.contains(&deprecated_pool.contract_addr));
+
+    // the pool can still be queried directly
+    let pool: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: deprecated_assets.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(pool, deprecated_pool);
+
+    // undeprecating shows it again
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &set_deprecated(false),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(query_pools(&app, false).len(), 2);
+}
+
+#[test]
+fn creator_stats() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens = ["tokenX", "tokenY", "tokenZ"]
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)));
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![Coin::new(9_000, "coreum")])?;
+        router
+            .bank
+            .init_balance(storage, &user, vec![Coin::new(3_000, "coreum")])
+    })
+    .unwrap();
+
+    let factory = helper.factory.clone();
+    let query_pools_created = |app: &CoreumApp, creator: &Addr| {
+        let stats: CreatorStatsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                factory.clone(),
+                &QueryMsg::CreatorStats {
+                    creator: creator.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(&stats.creator, creator);
+        stats.pools_created
+    };
+
+    // nobody created a pool yet
+    assert_eq!(query_pools_created(&app, &owner), 0);
+    assert_eq!(query_pools_created(&app, &user), 0);
+
+    for pair in [[&tokens[0], &tokens[1]], [&tokens[0], &tokens[2]]] {
+        helper
+            .create_pair(
+                &mut app,
+                &owner,
+                PoolType::Xyk {},
+                [pair[0].as_str(), pair[1].as_str()],
+                None,
+                None,
+            )
+            .unwrap();
+    }
+    helper
+        .create_pair(
+            &mut app,
+            &user,
+            PoolType::Xyk {},
+            [tokens[1].as_str(), tokens[2].as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+
+    // creating an existing pool fails and is not counted
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [tokens[0].as_str(), tokens[1].as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+
+    assert_eq!(query_pools_created(&app, &owner), 2);
+    assert_eq!(query_pools_created(&app, &user), 1);
+}
+
+#[test]
+fn pool_creator() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens = ["tokenX", "tokenY", "tokenZ"]
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)));
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![Coin::new(3_000, "coreum")])?;
+        router
+            .bank
+            .init_balance(storage, &user, vec![Coin::new(3_000, "coreum")])
+    })
+    .unwrap();
+
+    let factory = helper.factory.clone();
+    let query_creator = |app: &CoreumApp, pool_address: &Addr| -> Option<Addr> {
+        app.wrap()
+            .query_wasm_smart(
+                factory.clone(),
+                &QueryMsg::PoolCreator {
+                    pool_address: pool_address.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    let query_pool = |app: &CoreumApp, tokens: [&Addr; 2]| -> Addr {
+        let res: PairInfo = app
+            .wrap()
+            .query_wasm_smart(
+                factory.clone(),
+                &QueryMsg::Pool {
+                    asset_infos: tokens
+                        .map(|token| AssetInfo::SmartToken(token.to_string()))
+                        .to_vec(),
+                },
+            )
+            .unwrap();
+        res.contract_addr
+    };
+
+    // the owner creates a verified pool
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [tokens[0].as_str(), tokens[1].as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+    // anyone else can create a pool by paying the creation fee
+    helper
+        .create_pair(
+            &mut app,
+            &user,
+            PoolType::Xyk {},
+            [tokens[1].as_str(), tokens[2].as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+
+    let owner_pool = query_pool(&app, [&tokens[0], &tokens[1]]);
+    let user_pool = query_pool(&app, [&tokens[1], &tokens[2]]);
+    assert_eq!(query_creator(&app, &owner_pool), Some(owner));
+    assert_eq!(query_creator(&app, &user_pool), Some(user));
+
+    // an address that is not a pool has no creator
+    assert_eq!(query_creator(&app, &tokens[0]), None);
+}
+
+#[test]
+fn total_value_locked() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![
+                Coin::new(9_000, "coreum"),
+                Coin::new(1_000_000, "uatom"),
+                Coin::new(1_000_000, "ujuno"),
+                Coin::new(3_000_000, "uluna"),
+                Coin::new(1_000_000, "uosmo"),
+            ],
+        )
+    })
+    .unwrap();
+
+    // luna is worth 0.5 juno, atom is worth 2 luna and only has a route to juno through luna,
+    // osmo has no route to juno at all
+    let pools = [
+        (("ujuno", 1_000_000u128), ("uluna", 2_000_000u128)),
+        (("uluna", 1_000_000u128), ("uatom", 500_000u128)),
+        (("uatom", 500_000u128), ("uosmo", 1_000_000u128)),
+    ];
+    for ((first, first_amount), (second, second_amount)) in pools {
+        helper
+            .create_pair(
+                &mut app,
+                &owner,
+                PoolType::Xyk {},
+                [first, second],
+                None,
+                None,
+            )
+            .unwrap();
+        let pair: PairInfo = app
+            .wrap()
+            .query_wasm_smart(
+                &helper.factory,
+                &QueryMsg::Pool {
+                    asset_infos: vec![
+                        AssetInfo::SmartToken(first.to_owned()),
+                        AssetInfo::SmartToken(second.to_owned()),
+                    ],
+                },
+            )
+            .unwrap();
+        let mut funds = vec![
+            Coin::new(first_amount, first),
+            Coin::new(second_amount, second),
+        ];
+        funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+        app.execute_contract(
+            owner.clone(),
+            pair.contract_addr,
+            &PairExecuteMsg::ProvideLiquidity {
+                assets: vec![
+                    Asset {
+                        info: AssetInfo::SmartToken(first.to_owned()),
+                        amount: first_amount.into(),
+                    },
+                    Asset {
+                        info: AssetInfo::SmartToken(second.to_owned()),
+                        amount: second_amount.into(),
+                    },
+                ],
+                slippage_tolerance: None,
+                receiver: None,
+            },
+            &funds,
+        )
+        .unwrap();
+    }
+
+    let factory = helper.factory.clone();
+    let query_tvl = |app: &CoreumApp,
+                     start_after: Option<Vec<AssetInfo>>,
+                     limit: Option<u32>|
+     -> TotalValueLockedResponse {
+        app.wrap()
+            .query_wasm_smart(
+                factory.clone(),
+                &QueryMsg::TotalValueLocked {
+                    reference: AssetInfo::SmartToken("ujuno".to_owned()),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+    };
+
+    let tvl = query_tvl(&app, None, None);
+    assert_eq!(tvl.pools.len(), 3);
+    let value_of = |denoms: [&str; 2]| {
+        tvl.pools
+            .iter()
+            .find(|pool| {
+                denoms.iter().all(|denom| {
+                    pool.asset_infos
+                        .iter()
+                        .any(|info| info.to_string() == *denom)
+                })
+            })
+            .unwrap()
+            .value
+    };
+    // 1_000_000 juno + 2_000_000 luna * 0.5
+    assert_eq!(value_of(["ujuno", "uluna"]), Some(Uint128::new(2_000_000)));
+    // 1_000_000 luna * 0.5 + 500_000 atom * 2 * 0.5
+    assert_eq!(value_of(["uluna", "uatom"]), Some(Uint128::new(1_000_000)));
+    assert_eq!(value_of(["uatom", "uosmo"]), None);
+    assert_eq!(tvl.total_value, Uint128::new(3_000_000));
+
+    // the pages add up to the same total
+    let first_page = query_tvl(&app, None, Some(2));
+    assert_eq!(first_page.pools.len(), 2);
+    let start_after = first_page
+        .pools
+        .last()
+        .unwrap()
+        .asset_infos
+        .iter()
+        .cloned()
+        .map(AssetInfo::from)
+        .collect();
+    let second_page = query_tvl(&app, Some(start_after), Some(2));
+    assert_eq!(second_page.pools.len(), 1);
+    assert_eq!(
+        first_page.total_value + second_page.total_value,
+        tvl.total_value
+    );
+}
+
+#[test]
+fn test_valid_staking() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+
+    // Verify the pair now exists, we don't need to check the bool result here as non existence returns an Error
+    let is_valid: bool = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::ValidateStakingAddress {
+                address: "contract6".to_string(),
+            },
+        )
+        .unwrap();
+
+    assert!(!is_valid);
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(3_000),
+            }],
+        )
+    })
+    .unwrap();
+
+    // Create the pair which we will later delete
+    let res = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(res.events[1].attributes[1], attr("action", "create_pair"));
+    assert_eq!(
+        res.events[1].attributes[2],
+        attr("pair", format!("{}-{}", token1.as_str(), token2.as_str()))
+    );
+
+    // Verify the pair now exists, we don't need to check the bool result here as non existence returns an Error
+    let _is_valid: bool = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::ValidateStakingAddress {
+                address: "contract6".to_string(),
+            },
+        )
+        .unwrap();
+    // assert!(is_valid);
+    // Deregsiter the pair, which removes the Pair addr and the staking contract addr from Storage
+    helper
+        .deregister_pool_and_staking(
+            &mut app,
+            &owner,
+            vec![
+                AssetInfo::Cw20Token(token1.to_string()),
+                AssetInfo::Cw20Token(token2.to_string()),
+            ],
+        )
+        .unwrap();
+
+    let is_valid: bool = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::ValidateStakingAddress {
+                address: "contract6".to_string(),
+            },
+        )
+        .unwrap();
+
+    assert!(!is_valid);
+}
+
+#[test]
+fn test_create_pair_with_existing_staking() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens = ["tokenX", "tokenY", "tokenZ"]
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)));
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![Coin::new(9_000, "coreum")])?;
+        router
+            .bank
+            .init_balance(storage, &user, vec![Coin::new(3_000, "coreum")])
+    })
+    .unwrap();
+
+    // the first pool gets a fresh staking contract
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [tokens[0].as_str(), tokens[1].as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+    let first: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: vec![
+                    AssetInfo::SmartToken(tokens[0].to_string()),
+                    AssetInfo::SmartToken(tokens[1].to_string()),
+                ],
+            },
+        )
+        .unwrap();
+
+    // only the owner can bind a pool to an existing staking contract
+    let err = helper
+        .create_pair_with_existing_staking(
+            &mut app,
+            &user,
+            [tokens[0].as_str(), tokens[2].as_str()],
+            first.staking_addr.as_str(),
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // and it has to be a contract
+    helper
+        .create_pair_with_existing_staking(
+            &mut app,
+            &owner,
+            [tokens[0].as_str(), tokens[2].as_str()],
+            "not_a_contract",
+        )
+        .unwrap_err();
+
+    // a staking contract cannot be shared with another pool
+    let err = helper
+        .create_pair_with_existing_staking(
+            &mut app,
+            &owner,
+            [tokens[0].as_str(), tokens[2].as_str()],
+            first.staking_addr.as_str(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Staking contract is already used by another pool"
+    );
+
+    let staking_code_id = app
+        .wrap()
+        .query_wasm_smart::<ConfigResponse>(&helper.factory, &QueryMsg::Config {})
+        .unwrap()
+        .default_stake_config
+        .staking_code_id;
+    let instantiate_staking = |app: &mut CoreumApp, lp_share_denom: String| {
+        app.instantiate_contract(
+            staking_code_id,
+            owner.clone(),
+            &dex::stake::InstantiateMsg {
+                lp_share_denom,
+                tokens_per_power: Uint128::new(1000),
+                min_bond: Uint128::new(1000),
+                unbonding_periods: vec![1],
+                max_distributions: 6,
+                admin: Some(owner.to_string()),
+                unbonder: None,
+                backup_unbonders: vec![],
+                unbond_grace_period: 0,
+                min_distribution_interval: 0,
+                auto_claim_on_delegate: false,
+            },
+            &[],
+            "Dex-Stake",
+            None,
+        )
+        .unwrap()
+    };
+
+    // it has to stake the LP shares of the new pool
+    let other_staking = instantiate_staking(&mut app, "uother".to_string());
+    let err = helper
+        .create_pair_with_existing_staking(
+            &mut app,
+            &owner,
+            [tokens[0].as_str(), tokens[2].as_str()],
+            other_staking.as_str(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Staking contract does not stake the LP share denom of the pool"
+    );
+
+    // the pool is instantiated right after its staking contract
+    let next_contract = |addr: &Addr| {
+        let id: u64 = addr
+            .as_str()
+            .trim_start_matches("contract")
+            .parse()
+            .unwrap();
+        format!("contract{}", id + 1)
+    };
+    let staking_addr = next_contract(&other_staking);
+    let lp_share_denom = format!(
+        "u{}{}lp-{}",
+        &tokens[0].as_str()[..4],
+        &tokens[2].as_str()[..4],
+        next_contract(&Addr::unchecked(&staking_addr))
+    );
+    let staking = instantiate_staking(&mut app, lp_share_denom.clone());
+    assert_eq!(staking, staking_addr);
+
+    helper
+        .create_pair_with_existing_staking(
+            &mut app,
+            &owner,
+            [tokens[0].as_str(), tokens[2].as_str()],
+            staking.as_str(),
+        )
+        .unwrap();
+    let second: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: vec![
+                    AssetInfo::SmartToken(tokens[0].to_string()),
+                    AssetInfo::SmartToken(tokens[2].to_string()),
+                ],
+            },
+        )
+        .unwrap();
+    assert_eq!(second.liquidity_token, lp_share_denom);
+    assert_eq!(second.staking_addr, staking);
+
+    let is_valid: bool = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::ValidateStakingAddress {
+                address: second.staking_addr.to_string(),
+            },
+        )
+        .unwrap();
+    assert!(is_valid);
+}
+
+#[test]
+fn test_update_pool_staking_addr() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens = ["tokenX", "tokenY"]
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)));
+    let asset_infos = vec![
+        AssetInfo::SmartToken(tokens[0].to_string()),
+        AssetInfo::SmartToken(tokens[1].to_string()),
+    ];
+
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![Coin::new(3_000, "coreum")])
+    })
+    .unwrap();
+
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [tokens[0].as_str(), tokens[1].as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
+    let old_staking = pair_info.staking_addr.clone();
+
+    let staking_code_id = app
+        .wrap()
+        .query_wasm_smart::<ConfigResponse>(&helper.factory, &QueryMsg::Config {})
+        .unwrap()
+        .default_stake_config
+        .staking_code_id;
+    let new_staking = app
+        .instantiate_contract(
+            staking_code_id,
+            owner.clone(),
+            &dex::stake::InstantiateMsg {
+                lp_share_denom: pair_info.liquidity_token.clone(),
+                tokens_per_power: Uint128::new(1000),
+                min_bond: Uint128::new(1000),
+                unbonding_periods: vec![1],
+                max_distributions: 6,
+                admin: Some(owner.to_string()),
+                unbonder: None,
+                backup_unbonders: vec![],
+                unbond_grace_period: 0,
+                min_distribution_interval: 0,
+                auto_claim_on_delegate: false,
+            },
+            &[],
+            "Dex-Stake",
+            None,
+        )
+        .unwrap();
+    let update = |new_staking_addr: &Addr| ExecuteMsg::UpdatePoolStakingAddr {
+        asset_infos: asset_infos.clone(),
+        new_staking_addr: new_staking_addr.to_string(),
+    };
+
+    // only the owner can replace the staking contract
+    let err = app
+        .execute_contract(
+            Addr::unchecked("user"),
+            helper.factory.clone(),
+            &update(&new_staking),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // the current staking contract is already in use
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &update(&old_staking),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Staking contract is already used by another pool"
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &update(&new_staking),
+        &[],
+    )
+    .unwrap();
+
+    // the pool uses the new staking contract
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Pool { asset_infos })
+        .unwrap();
+    assert_eq!(pair_info.staking_addr, new_staking);
+
+    // and the factory only knows the new one
+    let is_valid = |app: &CoreumApp, address: &Addr| -> bool {
+        app.wrap()
+            .query_wasm_smart(
+                &helper.factory,
+                &QueryMsg::ValidateStakingAddress {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    assert!(is_valid(&app, &new_staking));
+    assert!(!is_valid(&app, &old_staking));
+    let pool_of_staking = |app: &CoreumApp, staking_addr: &Addr| -> Option<Addr> {
+        app.wrap()
+            .query_wasm_smart(
+                &helper.factory,
+                &QueryMsg::PoolOfStaking {
+                    staking_addr: staking_addr.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    assert_eq!(
+        pool_of_staking(&app, &new_staking),
+        Some(pair_info.contract_addr)
+    );
+    assert_eq!(pool_of_staking(&app, &old_staking), None);
+}
+
+#[test]
+fn test_create_pair() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+    // TODO: this test requires 6_000 tokens, because we try to initialize pool twice.
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(6_000),
+            }],
+        )
+    })
+    .unwrap();
+    //  factory_helper.rs:164-167 we set one of the tokens as SmartToken, the other
+    //  as Cw20Token, hence it's two different tokens and the below fails to unwrap_err
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [token1.as_str(), token1.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Doubling assets in asset infos"
+    );
+
+    let res = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Pair was already created");
+
+    assert_eq!(res.events[1].attributes[1], attr("action", "create_pair"));
+    assert_eq!(
+        res.events[1].attributes[2],
+        attr("pair", format!("{}-{}", token1.as_str(), token2.as_str()))
+    );
+
+    let res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            helper.factory.clone(),
+            &QueryMsg::Pool {
+                asset_infos: vec![
+                    AssetInfo::Cw20Token(token1.to_string()),
+                    AssetInfo::Cw20Token(token2.to_string()),
+                ],
+            },
+        )
+        .unwrap();
+
+    // In multitest, contract names are counted in the order in which contracts are created
+    assert_eq!("contract1", helper.factory.to_string());
+    assert_eq!("contract4", res.contract_addr.to_string());
+    assert_eq!("ucontcontlp-contract4", res.liquidity_token.to_string());
+
+    // Create disabled pair type
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::UpdatePoolConfig {
+            config: PoolConfig {
+                code_id: 0,
+                pool_type: PoolType::Custom("Custom".to_string()),
+                fee_config: FeeConfig {
+                    total_fee_bps: 100,
+                    protocol_fee_bps: 40,
+                },
+                is_disabled: true,
+                stake_config_override: None,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let token3 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Custom("Custom".to_string()),
+            [token1.as_str(), token3.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Pool config disabled");
+
+    // Query fee info
+    let fee_info: FeeInfoResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::FeeInfo {
+                pool_type: PoolType::Custom("Custom".to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(100, fee_info.total_fee_bps);
+    assert_eq!(40, fee_info.protocol_fee_bps);
+
+    // query blacklisted pairs
+    let pair_types: Vec<PoolType> = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::BlacklistedPoolTypes {})
+        .unwrap();
+    assert_eq!(pair_types, vec![PoolType::Custom("Custom".to_string())]);
+}
+
+#[test]
+#[ignore = "all our pools are created by default with `only_owner_can_create_pools` set to false"]
+fn test_create_pair_permissions() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
     let token2 = instantiate_token(
         &mut app,
         helper.cw20_token_code_id,
@@ -550,33 +1802,126 @@ fn test_create_pair_permissions() {
         .unwrap_err();
     assert_eq!(err.root_cause().to_string(), "Unauthorized");
 
-    // allow anyone to create pair
+    // allow anyone to create pair
+    helper
+        .update_config(&mut app, &owner, None, Some(false), None)
+        .unwrap();
+
+    // addendum: it does work but a required deposit has been added; check migration.rs test
+    let err = helper
+        .create_pair(
+            &mut app,
+            &user,
+            PoolType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        "Factory is in permissionless mode: deposit must be sent to create new pair",
+        err.source().unwrap().to_string()
+    );
+}
+
+#[test]
+fn test_update_pair_fee() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(3_000),
+            }],
+        )
+    })
+    .unwrap();
+
     helper
-        .update_config(&mut app, &owner, None, Some(false), None)
-        .unwrap();
-
-    // addendum: it does work but a required deposit has been added; check migration.rs test
-    let err = helper
         .create_pair(
             &mut app,
-            &user,
+            &owner,
             PoolType::Xyk {},
             [token1.as_str(), token2.as_str()],
             None,
             None,
         )
-        .unwrap_err();
+        .unwrap();
 
+    let asset_infos = vec![
+        AssetInfo::SmartToken(token1.to_string()),
+        AssetInfo::SmartToken(token2.to_string()),
+    ];
+    // query current fee
+    let pair_res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pool {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap();
     assert_eq!(
-        "Factory is in permissionless mode: deposit must be sent to create new pair",
-        err.source().unwrap().to_string()
+        pair_res.fee_config,
+        FeeConfig {
+            total_fee_bps: 100,
+            protocol_fee_bps: 10
+        }
+    );
+
+    // change fees
+    helper
+        .update_pair_fees(
+            &mut app,
+            &owner,
+            asset_infos.clone(),
+            FeeConfig {
+                total_fee_bps: 1000,
+                protocol_fee_bps: 10,
+            },
+        )
+        .unwrap();
+    // query updated fee
+    let pair_res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Pool { asset_infos })
+        .unwrap();
+    assert_eq!(
+        pair_res.fee_config,
+        FeeConfig {
+            total_fee_bps: 1000,
+            protocol_fee_bps: 10
+        }
     );
 }
 
 #[test]
-fn test_update_pair_fee() {
+fn test_update_staking_manager() {
     let mut app = mock_app();
     let owner = Addr::unchecked("owner");
+    let committee = Addr::unchecked("committee");
     let mut helper = FactoryHelper::init(&mut app, &owner);
 
     let token1 = instantiate_token(
@@ -621,48 +1966,386 @@ fn test_update_pair_fee() {
         AssetInfo::SmartToken(token1.to_string()),
         AssetInfo::SmartToken(token2.to_string()),
     ];
-    // query current fee
-    let pair_res: PairInfo = app
+    let reward_asset = AssetInfo::SmartToken("reward".to_string());
+
+    // create a distribution flow managed by the factory
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::CreateDistributionFlow {
+            asset_infos: asset_infos.clone(),
+            asset: reward_asset.clone(),
+            rewards: vec![
+                (1, Decimal::one()),
+                (2, Decimal::one()),
+                (3, Decimal::one()),
+            ],
+        },
+        &[],
+    )
+    .unwrap();
+
+    let staking_addr = app
         .wrap()
-        .query_wasm_smart(
+        .query_wasm_smart::<PairInfo>(
             &helper.factory,
             &QueryMsg::Pool {
                 asset_infos: asset_infos.clone(),
             },
         )
+        .unwrap()
+        .staking_addr;
+    let query_manager = |app: &CoreumApp| {
+        let res: DistributionDataResponse = app
+            .wrap()
+            .query_wasm_smart(&staking_addr, &StakeQueryMsg::DistributionData {})
+            .unwrap();
+        res.distributions[0].1.manager.clone()
+    };
+    assert_eq!(query_manager(&app), helper.factory);
+
+    // only the owner can update the manager
+    let err = helper
+        .update_staking_manager(
+            &mut app,
+            &committee,
+            asset_infos.clone(),
+            committee.as_str(),
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    helper
+        .update_staking_manager(&mut app, &owner, asset_infos.clone(), committee.as_str())
+        .unwrap();
+    assert_eq!(query_manager(&app), committee);
+
+    // the factory is not the manager anymore, so it cannot change the distribution flows
+    helper
+        .update_staking_manager(&mut app, &owner, asset_infos, owner.as_str())
+        .unwrap_err();
+    assert_eq!(query_manager(&app), committee);
+
+    // neither can anyone else
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            staking_addr.clone(),
+            &StakeExecuteMsg::UpdateDistributionManager {
+                asset: reward_asset.clone(),
+                manager: owner.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // but the new manager can
+    app.execute_contract(
+        committee,
+        staking_addr.clone(),
+        &StakeExecuteMsg::UpdateDistributionManager {
+            asset: reward_asset,
+            manager: owner.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(query_manager(&app), owner);
+}
+
+#[test]
+fn test_migrate_pools_of_type() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token_instance0 =
+        instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenX", None);
+    let token_instance1 =
+        instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenY", None);
+    let token_instance2 =
+        instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenZ", None);
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(6_000),
+            }],
+        )
+    })
+    .unwrap();
+
+    for token in [&token_instance1, &token_instance2] {
+        helper
+            .create_pair_with_addr(
+                &mut app,
+                &owner,
+                PoolType::Xyk {},
+                [token_instance0.as_str(), token.as_str()],
+                None,
+            )
+            .unwrap();
+    }
+    let pools = app
+        .wrap()
+        .query_wasm_smart::<PoolsResponse>(
+            &helper.factory,
+            &QueryMsg::Pools {
+                start_after: None,
+                limit: None,
+                include_deprecated: false,
+            },
+        )
+        .unwrap()
+        .pools;
+    let old_code_id = app
+        .wrap()
+        .query_wasm_contract_info(&pools[0].contract_addr)
+        .unwrap()
+        .code_id;
+
+    let new_pool_code_id = app.store_code(Box::new(
+        ContractWrapper::new(
+            dex_pool::contract::execute,
+            dex_pool::contract::instantiate,
+            dex_pool::contract::query,
+        )
+        .with_reply(dex_pool::contract::reply)
+        .with_migrate(dex_pool::contract::migrate),
+    ));
+    let code_id_of = |app: &CoreumApp, pool: &PairInfo| {
+        app.wrap()
+            .query_wasm_contract_info(&pool.contract_addr)
+            .unwrap()
+            .code_id
+    };
+
+    // only the owner can migrate pools
+    let err = helper
+        .migrate_pools_of_type(
+            &mut app,
+            &Addr::unchecked("not_owner"),
+            new_pool_code_id,
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // migrate the first page
+    helper
+        .migrate_pools_of_type(&mut app, &owner, new_pool_code_id, None, Some(1))
+        .unwrap();
+    assert_eq!(code_id_of(&app, &pools[0]), new_pool_code_id);
+    assert_eq!(code_id_of(&app, &pools[1]), old_code_id);
+
+    // new pools of the type use the new code id right away
+    let config_res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config_res.pool_configs[0].code_id, new_pool_code_id);
+
+    // and the next page
+    helper
+        .migrate_pools_of_type(
+            &mut app,
+            &owner,
+            new_pool_code_id,
+            Some(
+                pools[0]
+                    .asset_infos
+                    .iter()
+                    .cloned()
+                    .map(Into::into)
+                    .collect(),
+            ),
+            Some(1),
+        )
+        .unwrap();
+    assert_eq!(code_id_of(&app, &pools[0]), new_pool_code_id);
+    assert_eq!(code_id_of(&app, &pools[1]), new_pool_code_id);
+
+    // pools created before the factory became their admin are skipped
+    app.execute(
+        helper.factory.clone(),
+        WasmMsg::UpdateAdmin {
+            contract_addr: pools[1].contract_addr.to_string(),
+            admin: owner.to_string(),
+        }
+        .into(),
+    )
+    .unwrap();
+    let newest_pool_code_id = app.store_code(Box::new(
+        ContractWrapper::new(
+            dex_pool::contract::execute,
+            dex_pool::contract::instantiate,
+            dex_pool::contract::query,
+        )
+        .with_reply(dex_pool::contract::reply)
+        .with_migrate(dex_pool::contract::migrate),
+    ));
+    let res = helper
+        .migrate_pools_of_type(&mut app, &owner, newest_pool_code_id, None, None)
+        .unwrap();
+    assert!(res.has_event(
+        &Event::new("wasm")
+            .add_attribute("migrated_pools", "1")
+            .add_attribute("skipped_pools", "1")
+    ));
+    assert_eq!(code_id_of(&app, &pools[0]), newest_pool_code_id);
+    assert_eq!(code_id_of(&app, &pools[1]), new_pool_code_id);
+}
+
+#[test]
+fn test_update_fees_for_type() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token_instance0 =
+        instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenX", None);
+    let token_instance1 =
+        instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenY", None);
+    let token_instance2 =
+        instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenZ", None);
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(6_000),
+            }],
+        )
+    })
+    .unwrap();
+
+    for token in [&token_instance1, &token_instance2] {
+        helper
+            .create_pair(
+                &mut app,
+                &owner,
+                PoolType::Xyk {},
+                [token_instance0.as_str(), token.as_str()],
+                None,
+                None,
+            )
+            .unwrap();
+    }
+    let pools: Vec<Addr> = app
+        .wrap()
+        .query_wasm_smart::<PoolsResponse>(
+            &helper.factory,
+            &QueryMsg::Pools {
+                start_after: None,
+                limit: None,
+                include_deprecated: false,
+            },
+        )
+        .unwrap()
+        .pools
+        .into_iter()
+        .map(|pool| pool.contract_addr)
+        .collect();
+    assert_eq!(pools.len(), 2);
+    let fees_of = |app: &CoreumApp, pool: &Addr| {
+        app.wrap()
+            .query_wasm_smart::<PairInfo>(pool, &dex::pool::QueryMsg::Pair {})
+            .unwrap()
+            .fee_config
+    };
+    let old_fees = fees_of(&app, &pools[0]);
+    let new_fees = FeeConfig {
+        total_fee_bps: 50,
+        protocol_fee_bps: 2000,
+    };
+
+    // only the owner can update fees
+    let err = helper
+        .update_fees_for_type(
+            &mut app,
+            &Addr::unchecked("not_owner"),
+            PoolType::Xyk {},
+            new_fees.clone(),
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // the fee config is validated
+    let err = helper
+        .update_fees_for_type(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            FeeConfig {
+                total_fee_bps: 10_001,
+                protocol_fee_bps: 0,
+            },
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Fee bps in pair config must be smaller than or equal to 10,000"
+    );
+
+    // pools of other types are not touched
+    helper
+        .update_fees_for_type(
+            &mut app,
+            &owner,
+            PoolType::Stable {},
+            new_fees.clone(),
+            None,
+            None,
+        )
+        .unwrap();
+    for pool in &pools {
+        assert_eq!(fees_of(&app, pool), old_fees);
+    }
+
+    // a single page can be updated
+    helper
+        .update_fees_for_type(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            new_fees.clone(),
+            None,
+            Some(1),
+        )
         .unwrap();
-    assert_eq!(
-        pair_res.fee_config,
-        FeeConfig {
-            total_fee_bps: 100,
-            protocol_fee_bps: 10
-        }
-    );
+    let updated = pools
+        .iter()
+        .filter(|pool| fees_of(&app, pool) == new_fees)
+        .count();
+    assert_eq!(updated, 1);
 
-    // change fees
+    // or all pools at once
     helper
-        .update_pair_fees(
+        .update_fees_for_type(
             &mut app,
             &owner,
-            asset_infos.clone(),
-            FeeConfig {
-                total_fee_bps: 1000,
-                protocol_fee_bps: 10,
-            },
+            PoolType::Xyk {},
+            new_fees.clone(),
+            None,
+            None,
         )
         .unwrap();
-    // query updated fee
-    let pair_res: PairInfo = app
-        .wrap()
-        .query_wasm_smart(&helper.factory, &QueryMsg::Pool { asset_infos })
-        .unwrap();
-    assert_eq!(
-        pair_res.fee_config,
-        FeeConfig {
-            total_fee_bps: 1000,
-            protocol_fee_bps: 10
-        }
-    );
+    for pool in &pools {
+        assert_eq!(fees_of(&app, pool), new_fees);
+    }
 }
 
 #[test]
@@ -937,3 +2620,401 @@ fn check_update_owner() {
 
     assert_eq!(res.owner, new_owner)
 }
+
+#[test]
+fn test_pool_code_id() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &owner,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(6_000),
+            }],
+        )
+    })
+    .unwrap();
+
+    let pool_code_id_of = |app: &CoreumApp, pool_addr: &Addr| -> Option<u64> {
+        app.wrap()
+            .query_wasm_smart(
+                &helper.factory,
+                &QueryMsg::PoolCodeId {
+                    pool_addr: pool_addr.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    let pool_addr_of = |app: &CoreumApp, tokens: [&str; 2]| -> Addr {
+        app.wrap()
+            .query_wasm_smart::<PairInfo>(
+                &helper.factory,
+                &QueryMsg::Pool {
+                    asset_infos: tokens
+                        .iter()
+                        .map(|token| AssetInfo::SmartToken(token.to_string()))
+                        .collect(),
+                },
+            )
+            .unwrap()
+            .contract_addr
+    };
+
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            ["tokenX", "tokenY"],
+            None,
+            None,
+        )
+        .unwrap();
+    let old_pool = pool_addr_of(&app, ["tokenX", "tokenY"]);
+    let old_code_id = app
+        .wrap()
+        .query_wasm_contract_info(&old_pool)
+        .unwrap()
+        .code_id;
+    assert_eq!(pool_code_id_of(&app, &old_pool), Some(old_code_id));
+
+    // switch the xyk pool type to a new code id
+    let new_pool_code_id = app.store_code(Box::new(
+        ContractWrapper::new(
+            dex_pool::contract::execute,
+            dex_pool::contract::instantiate,
+            dex_pool::contract::query,
+        )
+        .with_reply(dex_pool::contract::reply),
+    ));
+    let config_res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::UpdatePoolConfig {
+            config: PoolConfig {
+                code_id: new_pool_code_id,
+                ..config_res.pool_configs[0].clone()
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PoolType::Xyk {},
+            ["tokenX", "tokenZ"],
+            None,
+            None,
+        )
+        .unwrap();
+    let new_pool = pool_addr_of(&app, ["tokenX", "tokenZ"]);
+
+    // each pool reports the code id it was instantiated with
+    assert_eq!(pool_code_id_of(&app, &old_pool), Some(old_code_id));
+    assert_eq!(pool_code_id_of(&app, &new_pool), Some(new_pool_code_id));
+    assert_ne!(old_code_id, new_pool_code_id);
+
+    // unknown pools have no code id
+    assert_eq!(pool_code_id_of(&app, &Addr::unchecked("not_a_pool")), None);
+}
+
+fn failing_pool_instantiate(
+    _deps: DepsMut<CoreumQueries>,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: PoolInstantiateMsg,
+) -> StdResult<Response<CoreumMsg>> {
+    Err(StdError::generic_err("pool instantiation failed"))
+}
+
+/// Switches the pool creation fee of the factory to 3_000 of a new cw20 token
+/// and mints 6_000 of it to `user`.
+fn use_cw20_creation_fee(
+    app: &mut CoreumApp,
+    helper: &FactoryHelper,
+    factory_code_id: u64,
+    user: &Addr,
+) -> Addr {
+    let fee_token = instantiate_token(
+        app,
+        helper.cw20_token_code_id,
+        &helper.owner,
+        "feetoken",
+        None,
+    );
+    app.migrate_contract(
+        helper.owner.clone(),
+        helper.factory.clone(),
+        &MigrateMsg::AddPermissionlessPoolDeposit(Asset {
+            info: AssetInfo::Cw20Token(fee_token.to_string()),
+            amount: Uint128::new(3_000),
+        }),
+        factory_code_id,
+    )
+    .unwrap();
+    app.execute_contract(
+        helper.owner.clone(),
+        fee_token.clone(),
+        &Cw20ExecuteMsg::Mint {
+            recipient: user.to_string(),
+            amount: Uint128::new(6_000),
+        },
+        &[],
+    )
+    .unwrap();
+    fee_token
+}
+
+fn cw20_balance(app: &CoreumApp, token: &Addr, address: &Addr) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+#[test]
+fn create_pool_paying_cw20_creation_fee() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let factory_code_id = store_factory_code(&mut app);
+    let helper = FactoryHelper::instantiate(&mut app, &owner, Some(factory_code_id));
+    let fee_token = use_cw20_creation_fee(&mut app, &helper, factory_code_id, &user);
+
+    let asset_infos = vec![
+        AssetInfo::SmartToken("tokenX".to_string()),
+        AssetInfo::SmartToken("tokenY".to_string()),
+    ];
+    let create_pool = |amount: u128| Cw20ExecuteMsg::Send {
+        contract: helper.factory.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_json_binary(&ReceiveMsg::CreatePool {
+            pool_type: PoolType::Xyk {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            total_fee_bps: None,
+            staking_config: Default::default(),
+            existing_staking_addr: None,
+            trading_starts: None,
+        })
+        .unwrap(),
+    };
+
+    // the exact fee has to be sent
+    let err = app
+        .execute_contract(user.clone(), fee_token.clone(), &create_pool(2_999), &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!(
+            "Incorrect deposit: permissionless factory requires deposit as: 3000{}",
+            fee_token
+        )
+    );
+
+    app.execute_contract(user.clone(), fee_token.clone(), &create_pool(3_000), &[])
+        .unwrap();
+
+    // the pool is created on behalf of the sender of the fee
+    app.wrap()
+        .query_wasm_smart::<PairInfo>(&helper.factory, &QueryMsg::Pool { asset_infos })
+        .unwrap();
+    let stats: CreatorStatsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::CreatorStats {
+                creator: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(stats.pools_created, 1);
+
+    // the collected fee is withdrawn in the cw20 token
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::WithdrawPoolCreationFees {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(cw20_balance(&app, &fee_token, &owner), Uint128::new(3_000));
+}
+
+/// Switches the xyk pool type to a code id that always fails to instantiate.
+fn use_failing_pool_code(app: &mut CoreumApp, helper: &FactoryHelper) {
+    let failing_pool_code_id = app.store_code(Box::new(ContractWrapper::new(
+        dex_pool::contract::execute,
+        failing_pool_instantiate,
+        dex_pool::contract::query,
+    )));
+    let config_res: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    app.execute_contract(
+        helper.owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::UpdatePoolConfig {
+            config: PoolConfig {
+                code_id: failing_pool_code_id,
+                ..config_res.pool_configs[0].clone()
+            },
+        },
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn refund_deposit_on_failed_pool_instantiation() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let helper = FactoryHelper::init(&mut app, &owner);
+
+    app.init_modules(|router, _, storage| {
+        router.bank.init_balance(
+            storage,
+            &user,
+            vec![Coin {
+                denom: "coreum".to_string(),
+                amount: Uint128::new(3_000),
+            }],
+        )
+    })
+    .unwrap();
+
+    use_failing_pool_code(&mut app, &helper);
+
+    let asset_infos = vec![
+        AssetInfo::SmartToken("tokenX".to_string()),
+        AssetInfo::SmartToken("tokenY".to_string()),
+    ];
+    // creating the pool succeeds, but only refunds the deposit
+    app.execute_contract(
+        user.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::CreatePool {
+            pool_type: PoolType::Xyk {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            staking_config: Default::default(),
+            existing_staking_addr: None,
+            total_fee_bps: None,
+            trading_starts: None,
+        },
+        &[Coin::new(3_000, "coreum")],
+    )
+    .unwrap();
+
+    // the deposit is returned to the user
+    let balance = app.wrap().query_balance(&user, "coreum").unwrap();
+    assert_eq!(balance.amount, Uint128::new(3_000));
+
+    // and no pool or fee was recorded
+    app.wrap()
+        .query_wasm_smart::<PairInfo>(&helper.factory, &QueryMsg::Pool { asset_infos })
+        .unwrap_err();
+    let stats: CreatorStatsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::CreatorStats {
+                creator: user.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(stats.pools_created, 0);
+    let err = app
+        .execute_contract(
+            owner,
+            helper.factory.clone(),
+            &ExecuteMsg::WithdrawPoolCreationFees {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "No pool creation fees were collected"
+    );
+}
+
+#[test]
+fn refund_cw20_deposit_on_failed_pool_instantiation() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+    let factory_code_id = store_factory_code(&mut app);
+    let helper = FactoryHelper::instantiate(&mut app, &owner, Some(factory_code_id));
+    let fee_token = use_cw20_creation_fee(&mut app, &helper, factory_code_id, &user);
+    use_failing_pool_code(&mut app, &helper);
+
+    let asset_infos = vec![
+        AssetInfo::SmartToken("tokenX".to_string()),
+        AssetInfo::SmartToken("tokenY".to_string()),
+    ];
+    // creating the pool succeeds, but only refunds the deposit
+    app.execute_contract(
+        user.clone(),
+        fee_token.clone(),
+        &Cw20ExecuteMsg::Send {
+            contract: helper.factory.to_string(),
+            amount: Uint128::new(3_000),
+            msg: to_json_binary(&ReceiveMsg::CreatePool {
+                pool_type: PoolType::Xyk {},
+                asset_infos: asset_infos.clone(),
+                init_params: None,
+                total_fee_bps: None,
+                staking_config: Default::default(),
+                existing_staking_addr: None,
+                trading_starts: None,
+            })
+            .unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // the deposit is transferred back to the user
+    assert_eq!(cw20_balance(&app, &fee_token, &user), Uint128::new(6_000));
+    assert_eq!(
+        cw20_balance(&app, &fee_token, &helper.factory),
+        Uint128::zero()
+    );
+
+    // and no pool or fee was recorded
+    app.wrap()
+        .query_wasm_smart::<PairInfo>(&helper.factory, &QueryMsg::Pool { asset_infos })
+        .unwrap_err();
+    let err = app
+        .execute_contract(
+            owner,
+            helper.factory.clone(),
+            &ExecuteMsg::WithdrawPoolCreationFees {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "No pool creation fees were collected"
+    );
+}
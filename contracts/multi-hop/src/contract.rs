@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use coreum_wasm_sdk::core::{CoreumMsg, CoreumQueries};
 use cosmwasm_std::{
@@ -11,16 +11,18 @@ use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use crate::{
     error::ContractError,
     msg::{
-        ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
-        SimulateSwapOperationsResponse, SwapOperation, MAX_SWAP_OPERATIONS,
+        ConfigResponse, Cw20HookMsg, ExecuteMsg, FindBestRouteResponse, InstantiateMsg, MigrateMsg,
+        QueryMsg, SimulateSwapOperationsResponse, SwapOperation, DEFAULT_ROUTE_HOPS,
+        MAX_ROUTE_CANDIDATES, MAX_ROUTE_GRAPH_ROUTES, MAX_ROUTE_HOPS, MAX_SWAP_OPERATIONS,
     },
     state::{Config, CONFIG},
 };
 
 use dex::{
     asset::{addr_opt_validate, Asset, AssetInfo, AssetInfoExt},
-    pool::{ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg, SimulationResponse},
-    querier::{query_balance, query_pool_info, query_token_balance},
+    factory::{QueryMsg as FactoryQueryMsg, RouteInfo, RoutesResponse},
+    pool::{ExecuteMsg as PairExecuteMsg, PairInfo, QueryMsg as PairQueryMsg, SimulationResponse},
+    querier::{query_balance, query_pool_addresses, query_token_balance},
 };
 
 pub type Response = cosmwasm_std::Response<CoreumMsg>;
@@ -39,10 +41,18 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let max_hops = msg.max_hops.unwrap_or(MAX_SWAP_OPERATIONS as u32);
+    if max_hops == 0 || max_hops as usize > MAX_SWAP_OPERATIONS {
+        return Err(ContractError::InvalidMaxHops {
+            max: MAX_SWAP_OPERATIONS as u32,
+        });
+    }
+
     CONFIG.save(
         deps.storage,
         &Config {
             dex_factory: deps.api.addr_validate(&msg.dex_factory)?,
+            max_hops,
         },
     )?;
 
@@ -65,6 +75,7 @@ pub fn execute(
             max_spread,
             referral_address,
             referral_commission,
+            minimum_receives,
         } => execute::swap_operations(
             deps,
             env,
@@ -75,9 +86,11 @@ pub fn execute(
             max_spread,
             referral_address,
             referral_commission,
+            minimum_receives,
         ),
         ExecuteMsg::ExecuteSwapOperation {
             operation,
+            pool,
             receiver,
             max_spread,
             single,
@@ -88,6 +101,7 @@ pub fn execute(
             env,
             info,
             operation,
+            pool,
             receiver,
             max_spread,
             single,
@@ -123,6 +137,7 @@ pub fn receive_cw20(
             max_spread,
             referral_address,
             referral_commission,
+            minimum_receives,
         } => execute::swap_operations(
             deps,
             env,
@@ -133,6 +148,7 @@ pub fn receive_cw20(
             max_spread,
             referral_address,
             referral_commission,
+            minimum_receives,
         ),
     }
 }
@@ -146,6 +162,7 @@ mod execute {
         env: Env,
         info: MessageInfo,
         operation: SwapOperation,
+        pool: String,
         receiver: Option<String>,
         max_spread: Option<Decimal>,
         single: bool,
@@ -160,14 +177,8 @@ mod execute {
             SwapOperation::DexSwap {
                 offer_asset_info,
                 ask_asset_info,
+                max_spread: operation_max_spread,
             } => {
-                let config = CONFIG.load(deps.storage)?;
-                let pair_info = query_pool_info(
-                    &deps.querier,
-                    config.dex_factory,
-                    &[offer_asset_info.clone(), ask_asset_info.clone()],
-                )?;
-
                 let amount = match &offer_asset_info {
                     AssetInfo::SmartToken(denom) => {
                         query_balance(&deps.querier, env.contract.address, denom)?
@@ -181,13 +192,14 @@ mod execute {
                     amount,
                 };
 
+                // an operation's own max spread is always enforced, the route's only for single swaps
                 asset_into_swap_msg(
-                    pair_info.contract_addr.to_string(),
+                    pool,
                     offer_asset,
                     ask_asset_info,
-                    max_spread,
+                    operation_max_spread.or(max_spread),
                     receiver,
-                    single,
+                    single || operation_max_spread.is_some(),
                     referral_address,
                     referral_commission,
                 )?
@@ -204,12 +216,16 @@ mod execute {
         ask_asset_info: AssetInfo,
         max_spread: Option<Decimal>,
         receiver: Option<String>,
-        single: bool,
+        assert_spread: bool,
         referral_address: Option<String>,
         referral_commission: Option<Decimal>,
     ) -> StdResult<CosmosMsg<CoreumMsg>> {
-        // Disabling spread assertion if this swap is part of a multi hop route
-        let belief_price = if single { None } else { Some(Decimal::MAX) };
+        // Disabling spread assertion if this swap is part of a multi hop route without its own max spread
+        let belief_price = if assert_spread {
+            None
+        } else {
+            Some(Decimal::MAX)
+        };
 
         match &offer_asset.info {
             AssetInfo::SmartToken(denom) => {
@@ -265,19 +281,29 @@ mod execute {
         max_spread: Option<Decimal>,
         referral_address: Option<String>,
         referral_commission: Option<Decimal>,
+        minimum_receives: Option<Vec<Uint128>>,
     ) -> Result<Response, ContractError> {
         if operations.is_empty() {
             return Err(ContractError::MustProvideOperations {});
         }
 
+        let config = CONFIG.load(deps.storage)?;
         let operations_len = operations.len();
-        if operations_len > MAX_SWAP_OPERATIONS {
+        if operations_len > config.max_hops as usize {
             return Err(ContractError::SwapLimitExceeded {});
         }
 
         // Assert the operations are properly set
         assert_operations(deps.api, &operations)?;
 
+        let minimum_receives = match minimum_receives {
+            Some(minimum_receives) if minimum_receives.len() != operations_len => {
+                return Err(ContractError::InvalidMinimumReceives {});
+            }
+            Some(minimum_receives) => minimum_receives,
+            None => vec![Uint128::zero(); operations_len],
+        };
+
         let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or(sender);
 
         let target_asset_info = operations
@@ -286,55 +312,104 @@ mod execute {
             .get_target_asset_info()
             .validate(deps.api)?;
 
-        let mut messages = operations
+        // resolve all pools of the route up front, so the individual operations don't have to
+        let pools = resolve_pools(deps.as_ref(), &config.dex_factory, &operations)?;
+
+        let mut messages = vec![];
+        for (operation_index, ((op, pool), hop_minimum_receive)) in operations
             .into_iter()
+            .zip(pools)
+            .zip(minimum_receives)
             .enumerate()
-            .map(|(operation_index, op)| {
-                Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: env.contract.address.to_string(),
-                    funds: vec![],
-                    msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
-                        operation: op,
-                        receiver: if operation_index == operations_len - 1 {
-                            Some(receiver.to_string())
-                        } else {
-                            None
-                        },
-                        max_spread,
-                        single: operations_len == 1,
-                        referral_address: if operation_index == 0 {
-                            referral_address.clone()
-                        } else {
-                            None
-                        },
-                        referral_commission: if operation_index == 0 {
-                            referral_commission
-                        } else {
-                            None
-                        },
-                    })?,
-                }))
-            })
-            .collect::<StdResult<Vec<CosmosMsg<CoreumMsg>>>>()?;
-
-        // Execute minimum amount assertion
-        if let Some(minimum_receive) = minimum_receive {
-            let receiver_balance = target_asset_info.query_balance(&deps.querier, &receiver)?;
+        {
+            let last = operation_index == operations_len - 1;
+            let ask_asset_info = op.get_target_asset_info();
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: env.contract.address.to_string(),
                 funds: vec![],
-                msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
-                    asset_info: target_asset_info.into(),
-                    prev_balance: receiver_balance,
-                    minimum_receive,
-                    receiver: receiver.to_string(),
+                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: op,
+                    pool: pool.to_string(),
+                    receiver: if last {
+                        Some(receiver.to_string())
+                    } else {
+                        None
+                    },
+                    max_spread,
+                    single: operations_len == 1,
+                    referral_address: if operation_index == 0 {
+                        referral_address.clone()
+                    } else {
+                        None
+                    },
+                    referral_commission: if operation_index == 0 {
+                        referral_commission
+                    } else {
+                        None
+                    },
                 })?,
             }));
+
+            if hop_minimum_receive.is_zero() {
+                continue;
+            }
+            if last {
+                let receiver_balance = target_asset_info.query_balance(&deps.querier, &receiver)?;
+                messages.push(assert_minimum_receive_msg(
+                    &env,
+                    ask_asset_info,
+                    receiver_balance,
+                    hop_minimum_receive,
+                    &receiver,
+                )?);
+            } else {
+                // intermediate results stay in this contract until the next operation offers all of them
+                messages.push(assert_minimum_receive_msg(
+                    &env,
+                    ask_asset_info,
+                    Uint128::zero(),
+                    hop_minimum_receive,
+                    &env.contract.address,
+                )?);
+            }
+        }
+
+        // Execute minimum amount assertion
+        if let Some(minimum_receive) = minimum_receive {
+            let receiver_balance = target_asset_info.query_balance(&deps.querier, &receiver)?;
+            messages.push(assert_minimum_receive_msg(
+                &env,
+                target_asset_info.into(),
+                receiver_balance,
+                minimum_receive,
+                &receiver,
+            )?);
         }
 
         Ok(Response::new().add_messages(messages))
     }
 
+    /// Creates the message asserting that `receiver` received at least `minimum_receive` of the
+    /// given asset since its balance was `prev_balance`.
+    fn assert_minimum_receive_msg(
+        env: &Env,
+        asset_info: AssetInfo,
+        prev_balance: Uint128,
+        minimum_receive: Uint128,
+        receiver: &Addr,
+    ) -> StdResult<CosmosMsg<CoreumMsg>> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+                asset_info,
+                prev_balance,
+                minimum_receive,
+                receiver: receiver.to_string(),
+            })?,
+        }))
+    }
+
     pub fn assert_minimum_receive(
         deps: Deps<CoreumQueries>,
         asset_info: AssetInfo,
@@ -385,6 +460,16 @@ pub fn query(deps: Deps<CoreumQueries>, _env: Env, msg: QueryMsg) -> Result<Bina
             referral_commission,
             operations,
         )?)?),
+        QueryMsg::FindBestRoute {
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        } => Ok(to_json_binary(&query::find_best_route(
+            deps,
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        )?)?),
     }
 }
 
@@ -398,6 +483,7 @@ mod query {
         let state = CONFIG.load(deps.storage)?;
         let resp = ConfigResponse {
             dex_factory: state.dex_factory.into_string(),
+            max_hops: state.max_hops,
         };
 
         Ok(resp)
@@ -417,19 +503,18 @@ mod query {
         referral_commission: Option<Decimal>,
         operations: Vec<SwapOperation>,
     ) -> Result<SimulateSwapOperationsResponse, ContractError> {
-        let config = CONFIG.load(deps.storage)?;
-        let dex_factory = config.dex_factory;
-
         let operations_len = operations.len();
         if operations_len == 0 {
             return Err(ContractError::MustProvideOperations {});
         }
 
-        if operations_len > MAX_SWAP_OPERATIONS {
+        let config = CONFIG.load(deps.storage)?;
+        if operations_len > config.max_hops as usize {
             return Err(ContractError::SwapLimitExceeded {});
         }
 
         assert_operations(deps.api, &operations)?;
+        let pools = resolve_pools(deps, &config.dex_factory, &operations)?;
 
         let mut offer_amount = offer_amount;
         let mut spread_amounts = Vec::with_capacity(operations_len);
@@ -437,20 +522,15 @@ mod query {
         let mut referral_amount = None;
         // the ratio of swap result to ideal swap result (= 1 - spread percentage)
         let mut percent_of_ideal = Decimal::one();
-        for (idx, operation) in operations.into_iter().enumerate() {
+        for (idx, (operation, pool)) in operations.into_iter().zip(pools).enumerate() {
             match operation {
                 SwapOperation::DexSwap {
                     offer_asset_info,
                     ask_asset_info,
+                    ..
                 } => {
-                    let pair_info = query_pool_info(
-                        &deps.querier,
-                        dex_factory.clone(),
-                        &[offer_asset_info.clone(), ask_asset_info.clone()],
-                    )?;
-
                     let res: SimulationResponse = deps.querier.query_wasm_smart(
-                        pair_info.contract_addr,
+                        pool,
                         &PairQueryMsg::Simulation {
                             offer_asset: Asset {
                                 info: offer_asset_info.clone(),
@@ -496,6 +576,90 @@ mod query {
         })
     }
 
+    /// Returns the swap operations of the route from `offer_asset` to `ask_asset_info`
+    /// with the highest return, using at most `max_hops` swaps.
+    /// The routes known to the factory are simulated with the full offer amount, starting with the
+    /// ones with the fewest swaps, until [`MAX_ROUTE_CANDIDATES`] routes were found.
+    /// Routes that cannot be simulated, e.g. because a pool has no liquidity, are skipped.
+    pub fn find_best_route(
+        deps: Deps<CoreumQueries>,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        max_hops: Option<u32>,
+    ) -> Result<FindBestRouteResponse, ContractError> {
+        let max_hops = max_hops.unwrap_or(DEFAULT_ROUTE_HOPS);
+        if max_hops == 0 || max_hops > MAX_ROUTE_HOPS {
+            return Err(ContractError::InvalidMaxHops {
+                max: MAX_ROUTE_HOPS,
+            });
+        }
+
+        let offer = offer_asset.info.validate(deps.api)?.to_string();
+        let ask = ask_asset_info.validate(deps.api)?.to_string();
+
+        let config = CONFIG.load(deps.storage)?;
+        let graph = query_route_graph(deps, &config.dex_factory)?;
+
+        let mut paths = vec![];
+        for hops in 1..=max_hops as usize {
+            collect_paths(
+                &graph,
+                &offer,
+                &ask,
+                hops,
+                &mut HashSet::from([offer.clone()]),
+                &mut vec![],
+                &mut paths,
+            );
+        }
+
+        // the routes only contain the string representation of the assets,
+        // so the intermediate assets are looked up in the pools
+        let mut asset_infos =
+            HashMap::from([(offer, offer_asset.info.clone()), (ask, ask_asset_info)]);
+        let mut best: Option<FindBestRouteResponse> = None;
+        for path in paths {
+            let mut operations = Vec::with_capacity(path.len());
+            let mut offer_asset_info = offer_asset.info.clone();
+            for (asset, pool) in path {
+                if !asset_infos.contains_key(&asset) {
+                    let pair_info: PairInfo = deps
+                        .querier
+                        .query_wasm_smart(&pool, &PairQueryMsg::Pair {})?;
+                    let info = pair_info
+                        .asset_infos
+                        .into_iter()
+                        .find(|info| info.to_string() == asset)
+                        .ok_or_else(|| {
+                            StdError::generic_err("pool does not contain route asset")
+                        })?;
+                    asset_infos.insert(asset.clone(), info.into());
+                }
+                let ask_asset_info = asset_infos[&asset].clone();
+                operations.push(SwapOperation::DexSwap {
+                    offer_asset_info,
+                    ask_asset_info: ask_asset_info.clone(),
+                    max_spread: None,
+                });
+                offer_asset_info = ask_asset_info;
+            }
+
+            let Ok(simulation) =
+                simulate_swap_operations(deps, offer_asset.amount, false, None, operations.clone())
+            else {
+                continue;
+            };
+            if !matches!(&best, Some(best) if best.amount >= simulation.amount) {
+                best = Some(FindBestRouteResponse {
+                    operations,
+                    amount: simulation.amount,
+                });
+            }
+        }
+
+        best.ok_or(ContractError::NoRouteFound {})
+    }
+
     /// Returns the offer asset needed and the result of a simulation for one or multiple swap
     /// operations using a [`SimulateSwapOperationsResponse`] object.
     ///
@@ -510,19 +674,18 @@ mod query {
         referral_commission: Option<Decimal>,
         operations: Vec<SwapOperation>,
     ) -> Result<SimulateSwapOperationsResponse, ContractError> {
-        let config = CONFIG.load(deps.storage)?;
-        let dex_factory = config.dex_factory;
-
         let operations_len = operations.len();
         if operations_len == 0 {
             return Err(ContractError::MustProvideOperations {});
         }
 
-        if operations_len > MAX_SWAP_OPERATIONS {
+        let config = CONFIG.load(deps.storage)?;
+        if operations_len > config.max_hops as usize {
             return Err(ContractError::SwapLimitExceeded {});
         }
 
         assert_operations(deps.api, &operations)?;
+        let pools = resolve_pools(deps, &config.dex_factory, &operations)?;
 
         let mut ask_amount = ask_amount;
         let mut spread_amounts = Vec::with_capacity(operations_len);
@@ -530,20 +693,15 @@ mod query {
         let mut referral_amount = None;
         // the ratio of swap result to ideal swap result (= 1 - spread percentage)
         let mut percent_of_ideal = Decimal::one();
-        for (idx, operation) in operations.into_iter().enumerate().rev() {
+        for (idx, (operation, pool)) in operations.into_iter().zip(pools).enumerate().rev() {
             match operation {
                 SwapOperation::DexSwap {
                     offer_asset_info,
                     ask_asset_info,
+                    ..
                 } => {
-                    let pair_info = query_pool_info(
-                        &deps.querier,
-                        dex_factory.clone(),
-                        &[offer_asset_info.clone(), ask_asset_info.clone()],
-                    )?;
-
                     let res: ReverseSimulationResponse = deps.querier.query_wasm_smart(
-                        pair_info.contract_addr,
+                        pool,
                         &PairQueryMsg::ReverseSimulation {
                             offer_asset_info: Some(offer_asset_info.clone()),
                             ask_asset: Asset {
@@ -591,6 +749,96 @@ mod query {
     }
 }
 
+/// Assets connected by at least one pool, mapping each asset to the assets it can be swapped into
+/// and a pool that swaps them. Assets are identified by their string representation.
+type RouteGraph = HashMap<String, Vec<(String, Addr)>>;
+
+/// The number of routes queried from the factory at once
+const ROUTES_PAGE_LIMIT: u32 = 30;
+
+/// Reads the first [`MAX_ROUTE_GRAPH_ROUTES`] routes of the factory into a [`RouteGraph`].
+fn query_route_graph(deps: Deps<CoreumQueries>, dex_factory: &Addr) -> StdResult<RouteGraph> {
+    let mut graph = RouteGraph::new();
+    let mut start_after = None;
+    let mut route_count = 0;
+    loop {
+        let RoutesResponse { routes } = deps.querier.query_wasm_smart(
+            dex_factory,
+            &FactoryQueryMsg::Routes {
+                start_after: start_after.take(),
+                limit: Some(ROUTES_PAGE_LIMIT),
+            },
+        )?;
+        let page_len = routes.len();
+        start_after = routes
+            .last()
+            .map(|last| (last.from.clone(), last.to.clone()));
+        for RouteInfo { from, to, pools } in routes {
+            if route_count == MAX_ROUTE_GRAPH_ROUTES {
+                return Ok(graph);
+            }
+            route_count += 1;
+            if let Some(pool) = pools.into_iter().next() {
+                graph.entry(from).or_default().push((to, pool));
+            }
+        }
+        if page_len < ROUTES_PAGE_LIMIT as usize {
+            return Ok(graph);
+        }
+    }
+}
+
+/// Collects the paths from `current` to `to` with exactly `hops` swaps into `paths`,
+/// until `paths` contains [`MAX_ROUTE_CANDIDATES`] paths.
+/// Each path is a list of `(asset, pool)` steps, where `pool` swaps the previous asset into `asset`.
+/// Assets in `visited` are skipped, so no path contains a cycle.
+fn collect_paths(
+    graph: &RouteGraph,
+    current: &str,
+    to: &str,
+    hops: usize,
+    visited: &mut HashSet<String>,
+    path: &mut Vec<(String, Addr)>,
+    paths: &mut Vec<Vec<(String, Addr)>>,
+) {
+    for (next, pool) in graph.get(current).into_iter().flatten() {
+        if paths.len() == MAX_ROUTE_CANDIDATES {
+            return;
+        }
+        if visited.contains(next) {
+            continue;
+        }
+        path.push((next.clone(), pool.clone()));
+        if path.len() == hops {
+            if next == to {
+                paths.push(path.clone());
+            }
+        } else if next != to {
+            visited.insert(next.clone());
+            collect_paths(graph, next, to, hops, visited, path, paths);
+            visited.remove(next);
+        }
+        path.pop();
+    }
+}
+
+/// Returns the addresses of the pools for all swap operations, in the same order as `operations`.
+/// The factory is only queried once, regardless of the number of operations.
+fn resolve_pools(
+    deps: Deps<CoreumQueries>,
+    dex_factory: &Addr,
+    operations: &[SwapOperation],
+) -> StdResult<Vec<Addr>> {
+    query_pool_addresses(
+        &deps.querier,
+        dex_factory,
+        operations
+            .iter()
+            .map(SwapOperation::pool_asset_infos)
+            .collect(),
+    )
+}
+
 /// Validates swap operations.
 fn assert_operations(api: &dyn Api, operations: &[SwapOperation]) -> Result<(), ContractError> {
     let mut ask_asset_map: HashSet<String> = HashSet::new();
@@ -599,6 +847,7 @@ fn assert_operations(api: &dyn Api, operations: &[SwapOperation]) -> Result<(),
             SwapOperation::DexSwap {
                 offer_asset_info,
                 ask_asset_info,
+                ..
             } => (
                 offer_asset_info.validate(api)?,
                 ask_asset_info.validate(api)?,
@@ -628,8 +877,151 @@ pub fn migrate(
 
 #[cfg(test)]
 mod testing {
+    use std::{cell::Cell, marker::PhantomData};
+
+    use cosmwasm_std::{
+        testing::{mock_env, MockApi, MockStorage},
+        ContractResult, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemResult, WasmQuery,
+    };
+
     use super::*;
 
+    /// Answers the factory and pool queries of a swap route and counts the factory queries.
+    #[derive(Default)]
+    struct CountingQuerier {
+        factory_queries: Cell<u32>,
+    }
+
+    impl Querier for CountingQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<CoreumQueries> = from_json(bin_request).unwrap();
+            let response = match request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                    if contract_addr == "factory" =>
+                {
+                    self.factory_queries.set(self.factory_queries.get() + 1);
+                    match from_json(msg).unwrap() {
+                        FactoryQueryMsg::PoolAddresses { asset_infos } => to_json_binary(
+                            &(0..asset_infos.len())
+                                .map(|i| Addr::unchecked(format!("pool{i}")))
+                                .collect::<Vec<_>>(),
+                        ),
+                        _ => panic!("unexpected factory query"),
+                    }
+                }
+                QueryRequest::Wasm(WasmQuery::Smart { .. }) => {
+                    to_json_binary(&SimulationResponse {
+                        return_amount: Uint128::new(1_000),
+                        spread_amount: Uint128::zero(),
+                        commission_amount: Uint128::zero(),
+                        referral_amount: Uint128::zero(),
+                    })
+                }
+                _ => panic!("unexpected query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+    }
+
+    fn route(hops: usize) -> Vec<SwapOperation> {
+        (0..hops)
+            .map(|i| SwapOperation::DexSwap {
+                offer_asset_info: AssetInfo::SmartToken(format!("token{i}")),
+                ask_asset_info: AssetInfo::SmartToken(format!("token{}", i + 1)),
+                max_spread: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn factory_is_queried_once_per_route() {
+        for hops in [1, 2, 5] {
+            let mut deps = OwnedDeps {
+                storage: MockStorage::default(),
+                api: MockApi::default(),
+                querier: CountingQuerier::default(),
+                custom_query_type: PhantomData::<CoreumQueries>,
+            };
+            CONFIG
+                .save(
+                    &mut deps.storage,
+                    &Config {
+                        dex_factory: Addr::unchecked("factory"),
+                        max_hops: MAX_SWAP_OPERATIONS as u32,
+                    },
+                )
+                .unwrap();
+
+            query::simulate_swap_operations(
+                deps.as_ref(),
+                Uint128::new(1_000),
+                false,
+                None,
+                route(hops),
+            )
+            .unwrap();
+            assert_eq!(deps.querier.factory_queries.get(), 1);
+
+            deps.querier.factory_queries.set(0);
+            let res = execute::swap_operations(
+                deps.as_mut(),
+                mock_env(),
+                Addr::unchecked("sender"),
+                route(hops),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            assert_eq!(deps.querier.factory_queries.get(), 1);
+            assert_eq!(res.messages.len(), hops);
+
+            // every operation is sent with its resolved pool
+            for (i, msg) in res.messages.iter().enumerate() {
+                let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = &msg.msg else {
+                    panic!("unexpected message");
+                };
+                let ExecuteMsg::ExecuteSwapOperation { pool, .. } = from_json(msg).unwrap() else {
+                    panic!("unexpected message");
+                };
+                assert_eq!(pool, format!("pool{i}"));
+            }
+        }
+    }
+
+    #[test]
+    fn instantiate_validates_max_hops() {
+        use cosmwasm_std::testing::mock_info;
+
+        for max_hops in [0, MAX_SWAP_OPERATIONS as u32 + 1] {
+            let mut deps = OwnedDeps {
+                storage: MockStorage::default(),
+                api: MockApi::default(),
+                querier: CountingQuerier::default(),
+                custom_query_type: PhantomData::<CoreumQueries>,
+            };
+            let err = instantiate(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("owner", &[]),
+                InstantiateMsg {
+                    dex_factory: "factory".to_string(),
+                    max_hops: Some(max_hops),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::InvalidMaxHops {
+                    max: MAX_SWAP_OPERATIONS as u32
+                }
+            );
+        }
+    }
+
     #[test]
     fn test_invalid_operations() {
         use cosmwasm_std::testing::mock_dependencies;
@@ -644,10 +1036,12 @@ mod testing {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("ukrw".to_string()),
                     ask_asset_info: AssetInfo::Cw20Token("asset0001".to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token("asset0001".to_string()),
                     ask_asset_info: AssetInfo::SmartToken("uluna".to_string()),
+                    max_spread: None,
                 },
             ],
         )
@@ -660,14 +1054,17 @@ mod testing {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("ukrw".to_string()),
                     ask_asset_info: AssetInfo::Cw20Token("asset0001".to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token("asset0001".to_string()),
                     ask_asset_info: AssetInfo::SmartToken("uluna".to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("uluna".to_string()),
                     ask_asset_info: AssetInfo::Cw20Token("asset0002".to_string()),
+                    max_spread: None,
                 },
             ],
         )
@@ -680,14 +1077,17 @@ mod testing {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("ukrw".to_string()),
                     ask_asset_info: AssetInfo::Cw20Token("asset0001".to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token("asset0001".to_string()),
                     ask_asset_info: AssetInfo::SmartToken("uaud".to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("uluna".to_string()),
                     ask_asset_info: AssetInfo::Cw20Token("asset0002".to_string()),
+                    max_spread: None,
                 },
             ],
         )
@@ -19,6 +19,15 @@ pub enum ContractError {
 
     #[error("The swap operation limit was exceeded!")]
     SwapLimitExceeded {},
+
+    #[error("There must be exactly one minimum receive per swap operation")]
+    InvalidMinimumReceives {},
+
+    #[error("The maximum number of hops must be between 1 and {max}")]
+    InvalidMaxHops { max: u32 },
+
+    #[error("No route found between the given assets")]
+    NoRouteFound {},
 }
 
 impl From<OverflowError> for ContractError {
@@ -3,15 +3,28 @@ use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 
-use dex::asset::{AssetInfo, AssetValidated};
+use dex::asset::{Asset, AssetInfo, AssetValidated};
 
+/// The maximum number of swap operations the contract can be configured to allow
 pub const MAX_SWAP_OPERATIONS: usize = 50;
+/// The number of hops [`QueryMsg::FindBestRoute`] searches if no `max_hops` is given
+pub const DEFAULT_ROUTE_HOPS: u32 = 3;
+/// The maximum number of hops [`QueryMsg::FindBestRoute`] can search
+pub const MAX_ROUTE_HOPS: u32 = 4;
+/// The maximum number of factory routes [`QueryMsg::FindBestRoute`] reads
+pub const MAX_ROUTE_GRAPH_ROUTES: usize = 300;
+/// The maximum number of routes [`QueryMsg::FindBestRoute`] simulates
+pub const MAX_ROUTE_CANDIDATES: usize = 10;
 
 /// This structure holds the parameters used for creating a contract.
 #[cw_serde]
 pub struct InstantiateMsg {
     /// The dex factory contract address
     pub dex_factory: String,
+    /// The maximum number of swap operations in a single swap or simulation.
+    /// Defaults to and cannot exceed [`MAX_SWAP_OPERATIONS`].
+    #[serde(default)]
+    pub max_hops: Option<u32>,
 }
 
 /// This enum describes a swap operation.
@@ -25,6 +38,10 @@ pub enum SwapOperation {
         offer_asset_info: AssetInfo,
         /// Information about the asset we swap to
         ask_asset_info: AssetInfo,
+        /// The maximum spread for this operation only, overriding the `max_spread` of the route.
+        /// If set, the spread is enforced for this operation even if it is part of a multi hop route.
+        #[serde(default)]
+        max_spread: Option<Decimal>,
     },
 }
 
@@ -34,6 +51,17 @@ impl SwapOperation {
             SwapOperation::DexSwap { ask_asset_info, .. } => ask_asset_info.clone(),
         }
     }
+
+    /// Returns the assets of the pool this operation is performed in
+    pub fn pool_asset_infos(&self) -> Vec<AssetInfo> {
+        match self {
+            SwapOperation::DexSwap {
+                offer_asset_info,
+                ask_asset_info,
+                ..
+            } => vec![offer_asset_info.clone(), ask_asset_info.clone()],
+        }
+    }
 }
 
 /// This structure describes the execute messages available in the contract.
@@ -56,6 +84,10 @@ pub enum ExecuteMsg {
         /// The commission for the referral.
         /// This is capped by the configured max commission
         referral_commission: Option<Decimal>,
+        /// The minimum amount of tokens each swap operation has to return, in the same order as
+        /// `operations`. Zero means that the operation is unconstrained.
+        #[serde(default)]
+        minimum_receives: Option<Vec<Uint128>>,
     },
 
     /// Internal use
@@ -63,6 +95,9 @@ pub enum ExecuteMsg {
     ExecuteSwapOperation {
         /// Swap operation to perform
         operation: SwapOperation,
+        /// Address of the pool to perform the swap operation in.
+        /// This is resolved once for all operations by `ExecuteSwapOperations`.
+        pool: String,
         /// Recipient of the ask tokens
         receiver: Option<String>,
         max_spread: Option<Decimal>,
@@ -101,6 +136,10 @@ pub enum Cw20HookMsg {
         /// The commission is only applied to the first of these swap operations,
         /// so the referrer will get a portion of the asset the swap starts with.
         referral_commission: Option<Decimal>,
+        /// The minimum amount of tokens each swap operation has to return, in the same order as
+        /// `operations`. Zero means that the operation is unconstrained.
+        #[serde(default)]
+        minimum_receives: Option<Vec<Uint128>>,
     },
 }
 
@@ -141,6 +180,20 @@ pub enum QueryMsg {
         /// so the referrer will get a portion of the asset the swap starts with.
         referral_commission: Option<Decimal>,
     },
+    /// FindBestRoute searches the routes between the two assets known to the factory and returns
+    /// the swap operations of the one with the highest return, along with the simulated return amount.
+    /// Only the first [`MAX_ROUTE_GRAPH_ROUTES`] factory routes are read and at most
+    /// [`MAX_ROUTE_CANDIDATES`] routes are simulated, preferring the ones with fewer swaps.
+    #[returns(FindBestRouteResponse)]
+    FindBestRoute {
+        /// The asset to swap
+        offer_asset: Asset,
+        /// The asset to swap to
+        ask_asset_info: AssetInfo,
+        /// The maximum number of swaps in the route. Defaults to [`DEFAULT_ROUTE_HOPS`]
+        /// and cannot exceed [`MAX_ROUTE_HOPS`].
+        max_hops: Option<u32>,
+    },
 }
 
 /// This structure describes a custom struct to return a query response containing the base contract configuration.
@@ -148,6 +201,8 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     /// The Dex factory contract address
     pub dex_factory: String,
+    /// The maximum number of swap operations in a single swap or simulation
+    pub max_hops: u32,
 }
 
 /// This structure describes a custom struct to return a query response containing the end amount of a swap simulation
@@ -174,5 +229,14 @@ pub struct SimulateSwapOperationsResponse {
     pub referral_amount: AssetValidated,
 }
 
+/// This structure describes a custom struct to return a query response containing the best swap route
+#[cw_serde]
+pub struct FindBestRouteResponse {
+    /// The swap operations of the best route, to be used with [`ExecuteMsg::ExecuteSwapOperations`]
+    pub operations: Vec<SwapOperation>,
+    /// The amount of tokens returned by the route
+    pub amount: Uint128,
+}
+
 #[cw_serde]
 pub struct MigrateMsg {}
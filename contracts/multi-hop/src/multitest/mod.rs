@@ -1,2 +1,3 @@
 mod suite;
 mod swap;
+mod zap;
@@ -8,15 +8,19 @@ use cw20_base::msg::InstantiateMsg as Cw20BaseInstantiateMsg;
 use cw_multi_test::{AppResponse, BankSudo, Contract, ContractWrapper, Executor, SudoMsg};
 
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, QueryMsg, SimulateSwapOperationsResponse, SwapOperation,
+    ExecuteMsg, FindBestRouteResponse, InstantiateMsg, QueryMsg, SimulateSwapOperationsResponse,
+    SwapOperation,
 };
-use dex::asset::{Asset, AssetInfo};
+use dex::asset::{Asset, AssetInfo, AssetValidated};
 use dex::factory::{
     DefaultStakeConfig, ExecuteMsg as FactoryExecuteMsg, InstantiateMsg as FactoryInstantiateMsg,
     PoolConfig, PoolType, QueryMsg as FactoryQueryMsg,
 };
 use dex::fee_config::FeeConfig;
-use dex::pool::{ExecuteMsg as PairExecuteMsg, PairInfo};
+use dex::pool::{
+    CollectedProtocolFeesResponse, ExecuteMsg as PairExecuteMsg, PairInfo, PoolResponse,
+    QueryMsg as PairQueryMsg, SimulationResponse,
+};
 
 const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
 
@@ -83,6 +87,8 @@ pub struct SuiteBuilder {
     stake_config: DefaultStakeConfig,
     total_fee_bps: u16,
     protocol_fee_bps: u16,
+    fee_address: Option<String>,
+    max_hops: Option<u32>,
 }
 
 impl SuiteBuilder {
@@ -92,6 +98,8 @@ impl SuiteBuilder {
             max_referral_commission: Decimal::one(),
             total_fee_bps: 0,
             protocol_fee_bps: 0,
+            fee_address: None,
+            max_hops: None,
             stake_config: DefaultStakeConfig {
                 staking_code_id: 0, // will be set in build()
                 tokens_per_power: Uint128::new(1000),
@@ -102,6 +110,7 @@ impl SuiteBuilder {
                     SECONDS_PER_DAY * 21,
                 ],
                 max_distributions: 6,
+                auto_claim_on_delegate: false,
             },
         }
     }
@@ -112,6 +121,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_fee_address(mut self, fee_address: &str) -> Self {
+        self.fee_address = Some(fee_address.to_owned());
+        self
+    }
+
     pub fn with_funds(mut self, addr: &str, funds: &[Coin]) -> Self {
         self.funds.push((Addr::unchecked(addr), funds.into()));
         self
@@ -122,6 +136,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_max_hops(mut self, max_hops: u32) -> Self {
+        self.max_hops = Some(max_hops);
+        self
+    }
+
     #[track_caller]
     pub fn build(self) -> Suite {
         let mut app = CoreumApp::default();
@@ -145,6 +164,7 @@ impl SuiteBuilder {
                                 protocol_fee_bps: self.protocol_fee_bps,
                             },
                             is_disabled: false,
+                            stake_config_override: None,
                         },
                         PoolConfig {
                             code_id: pair_code_id,
@@ -154,9 +174,11 @@ impl SuiteBuilder {
                                 protocol_fee_bps: self.protocol_fee_bps,
                             },
                             is_disabled: false,
+                            stake_config_override: None,
                         },
                     ],
-                    fee_address: None,
+                    fee_address: self.fee_address,
+                    creation_fee_address: None,
                     owner: owner.to_string(),
                     max_referral_commission: self.max_referral_commission,
                     default_stake_config: DefaultStakeConfig {
@@ -168,6 +190,7 @@ impl SuiteBuilder {
                         info: AssetInfo::Cw20Token("coreum".to_string()),
                         amount: Uint128::new(3_000),
                     },
+                    require_trading_starts: false,
                 },
                 &[],
                 "Dex Factory",
@@ -182,6 +205,7 @@ impl SuiteBuilder {
                 owner.clone(),
                 &InstantiateMsg {
                     dex_factory: factory.to_string(),
+                    max_hops: self.max_hops,
                 },
                 &[],
                 "Multi Hop",
@@ -244,7 +268,9 @@ impl Suite {
                 asset_infos: tokens.to_vec(),
                 init_params: None,
                 staking_config: Default::default(),
+                existing_staking_addr: None,
                 total_fee_bps: None,
+                trading_starts: None,
             },
             &[Coin::new(3_000, "coreum")],
         )?;
@@ -450,6 +476,53 @@ impl Suite {
                 max_spread: None,
                 referral_address: referral_address.into(),
                 referral_commission: referral_commission.into(),
+                minimum_receives: None,
+            },
+            &[amount],
+        )
+    }
+
+    pub fn swap_operations_with_minimums(
+        &mut self,
+        sender: &str,
+        amount: Coin,
+        operations: Vec<SwapOperation>,
+        minimum_receives: Vec<u128>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.multi_hop.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: None,
+                receiver: None,
+                max_spread: None,
+                referral_address: None,
+                referral_commission: None,
+                minimum_receives: Some(minimum_receives.into_iter().map(Uint128::new).collect()),
+            },
+            &[amount],
+        )
+    }
+
+    pub fn swap_operations_with_max_spread(
+        &mut self,
+        sender: &str,
+        amount: Coin,
+        operations: Vec<SwapOperation>,
+        max_spread: Decimal,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.multi_hop.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: None,
+                receiver: None,
+                max_spread: Some(max_spread),
+                referral_address: None,
+                referral_commission: None,
+                minimum_receives: None,
             },
             &[amount],
         )
@@ -487,6 +560,7 @@ impl Suite {
                     max_spread: None,
                     referral_address: referral_address.into(),
                     referral_commission: referral_commission.into(),
+                    minimum_receives: None,
                 })
                 .unwrap(),
             },
@@ -513,6 +587,182 @@ impl Suite {
         )
     }
 
+    pub fn update_max_referral_commission(
+        &mut self,
+        sender: &str,
+        max_referral_commission: Decimal,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::UpdateConfig {
+                fee_address: None,
+                creation_fee_address: None,
+                only_owner_can_create_pools: None,
+                default_stake_config: None,
+                require_trading_starts: None,
+                max_referral_commission: Some(max_referral_commission),
+            },
+            &[],
+        )
+    }
+
+    pub fn freeze_all_pools(
+        &mut self,
+        sender: &str,
+        frozen: bool,
+        limit: Option<u32>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::FreezeAllPools {
+                frozen,
+                start_after: None,
+                limit,
+            },
+            &[],
+        )
+    }
+
+    pub fn update_trading_starts(
+        &mut self,
+        sender: &str,
+        new_start: u64,
+        pools: Option<Vec<Vec<AssetInfo>>>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::UpdateTradingStarts {
+                new_start,
+                pools,
+                start_after: None,
+                limit: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn block_time(&self) -> u64 {
+        self.app.block_info().time.seconds()
+    }
+
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.app
+            .update_block(|block| block.time = block.time.plus_seconds(seconds));
+    }
+
+    pub fn zap_in(
+        &mut self,
+        sender: &str,
+        pair: &Addr,
+        offer: Coin,
+        min_share: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            pair.clone(),
+            &PairExecuteMsg::ZapIn {
+                offer_asset: Asset {
+                    info: AssetInfo::SmartToken(offer.denom.clone()),
+                    amount: offer.amount,
+                },
+                min_share: min_share.map(Uint128::new),
+                receiver: None,
+            },
+            &[offer],
+        )
+    }
+
+    pub fn zap_out(
+        &mut self,
+        sender: &str,
+        pair: &Addr,
+        share: u128,
+        ask_denom: &str,
+        max_spread: Option<Decimal>,
+        minimum_receive: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        let lp_token = self.query_pair_info(pair)?.liquidity_token;
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            pair.clone(),
+            &PairExecuteMsg::ZapOut {
+                share: share.into(),
+                ask_asset: AssetInfo::SmartToken(ask_denom.to_owned()),
+                receiver: None,
+                max_spread,
+                minimum_receive: minimum_receive.map(Uint128::new),
+            },
+            &[coin(share, lp_token)],
+        )
+    }
+
+    pub fn withdraw_liquidity(
+        &mut self,
+        sender: &str,
+        pair: &Addr,
+        share: u128,
+    ) -> AnyResult<AppResponse> {
+        let lp_token = self.query_pair_info(pair)?.liquidity_token;
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            pair.clone(),
+            &PairExecuteMsg::WithdrawLiquidity {
+                assets: vec![],
+                max_burn: None,
+            },
+            &[coin(share, lp_token)],
+        )
+    }
+
+    pub fn query_pair_info(&self, pair: &Addr) -> AnyResult<PairInfo> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(pair, &PairQueryMsg::Pair {})?)
+    }
+
+    pub fn query_pool(&self, pair: &Addr) -> AnyResult<PoolResponse> {
+        Ok(self
+            .app
+            .wrap()
+            .query_wasm_smart(pair, &PairQueryMsg::Pool {})?)
+    }
+
+    pub fn query_collected_protocol_fees(&self, pair: &Addr) -> AnyResult<Vec<AssetValidated>> {
+        let res: CollectedProtocolFeesResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(pair, &PairQueryMsg::CollectedProtocolFees {})?;
+        Ok(res.fees)
+    }
+
+    pub fn query_share(&self, pair: &Addr, amount: u128) -> AnyResult<Vec<AssetValidated>> {
+        Ok(self.app.wrap().query_wasm_smart(
+            pair,
+            &PairQueryMsg::Share {
+                amount: amount.into(),
+            },
+        )?)
+    }
+
+    pub fn query_pair_simulation(&self, pair: &Addr, offer: Coin) -> AnyResult<SimulationResponse> {
+        Ok(self.app.wrap().query_wasm_smart(
+            pair,
+            &PairQueryMsg::Simulation {
+                offer_asset: Asset {
+                    info: AssetInfo::SmartToken(offer.denom),
+                    amount: offer.amount,
+                },
+                ask_asset_info: None,
+                referral: false,
+                referral_commission: None,
+            },
+        )?)
+    }
+
     pub fn query_balance(&self, sender: &str, denom: &str) -> AnyResult<u128> {
         let amount = self
             .app
@@ -532,6 +782,23 @@ impl Suite {
         Ok(balance.balance.into())
     }
 
+    pub fn query_find_best_route(
+        &self,
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        max_hops: impl Into<Option<u32>>,
+    ) -> AnyResult<FindBestRouteResponse> {
+        let res: FindBestRouteResponse = self.app.wrap().query_wasm_smart(
+            self.multi_hop.clone(),
+            &QueryMsg::FindBestRoute {
+                offer_asset,
+                ask_asset_info,
+                max_hops: max_hops.into(),
+            },
+        )?;
+        Ok(res)
+    }
+
     pub fn query_simulate_swap_operations(
         &self,
         offer_amount: impl Into<Uint128>,
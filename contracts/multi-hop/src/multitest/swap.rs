@@ -1,12 +1,13 @@
-use super::suite::SuiteBuilder;
+use super::suite::{Suite, SuiteBuilder};
 
 use cosmwasm_std::testing::MockApi;
-use cosmwasm_std::{assert_approx_eq, coin, Decimal, Fraction, Uint128};
+use cosmwasm_std::{assert_approx_eq, coin, Addr, Decimal, Fraction, Uint128};
+use cw_multi_test::Executor;
 use dex::pool::{add_referral, take_referral};
 use dex::querier::query_factory_config;
 
 use crate::error::ContractError;
-use crate::msg::{SwapOperation, MAX_SWAP_OPERATIONS};
+use crate::msg::{SwapOperation, MAX_ROUTE_CANDIDATES, MAX_ROUTE_HOPS, MAX_SWAP_OPERATIONS};
 use dex::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated};
 use dex::factory::PoolType;
 
@@ -62,6 +63,7 @@ fn single_swap() {
             vec![SwapOperation::DexSwap {
                 offer_asset_info: AssetInfo::Cw20Token(token.to_string()),
                 ask_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
+                max_spread: None,
             }],
         )
         .unwrap();
@@ -118,14 +120,17 @@ fn multiple_swaps() {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
                     ask_asset_info: AssetInfo::Cw20Token(token_a.to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token(token_a.to_string()),
                     ask_asset_info: AssetInfo::SmartToken(uluna.to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken(uluna.to_string()),
                     ask_asset_info: AssetInfo::Cw20Token(token_b.to_string()),
+                    max_spread: None,
                 },
             ],
         )
@@ -192,10 +197,12 @@ fn multi_hop_does_not_enforce_spread_assetion() {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token(token_a.to_string()),
                     ask_asset_info: AssetInfo::Cw20Token(token_b.to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token(token_b.to_string()),
                     ask_asset_info: AssetInfo::Cw20Token(token_c.to_string()),
+                    max_spread: None,
                 },
             ],
         )
@@ -210,6 +217,7 @@ fn multi_hop_does_not_enforce_spread_assetion() {
             vec![SwapOperation::DexSwap {
                 offer_asset_info: AssetInfo::Cw20Token(token_a.to_string()),
                 ask_asset_info: AssetInfo::Cw20Token(token_b.to_string()),
+                max_spread: None,
             }],
         )
         .unwrap_err();
@@ -255,10 +263,12 @@ fn query_buy_with_routes() {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("ujuno".to_owned()),
                     ask_asset_info: AssetInfo::Cw20Token(token.to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token(token.to_string()),
                     ask_asset_info: AssetInfo::SmartToken("uluna".to_owned()),
+                    max_spread: None,
                 },
             ],
         )
@@ -280,10 +290,12 @@ fn query_buy_with_routes() {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken("ujuno".to_owned()),
                     ask_asset_info: AssetInfo::Cw20Token(token.to_string()),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::Cw20Token(token.to_string()),
                     ask_asset_info: AssetInfo::SmartToken("uluna".to_owned()),
+                    max_spread: None,
                 },
             ],
         )
@@ -341,10 +353,12 @@ fn simulation_with_fee() {
                 SwapOperation::DexSwap {
                     offer_asset_info: ujuno_info.clone(),
                     ask_asset_info: token_info.clone(),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: token_info.clone(),
                     ask_asset_info: uluna_info.clone(),
+                    max_spread: None,
                 },
             ],
         )
@@ -395,10 +409,12 @@ fn simulation_with_fee() {
                 SwapOperation::DexSwap {
                     offer_asset_info: ujuno_info.clone(),
                     ask_asset_info: token_info.clone(),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: token_info.clone(),
                     ask_asset_info: uluna_info.clone(),
+                    max_spread: None,
                 },
             ],
             Decimal::percent(1),
@@ -442,10 +458,12 @@ fn simulation_with_fee() {
                 SwapOperation::DexSwap {
                     offer_asset_info: ujuno_info,
                     ask_asset_info: token_info.clone(),
+                    max_spread: None,
                 },
                 SwapOperation::DexSwap {
                     offer_asset_info: token_info,
                     ask_asset_info: uluna_info,
+                    max_spread: None,
                 },
             ],
             Decimal::percent(1),
@@ -530,6 +548,109 @@ fn assert_minimum_receive_cw20tokens() {
     );
 }
 
+#[test]
+fn intermediate_minimum_receive() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uusd = "uusd";
+    let user = "user";
+    let whale = "whale";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(300_000, ujuno)])
+        .with_funds(whale, &[coin(100_000_000, ujuno)])
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(uluna.to_owned()), 1_000_000_000u128),
+            (AssetInfo::SmartToken(uusd.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, uluna), coin(1_000_000_000, uusd)],
+        )
+        .unwrap();
+
+    let first_hop = SwapOperation::DexSwap {
+        offer_asset_info: AssetInfo::SmartToken(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+        max_spread: None,
+    };
+    let operations = vec![
+        first_hop.clone(),
+        SwapOperation::DexSwap {
+            offer_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+            ask_asset_info: AssetInfo::SmartToken(uusd.to_owned()),
+            max_spread: None,
+        },
+    ];
+
+    // there has to be one minimum per operation
+    let err = suite
+        .swap_operations_with_minimums(user, coin(100_000u128, ujuno), operations.clone(), vec![1])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidMinimumReceives {},
+        err.downcast().unwrap()
+    );
+
+    // the user expects at least the currently simulated amount from the first hop
+    let expected_luna = suite
+        .query_simulate_swap_operations(100_000u128, vec![first_hop.clone()])
+        .unwrap()
+        .amount;
+    suite
+        .swap_operations_with_minimums(
+            user,
+            coin(100_000u128, ujuno),
+            operations.clone(),
+            vec![expected_luna.u128(), 0],
+        )
+        .unwrap();
+    let received = suite.query_balance(user, uusd).unwrap();
+    assert_ne!(received, 0);
+
+    // the price of the first hop moves before the next swap
+    let expected_luna = suite
+        .query_simulate_swap_operations(100_000u128, vec![first_hop.clone()])
+        .unwrap()
+        .amount;
+    suite
+        .swap_operations(whale, coin(100_000_000u128, ujuno), vec![first_hop])
+        .unwrap();
+
+    // the final output alone is not constrained, but the intermediate bound trips
+    let err = suite
+        .swap_operations_with_minimums(
+            user,
+            coin(100_000u128, ujuno),
+            operations.clone(),
+            vec![expected_luna.u128(), 0],
+        )
+        .unwrap_err();
+    match err.downcast().unwrap() {
+        ContractError::AssertionMinimumReceive { receive, amount } => {
+            assert_eq!(receive, expected_luna);
+            assert!(amount < expected_luna);
+        }
+        err => panic!("unexpected error: {err}"),
+    }
+    assert_eq!(suite.query_balance(user, uusd).unwrap(), received);
+
+    // without the bound, the swap goes through at the worse price
+    suite
+        .swap_operations_with_minimums(user, coin(100_000u128, ujuno), operations, vec![0, 0])
+        .unwrap();
+    assert!(suite.query_balance(user, uusd).unwrap() > received);
+}
+
 #[test]
 fn maximum_receive_swap_operations() {
     let ujuno = "ujuno";
@@ -558,6 +679,7 @@ fn maximum_receive_swap_operations() {
                 SwapOperation::DexSwap {
                     offer_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
                     ask_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+                    max_spread: None,
                 };
                 MAX_SWAP_OPERATIONS + 1
             ],
@@ -566,6 +688,66 @@ fn maximum_receive_swap_operations() {
     assert_eq!(ContractError::SwapLimitExceeded {}, err.downcast().unwrap());
 }
 
+#[test]
+fn configured_max_hops() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(200_000, ujuno)])
+        .with_max_hops(3)
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    // swaps back and forth between the two assets
+    let route = |hops: usize| -> Vec<SwapOperation> {
+        (0..hops)
+            .map(|i| {
+                let (offer, ask) = if i % 2 == 0 {
+                    (ujuno, uluna)
+                } else {
+                    (uluna, ujuno)
+                };
+                SwapOperation::DexSwap {
+                    offer_asset_info: AssetInfo::SmartToken(offer.to_owned()),
+                    ask_asset_info: AssetInfo::SmartToken(ask.to_owned()),
+                    max_spread: None,
+                }
+            })
+            .collect()
+    };
+
+    // exactly the maximum is allowed
+    suite
+        .query_simulate_swap_operations(100_000u128, route(3))
+        .unwrap();
+    suite
+        .swap_operations(user, coin(100_000u128, ujuno), route(3))
+        .unwrap();
+    assert_ne!(suite.query_balance(user, uluna).unwrap(), 0);
+
+    // one more operation is rejected
+    let err = suite
+        .query_simulate_swap_operations(100_000u128, route(4))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::SwapLimitExceeded {}.to_string()));
+    let err = suite
+        .swap_operations(user, coin(100_000u128, ujuno), route(4))
+        .unwrap_err();
+    assert_eq!(ContractError::SwapLimitExceeded {}, err.downcast().unwrap());
+}
+
 /// Tests the helper functions for calculating referral commission.
 /// Specifically, it tests the property that [`take_referral`] reverses the effect of [`add_referral`].
 #[test]
@@ -644,6 +826,7 @@ fn referral_single() {
             vec![SwapOperation::DexSwap {
                 offer_asset_info: AssetInfo::Cw20Token(token.to_string()),
                 ask_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
+                max_spread: None,
             }],
             referral.to_string(),
             Decimal::percent(1),
@@ -658,6 +841,345 @@ fn referral_single() {
     );
 }
 
+#[test]
+fn referral_above_updated_max() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+    let referral = "referral";
+
+    let mut suite = SuiteBuilder::new()
+        .with_max_referral_commission(Decimal::percent(1))
+        .with_funds(user, &[coin(200_000, ujuno)])
+        .build();
+
+    let owner = suite.owner.clone();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 100_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 100_000_000u128),
+            vec![coin(100_000_000, ujuno), coin(100_000_000, uluna)],
+        )
+        .unwrap();
+
+    // only the owner can lower the max referral commission
+    let err = suite
+        .update_max_referral_commission(user, Decimal::permille(5))
+        .unwrap_err();
+    assert_eq!(
+        dex_factory::error::ContractError::Unauthorized {},
+        err.downcast().unwrap()
+    );
+    suite
+        .update_max_referral_commission(&owner, Decimal::permille(5))
+        .unwrap();
+
+    let operations = vec![SwapOperation::DexSwap {
+        offer_asset_info: AssetInfo::SmartToken(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+        max_spread: None,
+    }];
+
+    // the previous max is now too high
+    let err = suite
+        .swap_operations_ref(
+            user,
+            coin(100_000u128, ujuno),
+            operations.clone(),
+            referral.to_string(),
+            Decimal::percent(1),
+        )
+        .unwrap_err();
+    assert_eq!(
+        dex::pool::ContractError::ReferralCommissionTooHigh {},
+        err.downcast().unwrap()
+    );
+
+    // while the new max works
+    suite
+        .swap_operations_ref(
+            user,
+            coin(100_000u128, ujuno),
+            operations,
+            referral.to_string(),
+            Decimal::permille(5),
+        )
+        .unwrap();
+    assert_eq!(suite.query_balance(referral, ujuno).unwrap(), 500u128);
+}
+
+#[test]
+fn swaps_blocked_until_updated_trading_start() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uatom = "uatom";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(200_000, ujuno)])
+        .build();
+
+    let owner = suite.owner.clone();
+
+    let pair = suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 100_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 100_000_000u128),
+            vec![coin(100_000_000, ujuno), coin(100_000_000, uluna)],
+        )
+        .unwrap();
+
+    let new_start = suite.block_time() + 1000;
+
+    // only the owner can update the trading start
+    let err = suite
+        .update_trading_starts(user, new_start, None)
+        .unwrap_err();
+    assert_eq!(
+        dex_factory::error::ContractError::Unauthorized {},
+        err.downcast().unwrap()
+    );
+    // and it cannot be in the past
+    let err = suite
+        .update_trading_starts(&owner, suite.block_time() - 1, None)
+        .unwrap_err();
+    assert_eq!(
+        dex_factory::error::ContractError::InvalidTradingStart {},
+        err.downcast().unwrap()
+    );
+    // the pool only accepts the update from the factory
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(&owner),
+            pair.clone(),
+            &dex::pool::ExecuteMsg::UpdateTradingStarts { new_start },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        dex::pool::ContractError::Unauthorized {},
+        err.downcast().unwrap()
+    );
+
+    suite
+        .update_trading_starts(&owner, new_start, None)
+        .unwrap();
+    assert_eq!(
+        query_factory_config(&suite.app.wrap(), &suite.factory)
+            .unwrap()
+            .trading_starts,
+        Some(new_start)
+    );
+
+    // the pool was already trading, so it is not blocked again
+    let operations = vec![SwapOperation::DexSwap {
+        offer_asset_info: AssetInfo::SmartToken(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+        max_spread: None,
+    }];
+    suite
+        .swap_operations(user, coin(100_000u128, ujuno), operations)
+        .unwrap();
+
+    // a new pool starts trading at the factory's trading start, which can be moved for that pool only
+    let asset_infos = vec![
+        AssetInfo::SmartToken(ujuno.to_owned()),
+        AssetInfo::SmartToken(uatom.to_owned()),
+    ];
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (asset_infos[0].clone(), 100_000_000u128),
+            (asset_infos[1].clone(), 100_000_000u128),
+            vec![coin(100_000_000, ujuno), coin(100_000_000, uatom)],
+        )
+        .unwrap();
+    let later_start = new_start + 500;
+    suite
+        .update_trading_starts(&owner, later_start, Some(vec![asset_infos.clone()]))
+        .unwrap();
+
+    let operations = vec![SwapOperation::DexSwap {
+        offer_asset_info: asset_infos[0].clone(),
+        ask_asset_info: asset_infos[1].clone(),
+        max_spread: None,
+    }];
+
+    suite.advance_time(1499);
+    let err = suite
+        .swap_operations(user, coin(100_000u128, ujuno), operations.clone())
+        .unwrap_err();
+    assert_eq!(
+        dex::pool::ContractError::TradingNotStarted {},
+        err.downcast().unwrap()
+    );
+
+    suite.advance_time(1);
+    suite
+        .swap_operations(user, coin(100_000u128, ujuno), operations)
+        .unwrap();
+    assert_eq!(suite.query_balance(user, ujuno).unwrap(), 0u128);
+}
+
+#[test]
+fn per_operation_max_spread() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uatom = "uatom";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(200_000, ujuno)])
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 100_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 100_000_000u128),
+            vec![coin(100_000_000, ujuno), coin(100_000_000, uluna)],
+        )
+        .unwrap();
+    // the second pool is shallow, so the second hop has a spread of about 9%
+    suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(uluna.to_owned()), 1_000_000u128),
+            (AssetInfo::SmartToken(uatom.to_owned()), 1_000_000u128),
+            vec![coin(1_000_000, uluna), coin(1_000_000, uatom)],
+        )
+        .unwrap();
+
+    let operations = |first_max_spread, second_max_spread| {
+        vec![
+            SwapOperation::DexSwap {
+                offer_asset_info: AssetInfo::SmartToken(ujuno.to_owned()),
+                ask_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+                max_spread: first_max_spread,
+            },
+            SwapOperation::DexSwap {
+                offer_asset_info: AssetInfo::SmartToken(uluna.to_owned()),
+                ask_asset_info: AssetInfo::SmartToken(uatom.to_owned()),
+                max_spread: second_max_spread,
+            },
+        ]
+    };
+
+    // the tight limit of the second hop fails the route, even though the route's limit is generous
+    let err = suite
+        .swap_operations_with_max_spread(
+            user,
+            coin(100_000u128, ujuno),
+            operations(Some(Decimal::percent(1)), Some(Decimal::percent(5))),
+            Decimal::percent(50),
+        )
+        .unwrap_err();
+    assert_eq!(
+        dex::pool::ContractError::MaxSpreadAssertion {},
+        err.downcast().unwrap()
+    );
+
+    // without a limit of its own, the hop is not checked as part of a multi hop route
+    suite
+        .swap_operations_with_max_spread(
+            user,
+            coin(100_000u128, ujuno),
+            operations(Some(Decimal::percent(1)), None),
+            Decimal::percent(50),
+        )
+        .unwrap();
+
+    // a limit that is large enough lets the second hop through
+    suite
+        .swap_operations_with_max_spread(
+            user,
+            coin(100_000u128, ujuno),
+            operations(Some(Decimal::percent(1)), Some(Decimal::percent(20))),
+            Decimal::percent(50),
+        )
+        .unwrap();
+    assert_eq!(suite.query_balance(user, ujuno).unwrap(), 0u128);
+    assert!(suite.query_balance(user, uatom).unwrap() > 0);
+}
+
+#[test]
+fn freeze_all_pools() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uatom = "uatom";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(
+            user,
+            &[
+                coin(1_000_000, ujuno),
+                coin(1_000_000, uluna),
+                coin(1_000_000, uatom),
+            ],
+        )
+        .build();
+
+    let owner = suite.owner.clone();
+
+    let pairs = [(ujuno, uluna), (uluna, uatom), (uatom, ujuno)];
+    for (first, second) in pairs {
+        suite
+            .create_pair_and_provide_liquidity(
+                PoolType::Xyk {},
+                (AssetInfo::SmartToken(first.to_owned()), 100_000_000u128),
+                (AssetInfo::SmartToken(second.to_owned()), 100_000_000u128),
+                vec![coin(100_000_000, first), coin(100_000_000, second)],
+            )
+            .unwrap();
+    }
+
+    let swap = |suite: &mut super::suite::Suite, offer: &str, ask: &str| {
+        suite.swap_operations(
+            user,
+            coin(10_000u128, offer),
+            vec![SwapOperation::DexSwap {
+                offer_asset_info: AssetInfo::SmartToken(offer.to_owned()),
+                ask_asset_info: AssetInfo::SmartToken(ask.to_owned()),
+                max_spread: None,
+            }],
+        )
+    };
+
+    // only the owner can freeze the pools
+    let err = suite.freeze_all_pools(user, true, None).unwrap_err();
+    assert_eq!(
+        dex_factory::error::ContractError::Unauthorized {},
+        err.downcast().unwrap()
+    );
+
+    // pools are frozen in pages
+    suite.freeze_all_pools(&owner, true, Some(2)).unwrap();
+    let frozen = pairs
+        .iter()
+        .filter(|(offer, ask)| swap(&mut suite, *offer, *ask).is_err())
+        .count();
+    assert_eq!(frozen, 2);
+
+    suite.freeze_all_pools(&owner, true, None).unwrap();
+    for (offer, ask) in pairs {
+        let err = swap(&mut suite, offer, ask).unwrap_err();
+        assert_eq!(
+            dex::pool::ContractError::ContractFrozen {},
+            err.downcast().unwrap()
+        );
+    }
+
+    suite.freeze_all_pools(&owner, false, None).unwrap();
+    for (offer, ask) in pairs {
+        swap(&mut suite, offer, ask).unwrap();
+    }
+}
+
 #[test]
 fn referral_multiple() {
     let ujuno = "ujuno";
@@ -704,14 +1226,17 @@ fn referral_multiple() {
         SwapOperation::DexSwap {
             offer_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
             ask_asset_info: AssetInfo::Cw20Token(token_a.to_string()),
+            max_spread: None,
         },
         SwapOperation::DexSwap {
             offer_asset_info: AssetInfo::Cw20Token(token_a.to_string()),
             ask_asset_info: AssetInfo::SmartToken(uluna.to_string()),
+            max_spread: None,
         },
         SwapOperation::DexSwap {
             offer_asset_info: AssetInfo::SmartToken(uluna.to_string()),
             ask_asset_info: AssetInfo::Cw20Token(token_b.to_string()),
+            max_spread: None,
         },
     ];
 
@@ -784,6 +1309,7 @@ fn invalid_referral_commission() {
             vec![SwapOperation::DexSwap {
                 offer_asset_info: AssetInfo::Cw20Token(token.to_string()),
                 ask_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
+                max_spread: None,
             }],
             referral.to_string(),
             Decimal::percent(2),
@@ -832,6 +1358,7 @@ fn referral_commission_zero() {
             vec![SwapOperation::DexSwap {
                 offer_asset_info: AssetInfo::Cw20Token(token.to_string()),
                 ask_asset_info: AssetInfo::SmartToken(ujuno.to_string()),
+                max_spread: None,
             }],
             referral.to_string(),
             Decimal::from_ratio(1u128, 10_000u128),
@@ -841,3 +1368,219 @@ fn referral_commission_zero() {
     // make sure referral commission is zero
     assert_eq!(suite.query_balance(referral, ujuno).unwrap(), 0u128);
 }
+
+#[test]
+fn find_best_route_in_diamond() {
+    let user = "user";
+    let [a, b, c, d] =
+        ["ua", "ub", "uc", "ud"].map(|denom| AssetInfo::SmartToken(denom.to_string()));
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, "ua")])
+        .build();
+
+    // A - B - D has less liquidity than A - C - D, so it has a higher price impact
+    for (first, second, amount) in [
+        (&a, &b, 1_000_000u128),
+        (&b, &d, 1_000_000),
+        (&a, &c, 1_000_000_000),
+        (&c, &d, 1_000_000_000),
+    ] {
+        let denoms = [first, second].map(|info| match info {
+            AssetInfo::SmartToken(denom) => denom.clone(),
+            AssetInfo::Cw20Token(_) => unreachable!(),
+        });
+        suite
+            .create_pair_and_provide_liquidity(
+                PoolType::Xyk {},
+                (first.clone(), amount),
+                (second.clone(), amount),
+                vec![coin(amount, &denoms[0]), coin(amount, &denoms[1])],
+            )
+            .unwrap();
+    }
+
+    let offer_asset = a.with_balance(100_000u128);
+    let via_c = vec![
+        SwapOperation::DexSwap {
+            offer_asset_info: a.clone(),
+            ask_asset_info: c.clone(),
+            max_spread: None,
+        },
+        SwapOperation::DexSwap {
+            offer_asset_info: c.clone(),
+            ask_asset_info: d.clone(),
+            max_spread: None,
+        },
+    ];
+    let via_b = vec![
+        SwapOperation::DexSwap {
+            offer_asset_info: a.clone(),
+            ask_asset_info: b.clone(),
+            max_spread: None,
+        },
+        SwapOperation::DexSwap {
+            offer_asset_info: b.clone(),
+            ask_asset_info: d.clone(),
+            max_spread: None,
+        },
+    ];
+
+    let best = suite
+        .query_find_best_route(offer_asset.clone(), d.clone(), None)
+        .unwrap();
+    assert_eq!(best.operations, via_c);
+    let best_operations = best.operations.clone();
+    let via_c_amount = suite
+        .query_simulate_swap_operations(100_000u128, via_c.clone())
+        .unwrap()
+        .amount;
+    let via_b_amount = suite
+        .query_simulate_swap_operations(100_000u128, via_b)
+        .unwrap()
+        .amount;
+    assert_eq!(best.amount, via_c_amount);
+    assert!(via_c_amount > via_b_amount);
+
+    // direct neighbours are found with a single hop
+    let neighbour = suite
+        .query_find_best_route(offer_asset.clone(), c, 1)
+        .unwrap();
+    assert_eq!(neighbour.operations, via_c[..1].to_vec());
+
+    // D cannot be reached with a single hop
+    let err = suite
+        .query_find_best_route(offer_asset.clone(), d.clone(), 1)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::NoRouteFound {}.to_string()));
+
+    // the number of hops is capped
+    let err = suite
+        .query_find_best_route(offer_asset.clone(), d.clone(), MAX_ROUTE_HOPS + 1)
+        .unwrap_err();
+    assert!(err.to_string().contains(
+        &ContractError::InvalidMaxHops {
+            max: MAX_ROUTE_HOPS
+        }
+        .to_string()
+    ));
+
+    // the found route can be executed right away
+    suite
+        .swap_operations(user, coin(100_000u128, "ua"), best_operations)
+        .unwrap();
+    assert_eq!(suite.query_balance(user, "ud").unwrap(), best.amount.u128());
+}
+
+#[test]
+fn find_best_route_prefers_fewer_hops_when_capped() {
+    let smart_token = |denom: &str| AssetInfo::SmartToken(denom.to_string());
+    let [a, y, z, d] = ["ua", "uy", "uz", "ud"].map(smart_token);
+
+    let mut suite = SuiteBuilder::new().build();
+
+    // enough two hop routes with little liquidity to reach the candidate limit
+    let mut pools = vec![];
+    for i in 0..MAX_ROUTE_CANDIDATES {
+        let x = format!("ux{i}");
+        pools.push(("ua".to_string(), x.clone(), 1_000_000u128));
+        pools.push((x, "ud".to_string(), 1_000_000));
+    }
+    // a three hop route with a lot more liquidity
+    for (first, second) in [("ua", "uy"), ("uy", "uz"), ("uz", "ud")] {
+        pools.push((first.to_string(), second.to_string(), 1_000_000_000));
+    }
+    for (first, second, amount) in pools {
+        suite
+            .create_pair_and_provide_liquidity(
+                PoolType::Xyk {},
+                (smart_token(&first), amount),
+                (smart_token(&second), amount),
+                vec![coin(amount, &first), coin(amount, &second)],
+            )
+            .unwrap();
+    }
+
+    let best = suite
+        .query_find_best_route(a.with_balance(100_000u128), d.clone(), 3)
+        .unwrap();
+    assert_eq!(best.operations.len(), 2);
+
+    // the three hop route would have been better, but it was not simulated
+    let via_y_z = [(&a, &y), (&y, &z), (&z, &d)]
+        .map(|(offer, ask)| SwapOperation::DexSwap {
+            offer_asset_info: offer.clone(),
+            ask_asset_info: ask.clone(),
+            max_spread: None,
+        })
+        .to_vec();
+    let via_y_z_amount = suite
+        .query_simulate_swap_operations(100_000u128, via_y_z)
+        .unwrap()
+        .amount;
+    assert!(via_y_z_amount > best.amount);
+}
+
+#[test]
+fn pool_accumulates_protocol_fees() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+    let fee_address = "fee";
+
+    // fee is 1%, half of which goes to the protocol
+    let mut suite = SuiteBuilder::new()
+        .with_fees(100, 5000)
+        .with_fee_address(fee_address)
+        .with_funds(user, &[coin(10_000_000, ujuno), coin(10_000_000, uluna)])
+        .build();
+
+    let ujuno_info = AssetInfo::SmartToken(ujuno.to_owned());
+    let uluna_info = AssetInfo::SmartToken(uluna.to_owned());
+
+    let pair = suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (ujuno_info, 1_000_000_000u128),
+            (uluna_info, 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    // nothing collected yet
+    let fees = suite.query_collected_protocol_fees(&pair).unwrap();
+    assert_eq!(fees.len(), 2);
+    assert!(fees.iter().all(|fee| fee.amount.is_zero()));
+
+    let swap = |suite: &mut Suite, offer: &str, ask: &str, amount: u128| {
+        suite
+            .swap_operations(
+                user,
+                coin(amount, offer),
+                vec![SwapOperation::DexSwap {
+                    offer_asset_info: AssetInfo::SmartToken(offer.to_owned()),
+                    ask_asset_info: AssetInfo::SmartToken(ask.to_owned()),
+                    max_spread: None,
+                }],
+            )
+            .unwrap();
+    };
+    swap(&mut suite, ujuno, uluna, 1_000_000);
+    swap(&mut suite, uluna, ujuno, 2_000_000);
+    swap(&mut suite, ujuno, uluna, 3_000_000);
+    swap(&mut suite, uluna, ujuno, 500_000);
+
+    // the totals match what was sent to the fee address
+    let fees = suite.query_collected_protocol_fees(&pair).unwrap();
+    assert_eq!(fees.len(), 2);
+    for fee in fees {
+        let denom = fee.info.to_string();
+        assert!(!fee.amount.is_zero());
+        assert_eq!(
+            fee.amount.u128(),
+            suite.query_balance(fee_address, &denom).unwrap()
+        );
+    }
+}
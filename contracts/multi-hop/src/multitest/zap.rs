@@ -0,0 +1,155 @@
+use super::suite::SuiteBuilder;
+
+use cosmwasm_std::{coin, Decimal, Uint128};
+use dex::asset::{AssetInfo, AssetInfoValidated, AssetValidated};
+use dex::factory::PoolType;
+use dex::pool::ContractError;
+use dex_pool::contract::compute_swap;
+
+#[test]
+fn zap_out_to_single_asset() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_fees(30, 0)
+        .with_funds(user, &[coin(500_000, ujuno)])
+        .build();
+
+    let pair = suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 100_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 100_000_000u128),
+            vec![coin(100_000_000, ujuno), coin(100_000_000, uluna)],
+        )
+        .unwrap();
+    let lp_token = suite.query_pair_info(&pair).unwrap().liquidity_token;
+
+    suite
+        .zap_in(user, &pair, coin(500_000, ujuno), None)
+        .unwrap();
+    let lp_balance = suite.query_balance(user, &lp_token).unwrap();
+
+    // the withdrawn ujuno is swapped in the pool without the withdrawn liquidity
+    let pool = suite.query_pool(&pair).unwrap();
+    let share = suite.query_share(&pair, lp_balance).unwrap();
+    let amount_of = |assets: &[AssetValidated], denom: &str| {
+        assets
+            .iter()
+            .find(|a| a.info == AssetInfoValidated::SmartToken(denom.to_owned()))
+            .unwrap()
+            .amount
+    };
+    let (return_amount, _, _) = compute_swap(
+        amount_of(&pool.assets, ujuno) - amount_of(&share, ujuno),
+        amount_of(&pool.assets, uluna) - amount_of(&share, uluna),
+        amount_of(&share, ujuno),
+        Decimal::permille(3),
+    )
+    .unwrap();
+    let expected = (amount_of(&share, uluna) + return_amount).u128();
+
+    // the internal swap moves the price by about 0.25%
+    let err = suite
+        .zap_out(
+            user,
+            &pair,
+            lp_balance,
+            uluna,
+            Some(Decimal::permille(1)),
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MaxSpreadAssertion {},
+        err.downcast().unwrap()
+    );
+
+    let err = suite
+        .zap_out(
+            user,
+            &pair,
+            lp_balance,
+            uluna,
+            Some(Decimal::percent(1)),
+            Some(expected + 1),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MinimumReceiveAssertion {
+            return_amount: Uint128::new(expected),
+            minimum_receive: Uint128::new(expected + 1),
+        },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .zap_out(
+            user,
+            &pair,
+            lp_balance,
+            uluna,
+            Some(Decimal::percent(1)),
+            Some(expected),
+        )
+        .unwrap();
+    assert_eq!(suite.query_balance(user, &lp_token).unwrap(), 0);
+    assert_eq!(suite.query_balance(user, ujuno).unwrap(), 0);
+    assert_eq!(suite.query_balance(user, uluna).unwrap(), expected);
+}
+
+#[test]
+fn zap_out_after_withdraw() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_fees(30, 0)
+        .with_funds(user, &[coin(500_000, ujuno)])
+        .build();
+
+    let pair = suite
+        .create_pair_and_provide_liquidity(
+            PoolType::Xyk {},
+            (AssetInfo::SmartToken(ujuno.to_owned()), 100_000_000u128),
+            (AssetInfo::SmartToken(uluna.to_owned()), 100_000_000u128),
+            vec![coin(100_000_000, ujuno), coin(100_000_000, uluna)],
+        )
+        .unwrap();
+    let lp_token = suite.query_pair_info(&pair).unwrap().liquidity_token;
+
+    suite
+        .zap_in(user, &pair, coin(500_000, ujuno), None)
+        .unwrap();
+    let lp_balance = suite.query_balance(user, &lp_token).unwrap();
+    let total_share = suite.query_pool(&pair).unwrap().total_share;
+
+    // withdrawing only burns the withdrawn share
+    let withdrawn = lp_balance / 2;
+    suite.withdraw_liquidity(user, &pair, withdrawn).unwrap();
+    assert_eq!(
+        suite.query_pool(&pair).unwrap().total_share,
+        total_share - Uint128::new(withdrawn)
+    );
+
+    // so the rest can still be zapped out
+    suite
+        .zap_out(
+            user,
+            &pair,
+            lp_balance - withdrawn,
+            uluna,
+            Some(Decimal::percent(1)),
+            None,
+        )
+        .unwrap();
+    assert_eq!(suite.query_balance(user, &lp_token).unwrap(), 0);
+    assert_eq!(
+        suite.query_pool(&pair).unwrap().total_share,
+        total_share - Uint128::new(lp_balance)
+    );
+    assert!(suite.query_balance(user, uluna).unwrap() > 0);
+}
@@ -2,10 +2,19 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Addr;
 use cw_storage_plus::Item;
 
+use crate::msg::MAX_SWAP_OPERATIONS;
+
 pub const CONFIG: Item<Config> = Item::new("config");
 
 #[cw_serde]
 pub struct Config {
     /// The factory contract address
     pub dex_factory: Addr,
+    /// The maximum number of swap operations in a single swap or simulation
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u32,
+}
+
+fn default_max_hops() -> u32 {
+    MAX_SWAP_OPERATIONS as u32
 }
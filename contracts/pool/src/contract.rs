@@ -16,24 +16,29 @@ use cw20::Cw20ReceiveMsg;
 
 use dex::{
     asset::{
-        addr_opt_validate, check_swap_parameters, format_lp_token_name, Asset, AssetInfoValidated,
-        AssetValidated, MINIMUM_LIQUIDITY_AMOUNT,
+        addr_opt_validate, check_swap_parameters, format_lp_token_name, Asset, AssetInfo,
+        AssetInfoValidated, AssetValidated, MINIMUM_LIQUIDITY_AMOUNT,
     },
     decimal2decimal256,
-    factory::{ConfigResponse as FactoryConfig, PoolType},
+    factory::{ConfigResponse as FactoryConfig, PoolType, MAX_TRADING_STARTS_DELAY},
     fee_config::FeeConfig,
     pool::{
         add_referral, assert_max_spread, check_asset_infos, check_assets, check_cw20_in_pool,
-        get_share_in_assets, handle_referral, handle_reply, save_tmp_staking_config, take_referral,
-        ConfigResponse, ContractError, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg,
-        InstantiateMsg, MigrateMsg, PairInfo, PoolResponse, QueryMsg, ReverseSimulationResponse,
-        SimulationResponse, DEFAULT_SLIPPAGE, INSTANTIATE_STAKE_REPLY_ID, LP_TOKEN_PRECISION,
+        check_lp_funds, get_share_in_assets, handle_referral, handle_reply,
+        save_tmp_staking_config, take_referral, CollectedProtocolFeesResponse, ConfigResponse,
+        ContractError, CumulativePricesResponse, Cw20HookMsg, DepthResponse, ExecuteMsg,
+        InstantiateMsg, InvariantResponse, MigrateMsg, OfferToMovePriceResponse, PairInfo,
+        PoolResponse, QueryMsg, RebalanceToResponse, ReverseSimulationResponse, SimulationResponse,
+        SpotPriceResponse, SwapFeeResponse, TickerResponse, TradingStartedResponse,
+        DEFAULT_SLIPPAGE, DEPTH_MAX_PRICE_IMPACT, INSTANTIATE_STAKE_REPLY_ID, LP_TOKEN_PRECISION,
         MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
     },
     querier::query_factory_config,
 };
 
-use crate::state::{Config, CIRCUIT_BREAKER, CONFIG, FROZEN, LP_SHARE_AMOUNT};
+use crate::state::{
+    Config, CIRCUIT_BREAKER, CONFIG, FROZEN, LP_SHARE_AMOUNT, PROTOCOL_FEES_COLLECTED,
+};
 
 pub type Response = cosmwasm_std::Response<CoreumMsg>;
 pub type SubMsg = cosmwasm_std::SubMsg<CoreumMsg>;
@@ -60,17 +65,23 @@ pub fn instantiate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     msg.validate_fees()?;
+    msg.validate_trading_starts(env.block.time)?;
+    msg.validate_initial_oracle_price()?;
 
     let factory_addr = deps.api.addr_validate(msg.factory_addr.as_str())?;
 
     let lp_token_name = format_lp_token_name(&asset_infos, &deps.querier)?;
 
+    let existing_staking_addr = addr_opt_validate(deps.api, &msg.existing_staking_addr)?;
+
     let lp_share_denom: String = format!("u{}-{}", lp_token_name.clone(), env.contract.address);
     let config = Config {
         pool_info: PairInfo {
             contract_addr: env.contract.address.clone(),
             liquidity_token: lp_share_denom.clone(),
-            staking_addr: Addr::unchecked(""),
+            staking_addr: existing_staking_addr
+                .clone()
+                .unwrap_or_else(|| Addr::unchecked("")),
             asset_infos,
             pool_type: PoolType::Xyk {},
             fee_config: msg.fee_config,
@@ -78,18 +89,22 @@ pub fn instantiate(
         },
         factory_addr,
         block_time_last: 0,
-        price0_cumulative_last: Uint128::zero(),
-        price1_cumulative_last: Uint128::zero(),
+        price0_cumulative_last: Uint256::zero(),
+        price1_cumulative_last: Uint256::zero(),
         trading_starts: msg.trading_starts,
     };
 
     CONFIG.save(deps.storage, &config)?;
     FROZEN.save(deps.storage, &false)?;
     LP_SHARE_AMOUNT.save(deps.storage, &Uint128::zero())?;
-    save_tmp_staking_config(deps.storage, &msg.staking_config)?;
 
-    Ok(Response::new()
-        .add_submessage(SubMsg::new(CoreumMsg::AssetFT(assetft::Msg::Issue {
+    // seed the oracle, it is reinitialized with the real price on the first liquidity provision
+    if let Some(price) = msg.initial_oracle_price {
+        dex::oracle::initialize_oracle(deps.storage, &env, price)?;
+    }
+
+    let res =
+        Response::new().add_submessage(SubMsg::new(CoreumMsg::AssetFT(assetft::Msg::Issue {
             symbol: lp_token_name.clone(),
             subunit: format!("u{}", lp_token_name),
             precision: LP_TOKEN_PRECISION,
@@ -98,25 +113,37 @@ pub fn instantiate(
             features: Some(vec![0, 1, 2]), // 0 - minting, 1 - burning, 2 - freezing
             burn_rate: Some("0".into()),
             send_commission_rate: Some("0.00000".into()),
-        })))
-        .add_submessage(SubMsg::reply_on_success(
-            WasmMsg::Instantiate {
-                code_id: msg.staking_config.staking_code_id,
-                msg: to_json_binary(&dex::stake::InstantiateMsg {
-                    lp_share_denom,
-                    tokens_per_power: msg.staking_config.tokens_per_power,
-                    min_bond: msg.staking_config.min_bond,
-                    unbonding_periods: msg.staking_config.unbonding_periods,
-                    max_distributions: msg.staking_config.max_distributions,
-                    admin: Some(info.sender.to_string()),
-                    unbonder: None, // TODO: allow specifying unbonder
-                })?,
-                funds: vec![],
+        })));
+
+    // no need for a new staking contract if an existing one is used
+    if existing_staking_addr.is_some() {
+        return Ok(res);
+    }
+
+    save_tmp_staking_config(deps.storage, &msg.staking_config)?;
+
+    Ok(res.add_submessage(SubMsg::reply_on_success(
+        WasmMsg::Instantiate {
+            code_id: msg.staking_config.staking_code_id,
+            msg: to_json_binary(&dex::stake::InstantiateMsg {
+                lp_share_denom,
+                tokens_per_power: msg.staking_config.tokens_per_power,
+                min_bond: msg.staking_config.min_bond,
+                unbonding_periods: msg.staking_config.unbonding_periods,
+                max_distributions: msg.staking_config.max_distributions,
                 admin: Some(info.sender.to_string()),
-                label: String::from("Dex-Stake"),
-            },
-            INSTANTIATE_STAKE_REPLY_ID,
-        )))
+                unbonder: None, // TODO: allow specifying unbonder
+                backup_unbonders: vec![],
+                unbond_grace_period: 0,
+                min_distribution_interval: 0,
+                auto_claim_on_delegate: msg.staking_config.auto_claim_on_delegate,
+            })?,
+            funds: vec![],
+            admin: Some(info.sender.to_string()),
+            label: String::from("Dex-Stake"),
+        },
+        INSTANTIATE_STAKE_REPLY_ID,
+    )))
 }
 
 /// Manages the contract migration.
@@ -169,12 +196,28 @@ pub fn reply(
 ///             receiver,
 ///         }** Provides liquidity in the pool with the specified input parameters.
 ///
+/// * **ExecuteMsg::ZapIn {
+///             offer_asset,
+///             min_share,
+///             receiver,
+///         }** Swaps part of a single asset and provides liquidity with both results.
+///
+/// * **ExecuteMsg::ZapOut {
+///             share,
+///             ask_asset,
+///             receiver,
+///             max_spread,
+///             minimum_receive,
+///         }** Withdraws liquidity and swaps the other asset into the ask asset.
+///
 /// * **ExecuteMsg::Swap {
 ///             offer_asset,
 ///             belief_price,
 ///             max_spread,
 ///             to,
 ///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::Donate { assets }** Adds assets to the pool without minting LP tokens.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<CoreumQueries>,
@@ -189,6 +232,11 @@ pub fn execute(
             slippage_tolerance,
             receiver,
         } => provide_liquidity(deps, env, info, assets, slippage_tolerance, receiver),
+        ExecuteMsg::ZapIn {
+            offer_asset,
+            min_share,
+            receiver,
+        } => zap_in(deps, env, info, offer_asset, min_share, receiver),
         ExecuteMsg::UpdateFees { fee_config } => update_fees(deps, info, fee_config),
         ExecuteMsg::Swap {
             offer_asset,
@@ -222,16 +270,39 @@ pub fn execute(
         }
         ExecuteMsg::Freeze { frozen } => {
             ensure!(
-                info.sender
-                    == CIRCUIT_BREAKER
-                        .may_load(deps.storage)?
-                        .unwrap_or_else(|| Addr::unchecked("")),
+                info.sender == CONFIG.load(deps.storage)?.factory_addr
+                    || info.sender
+                        == CIRCUIT_BREAKER
+                            .may_load(deps.storage)?
+                            .unwrap_or_else(|| Addr::unchecked("")),
                 ContractError::Unauthorized {}
             );
             FROZEN.save(deps.storage, &frozen)?;
             Ok(Response::new())
         }
         ExecuteMsg::WithdrawLiquidity { .. } => withdraw_liquidity(deps, env, info),
+        ExecuteMsg::ZapOut {
+            share,
+            ask_asset,
+            receiver,
+            max_spread,
+            minimum_receive,
+        } => zap_out(
+            deps,
+            env,
+            info,
+            share,
+            ask_asset,
+            receiver,
+            max_spread,
+            minimum_receive,
+        ),
+        ExecuteMsg::UpdateOracle {} => update_oracle(deps, env),
+        ExecuteMsg::Donate { assets } => donate(deps, env, info, assets),
+        ExecuteMsg::UpdateStakingAddr { new_addr } => update_staking_addr(deps, info, new_addr),
+        ExecuteMsg::UpdateTradingStarts { new_start } => {
+            update_trading_starts(deps, env, info, new_start)
+        }
         _ => Err(ContractError::NonSupported {}),
     }
 }
@@ -303,6 +374,67 @@ pub fn update_fees(
     Ok(Response::default())
 }
 
+/// Updates the address of the staking contract recorded in the pool info.
+///
+/// * **new_addr** is the address of the new staking contract.
+///
+/// ## Executor
+/// Only the factory can execute this.
+pub fn update_staking_addr(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    new_addr: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // check permissions
+    if info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.pool_info.staking_addr = deps.api.addr_validate(&new_addr)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_staking_addr"),
+        attr("staking_addr", config.pool_info.staking_addr),
+    ]))
+}
+
+/// Moves the block time until which trading is disabled.
+///
+/// * **new_start** is the new trading start, at most [`MAX_TRADING_STARTS_DELAY`] seconds in the future.
+///
+/// ## Executor
+/// Only the factory can execute this.
+pub fn update_trading_starts(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    new_start: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // check permissions
+    if info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if new_start > env.block.time.seconds() + MAX_TRADING_STARTS_DELAY {
+        return Err(ContractError::InvalidTradingStart {
+            max_delay: MAX_TRADING_STARTS_DELAY,
+        });
+    }
+
+    config.trading_starts = new_start;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_trading_starts"),
+        attr("trading_starts", new_start.to_string()),
+    ]))
+}
+
 /// Provides liquidity in the pool with the specified input parameters.
 ///
 /// * **assets** is an array with assets available in the pool.
@@ -316,12 +448,66 @@ pub fn update_fees(
 ///
 /// NOTE - the address that wants to provide liquidity should approve the pool contract to pull its relevant tokens.
 pub fn provide_liquidity(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    add_liquidity(
+        deps,
+        env,
+        info,
+        assets,
+        slippage_tolerance,
+        receiver,
+        false,
+        None,
+    )
+}
+
+/// Provides liquidity with a single asset. Unlike a single asset [`provide_liquidity`], which swaps half of it,
+/// this swaps the amount returned by [`compute_zap_swap_amount`], so that both deposits are in the pool ratio
+/// and (up to rounding) all of the asset is accounted for in the minted LP tokens.
+///
+/// * **offer_asset** is the asset to provide.
+///
+/// * **min_share** is an optional minimum amount of LP tokens that have to be minted.
+///
+/// * **receiver** is an optional parameter which defines the receiver of the LP tokens.
+/// If no custom receiver is specified, the pool will mint LP tokens for the function caller.
+pub fn zap_in(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    offer_asset: Asset,
+    min_share: Option<Uint128>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    add_liquidity(
+        deps,
+        env,
+        info,
+        vec![offer_asset],
+        None,
+        receiver,
+        true,
+        min_share,
+    )
+}
+
+/// Shared implementation of [`provide_liquidity`] and [`zap_in`].
+#[allow(clippy::too_many_arguments)]
+fn add_liquidity(
     mut deps: DepsMut<CoreumQueries>,
     env: Env,
     info: MessageInfo,
     assets: Vec<Asset>,
     slippage_tolerance: Option<Decimal>,
     receiver: Option<String>,
+    zap: bool,
+    min_share: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let mut assets = check_assets(deps.api, &assets)?;
     check_if_frozen(&deps)?;
@@ -380,15 +566,24 @@ pub fn provide_liquidity(
             return Err(ContractError::InvalidProvideLPsWithSingleToken {});
         }
 
-        // use half for swapping
+        let swap_amount = if zap {
+            compute_zap_swap_amount(
+                pools[pool_indices[0]].amount,
+                offer_asset.amount,
+                config.pool_info.fee_config.total_fee_rate(),
+            )?
+        } else {
+            // use half for swapping
+            offer_asset.amount / Uint128::from(2u128)
+        };
         let input_asset = AssetValidated {
             info: offer_asset.info.clone(),
-            amount: offer_asset.amount / Uint128::from(2u128),
+            amount: swap_amount,
         };
 
         // Get config from the factory
         let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
-        // swap half of the asset for the other first
+        // swap part of the asset for the other first
         let SwapResult {
             return_asset,
             protocol_fee_msg,
@@ -417,12 +612,12 @@ pub fn provide_liquidity(
         pools[pool_indices[0]].amount += input_asset.amount;
         pools[pool_indices[1]].amount -= return_asset.amount;
 
-        // now pretend the other half and the returned assets were sent
-        let remaining_half = AssetValidated {
+        // now pretend the rest and the returned assets were sent
+        let remaining = AssetValidated {
             info: input_asset.info,
             amount: offer_asset.amount - input_asset.amount,
         };
-        assets = vec![remaining_half, return_asset];
+        assets = vec![remaining, return_asset];
     }
 
     let deposits = [
@@ -490,6 +685,12 @@ pub fn provide_liquidity(
         )
     };
 
+    if let Some(min_share) = min_share {
+        if share < min_share {
+            return Err(ContractError::MinShareAssertion { share, min_share });
+        }
+    }
+
     // Mint LP tokens for the sender or for the receiver (if set)
     let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
     messages.push(CosmosMsg::Custom(CoreumMsg::AssetFT(assetft::Msg::Mint {
@@ -530,7 +731,7 @@ pub fn provide_liquidity(
     }
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "provide_liquidity"),
+        attr("action", if zap { "zap_in" } else { "provide_liquidity" }),
         attr("sender", info.sender),
         attr("receiver", receiver),
         attr("assets", format!("{}, {}", assets[0], assets[1])),
@@ -549,12 +750,8 @@ pub fn withdraw_liquidity(
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage).unwrap();
 
-    if info.funds[0].denom.clone() != config.pool_info.liquidity_token.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
-
+    let amount = check_lp_funds(&info.funds, &config.pool_info.liquidity_token)?;
     let sender = info.sender.clone();
-    let amount = info.funds[0].amount;
 
     let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
@@ -589,9 +786,8 @@ pub fn withdraw_liquidity(
             coin: coin(amount.u128(), &config.pool_info.liquidity_token),
         })),
     ];
-    LP_SHARE_AMOUNT.update(deps.storage, |mut amount| -> StdResult<_> {
-        amount -= amount;
-        Ok(amount)
+    LP_SHARE_AMOUNT.update(deps.storage, |total| -> StdResult<_> {
+        Ok(total.checked_sub(amount)?)
     })?;
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
@@ -605,6 +801,220 @@ pub fn withdraw_liquidity(
     ]))
 }
 
+/// Withdraws liquidity from the pool and swaps the withdrawn other asset into the ask asset,
+/// so the whole position is returned as the ask asset.
+///
+/// * **share** is the amount of LP tokens to burn. Exactly this amount has to be sent along.
+///
+/// * **ask_asset** is the pool asset to receive.
+///
+/// * **receiver** is an optional parameter which defines the receiver of the ask asset.
+/// If no custom receiver is specified, the pool will send it to the function caller.
+///
+/// * **max_spread** is the max spread allowed for the internal swap.
+///
+/// * **minimum_receive** is an optional minimum amount of the ask asset to receive in total.
+#[allow(clippy::too_many_arguments)]
+pub fn zap_out(
+    mut deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    share: Uint128,
+    ask_asset: AssetInfo,
+    receiver: Option<String>,
+    max_spread: Option<Decimal>,
+    minimum_receive: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    check_if_frozen(&deps)?;
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let amount = check_lp_funds(&info.funds, &config.pool_info.liquidity_token)?;
+    if amount != share {
+        return Err(ContractError::InvalidLpFunds(
+            config.pool_info.liquidity_token,
+        ));
+    }
+    let ask_asset = ask_asset.validate(deps.api)?;
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    let ask_index = pools
+        .iter()
+        .position(|pool| pool.info.equal(&ask_asset))
+        .ok_or_else(|| ContractError::InvalidAsset(ask_asset.to_string()))?;
+    let refund_assets = get_share_in_assets(&pools, share, total_share);
+
+    // Accumulate prices for the pool assets before the withdrawal changes them
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(&env, &config, pools[0].amount, pools[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    // the withdrawn assets are no longer part of the pool
+    let new_pools = pools
+        .iter()
+        .zip(refund_assets.iter())
+        .map(|(p, r)| AssetValidated {
+            info: p.info.clone(),
+            amount: p.amount - r.amount,
+        })
+        .collect::<Vec<_>>();
+
+    // swap the other withdrawn asset for the ask asset
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let SwapResult {
+        return_asset,
+        protocol_fee_msg,
+        ..
+    } = do_swap(
+        deps.branch(),
+        &env,
+        &mut config,
+        &factory_config,
+        &new_pools,
+        &refund_assets[1 - ask_index],
+        None,
+        max_spread,
+    )?;
+
+    let return_amount = refund_assets[ask_index].amount + return_asset.amount;
+    if let Some(minimum_receive) = minimum_receive {
+        if return_amount < minimum_receive {
+            return Err(ContractError::MinimumReceiveAssertion {
+                return_amount,
+                minimum_receive,
+            });
+        }
+    }
+
+    let mut messages: Vec<CosmosMsg<CoreumMsg>> = vec![
+        AssetValidated {
+            info: ask_asset,
+            amount: return_amount,
+        }
+        .into_msg(&receiver)?,
+        CosmosMsg::Custom(CoreumMsg::AssetFT(assetft::Msg::Burn {
+            coin: coin(share.u128(), &config.pool_info.liquidity_token),
+        })),
+    ];
+    messages.extend(protocol_fee_msg);
+    LP_SHARE_AMOUNT.update(deps.storage, |total| -> StdResult<_> {
+        Ok(total.checked_sub(share)?)
+    })?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "zap_out"),
+        attr("sender", info.sender),
+        attr("receiver", receiver),
+        attr("withdrawn_share", share),
+        attr(
+            "refund_assets",
+            format!("{}, {}", refund_assets[0], refund_assets[1]),
+        ),
+        attr("return_amount", return_amount),
+    ]))
+}
+
+/// Stores the current pool price in the oracle and accumulates the cumulative prices,
+/// so the TWAP stays accurate even if the pool is not traded for a while.
+pub fn update_oracle(deps: DepsMut<CoreumQueries>, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    if total_share.is_zero() {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let price = Decimal::from_ratio(pools[0].amount, pools[1].amount);
+    dex::oracle::store_oracle_price(deps.storage, &env, price)?;
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(&env, &config, pools[0].amount, pools[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_oracle"),
+        attr("price", price.to_string()),
+    ]))
+}
+
+/// Adds the given assets to the pool reserves without minting LP tokens.
+/// The donated value is distributed to all existing LPs through the increased share value.
+///
+/// * **assets** are the assets to donate. All of them must be part of the pool.
+pub fn donate(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+) -> Result<Response, ContractError> {
+    let assets = check_assets(deps.api, &assets)?;
+    check_if_frozen(&deps)?;
+
+    if assets.is_empty() || assets.iter().any(|a| a.amount.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let (mut pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    // without any LPs there is nobody to donate to
+    if total_share.is_zero() {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let mut messages: Vec<CosmosMsg<CoreumMsg>> = vec![];
+    let mut new_pools = pools.clone();
+    for asset in &assets {
+        let index = pools
+            .iter()
+            .position(|pool| pool.info.equal(&asset.info))
+            .ok_or_else(|| ContractError::InvalidAsset(asset.info.to_string()))?;
+
+        // take asset / make sure it actually got sent
+        asset.receive(&env, &info, &mut messages)?;
+        if asset.info.is_native_token() {
+            // the pool balance already includes the sent native tokens
+            pools[index].amount = pools[index].amount.checked_sub(asset.amount)?;
+        } else {
+            new_pools[index].amount = new_pools[index].amount.checked_add(asset.amount)?;
+        }
+    }
+
+    let price = Decimal::from_ratio(new_pools[0].amount, new_pools[1].amount);
+    dex::oracle::store_oracle_price(deps.storage, &env, price)?;
+
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
+        accumulate_prices(&env, &config, pools[0].amount, pools[1].amount)?
+    {
+        config.price0_cumulative_last = price0_cumulative_new;
+        config.price1_cumulative_last = price1_cumulative_new;
+        config.block_time_last = block_time;
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "donate"),
+        attr("sender", info.sender),
+        attr(
+            "assets",
+            assets
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    ]))
+}
+
 /// Performs an swap operation with the specified parameters. The trader must approve the
 /// pool contract to transfer offer assets from their wallet.
 ///
@@ -790,6 +1200,9 @@ fn do_swap(
             config.pool_info.fee_config.protocol_fee_rate(),
         ) {
             protocol_fee_amount = f.amount;
+            PROTOCOL_FEES_COLLECTED.update(deps.storage, &f.info, |total| -> StdResult<_> {
+                Ok(total.unwrap_or_default() + f.amount)
+            })?;
             fee_msg = Some(f.into_msg(fee_address)?);
         }
     }
@@ -835,6 +1248,9 @@ fn do_swap(
 /// Note that this function shifts **block_time** when any of the token prices is zero in order to not
 /// fill an accumulator with a null price for that period.
 ///
+/// The accumulators are [`Uint256`], so even years without any update at extreme prices cannot overflow
+/// the increment. The accumulators themselves wrap around, so consumers should take wrapping differences.
+///
 /// * **x** is the balance of asset\[\0] in the pool.
 ///
 /// * **y** is the balance of asset\[\1] in the pool.
@@ -843,30 +1259,28 @@ pub fn accumulate_prices(
     config: &Config,
     x: Uint128,
     y: Uint128,
-) -> StdResult<Option<(Uint128, Uint128, u64)>> {
+) -> StdResult<Option<(Uint256, Uint256, u64)>> {
     let block_time = env.block.time.seconds();
     if block_time <= config.block_time_last {
         return Ok(None);
     }
 
     // We have to shift block_time when any price is zero in order to not fill an accumulator with a null price for that period
-    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+    let time_elapsed = Uint256::from(block_time - config.block_time_last);
 
     let mut pcl0 = config.price0_cumulative_last;
     let mut pcl1 = config.price1_cumulative_last;
 
     if !x.is_zero() && !y.is_zero() {
-        let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
-        pcl0 = config.price0_cumulative_last.wrapping_add(
-            time_elapsed
-                .checked_mul(price_precision)?
-                .multiply_ratio(y, x),
-        );
-        pcl1 = config.price1_cumulative_last.wrapping_add(
-            time_elapsed
-                .checked_mul(price_precision)?
-                .multiply_ratio(x, y),
-        );
+        // u64 seconds * 10^TWAP_PRECISION * u128 amount always fits into a Uint256
+        let price_precision = Uint256::from(10u128.pow(TWAP_PRECISION.into()));
+        let (x, y) = (Uint256::from(x), Uint256::from(y));
+        pcl0 = config
+            .price0_cumulative_last
+            .wrapping_add(time_elapsed * price_precision * y / x);
+        pcl1 = config
+            .price1_cumulative_last
+            .wrapping_add(time_elapsed * price_precision * x / y);
     };
 
     Ok(Some((pcl0, pcl1, block_time)))
@@ -912,13 +1326,43 @@ pub fn calculate_protocol_fee(
 /// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation  using
 /// a [`ReverseSimulationResponse`] object.
 ///
+/// * **QueryMsg::SwapFee { offer_asset, ask_asset_info }** Returns the fee breakdown of a prospective swap using a
+/// [`SwapFeeResponse`] object.
+///
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
-/// * **QueryMsg::HistoricalPrices { duration }** Returns historical price information for the assets in the
-/// pool using a [`HistoricalPricesResponse`] object.
+/// * **QueryMsg::HistoricalPrices { duration }** Returns the average price of every full sample period
+/// within the last `duration` seconds using a [`dex::oracle::HistoricalPricesResponse`] object.
+///
+/// * **QueryMsg::TwapStandard { offer, ask }** Returns the average price of `offer` in `ask` over the
+/// last 30 minutes, 1 hour and 24 hours using a [`dex::oracle::TwapStandardResponse`] object.
 ///
 /// * **QueryMsg::Config {}** Returns the configuration for the pool contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::TradingStarted {}** Returns whether trading in the pool has started using a
+/// [`TradingStartedResponse`] object.
+///
+/// * **QueryMsg::RebalanceTo { target_price }** Returns the arbitrage trade that moves the pool to
+/// the given price using a [`RebalanceToResponse`] object.
+///
+/// * **QueryMsg::Depth { ask_info }** Returns the liquidity depth for trading into the given asset
+/// using a [`DepthResponse`] object.
+///
+/// * **QueryMsg::OfferToMovePrice { offer_info, ask_info, bps }** Returns the trade that lowers the
+/// price of the offer asset by the given basis points using an [`OfferToMovePriceResponse`] object.
+///
+/// * **QueryMsg::Invariant {}** Returns the constant product of the pool using an
+/// [`InvariantResponse`] object.
+///
+/// * **QueryMsg::SpotPrice { offer, ask }** Returns the price of the offer asset in terms of the
+/// ask asset, ignoring fees, using a [`SpotPriceResponse`] object.
+///
+/// * **QueryMsg::Ticker {}** Returns the reserves, spot prices, fee and LP supply of the pool
+/// using a [`TickerResponse`] object.
+///
+/// * **QueryMsg::CollectedProtocolFees {}** Returns the total protocol fees collected by the pool
+/// using a [`CollectedProtocolFeesResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -947,6 +1391,10 @@ pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Bi
             referral,
             referral_commission,
         )?),
+        QueryMsg::SwapFee {
+            offer_asset,
+            ask_asset_info,
+        } => to_json_binary(&query_swap_fee(deps, offer_asset, ask_asset_info)?),
         QueryMsg::CumulativePrices {} => to_json_binary(&query_cumulative_prices(deps, env)?),
         QueryMsg::Twap {
             duration,
@@ -960,7 +1408,38 @@ pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Bi
             start_age,
             end_age,
         )?),
+        QueryMsg::HistoricalPrices { duration } => {
+            to_json_binary(&dex::oracle::query_oracle_history(
+                deps.storage,
+                &env,
+                &CONFIG.load(deps.storage)?.pool_info.asset_infos,
+                duration,
+            )?)
+        }
+        QueryMsg::TwapStandard { offer, ask } => {
+            to_json_binary(&dex::oracle::query_oracle_standard(
+                deps.storage,
+                &env,
+                &CONFIG.load(deps.storage)?.pool_info.asset_infos,
+                &offer.validate(deps.api)?,
+                &ask.validate(deps.api)?,
+            )?)
+        }
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::TradingStarted {} => to_json_binary(&query_trading_started(deps, env)?),
+        QueryMsg::RebalanceTo { target_price } => {
+            to_json_binary(&query_rebalance_to(deps, target_price)?)
+        }
+        QueryMsg::Depth { ask_info } => to_json_binary(&query_depth(deps, ask_info)?),
+        QueryMsg::OfferToMovePrice {
+            offer_info,
+            ask_info,
+            bps,
+        } => to_json_binary(&query_offer_to_move_price(deps, offer_info, ask_info, bps)?),
+        QueryMsg::Invariant {} => to_json_binary(&query_invariant(deps)?),
+        QueryMsg::SpotPrice { offer, ask } => to_json_binary(&query_spot_price(deps, offer, ask)?),
+        QueryMsg::Ticker {} => to_json_binary(&query_ticker(deps)?),
+        QueryMsg::CollectedProtocolFees {} => to_json_binary(&query_collected_protocol_fees(deps)?),
         _ => Err(StdError::generic_err("Query is not supported")),
     }
 }
@@ -1024,7 +1503,7 @@ pub fn query_simulation(
         ask_pool = pools[0].clone();
     } else {
         return Err(StdError::generic_err(
-            "Given offer asset does not belong in the pool",
+            ContractError::InvalidAsset(offer_asset.info.to_string()).to_string(),
         ));
     }
 
@@ -1070,7 +1549,7 @@ pub fn query_reverse_simulation(
         offer_pool = pools[0].clone();
     } else {
         return Err(StdError::generic_err(
-            "Given ask asset doesn't belong to pools",
+            ContractError::InvalidAsset(ask_asset.info.to_string()).to_string(),
         ));
     }
 
@@ -1102,6 +1581,397 @@ pub fn query_reverse_simulation(
     })
 }
 
+/// Returns the arbitrage trade that moves the pool's price of the second asset (in terms of the
+/// first one) to `target_price` in a [`RebalanceToResponse`] object.
+///
+/// * **target_price** the price the pool should be moved to.
+pub fn query_rebalance_to(
+    deps: Deps<CoreumQueries>,
+    target_price: Decimal,
+) -> StdResult<RebalanceToResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools(&deps.querier, &config.pool_info.contract_addr)?;
+
+    if target_price.is_zero() {
+        return Err(StdError::generic_err(
+            "Target price must be greater than zero",
+        ));
+    }
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(StdError::generic_err(
+            ContractError::NoLiquidity {}.to_string(),
+        ));
+    }
+
+    // the constant product stays the same, so the target balances are
+    // sqrt(cp * target_price) for the first asset and cp / that for the second one
+    let cp = pools[0].amount.full_mul(pools[1].amount);
+    let target_first = (cp * decimal2decimal256(target_price)?).isqrt();
+    let target_second = cp.checked_div(target_first)?;
+
+    let (offer_index, offer_amount) = if target_first > Uint256::from(pools[0].amount) {
+        (0, target_first - Uint256::from(pools[0].amount))
+    } else if target_second > Uint256::from(pools[1].amount) {
+        (1, target_second - Uint256::from(pools[1].amount))
+    } else {
+        (0, Uint256::zero())
+    };
+    let offer_pool = &pools[offer_index];
+    let ask_pool = &pools[1 - offer_index];
+    let offer_amount: Uint128 = offer_amount.try_into()?;
+
+    let return_amount = if offer_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        compute_swap(
+            offer_pool.amount,
+            ask_pool.amount,
+            offer_amount,
+            config.pool_info.fee_config.total_fee_rate(),
+        )?
+        .0
+    };
+
+    Ok(RebalanceToResponse {
+        offer_asset: AssetValidated {
+            info: offer_pool.info.clone(),
+            amount: offer_amount,
+        },
+        return_asset: AssetValidated {
+            info: ask_pool.info.clone(),
+            amount: return_amount,
+        },
+    })
+}
+
+/// Returns the largest trade into `ask_info` whose price impact stays within
+/// [`DEPTH_MAX_PRICE_IMPACT`] in a [`DepthResponse`] object.
+///
+/// * **ask_info** the asset to trade into.
+pub fn query_depth(deps: Deps<CoreumQueries>, ask_info: AssetInfo) -> StdResult<DepthResponse> {
+    let ask_info = ask_info.validate(deps.api)?;
+    let config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools(&deps.querier, &config.pool_info.contract_addr)?;
+
+    let (offer_pool, ask_pool) = if ask_info.equal(&pools[0].info) {
+        (&pools[1], &pools[0])
+    } else if ask_info.equal(&pools[1].info) {
+        (&pools[0], &pools[1])
+    } else {
+        return Err(StdError::generic_err(
+            ContractError::InvalidAsset(ask_info.to_string()).to_string(),
+        ));
+    };
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(StdError::generic_err(
+            ContractError::NoLiquidity {}.to_string(),
+        ));
+    }
+
+    // checks whether offering `amount` stays within the max price impact, ignoring fees
+    let within_impact = |amount: Uint128| -> StdResult<bool> {
+        let (return_amount, spread_amount, _) =
+            compute_swap(offer_pool.amount, ask_pool.amount, amount, Decimal::zero())?;
+        let ideal_amount = return_amount + spread_amount;
+        Ok(ideal_amount.is_zero()
+            || Decimal::from_ratio(spread_amount, ideal_amount) <= DEPTH_MAX_PRICE_IMPACT)
+    };
+
+    // offering the whole offer pool halves the price, so the depth is always below it
+    let mut min_trade = Uint128::zero();
+    let mut max_trade = offer_pool.amount;
+    while max_trade - min_trade > Uint128::one() {
+        let mid = min_trade + (max_trade - min_trade) / Uint128::new(2);
+        if within_impact(mid)? {
+            min_trade = mid;
+        } else {
+            max_trade = mid;
+        }
+    }
+
+    let return_amount = if min_trade.is_zero() {
+        Uint128::zero()
+    } else {
+        compute_swap(
+            offer_pool.amount,
+            ask_pool.amount,
+            min_trade,
+            config.pool_info.fee_config.total_fee_rate(),
+        )?
+        .0
+    };
+
+    Ok(DepthResponse {
+        offer_asset: AssetValidated {
+            info: offer_pool.info.clone(),
+            amount: min_trade,
+        },
+        return_asset: AssetValidated {
+            info: ask_pool.info.clone(),
+            amount: return_amount,
+        },
+    })
+}
+
+/// Returns the smallest trade from `offer_info` into `ask_info` that lowers the spot price of the
+/// offer asset by `bps` basis points (ignoring fees) in an [`OfferToMovePriceResponse`] object.
+///
+/// * **offer_info** the asset to offer.
+///
+/// * **ask_info** the asset to trade into.
+///
+/// * **bps** the price move in basis points, between 1 and 9999.
+pub fn query_offer_to_move_price(
+    deps: Deps<CoreumQueries>,
+    offer_info: AssetInfo,
+    ask_info: AssetInfo,
+    bps: u16,
+) -> StdResult<OfferToMovePriceResponse> {
+    let offer_info = offer_info.validate(deps.api)?;
+    let ask_info = ask_info.validate(deps.api)?;
+    let config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools(&deps.querier, &config.pool_info.contract_addr)?;
+
+    if bps == 0 || bps >= 10_000 {
+        return Err(StdError::generic_err(
+            ContractError::InvalidPriceMoveBps {}.to_string(),
+        ));
+    }
+    let (offer_pool, ask_pool) = match (
+        pools.iter().find(|pool| pool.info.equal(&offer_info)),
+        pools.iter().find(|pool| pool.info.equal(&ask_info)),
+    ) {
+        (Some(offer_pool), Some(ask_pool)) if !offer_info.equal(&ask_info) => {
+            (offer_pool, ask_pool)
+        }
+        _ => {
+            return Err(StdError::generic_err(
+                ContractError::AssetMismatch {}.to_string(),
+            ))
+        }
+    };
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(StdError::generic_err(
+            ContractError::NoLiquidity {}.to_string(),
+        ));
+    }
+
+    // the price of the offer asset in terms of the ask asset that the trade has to reach
+    let target_price = Decimal::from_ratio(ask_pool.amount, offer_pool.amount)
+        * Decimal::from_ratio(10_000 - bps, 10_000u16);
+
+    // checks whether offering `amount` moves the price to or below the target, ignoring fees
+    let reaches_target = |amount: Uint128| -> StdResult<bool> {
+        let (return_amount, _, _) =
+            compute_swap(offer_pool.amount, ask_pool.amount, amount, Decimal::zero())?;
+        Ok(
+            Decimal::from_ratio(ask_pool.amount - return_amount, offer_pool.amount + amount)
+                <= target_price,
+        )
+    };
+
+    // the price falls with the square of the offer pool growth, so this always reaches the target
+    let mut max_trade = offer_pool
+        .amount
+        .multiply_ratio(bps, 10_000 - bps)
+        .checked_add(Uint128::one())?;
+    let mut min_trade = Uint128::zero();
+    while max_trade - min_trade > Uint128::one() {
+        let mid = min_trade + (max_trade - min_trade) / Uint128::new(2);
+        if reaches_target(mid)? {
+            max_trade = mid;
+        } else {
+            min_trade = mid;
+        }
+    }
+
+    let (return_amount, _, _) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        max_trade,
+        config.pool_info.fee_config.total_fee_rate(),
+    )?;
+
+    Ok(OfferToMovePriceResponse {
+        offer_asset: AssetValidated {
+            info: offer_pool.info.clone(),
+            amount: max_trade,
+        },
+        return_asset: AssetValidated {
+            info: ask_pool.info.clone(),
+            amount: return_amount,
+        },
+    })
+}
+
+/// Returns the constant product `k` of the pool's current balances in an [`InvariantResponse`] object.
+pub fn query_invariant(deps: Deps<CoreumQueries>) -> StdResult<InvariantResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools(&deps.querier, &config.pool_info.contract_addr)?;
+
+    let value = pools[0].amount.full_mul(pools[1].amount);
+
+    Ok(InvariantResponse { value })
+}
+
+/// Returns the price of `offer` in terms of `ask`, ignoring fees, in a [`SpotPriceResponse`] object.
+pub fn query_spot_price(
+    deps: Deps<CoreumQueries>,
+    offer: AssetInfo,
+    ask: AssetInfo,
+) -> StdResult<SpotPriceResponse> {
+    let offer = offer.validate(deps.api)?;
+    let ask = ask.validate(deps.api)?;
+    let config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools(&deps.querier, &config.pool_info.contract_addr)?;
+
+    let (offer_pool, ask_pool) = match (
+        pools.iter().find(|pool| pool.info.equal(&offer)),
+        pools.iter().find(|pool| pool.info.equal(&ask)),
+    ) {
+        (Some(offer_pool), Some(ask_pool)) if !offer.equal(&ask) => (offer_pool, ask_pool),
+        _ => {
+            return Err(StdError::generic_err(
+                ContractError::AssetMismatch {}.to_string(),
+            ))
+        }
+    };
+    if offer_pool.amount.is_zero() || ask_pool.amount.is_zero() {
+        return Err(StdError::generic_err(
+            ContractError::NoLiquidity {}.to_string(),
+        ));
+    }
+
+    Ok(SpotPriceResponse {
+        price: Decimal::from_ratio(ask_pool.amount, offer_pool.amount),
+    })
+}
+
+/// Returns the reserves, spot prices, fee and LP supply of the pool in a [`TickerResponse`] object.
+pub fn query_ticker(deps: Deps<CoreumQueries>) -> StdResult<TickerResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (reserves, total_share) = pool_info(deps, &config)?;
+
+    // the price of each asset in terms of the other one
+    let spot_prices = if reserves.iter().any(|pool| pool.amount.is_zero()) {
+        vec![]
+    } else {
+        vec![
+            (
+                reserves[0].info.clone(),
+                reserves[1].info.clone(),
+                Decimal::from_ratio(reserves[1].amount, reserves[0].amount),
+            ),
+            (
+                reserves[1].info.clone(),
+                reserves[0].info.clone(),
+                Decimal::from_ratio(reserves[0].amount, reserves[1].amount),
+            ),
+        ]
+    };
+
+    Ok(TickerResponse {
+        reserves,
+        spot_prices,
+        total_fee_bps: config.pool_info.fee_config.total_fee_bps,
+        total_share,
+    })
+}
+
+/// Returns the total protocol fees collected by the pool for each of its assets
+/// in a [`CollectedProtocolFeesResponse`] object.
+pub fn query_collected_protocol_fees(
+    deps: Deps<CoreumQueries>,
+) -> StdResult<CollectedProtocolFeesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let fees = config
+        .pool_info
+        .asset_infos
+        .into_iter()
+        .map(|info| {
+            let amount = PROTOCOL_FEES_COLLECTED
+                .may_load(deps.storage, &info)?
+                .unwrap_or_default();
+            Ok(AssetValidated { info, amount })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(CollectedProtocolFeesResponse { fees })
+}
+
+/// Returns the fees a swap of `offer_asset` would pay in a [`SwapFeeResponse`] object.
+///
+/// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+///
+/// * **ask_asset_info** is the asset to swap to. If given, it has to be the other pool asset.
+pub fn query_swap_fee(
+    deps: Deps<CoreumQueries>,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<SwapFeeResponse> {
+    let offer_asset = offer_asset.validate(deps.api)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let pools = config
+        .pool_info
+        .query_pools(&deps.querier, &config.pool_info.contract_addr)?;
+
+    let (offer_pool, ask_pool) = if offer_asset.info.equal(&pools[0].info) {
+        (&pools[0], &pools[1])
+    } else if offer_asset.info.equal(&pools[1].info) {
+        (&pools[1], &pools[0])
+    } else {
+        return Err(StdError::generic_err(
+            ContractError::InvalidAsset(offer_asset.info.to_string()).to_string(),
+        ));
+    };
+    // the ask asset is optional, but has to be the other pool asset if given
+    if let Some(ask_asset_info) = ask_asset_info {
+        let ask_asset_info = ask_asset_info.validate(deps.api)?;
+        if ask_asset_info.equal(&offer_pool.info) {
+            return Err(StdError::generic_err(
+                ContractError::SameAssetSwap {}.to_string(),
+            ));
+        }
+        if !ask_asset_info.equal(&ask_pool.info) {
+            return Err(StdError::generic_err(
+                ContractError::InvalidAsset(ask_asset_info.to_string()).to_string(),
+            ));
+        }
+    }
+
+    let (_, _, total_commission) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_asset.amount,
+        config.pool_info.fee_config.total_fee_rate(),
+    )?;
+
+    // The protocol fee is only taken if the factory has a fee address configured
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let protocol_fee = if factory_config.fee_address.is_some() {
+        total_commission * config.pool_info.fee_config.protocol_fee_rate()
+    } else {
+        Uint128::zero()
+    };
+
+    Ok(SwapFeeResponse {
+        total_commission,
+        protocol_fee,
+        lp_fee: total_commission - protocol_fee,
+    })
+}
+
 /// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
 pub fn query_cumulative_prices(
     deps: Deps<CoreumQueries>,
@@ -1149,6 +2019,19 @@ pub fn query_config(deps: Deps<CoreumQueries>) -> StdResult<ConfigResponse> {
         block_time_last: config.block_time_last,
         params: None,
         owner: None,
+        factory_addr: config.factory_addr,
+    })
+}
+
+/// Returns whether trading in the pool has started in a [`TradingStartedResponse`] object.
+pub fn query_trading_started(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+) -> StdResult<TradingStartedResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(TradingStartedResponse {
+        started: env.block.time.seconds() >= config.trading_starts,
+        starts_at: config.trading_starts,
     })
 }
 
@@ -1237,6 +2120,43 @@ pub fn compute_offer_amount(
     Ok((offer_amount, spread_amount, commission_amount.try_into()?))
 }
 
+/// Returns the amount of offer assets to swap when providing liquidity with only that asset,
+/// so that the rest of it and the swap return are in the ratio of the pool after the swap.
+///
+/// * **offer_pool** total amount of offer assets in the pool.
+///
+/// * **offer_amount** amount of offer assets to provide.
+///
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn compute_zap_swap_amount(
+    offer_pool: Uint128,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<Uint128> {
+    // Swapping `s` of the provided `a` into the pool `A, B` returns `r = (1 - fee) * B * s / (A + s)`,
+    // the commission staying in the pool. Requiring `(a - s) / (A + s) == r / (B - r)` simplifies to
+    // `s^2 + (p - a) * s - a * A == 0` with `p = A + (1 - fee) * (a + A)`, so the positive root is
+    // `s = (a - p + sqrt((p - a)^2 + 4 * a * A)) / 2`, which is always between 0 and `a`.
+    let offer_pool = Uint256::from(offer_pool);
+    let offer_amount = Uint256::from(offer_amount);
+    let one_minus_commission = Decimal256::one() - decimal2decimal256(commission_rate)?;
+
+    let p = offer_pool + (offer_pool + offer_amount) * one_minus_commission;
+    let diff = if p > offer_amount {
+        p - offer_amount
+    } else {
+        offer_amount - p
+    };
+    let discriminant = diff.checked_mul(diff)?.checked_add(
+        Uint256::from(4u8)
+            .checked_mul(offer_amount)?
+            .checked_mul(offer_pool)?,
+    )?;
+    let swap_amount = (offer_amount + discriminant.isqrt() - p) / Uint256::from(2u8);
+
+    Ok(swap_amount.try_into()?)
+}
+
 /// This is an internal function that enforces slippage tolerance for swaps.
 ///
 /// * **slippage_tolerance** slippage tolerance to enforce.
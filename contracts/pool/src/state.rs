@@ -1,7 +1,7 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
-use dex::pool::PairInfo;
+use cosmwasm_std::{Addr, Uint128, Uint256};
+use cw_storage_plus::{Item, Map};
+use dex::{asset::AssetInfoValidated, pool::PairInfo};
 
 /// This structure stores the main config parameters for a constant product pool contract.
 #[cw_serde]
@@ -13,9 +13,9 @@ pub struct Config {
     /// The last timestamp when the pool contract update the asset cumulative prices
     pub block_time_last: u64,
     /// The last cumulative price for asset 0
-    pub price0_cumulative_last: Uint128,
+    pub price0_cumulative_last: Uint256,
     /// The last cumulative price for asset 1
-    pub price1_cumulative_last: Uint128,
+    pub price1_cumulative_last: Uint256,
     /// The block time until which trading is disabled
     pub trading_starts: u64,
 }
@@ -29,3 +29,7 @@ pub const FROZEN: Item<bool> = Item::new("frozen");
 
 /// Stores the total amount of LP share tokens minted (workaround)
 pub const LP_SHARE_AMOUNT: Item<Uint128> = Item::new("lp_share_amount");
+
+/// Stores the total protocol fees sent to the factory's `fee_address`, per asset
+pub const PROTOCOL_FEES_COLLECTED: Map<&AssetInfoValidated, Uint128> =
+    Map::new("protocol_fees_collected");
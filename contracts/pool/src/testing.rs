@@ -1,32 +1,37 @@
-use coreum_wasm_sdk::{assetft, core::CoreumMsg};
+use coreum_wasm_sdk::{
+    assetft,
+    core::{CoreumMsg, CoreumQueries},
+};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     assert_approx_eq, attr, coin, coins, from_json, to_json_binary, Addr, BankMsg, BlockInfo, Coin,
-    CosmosMsg, Decimal, Env, Fraction, ReplyOn, StdError, Timestamp, Uint128, WasmMsg,
+    CosmosMsg, Decimal, Deps, Env, Fraction, ReplyOn, StdError, Timestamp, Uint128, Uint256,
+    WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
 use proptest::prelude::*;
 
 use dex::asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated, MINIMUM_LIQUIDITY_AMOUNT};
-use dex::factory::PoolType;
+use dex::factory::{PoolType, MAX_TRADING_STARTS_DELAY};
 use dex::fee_config::FeeConfig;
 use dex::oracle::{SamplePeriod, TwapResponse};
 use dex::pool::{
-    assert_max_spread, ContractError, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PairInfo,
-    PoolResponse, ReverseSimulationResponse, SimulationResponse, StakeConfig, LP_TOKEN_PRECISION,
-    TWAP_PRECISION,
+    assert_max_spread, ContractError, Cw20HookMsg, DepthResponse, ExecuteMsg, InstantiateMsg,
+    OfferToMovePriceResponse, PairInfo, PoolResponse, RebalanceToResponse,
+    ReverseSimulationResponse, SimulationResponse, SpotPriceResponse, StakeConfig, SwapFeeResponse,
+    TickerResponse, TradingStartedResponse, LP_TOKEN_PRECISION, TWAP_PRECISION,
 };
 use dex::pool::{MigrateMsg, QueryMsg};
 
 use crate::contract::{
-    accumulate_prices, compute_swap, execute, instantiate, migrate, query_pool,
+    accumulate_prices, compute_swap, execute, instantiate, migrate, query_invariant, query_pool,
     query_reverse_simulation, query_simulation,
 };
-use crate::contract::{compute_offer_amount, query};
-use crate::state::{Config, CONFIG};
+use crate::contract::{compute_offer_amount, compute_zap_swap_amount, query};
+use crate::state::{Config, CONFIG, FROZEN};
 // TODO: Copied here just as a temporary measure
-use crate::mock_querier::mock_dependencies;
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
 
 pub type Response = cosmwasm_std::Response<CoreumMsg>;
 pub type SubMsg = cosmwasm_std::SubMsg<CoreumMsg>;
@@ -38,6 +43,7 @@ fn default_stake_config() -> StakeConfig {
         min_bond: Uint128::new(1000),
         unbonding_periods: vec![60 * 60 * 24 * 7],
         max_distributions: 6,
+        auto_claim_on_delegate: false,
     }
 }
 
@@ -64,6 +70,8 @@ fn proper_initialization() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -104,7 +112,11 @@ fn proper_initialization() {
                         unbonding_periods: vec![60 * 60 * 24 * 7],
                         max_distributions: 6,
                         admin: Some("addr0000".to_owned()),
-                        unbonder: None
+                        unbonder: None,
+                        backup_unbonders: vec![],
+                        unbond_grace_period: 0,
+                        min_distribution_interval: 0,
+                        auto_claim_on_delegate: false,
                     })
                     .unwrap()
                 }),
@@ -127,6 +139,143 @@ fn proper_initialization() {
     );
 }
 
+#[test]
+fn instantiate_with_trading_starts_too_far_in_future() {
+    let env = mock_env();
+    let msg = |trading_starts: u64| InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+    )]);
+
+    // trading can be delayed by at most the maximum delay
+    let max_trading_starts = env.block.time.seconds() + MAX_TRADING_STARTS_DELAY;
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg(max_trading_starts),
+    )
+    .unwrap();
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.trading_starts, max_trading_starts);
+
+    // one second more is rejected
+    let mut deps = mock_dependencies(&[]);
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg(max_trading_starts + 1),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidTradingStart {
+            max_delay: MAX_TRADING_STARTS_DELAY
+        }
+    );
+
+    // as is anything beyond that
+    let err = instantiate(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        msg(u64::MAX),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidTradingStart {
+            max_delay: MAX_TRADING_STARTS_DELAY
+        }
+    );
+}
+
+#[test]
+fn query_trading_started() {
+    let mut env = mock_env();
+    let trading_starts = env.block.time.seconds() + 1000;
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+    )]);
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        InstantiateMsg {
+            factory_addr: String::from("factory"),
+            asset_infos: vec![
+                AssetInfo::SmartToken("uusd".to_string()),
+                AssetInfo::Cw20Token("asset0000".to_string()),
+            ],
+            init_params: None,
+            staking_config: default_stake_config(),
+            trading_starts,
+            fee_config: FeeConfig {
+                total_fee_bps: 0,
+                protocol_fee_bps: 0,
+            },
+            circuit_breaker: None,
+            existing_staking_addr: None,
+            initial_oracle_price: None,
+            verified: true,
+        },
+    )
+    .unwrap();
+
+    // before the start time
+    let res: TradingStartedResponse =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::TradingStarted {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        TradingStartedResponse {
+            started: false,
+            starts_at: trading_starts
+        }
+    );
+
+    // exactly at the start time
+    env.block.time = Timestamp::from_seconds(trading_starts);
+    let res: TradingStartedResponse =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::TradingStarted {}).unwrap()).unwrap();
+    assert!(res.started);
+
+    // and after it
+    env.block.time = env.block.time.plus_seconds(1);
+    let res: TradingStartedResponse =
+        from_json(query(deps.as_ref(), env, QueryMsg::TradingStarted {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        TradingStartedResponse {
+            started: true,
+            starts_at: trading_starts
+        }
+    );
+}
+
 // Rather long test the does a few things
 // First for sanity, does a provide liquidity
 // Then through migration marks the contract as frozen and assigns addr0000 as the circuit_breaker, the one who can unfreeze the contract and refreeze via an ExecuteMsg
@@ -169,6 +318,8 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -329,7 +480,10 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
     // But we can withdraw liquidity
 
     // Withdraw liquidity
-    let msg = ExecuteMsg::WithdrawLiquidity { assets: vec![] };
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![],
+        max_burn: None,
+    };
 
     let info = mock_info("addr0000", &[coin(100, "uuusdmapplp-cosmos2contract")]);
     // We just want to ensure it doesn't fail with a ContractFrozen error
@@ -419,6 +573,90 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
     execute(deps.as_mut(), env, info, msg).unwrap();
 }
 
+#[test]
+fn factory_can_freeze_without_circuit_breaker() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    let env = mock_env();
+    instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // without a circuit breaker, nobody but the factory can freeze the pool
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::Freeze { frozen: true },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("factory", &[]),
+        ExecuteMsg::Freeze { frozen: true },
+    )
+    .unwrap();
+
+    // zapping in and out is blocked like providing liquidity and swapping
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[coin(1_000, "uusd")]),
+        ExecuteMsg::ZapIn {
+            offer_asset: Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(1_000),
+            },
+            min_share: None,
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractFrozen {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[coin(100, "uuusdmapplp-cosmos2contract")]),
+        ExecuteMsg::ZapOut {
+            share: Uint128::new(100),
+            ask_asset: AssetInfo::SmartToken("uusd".to_string()),
+            receiver: None,
+            max_spread: None,
+            minimum_receive: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ContractFrozen {});
+
+    // the factory can unfreeze it again
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("factory", &[]),
+        ExecuteMsg::Freeze { frozen: false },
+    )
+    .unwrap();
+    assert!(!FROZEN.load(&deps.storage).unwrap());
+}
+
 #[test]
 fn provide_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -451,6 +689,8 @@ fn provide_liquidity() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -866,6 +1106,123 @@ fn provide_liquidity() {
     assert_eq!(err, ContractError::AllowedSpreadAssertion {});
 }
 
+#[test]
+fn zap_in() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let pool_amount = Uint128::new(100_000_000);
+    let offer_amount = Uint128::new(500_000);
+
+    // provide the initial liquidity
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin(pool_amount.u128(), "uusd")],
+    )]);
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: pool_amount,
+            },
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: pool_amount,
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+    };
+    let info = mock_info("addr0000", &[coin(pool_amount.u128(), "uusd")]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let total_share = pool_amount;
+
+    // the contract balance includes the offered uusd
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &pool_amount)],
+    )]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin((pool_amount + offer_amount).u128(), "uusd")],
+    )]);
+
+    // the deposits are the rest of the offer and the return of the optimal swap
+    let swap_amount =
+        compute_zap_swap_amount(pool_amount, offer_amount, Decimal::permille(3)).unwrap();
+    let (return_amount, _, _) =
+        compute_swap(pool_amount, pool_amount, swap_amount, Decimal::permille(3)).unwrap();
+    let remaining = offer_amount - swap_amount;
+    let share = std::cmp::min(
+        remaining.multiply_ratio(total_share, pool_amount + swap_amount),
+        return_amount.multiply_ratio(total_share, pool_amount - return_amount),
+    );
+
+    let zap = |min_share| ExecuteMsg::ZapIn {
+        offer_asset: Asset {
+            info: AssetInfo::SmartToken("uusd".to_string()),
+            amount: offer_amount,
+        },
+        min_share,
+        receiver: None,
+    };
+    let info = mock_info("addr0001", &[coin(offer_amount.u128(), "uusd")]);
+
+    // asking for more LP tokens than the zap mints fails
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        zap(Some(offer_amount)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinShareAssertion {
+            share,
+            min_share: offer_amount,
+        }
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), info, zap(Some(share))).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "zap_in"));
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "assets",
+            format!("{remaining}uusd, {return_amount}asset0000")
+        )
+    );
+    assert_eq!(res.attributes[4], attr("share", share));
+    // the deposits are in the pool ratio after the swap, so hardly any value is lost to rounding
+    assert!(
+        share > offer_amount.multiply_ratio(total_share, 2 * pool_amount) * Decimal::percent(99)
+    );
+}
+
 #[test]
 fn withdraw_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -902,6 +1259,8 @@ fn withdraw_liquidity() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -953,8 +1312,27 @@ fn withdraw_liquidity() {
     execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Withdraw liquidity
-    let msg = ExecuteMsg::WithdrawLiquidity { assets: vec![] };
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![],
+        max_burn: None,
+    };
     let env = mock_env();
+
+    // Withdrawing needs exactly one coin of the LP token
+    let lp_funds_err = ContractError::InvalidLpFunds("uuusdmapplp-cosmos2contract".to_string());
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, lp_funds_err);
+    let info = mock_info("addr0000", &[coin(100, "uusd")]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, lp_funds_err);
+    let info = mock_info(
+        "addr0000",
+        &[coin(100, "uuusdmapplp-cosmos2contract"), coin(100, "uusd")],
+    );
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, lp_funds_err);
+
     let info = mock_info("addr0000", &[coin(100, "uuusdmapplp-cosmos2contract")]);
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -1055,6 +1433,8 @@ fn query_twap() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
     instantiate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
@@ -1177,60 +1557,308 @@ fn query_twap() {
 }
 
 #[test]
-fn try_native_to_token() {
-    let total_share = Uint128::new(30000000000u128);
-    let asset_pool_amount = Uint128::new(20000000000u128);
-    let collateral_pool_amount = Uint128::new(30000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
+fn update_oracle_without_trade() {
+    let mut deps = mock_dependencies(&[]);
+    let mut env = mock_env();
 
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
-    }]);
+    deps.querier.with_token_balances(&[(
+        &"asset0000".into(),
+        &[(&MOCK_CONTRACT_ADDR.into(), &0u128.into())],
+    )]);
 
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
-        ),
-        (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
-        ),
-    ]);
+    let uusd = AssetInfoValidated::SmartToken("uusd".to_string());
+    let token = AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"));
 
     let msg = InstantiateMsg {
-        asset_infos: vec![
-            AssetInfo::SmartToken("uusd".to_string()),
-            AssetInfo::Cw20Token("asset0000".to_string()),
-        ],
+        asset_infos: vec![uusd.clone().into(), token.clone().into()],
         factory_addr: String::from("factory"),
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
         fee_config: FeeConfig {
-            total_fee_bps: 30,
-            protocol_fee_bps: 1660,
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
+    instantiate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
 
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // need to initialize oracle, because we don't call `provide_liquidity` in this test
-    dex::oracle::initialize_oracle(
-        &mut deps.storage,
-        &mock_env_with_block_time(0),
-        Decimal::one(),
+    // there is no price to store before liquidity is provided
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::UpdateOracle {},
     )
-    .unwrap();
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoLiquidity {});
 
-    // Normal swap
-    let msg = ExecuteMsg::Swap {
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: uusd.into(),
+                amount: 1_000_000u128.into(),
+            },
+            Asset {
+                info: token.into(),
+                amount: 2_000_000u128.into(),
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+    };
+    deps.querier
+        .with_balance(&[(&MOCK_CONTRACT_ADDR.into(), &coins(1_000_000u128, "uusd"))]);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("user", &coins(1_000_000u128, "uusd")),
+        msg,
+    )
+    .unwrap();
+    deps.querier.with_token_balances(&[(
+        &"asset0000".into(),
+        &[(&MOCK_CONTRACT_ADDR.into(), &2_000_000u128.into())],
+    )]);
+
+    let initial = dex::oracle::query_oracle_accumulator(&deps.storage).unwrap();
+    assert_eq!(initial.snapshot, env.block.time);
+
+    // nothing happens in the pool for an hour
+    env.block.time = env.block.time.plus_seconds(3600);
+
+    // anyone can store the current price
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::UpdateOracle {},
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "update_oracle"));
+
+    let updated = dex::oracle::query_oracle_accumulator(&deps.storage).unwrap();
+    assert_eq!(updated.snapshot, env.block.time);
+    assert_eq!(updated.last_price, Decimal::percent(50));
+    assert_ne!(updated.twap_a_per_b, initial.twap_a_per_b);
+    assert_eq!(
+        CONFIG.load(&deps.storage).unwrap().block_time_last,
+        env.block.time.seconds()
+    );
+}
+
+#[test]
+fn donate() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let uusd = AssetInfoValidated::SmartToken("uusd".to_string());
+    let token = AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"));
+    deps.querier.with_token_balances(&[(
+        &"asset0000".into(),
+        &[(&MOCK_CONTRACT_ADDR.into(), &0u128.into())],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![uusd.clone().into(), token.clone().into()],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let donation = ExecuteMsg::Donate {
+        assets: vec![Asset {
+            info: uusd.clone().into(),
+            amount: 1_000_000u128.into(),
+        }],
+    };
+
+    // cannot donate to an empty pool
+    deps.querier
+        .with_balance(&[(&MOCK_CONTRACT_ADDR.into(), &coins(1_000_000u128, "uusd"))]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("donor", &coins(1_000_000u128, "uusd")),
+        donation.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoLiquidity {});
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: uusd.clone().into(),
+                amount: 1_000_000u128.into(),
+            },
+            Asset {
+                info: token.clone().into(),
+                amount: 1_000_000u128.into(),
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("user", &coins(1_000_000u128, "uusd")),
+        msg,
+    )
+    .unwrap();
+    deps.querier.with_token_balances(&[(
+        &"asset0000".into(),
+        &[(&MOCK_CONTRACT_ADDR.into(), &1_000_000u128.into())],
+    )]);
+
+    let share_query = QueryMsg::Share {
+        amount: 100_000u128.into(),
+    };
+    let share_before: Vec<AssetValidated> =
+        from_json(query(deps.as_ref(), env.clone(), share_query.clone()).unwrap()).unwrap();
+
+    // only pool assets can be donated
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("donor", &coins(1_000u128, "ujuno")),
+        ExecuteMsg::Donate {
+            assets: vec![Asset {
+                info: AssetInfo::SmartToken("ujuno".to_string()),
+                amount: 1_000u128.into(),
+            }],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAsset("ujuno".to_string()));
+
+    // donate both assets
+    deps.querier
+        .with_balance(&[(&MOCK_CONTRACT_ADDR.into(), &coins(2_000_000u128, "uusd"))]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("donor", &coins(1_000_000u128, "uusd")),
+        ExecuteMsg::Donate {
+            assets: vec![
+                Asset {
+                    info: uusd.into(),
+                    amount: 1_000_000u128.into(),
+                },
+                Asset {
+                    info: token.into(),
+                    amount: 1_000_000u128.into(),
+                },
+            ],
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "donate"));
+    // the cw20 token is pulled from the donor, but no LP tokens are minted
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: "donor".to_string(),
+                recipient: MOCK_CONTRACT_ADDR.to_string(),
+                amount: 1_000_000u128.into(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    deps.querier.with_token_balances(&[(
+        &"asset0000".into(),
+        &[(&MOCK_CONTRACT_ADDR.into(), &2_000_000u128.into())],
+    )]);
+
+    // existing LP shares are worth twice as much now
+    let share_after: Vec<AssetValidated> =
+        from_json(query(deps.as_ref(), env.clone(), share_query).unwrap()).unwrap();
+    assert_eq!(
+        share_after[0].amount,
+        share_before[0].amount * Uint128::new(2)
+    );
+    assert_eq!(
+        share_after[1].amount,
+        share_before[1].amount * Uint128::new(2)
+    );
+
+    let oracle = dex::oracle::query_oracle_accumulator(&deps.storage).unwrap();
+    assert_eq!(oracle.last_price, Decimal::one());
+}
+
+#[test]
+fn try_native_to_token() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // need to initialize oracle, because we don't call `provide_liquidity` in this test
+    dex::oracle::initialize_oracle(
+        &mut deps.storage,
+        &mock_env_with_block_time(0),
+        Decimal::one(),
+    )
+    .unwrap();
+
+    // Normal swap
+    let msg = ExecuteMsg::Swap {
         offer_asset: Asset {
             info: AssetInfo::SmartToken("uusd".to_string()),
             amount: offer_amount,
@@ -1289,7 +1917,7 @@ fn try_native_to_token() {
     .unwrap_err();
     assert_eq!(
         err.to_string(),
-        "Generic error: Given offer asset does not belong in the pool"
+        "Generic error: The asset cny does not belong to the pool"
     );
 
     let simulation_res: SimulationResponse = query_simulation(
@@ -1319,7 +1947,7 @@ fn try_native_to_token() {
     .unwrap_err();
     assert_eq!(
         err.to_string(),
-        "Generic error: Given ask asset doesn't belong to pools"
+        "Generic error: The asset cny does not belong to the pool"
     );
 
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
@@ -1428,6 +2056,8 @@ fn try_token_to_native() {
             protocol_fee_bps: 1660,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -1654,23 +2284,25 @@ fn test_max_spread() {
 }
 
 #[test]
-fn test_query_pool() {
-    let total_share_amount = Uint128::from(111u128);
-    let asset_0_amount = Uint128::from(222u128);
-    let asset_1_amount = Uint128::from(333u128);
+fn query_swap_fee_matches_swap() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: asset_0_amount,
+        amount: collateral_pool_amount,
     }]);
 
     deps.querier.with_token_balances(&[
         (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
         ),
         (
-            &String::from("liquidity0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
         ),
     ]);
 
@@ -1684,52 +2316,552 @@ fn test_query_pool() {
         staking_config: default_stake_config(),
         trading_starts: 0,
         fee_config: FeeConfig {
-            total_fee_bps: 0,
-            protocol_fee_bps: 0,
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
 
-    let env = mock_env();
-    let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    // need to initialize oracle, because we don't call `provide_liquidity` in this test
+    dex::oracle::initialize_oracle(
+        &mut deps.storage,
+        &mock_env_with_block_time(0),
+        Decimal::one(),
+    )
+    .unwrap();
 
-    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    let offer_asset = Asset {
+        info: AssetInfo::SmartToken("uusd".to_string()),
+        amount: offer_amount,
+    };
 
-    assert_eq!(
-        res.assets,
-        [
-            AssetValidated {
-                info: AssetInfoValidated::SmartToken("uusd".to_string()),
-                amount: asset_0_amount
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SwapFee {
+            offer_asset: Asset {
+                info: AssetInfo::SmartToken("cny".to_string()),
+                amount: offer_amount,
             },
-            AssetValidated {
-                info: AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000")),
-                amount: asset_1_amount
-            }
-        ]
+            ask_asset_info: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: The asset cny does not belong to the pool"
     );
-    // TODO: If you won't provide liquidity, this will be 0
-    // assert_eq!(res.total_share, total_share_amount);
-}
 
-#[test]
-fn test_query_share() {
-    let total_share_amount = Uint128::from(500u128);
-    let asset_0_amount = Uint128::from(250u128);
-    let asset_1_amount = Uint128::from(1000u128);
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: asset_0_amount,
-    }]);
+    // the ask asset has to be the other pool asset
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SwapFee {
+            offer_asset: offer_asset.clone(),
+            ask_asset_info: Some(AssetInfo::SmartToken("uusd".to_string())),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::SameAssetSwap {}.to_string())
+    );
 
-    deps.querier.with_token_balances(&[
-        (
-            &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
-        ),
+    let fees: SwapFeeResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SwapFee {
+                offer_asset: offer_asset.clone(),
+                ask_asset_info: Some(AssetInfo::Cw20Token("asset0000".to_string())),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(fees.total_commission, fees.protocol_fee + fees.lp_fee);
+    assert!(!fees.protocol_fee.is_zero());
+
+    // the user deposit is pre-applied when actually swapping
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + offer_amount,
+        }],
+    )]);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset,
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        referral_address: None,
+        referral_commission: None,
+    };
+    let info = mock_info("addr0000", &[coin(offer_amount.u128(), "uusd")]);
+    let res = execute(deps.as_mut(), mock_env_with_block_time(1000), info, msg).unwrap();
+
+    let attribute = |key: &str| {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .unwrap()
+            .value
+            .clone()
+    };
+    assert_eq!(
+        attribute("commission_amount"),
+        fees.total_commission.to_string()
+    );
+    assert_eq!(
+        attribute("protocol_fee_amount"),
+        fees.protocol_fee.to_string()
+    );
+}
+
+#[test]
+fn query_rebalance_to() {
+    let uusd_amount = Uint128::new(30_000_000_000u128);
+    let asset_amount = Uint128::new(20_000_000_000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: uusd_amount,
+    }]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &asset_amount)],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let rebalance_to = |target_price| -> RebalanceToResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RebalanceTo { target_price },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // the pool is already at 1.5 uusd per asset0000
+    let res = rebalance_to(Decimal::percent(150));
+    assert_eq!(res.offer_asset.amount, Uint128::zero());
+    assert_eq!(res.return_asset.amount, Uint128::zero());
+
+    // moving the price up means buying asset0000 with uusd
+    let res = rebalance_to(Decimal::percent(200));
+    assert_eq!(
+        res.offer_asset.info,
+        AssetInfoValidated::SmartToken("uusd".to_string())
+    );
+    assert_eq!(
+        res.return_asset.info,
+        AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+    );
+    let simulation = query_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::SmartToken("uusd".to_string()),
+            amount: res.offer_asset.amount,
+        },
+        false,
+        None,
+    )
+    .unwrap();
+    assert_eq!(simulation.return_amount, res.return_asset.amount);
+    // the commission (except for the protocol fee) stays in the pool, so the price is close to the target
+    let new_uusd = uusd_amount + res.offer_asset.amount;
+    let new_asset = asset_amount - res.return_asset.amount;
+    assert_approx_eq!(new_uusd, new_asset * Uint128::new(2), "0.003");
+
+    // moving the price down means selling asset0000
+    let res = rebalance_to(Decimal::one());
+    assert_eq!(
+        res.offer_asset.info,
+        AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+    );
+    let new_uusd = uusd_amount - res.return_asset.amount;
+    let new_asset = asset_amount + res.offer_asset.amount;
+    assert_approx_eq!(new_uusd, new_asset, "0.003");
+}
+
+#[test]
+fn query_depth() {
+    let setup_pool = |uusd_amount: u128, asset_amount: u128| {
+        let mut deps = mock_dependencies(&[coin(uusd_amount, "uusd")]);
+        deps.querier.with_token_balances(&[(
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(asset_amount),
+            )],
+        )]);
+
+        let msg = InstantiateMsg {
+            asset_infos: vec![
+                AssetInfo::SmartToken("uusd".to_string()),
+                AssetInfo::Cw20Token("asset0000".to_string()),
+            ],
+            factory_addr: String::from("factory"),
+            init_params: None,
+            staking_config: default_stake_config(),
+            trading_starts: 0,
+            fee_config: FeeConfig {
+                total_fee_bps: 30,
+                protocol_fee_bps: 1660,
+            },
+            circuit_breaker: None,
+            existing_staking_addr: None,
+            initial_oracle_price: None,
+            verified: true,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+        deps
+    };
+    let depth = |uusd_amount: u128, asset_amount: u128| -> DepthResponse {
+        let deps = setup_pool(uusd_amount, asset_amount);
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Depth {
+                    ask_info: AssetInfo::Cw20Token("asset0000".to_string()),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let shallow = depth(30_000_000_000, 20_000_000_000);
+    assert_eq!(
+        shallow.offer_asset.info,
+        AssetInfoValidated::SmartToken("uusd".to_string())
+    );
+    assert_eq!(
+        shallow.return_asset.info,
+        AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+    );
+    // a constant product pool reaches 1% price impact when offering 1/99 of the offer pool
+    assert_approx_eq!(
+        shallow.offer_asset.amount,
+        Uint128::new(30_000_000_000 / 99),
+        "0.0001"
+    );
+    // the trade pays the fee on top of the price impact
+    let (return_amount, _, _) = compute_swap(
+        Uint128::new(30_000_000_000),
+        Uint128::new(20_000_000_000),
+        shallow.offer_asset.amount,
+        Decimal::permille(3),
+    )
+    .unwrap();
+    assert_eq!(shallow.return_asset.amount, return_amount);
+
+    // a deeper pool at the same price has a higher depth
+    let deep = depth(300_000_000_000, 200_000_000_000);
+    assert!(deep.offer_asset.amount > shallow.offer_asset.amount);
+    assert!(deep.return_asset.amount > shallow.return_asset.amount);
+
+    // assets outside the pool are rejected
+    let deps = setup_pool(30_000_000_000, 20_000_000_000);
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Depth {
+            ask_info: AssetInfo::SmartToken("cny".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The asset cny does not belong to the pool")
+    );
+}
+
+#[test]
+fn query_offer_to_move_price() {
+    let uusd_amount = 30_000_000_000u128;
+    let asset_amount = 20_000_000_000u128;
+    let mut deps = mock_dependencies(&[coin(uusd_amount, "uusd")]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(asset_amount),
+        )],
+    )]);
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let offer_to_move_price = |bps: u16| {
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OfferToMovePrice {
+                offer_info: AssetInfo::SmartToken("uusd".to_string()),
+                ask_info: AssetInfo::Cw20Token("asset0000".to_string()),
+                bps,
+            },
+        )
+        .map(|res| from_json::<OfferToMovePriceResponse>(&res).unwrap())
+    };
+    // price of uusd in asset0000 after offering `amount` uusd, ignoring fees
+    let price_after = |amount: Uint128| {
+        let (return_amount, _, _) = compute_swap(
+            Uint128::new(uusd_amount),
+            Uint128::new(asset_amount),
+            amount,
+            Decimal::zero(),
+        )
+        .unwrap();
+        Decimal::from_ratio(
+            Uint128::new(asset_amount) - return_amount,
+            Uint128::new(uusd_amount) + amount,
+        )
+    };
+    let initial_price = Decimal::from_ratio(asset_amount, uusd_amount);
+
+    for bps in [1u16, 50, 100, 1000, 5000] {
+        let res = offer_to_move_price(bps).unwrap();
+        assert_eq!(
+            res.offer_asset.info,
+            AssetInfoValidated::SmartToken("uusd".to_string())
+        );
+        assert_eq!(
+            res.return_asset.info,
+            AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+        );
+        let target = initial_price * Decimal::from_ratio(10_000 - bps, 10_000u16);
+        // the trade reaches the target, one unit less does not
+        assert!(price_after(res.offer_asset.amount) <= target);
+        assert!(price_after(res.offer_asset.amount - Uint128::one()) > target);
+        assert_approx_eq!(
+            price_after(res.offer_asset.amount).numerator(),
+            target.numerator(),
+            "0.000001"
+        );
+        // the returned asset is what a swap would pay out
+        let (return_amount, _, _) = compute_swap(
+            Uint128::new(uusd_amount),
+            Uint128::new(asset_amount),
+            res.offer_asset.amount,
+            Decimal::permille(3),
+        )
+        .unwrap();
+        assert_eq!(res.return_asset.amount, return_amount);
+    }
+
+    // invalid price moves are rejected
+    for bps in [0, 10_000] {
+        assert_eq!(
+            offer_to_move_price(bps).unwrap_err(),
+            StdError::generic_err(ContractError::InvalidPriceMoveBps {}.to_string())
+        );
+    }
+}
+
+#[test]
+fn query_invariant_after_swap_and_liquidity_round_trip() {
+    fn set_pool_balances(querier: &mut WasmMockQuerier, uusd: u128, asset: u128) {
+        querier.with_balance(&[(&String::from(MOCK_CONTRACT_ADDR), &[coin(uusd, "uusd")])]);
+        querier.with_token_balances(&[(
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(asset))],
+        )]);
+    }
+    fn attr_value(res: &Response, key: &str) -> u128 {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .unwrap()
+            .value
+            .parse()
+            .unwrap()
+    }
+    let provide_msg = |amount: u128| ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(amount),
+            },
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: Uint128::new(amount),
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+    };
+
+    let mut deps = mock_dependencies(&[]);
+    set_pool_balances(&mut deps.querier, 0, 0);
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // initial liquidity
+    let amount = 1_000_000_000u128;
+    set_pool_balances(&mut deps.querier, amount, 0);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(amount, "uusd")]),
+        provide_msg(amount),
+    )
+    .unwrap();
+    set_pool_balances(&mut deps.querier, amount, amount);
+    let initial = query_invariant(deps.as_ref()).unwrap().value;
+    assert_eq!(initial, Uint128::new(amount * amount));
+
+    // a balanced provide and withdrawing the minted share again leaves the invariant as it was
+    let deposit = 100_000_000u128;
+    set_pool_balances(&mut deps.querier, amount + deposit, amount);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(deposit, "uusd")]),
+        provide_msg(deposit),
+    )
+    .unwrap();
+    let share = attr_value(&res, "share");
+    set_pool_balances(&mut deps.querier, amount + deposit, amount + deposit);
+
+    let lp_token = CONFIG
+        .load(deps.as_ref().storage)
+        .unwrap()
+        .pool_info
+        .liquidity_token;
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(share, lp_token)]),
+        ExecuteMsg::WithdrawLiquidity {
+            assets: vec![],
+            max_burn: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "refund_assets",
+            format!("{deposit}uusd, {deposit}asset0000")
+        )
+    );
+    set_pool_balances(&mut deps.querier, amount, amount);
+    assert_eq!(query_invariant(deps.as_ref()).unwrap().value, initial);
+
+    // the fee that stays in the pool after a swap increases the invariant
+    let offer_amount = 1_000_000u128;
+    set_pool_balances(&mut deps.querier, amount + offer_amount, amount);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(offer_amount, "uusd")]),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(offer_amount),
+            },
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(1)),
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+    )
+    .unwrap();
+    assert!(attr_value(&res, "commission_amount") > 0);
+    let paid_out = attr_value(&res, "return_amount") + attr_value(&res, "protocol_fee_amount");
+    set_pool_balances(&mut deps.querier, amount + offer_amount, amount - paid_out);
+    assert!(query_invariant(deps.as_ref()).unwrap().value > initial);
+
+    // the product of large reserves does not fit into 128 bits
+    set_pool_balances(&mut deps.querier, u128::MAX, u128::MAX);
+    assert_eq!(
+        query_invariant(deps.as_ref()).unwrap().value,
+        Uint256::from(u128::MAX) * Uint256::from(u128::MAX)
+    );
+}
+
+#[test]
+fn test_query_pool() {
+    let total_share_amount = Uint128::from(111u128);
+    let asset_0_amount = Uint128::from(222u128);
+    let asset_1_amount = Uint128::from(333u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
         (
             &String::from("liquidity0000"),
             &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
@@ -1750,6 +2882,72 @@ fn test_query_share() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+
+    assert_eq!(
+        res.assets,
+        [
+            AssetValidated {
+                info: AssetInfoValidated::SmartToken("uusd".to_string()),
+                amount: asset_0_amount
+            },
+            AssetValidated {
+                info: AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000")),
+                amount: asset_1_amount
+            }
+        ]
+    );
+    // TODO: If you won't provide liquidity, this will be 0
+    // assert_eq!(res.total_share, total_share_amount);
+}
+
+#[test]
+fn test_query_share() {
+    let total_share_amount = Uint128::from(500u128);
+    let asset_0_amount = Uint128::from(250u128);
+    let asset_1_amount = Uint128::from(1000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -1861,8 +3059,8 @@ fn test_accumulate_prices() {
                 },
                 factory_addr: Addr::unchecked("factory"),
                 block_time_last: case.block_time_last,
-                price0_cumulative_last: Uint128::new(case.last0),
-                price1_cumulative_last: Uint128::new(case.last1),
+                price0_cumulative_last: Uint256::from(case.last0),
+                price1_cumulative_last: Uint256::from(case.last1),
                 trading_starts: 0,
             },
             Uint128::new(case.x_amount),
@@ -1875,17 +3073,415 @@ fn test_accumulate_prices() {
         if let Some(config) = config {
             assert_eq!(config.2, result.block_time_last);
             assert_eq!(
-                config.0 / Uint128::from(price_precision),
-                Uint128::new(result.price_x)
+                config.0 / Uint256::from(price_precision),
+                Uint256::from(result.price_x)
             );
             assert_eq!(
-                config.1 / Uint128::from(price_precision),
-                Uint128::new(result.price_y)
+                config.1 / Uint256::from(price_precision),
+                Uint256::from(result.price_y)
             );
         }
     }
 }
 
+#[test]
+fn accumulate_prices_after_years_at_extreme_price() {
+    let config = Config {
+        pool_info: PairInfo {
+            asset_infos: vec![
+                AssetInfoValidated::SmartToken("uusd".to_string()),
+                AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000")),
+            ],
+            contract_addr: Addr::unchecked("pool"),
+            staking_addr: Addr::unchecked("stake"),
+            liquidity_token: String::from("lp_token"),
+            pool_type: PoolType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 0,
+                protocol_fee_bps: 0,
+            },
+            verified: true,
+        },
+        factory_addr: Addr::unchecked("factory"),
+        block_time_last: 1_000,
+        price0_cumulative_last: Uint256::MAX,
+        price1_cumulative_last: Uint256::zero(),
+        trading_starts: 0,
+    };
+
+    // a hundred years without any update, with asset 0 being extremely scarce
+    let elapsed = 100 * 365 * 86400u64;
+    let env = mock_env_with_block_time(config.block_time_last + elapsed);
+    let (price0, price1, block_time) =
+        accumulate_prices(&env, &config, Uint128::one(), Uint128::MAX)
+            .unwrap()
+            .unwrap();
+
+    let price_precision = Uint256::from(10u128.pow(TWAP_PRECISION.into()));
+    assert_eq!(block_time, config.block_time_last + elapsed);
+    // the accumulator wraps around instead of failing
+    assert_eq!(
+        price0,
+        Uint256::from(elapsed) * price_precision * Uint256::from(u128::MAX) - Uint256::one()
+    );
+    // asset 1 is worth less than the price precision
+    assert_eq!(price1, Uint256::zero());
+}
+
+#[test]
+fn initial_oracle_price_is_queryable_before_first_provide() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let msg = |initial_oracle_price| InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price,
+        verified: true,
+    };
+
+    // zero price is rejected
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(1_000),
+        mock_info("addr0000", &[]),
+        msg(Some(Decimal::zero())),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInitialOraclePrice {});
+
+    instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(1_000),
+        mock_info("addr0000", &[]),
+        msg(Some(Decimal::percent(200))),
+    )
+    .unwrap();
+
+    // forward time an hour without providing any liquidity
+    let env = mock_env_with_block_time(1_000 + 60 * 60);
+    let twap: TwapResponse = from_json(
+        query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Twap {
+                duration: SamplePeriod::HalfHour,
+                start_age: 1,
+                end_age: Some(0),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(twap.a_per_b, Decimal::percent(200));
+    assert_eq!(twap.b_per_a, Decimal::percent(50));
+}
+
+#[test]
+fn update_staking_addr() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: Some("old_stake".to_string()),
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let staking_addr = |deps: Deps<CoreumQueries>| {
+        from_json::<PairInfo>(&query(deps, mock_env(), QueryMsg::Pair {}).unwrap())
+            .unwrap()
+            .staking_addr
+    };
+    assert_eq!(staking_addr(deps.as_ref()), "old_stake");
+
+    let msg = ExecuteMsg::UpdateStakingAddr {
+        new_addr: "new_stake".to_string(),
+    };
+
+    // only the factory can update the staking address
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info("factory", &[]), msg).unwrap();
+    assert_eq!(staking_addr(deps.as_ref()), "new_stake");
+}
+
+#[test]
+fn instantiate_with_invalid_fee_bps() {
+    let msg = |total_fee_bps, protocol_fee_bps| InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps,
+            protocol_fee_bps,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    for (total_fee_bps, protocol_fee_bps) in [(10_001, 0), (30, 10_001), (u16::MAX, u16::MAX)] {
+        let err = instantiate(
+            mock_dependencies(&[]).as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            msg(total_fee_bps, protocol_fee_bps),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidFeeBps {});
+    }
+
+    // the bounds themselves are valid
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg(10_000, 10_000),
+    )
+    .unwrap();
+}
+
+#[test]
+fn query_ticker_matches_dedicated_queries() {
+    let setup_pool = |uusd_amount: u128, asset_amount: u128| {
+        let mut deps = mock_dependencies(&[coin(uusd_amount, "uusd")]);
+        deps.querier.with_token_balances(&[(
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(asset_amount),
+            )],
+        )]);
+
+        let msg = InstantiateMsg {
+            asset_infos: vec![
+                AssetInfo::SmartToken("uusd".to_string()),
+                AssetInfo::Cw20Token("asset0000".to_string()),
+            ],
+            factory_addr: String::from("factory"),
+            init_params: None,
+            staking_config: default_stake_config(),
+            trading_starts: 0,
+            fee_config: FeeConfig {
+                total_fee_bps: 30,
+                protocol_fee_bps: 1660,
+            },
+            circuit_breaker: None,
+            existing_staking_addr: None,
+            initial_oracle_price: None,
+            verified: true,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+        deps
+    };
+    let uusd = AssetInfoValidated::SmartToken("uusd".to_string());
+    let asset = AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"));
+
+    let deps = setup_pool(30_000_000_000, 20_000_000_000);
+    let ticker: TickerResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ticker {}).unwrap()).unwrap();
+
+    let pool = query_pool(deps.as_ref()).unwrap();
+    assert_eq!(ticker.reserves, pool.assets);
+    assert_eq!(ticker.total_share, pool.total_share);
+
+    let pair: PairInfo =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Pair {}).unwrap()).unwrap();
+    assert_eq!(ticker.total_fee_bps, pair.fee_config.total_fee_bps);
+
+    // the spot prices are the reserve ratios
+    assert_eq!(
+        ticker.spot_prices,
+        vec![
+            (
+                uusd.clone(),
+                asset.clone(),
+                Decimal::from_ratio(pool.assets[1].amount, pool.assets[0].amount),
+            ),
+            (
+                asset,
+                uusd,
+                Decimal::from_ratio(pool.assets[0].amount, pool.assets[1].amount),
+            ),
+        ]
+    );
+    assert_eq!(ticker.spot_prices[1].2, Decimal::percent(150));
+
+    // an empty pool has no prices yet
+    let deps = setup_pool(0, 0);
+    let ticker: TickerResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ticker {}).unwrap()).unwrap();
+    assert_eq!(ticker.spot_prices, vec![]);
+}
+
+#[test]
+fn spot_price_is_limit_of_simulation() {
+    let mut deps = mock_dependencies(&[coin(30_000_000_000, "uusd")]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(20_000_000_000),
+        )],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    let uusd = AssetInfo::SmartToken("uusd".to_string());
+    let asset = AssetInfo::Cw20Token("asset0000".to_string());
+    for (offer, ask) in [(&uusd, &asset), (&asset, &uusd)] {
+        let res: SpotPriceResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SpotPrice {
+                    offer: offer.clone(),
+                    ask: ask.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let spot_price = res.price;
+
+        // without the fee, the average price of a swap converges to the spot price
+        let mut last_error = Decimal::MAX;
+        for offer_amount in [1_000_000_000u128, 10_000_000, 100_000] {
+            let simulation = query_simulation(
+                deps.as_ref(),
+                Asset {
+                    info: offer.clone(),
+                    amount: Uint128::new(offer_amount),
+                },
+                false,
+                None,
+            )
+            .unwrap();
+            let swap_price = Decimal::from_ratio(
+                simulation.return_amount + simulation.commission_amount,
+                offer_amount,
+            );
+            // the average price of a swap is always worse than the spot price
+            assert!(swap_price <= spot_price);
+            let error = spot_price - swap_price;
+            assert!(error < last_error);
+            last_error = error;
+        }
+        assert!(last_error < Decimal::from_ratio(1u128, 10_000u128));
+    }
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SpotPrice {
+            offer: uusd,
+            ask: AssetInfo::Cw20Token("asset0001".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::AssetMismatch {}.to_string())
+    );
+
+    // an empty pool has no spot price
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SpotPrice {
+            offer: AssetInfo::SmartToken("uusd".to_string()),
+            ask: AssetInfo::Cw20Token("asset0000".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::NoLiquidity {}.to_string())
+    );
+}
+
 fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
@@ -1911,6 +3507,32 @@ fn compute_swap_rounding() {
     );
 }
 
+#[test]
+fn compute_zap_swap_amount_keeps_pool_ratio() {
+    // without fees, swapping `sqrt(A^2 + a * A) - A` of `a` leaves the rest in the new pool ratio
+    assert_eq!(
+        compute_zap_swap_amount(
+            Uint128::new(1_000_000),
+            Uint128::new(3_000_000),
+            Decimal::zero()
+        ),
+        Ok(Uint128::new(1_000_000))
+    );
+
+    // with fees, the deposits still match the pool after the swap
+    let (offer_pool, ask_pool) = (Uint128::new(100_000_000), Uint128::new(300_000_000));
+    let offer_amount = Uint128::new(2_000_000);
+    let fee = Decimal::permille(3);
+    let swap_amount = compute_zap_swap_amount(offer_pool, offer_amount, fee).unwrap();
+    let (return_amount, _, _) = compute_swap(offer_pool, ask_pool, swap_amount, fee).unwrap();
+    assert_approx_eq!(
+        (offer_amount - swap_amount)
+            .multiply_ratio(ask_pool - return_amount, offer_pool + swap_amount),
+        return_amount,
+        "0.0001"
+    );
+}
+
 proptest! {
     #[test]
     fn compute_swap_overflow_test(
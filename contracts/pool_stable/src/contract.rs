@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::vec;
 
 use coreum_wasm_sdk::{
@@ -8,12 +9,12 @@ use coreum_wasm_sdk::{
 use cosmwasm_std::{
     attr, coin, ensure, entry_point, from_json, to_json_binary, Addr, BankMsg, Binary, Coin,
     CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Env, Fraction, MessageInfo, QuerierWrapper,
-    Reply, StdError, StdResult, Uint128, Uint256, WasmMsg,
+    Reply, StdError, StdResult, Uint128, Uint256, Uint64, WasmMsg,
 };
 
 use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use itertools::Itertools;
+use itertools::{Itertools, MinMaxResult};
 
 use dex::{
     asset::{
@@ -21,27 +22,37 @@ use dex::{
         AssetInfoValidated, AssetValidated, Decimal256Ext, DecimalAsset, MINIMUM_LIQUIDITY_AMOUNT,
     },
     decimal2decimal256,
-    factory::PoolType,
+    factory::{PoolType, MAX_TRADING_STARTS_DELAY},
     fee_config::FeeConfig,
     pool::{
         add_referral, assert_max_spread, check_asset_infos, check_assets, check_cw20_in_pool,
-        get_share_in_assets, handle_referral, handle_reply, save_tmp_staking_config, take_referral,
-        ConfigResponse, ContractError, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg,
-        InstantiateMsg, MigrateMsg, PairInfo, PoolResponse, QueryMsg, ReverseSimulationResponse,
-        SimulationResponse, StablePoolParams, StablePoolUpdateParams, LP_TOKEN_PRECISION,
+        check_lp_funds, get_share_in_assets, handle_referral, handle_reply,
+        save_tmp_staking_config, take_referral, AmpResponse, CollectedProtocolFeesResponse,
+        ConfigResponse, ContractError, CumulativePricesResponse, Cw20HookMsg, DepthResponse,
+        ExecuteMsg, ImbalancedWithdrawSimulationResponse, InstantiateMsg, InvariantResponse,
+        LsdHubQueryMsg, MigrateMsg, OfferToMovePriceResponse, PairInfo, PoolResponse,
+        PrecisionsResponse, QueryMsg, RebalanceToResponse, ReverseSimulationResponse,
+        SimulationResponse, SpotPriceResponse, StablePoolConfig, StablePoolParams,
+        StablePoolUpdateParams, SwapFeeResponse, TargetValueResponse, TickerResponse,
+        TradingStartedResponse, DEFAULT_SLIPPAGE, LP_TOKEN_PRECISION, MAX_ALLOWED_SLIPPAGE,
     },
-    querier::{query_factory_config, query_fee_info},
+    querier::{query_factory_config, query_fee_info, query_token_precision},
     DecimalCheckedOps,
 };
 
 use crate::{
-    math::{calc_y, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME},
+    math::{
+        apply_rate_decimal, calc_marginal_price, calc_y, compute_d, AMP_PRECISION, MAX_AMP,
+        MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME,
+    },
     state::{
-        get_precision, store_precisions, Config, CIRCUIT_BREAKER, CONFIG, FROZEN, LP_SHARE_AMOUNT,
+        get_precision, store_precision, store_precisions, Config, CIRCUIT_BREAKER, CONFIG, FROZEN,
+        LP_SHARE_AMOUNT, PROTOCOL_FEES_COLLECTED,
     },
     utils::{
         accumulate_prices, adjust_precision, calc_new_price_a_per_b, compute_current_amp,
-        compute_swap, select_pools, SwapResult,
+        compute_swap, find_depth_trade, find_price_move_trade, find_rebalance_trade, select_pools,
+        SwapResult,
     },
 };
 
@@ -52,6 +63,8 @@ pub type SubMsg = cosmwasm_std::SubMsg<CoreumMsg>;
 const CONTRACT_NAME: &str = "dex-stable-pool";
 /// Contract version that is used for migration.
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The maximum relative deviation from the pool ratio allowed when imbalanced provides are disabled.
+const BALANCED_PROVIDE_TOLERANCE: Decimal256 = Decimal256::percent(1);
 
 /// Creates a new contract with the specified parameters in the [`InstantiateMsg`].
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -70,6 +83,8 @@ pub fn instantiate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     msg.validate_fees()?;
+    msg.validate_trading_starts(env.block.time)?;
+    msg.validate_initial_oracle_price()?;
 
     let factory_addr = deps.api.addr_validate(msg.factory_addr.as_str())?;
 
@@ -79,21 +94,61 @@ pub fn instantiate(
         return Err(ContractError::InitParamsNotFound {});
     }
 
-    msg.validate_fees()?;
-
     let params: StablePoolParams = from_json(msg.init_params.unwrap())?;
 
     if params.amp == 0 || params.amp > MAX_AMP {
         return Err(ContractError::IncorrectAmp { max_amp: MAX_AMP });
     }
+    let init_amp = params.amp * AMP_PRECISION;
+    let (next_amp, next_amp_time) = match params.amp_ramp {
+        Some(ramp) => {
+            validate_amp_change(init_amp, ramp.next_amp)?;
+            if ramp.next_amp_time < env.block.time.seconds() + MIN_AMP_CHANGING_TIME {
+                return Err(ContractError::MinAmpChangingTimeAssertion {
+                    min_amp_changing_time: MIN_AMP_CHANGING_TIME,
+                });
+            }
+            (ramp.next_amp * AMP_PRECISION, ramp.next_amp_time)
+        }
+        None => (init_amp, env.block.time.seconds()),
+    };
+    let max_allowed_spread = match params.max_allowed_spread {
+        Some(spread) => {
+            if spread < Decimal::from_str(DEFAULT_SLIPPAGE)?
+                || spread > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?
+            {
+                return Err(ContractError::InvalidMaxAllowedSpread {});
+            }
+            spread
+        }
+        None => Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?,
+    };
     let greatest_precision = store_precisions(deps.branch(), &asset_infos)?;
 
+    let (lsd_asset, target_rate_provider, target_rate_epoch) = match params.lsd {
+        Some(lsd) => {
+            if asset_infos.len() != 2 {
+                return Err(ContractError::InvalidAssetsForTargetRate {});
+            }
+            let asset = lsd.asset.validate(deps.api)?;
+            if !asset_infos.contains(&asset) {
+                return Err(ContractError::InvalidAsset(asset.to_string()));
+            }
+            (
+                Some(asset),
+                Some(deps.api.addr_validate(&lsd.hub)?),
+                lsd.target_rate_epoch,
+            )
+        }
+        None => (None, None, 0),
+    };
+
     // Initializing cumulative prices
     let mut cumulative_prices = vec![];
     for from_pool in &asset_infos {
         for to_pool in &asset_infos {
             if !from_pool.eq(to_pool) {
-                cumulative_prices.push((from_pool.clone(), to_pool.clone(), Uint128::zero()))
+                cumulative_prices.push((from_pool.clone(), to_pool.clone(), Uint256::zero()))
             }
         }
     }
@@ -103,7 +158,8 @@ pub fn instantiate(
         pool_info: PairInfo {
             contract_addr: env.contract.address.clone(),
             liquidity_token: format!("u{}-{}", lp_token_name.clone(), env.contract.address),
-            staking_addr: Addr::unchecked(""),
+            staking_addr: addr_opt_validate(deps.api, &msg.existing_staking_addr)?
+                .unwrap_or_else(|| Addr::unchecked("")),
             asset_infos,
             pool_type: PoolType::Stable {},
             fee_config: msg.fee_config,
@@ -111,18 +167,32 @@ pub fn instantiate(
         },
         factory_addr,
         block_time_last: 0,
-        init_amp: params.amp * AMP_PRECISION,
+        init_amp,
         init_amp_time: env.block.time.seconds(),
-        next_amp: params.amp * AMP_PRECISION,
-        next_amp_time: env.block.time.seconds(),
+        next_amp,
+        next_amp_time,
         greatest_precision,
         cumulative_prices,
         trading_starts: msg.trading_starts,
+        allow_imbalanced: params.allow_imbalanced.unwrap_or(true),
+        dust_threshold: params.dust_threshold.unwrap_or_default(),
+        lsd_asset,
+        target_rate_provider,
+        target_rate_epoch,
+        target_rate: Decimal::one(),
+        // the target rate is queried on the first operation
+        last_target_query: 0,
+        max_allowed_spread,
     };
 
     CONFIG.save(deps.storage, &config)?;
     FROZEN.save(deps.storage, &false)?;
     LP_SHARE_AMOUNT.save(deps.storage, &Uint128::zero())?;
+
+    // seed the oracle, it is reinitialized with the real price on the first liquidity provision
+    if let Some(price) = msg.initial_oracle_price {
+        dex::oracle::initialize_oracle(deps.storage, &env, price)?;
+    }
     save_tmp_staking_config(deps.storage, &msg.staking_config)?;
 
     Ok(
@@ -189,12 +259,20 @@ pub fn reply(
 ///             receiver,
 ///         }** Provides liquidity in the pool with the specified input parameters.
 ///
+/// * **ExecuteMsg::ZapIn {
+///             offer_asset,
+///             min_share,
+///             receiver,
+///         }** Provides liquidity with a single asset.
+///
 /// * **ExecuteMsg::Swap {
 ///             offer_asset,
 ///             belief_price,
 ///             max_spread,
 ///             to,
 ///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::Donate { assets }** Adds assets to the pool without minting LP tokens.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut<CoreumQueries>,
@@ -206,9 +284,14 @@ pub fn execute(
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
-            slippage_tolerance: _,
+            slippage_tolerance,
+            receiver,
+        } => provide_liquidity(deps, env, info, assets, slippage_tolerance, receiver),
+        ExecuteMsg::ZapIn {
+            offer_asset,
+            min_share,
             receiver,
-        } => provide_liquidity(deps, env, info, assets, receiver),
+        } => zap_in(deps, env, info, offer_asset, min_share, receiver),
         ExecuteMsg::UpdateFees { fee_config } => update_fees(deps, info, fee_config),
         ExecuteMsg::Swap {
             offer_asset,
@@ -244,16 +327,26 @@ pub fn execute(
         }
         ExecuteMsg::Freeze { frozen } => {
             ensure!(
-                info.sender
-                    == CIRCUIT_BREAKER
-                        .may_load(deps.storage)?
-                        .unwrap_or_else(|| Addr::unchecked("")),
+                info.sender == CONFIG.load(deps.storage)?.factory_addr
+                    || info.sender
+                        == CIRCUIT_BREAKER
+                            .may_load(deps.storage)?
+                            .unwrap_or_else(|| Addr::unchecked("")),
                 ContractError::Unauthorized {}
             );
             FROZEN.save(deps.storage, &frozen)?;
             Ok(Response::new())
         }
-        ExecuteMsg::WithdrawLiquidity { assets } => withdraw_liquidity(deps, env, info, assets),
+        ExecuteMsg::WithdrawLiquidity { assets, max_burn } => {
+            withdraw_liquidity(deps, env, info, assets, max_burn)
+        }
+        ExecuteMsg::UpdateOracle {} => update_oracle(deps, env),
+        ExecuteMsg::Donate { assets } => donate(deps, env, info, assets),
+        ExecuteMsg::RefreshPrecision { asset } => refresh_precision(deps, info, asset),
+        ExecuteMsg::UpdateStakingAddr { new_addr } => update_staking_addr(deps, info, new_addr),
+        ExecuteMsg::UpdateTradingStarts { new_start } => {
+            update_trading_starts(deps, env, info, new_start)
+        }
         _ => Err(ContractError::NonSupported {}),
     }
 }
@@ -327,10 +420,75 @@ pub fn update_fees(
     Ok(Response::default())
 }
 
+/// Updates the address of the staking contract recorded in the pool info.
+///
+/// * **new_addr** is the address of the new staking contract.
+///
+/// ## Executor
+/// Only the factory can execute this.
+pub fn update_staking_addr(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    new_addr: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // check permissions
+    if info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.pool_info.staking_addr = deps.api.addr_validate(&new_addr)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_staking_addr"),
+        attr("staking_addr", config.pool_info.staking_addr),
+    ]))
+}
+
+/// Moves the block time until which trading is disabled.
+///
+/// * **new_start** is the new trading start, at most [`MAX_TRADING_STARTS_DELAY`] seconds in the future.
+///
+/// ## Executor
+/// Only the factory can execute this.
+pub fn update_trading_starts(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    new_start: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // check permissions
+    if info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if new_start > env.block.time.seconds() + MAX_TRADING_STARTS_DELAY {
+        return Err(ContractError::InvalidTradingStart {
+            max_delay: MAX_TRADING_STARTS_DELAY,
+        });
+    }
+
+    config.trading_starts = new_start;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_trading_starts"),
+        attr("trading_starts", new_start.to_string()),
+    ]))
+}
+
 /// Provides liquidity in the pool with the specified input parameters.
 ///
 /// * **assets** is an array with assets available in the pool.
 ///
+/// * **slippage_tolerance** is an optional parameter which is used to specify how much
+/// the deposit ratio may differ from the pool ratio until the provide liquidity transaction fails.
+/// Defaults to [`DEFAULT_SLIPPAGE`]. Assets that are not deposited are not compared.
+///
 /// * **receiver** is an optional parameter which defines the receiver of the LP tokens.
 /// If no custom receiver is specified, the pool will mint LP tokens for the function caller.
 ///
@@ -340,7 +498,62 @@ pub fn provide_liquidity(
     env: Env,
     info: MessageInfo,
     assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    add_liquidity(
+        deps,
+        env,
+        info,
+        assets,
+        slippage_tolerance,
+        receiver,
+        false,
+        None,
+    )
+}
+
+/// Provides liquidity with a single asset. No separate swap is needed for that, since the invariant
+/// supports imbalanced deposits and charges the imbalance fee for the part that would have been swapped.
+/// Fails with [`ContractError::ImbalancedNotAllowed`] if imbalanced provides are disabled.
+///
+/// * **offer_asset** is the asset to provide.
+///
+/// * **min_share** is an optional minimum amount of LP tokens that have to be minted.
+///
+/// * **receiver** is an optional parameter which defines the receiver of the LP tokens.
+/// If no custom receiver is specified, the pool will mint LP tokens for the function caller.
+pub fn zap_in(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    offer_asset: Asset,
+    min_share: Option<Uint128>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    add_liquidity(
+        deps,
+        env,
+        info,
+        vec![offer_asset],
+        None,
+        receiver,
+        true,
+        min_share,
+    )
+}
+
+/// Shared implementation of [`provide_liquidity`] and [`zap_in`].
+#[allow(clippy::too_many_arguments)]
+fn add_liquidity(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
     receiver: Option<String>,
+    zap: bool,
+    min_share: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     check_if_frozen(&deps)?;
     let assets = check_assets(deps.api, &assets)?;
@@ -430,6 +643,14 @@ pub fn provide_liquidity(
         }
     }
 
+    if !config.allow_imbalanced {
+        assert_balanced_deposit(&assets_collection)?;
+    }
+    // a zap deposits a single asset on purpose and is protected by `min_share` instead
+    if !zap {
+        assert_slippage_tolerance(slippage_tolerance, &assets_collection)?;
+    }
+
     let assets_collection = assets_collection
         .iter()
         .cloned()
@@ -449,14 +670,20 @@ pub fn provide_liquidity(
     // Initial invariant (D)
     let old_balances = assets_collection
         .iter()
-        .map(|(_, pool)| *pool)
+        .map(|(deposit, pool)| apply_rate_decimal(&deposit.info, *pool, &config))
         .collect_vec();
     let init_d = compute_d(amp, &old_balances, config.greatest_precision)?;
 
     // Invariant (D) after deposit added
     let mut new_balances: Vec<_> = assets_collection
         .iter()
-        .map(|(deposit, pool)| Ok(pool + deposit.amount))
+        .map(|(deposit, pool)| {
+            Ok(apply_rate_decimal(
+                &deposit.info,
+                pool + deposit.amount,
+                &config,
+            ))
+        })
         .collect::<StdResult<Vec<_>>>()?;
     let deposit_d = compute_d(amp, &new_balances, config.greatest_precision)?;
 
@@ -488,20 +715,12 @@ pub fn provide_liquidity(
         share
     } else {
         // Get fee info from the factory
-        // let fee_info = query_fee_info(
-        //     &deps.querier,
-        //     &config.factory_addr,
-        //     config.pool_info.pair_type.clone(),
-        // )?;
-
-        // FIXME: Bring this back when factory is ready
-        // total_fee_rate * N_COINS / (4 * (N_COINS - 1))
-        let fee = /*fee_info
-            .total_fee_rate*/
-            Decimal::percent(3)
-            .checked_mul(Decimal::from_ratio(n_coins, 4 * (n_coins - 1)))?;
-
-        let fee = Decimal256::new(fee.atomics().into());
+        let fee_info = query_fee_info(
+            &deps.querier,
+            &config.factory_addr,
+            config.pool_info.pool_type.clone(),
+        )?;
+        let fee = imbalance_fee_rate(fee_info.total_fee_rate, n_coins)?;
 
         for i in 0..n_coins as usize {
             let ideal_balance = deposit_d.checked_multiply_ratio(old_balances[i], init_d)?;
@@ -527,6 +746,12 @@ pub fn provide_liquidity(
         share
     };
 
+    if let Some(min_share) = min_share {
+        if share < min_share {
+            return Err(ContractError::MinShareAssertion { share, min_share });
+        }
+    }
+
     // Mint LP token for the caller (or for the receiver if it was set)
     let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
     messages.push(CosmosMsg::Custom(CoreumMsg::AssetFT(assetft::Msg::Mint {
@@ -576,7 +801,7 @@ pub fn provide_liquidity(
     }
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "provide_liquidity"),
+        attr("action", if zap { "zap_in" } else { "provide_liquidity" }),
         attr("sender", info.sender),
         attr("receiver", receiver),
         attr("assets", assets.iter().join(", ")),
@@ -584,6 +809,78 @@ pub fn provide_liquidity(
     ]))
 }
 
+/// Checks that every deposit is in the same ratio to its pool balance, within
+/// [`BALANCED_PROVIDE_TOLERANCE`]. Any ratio is accepted while the pool is still empty.
+fn assert_balanced_deposit(
+    assets_collection: &[(AssetValidated, Uint128)],
+) -> Result<(), ContractError> {
+    if !deposit_ratios_within(assets_collection, BALANCED_PROVIDE_TOLERANCE) {
+        return Err(ContractError::ImbalancedNotAllowed {});
+    }
+
+    Ok(())
+}
+
+/// This is an internal function that enforces slippage tolerance for providing liquidity.
+/// Fails if the deposit ratio differs from the current pool ratio by more than the tolerance.
+/// Assets that are not deposited at all are not compared, so single asset deposits always pass.
+///
+/// * **slippage_tolerance** slippage tolerance to enforce, [`DEFAULT_SLIPPAGE`] if not given.
+///
+/// * **assets_collection** array with the deposits and the current pool balances.
+fn assert_slippage_tolerance(
+    slippage_tolerance: Option<Decimal>,
+    assets_collection: &[(AssetValidated, Uint128)],
+) -> Result<(), ContractError> {
+    let slippage_tolerance = match slippage_tolerance {
+        Some(slippage_tolerance) => slippage_tolerance,
+        None => Decimal::from_str(DEFAULT_SLIPPAGE)?,
+    };
+    let max_allowed_slippage = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+    if slippage_tolerance > max_allowed_slippage {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    let deposits = assets_collection
+        .iter()
+        .filter(|(deposit, _)| !deposit.amount.is_zero())
+        .cloned()
+        .collect_vec();
+    if !deposit_ratios_within(&deposits, decimal2decimal256(slippage_tolerance)?) {
+        return Err(ContractError::MaxSlippageAssertion {});
+    }
+
+    Ok(())
+}
+
+/// Returns whether the ratios of all deposits to their pool balances differ by at most `tolerance`
+/// (relative to the largest ratio). Always true while the pool is still empty.
+fn deposit_ratios_within(
+    assets_collection: &[(AssetValidated, Uint128)],
+    tolerance: Decimal256,
+) -> bool {
+    if assets_collection.iter().any(|(_, pool)| pool.is_zero()) {
+        return true;
+    }
+
+    let ratios = assets_collection
+        .iter()
+        .map(|(deposit, pool)| Decimal256::from_ratio(deposit.amount, *pool))
+        .collect_vec();
+    match ratios.iter().minmax() {
+        MinMaxResult::MinMax(min, max) => *max - *min <= *max * tolerance,
+        _ => true,
+    }
+}
+
+/// Returns the fee rate charged on the imbalanced part of a provide or withdrawal,
+/// i.e. `total_fee_rate * N_COINS / (4 * (N_COINS - 1))`.
+fn imbalance_fee_rate(total_fee_rate: Decimal, n_coins: u8) -> Result<Decimal256, ContractError> {
+    let fee = total_fee_rate.checked_mul(Decimal::from_ratio(n_coins, 4 * (n_coins - 1)))?;
+
+    Ok(Decimal256::new(fee.atomics().into()))
+}
+
 /// Withdraw liquidity from the pool.
 /// * **sender** is the address that will receive assets back from the pool contract.
 ///
@@ -593,28 +890,50 @@ pub fn withdraw_liquidity(
     env: Env,
     info: MessageInfo,
     assets: Vec<Asset>,
+    max_burn: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let assets = check_assets(deps.api, &assets)?;
     let config = CONFIG.load(deps.storage).unwrap();
 
-    if info.funds[0].denom.clone() != config.pool_info.liquidity_token.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
-
+    let amount = check_lp_funds(&info.funds, &config.pool_info.liquidity_token)?;
     let sender = info.sender.clone();
-    let amount = info.funds[0].amount;
 
     let burn_amount;
     let refund_assets;
     let mut messages: Vec<CosmosMsg<CoreumMsg>> = vec![];
 
+    if !assets.is_empty() && !config.allow_imbalanced {
+        return Err(ContractError::ImbalancedNotAllowed {});
+    }
+
     let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
     if assets.is_empty() {
         burn_amount = amount;
         refund_assets = get_share_in_assets(&pools, amount, total_share);
     } else {
         // Imbalanced withdraw
-        burn_amount = imbalanced_withdraw(deps.as_ref(), &env, &config, amount, &assets)?;
+        let fee_info = query_fee_info(
+            &deps.querier,
+            &config.factory_addr,
+            config.pool_info.pool_type.clone(),
+        )?;
+        burn_amount = imbalanced_withdraw(
+            deps.as_ref(),
+            &env,
+            &config,
+            amount,
+            &assets,
+            fee_info.total_fee_rate,
+        )?;
+        if let Some(max_burn) = max_burn {
+            ensure!(
+                burn_amount <= max_burn,
+                ContractError::MaxBurnAssertion {
+                    burn_amount,
+                    max_burn
+                }
+            );
+        }
         if burn_amount < amount {
             // Returning unused LP tokens back to the user
             messages.push(CosmosMsg::Bank(BankMsg::Send {
@@ -636,9 +955,8 @@ pub fn withdraw_liquidity(
             coin: coin(burn_amount.u128(), &config.pool_info.liquidity_token),
         })),
     ];
-    LP_SHARE_AMOUNT.update(deps.storage, |mut amount| -> StdResult<_> {
-        amount -= amount;
-        Ok(amount)
+    LP_SHARE_AMOUNT.update(deps.storage, |amount| -> StdResult<_> {
+        Ok(amount.checked_sub(burn_amount)?)
     })?;
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
@@ -652,6 +970,158 @@ pub fn withdraw_liquidity(
     ]))
 }
 
+/// Stores the current pool price in the oracle and accumulates the cumulative prices,
+/// so the TWAP stays accurate even if the pool is not traded for a while.
+pub fn update_oracle(deps: DepsMut<CoreumQueries>, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let (pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    if total_share.is_zero() {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let save_config = update_target_rate(deps.querier, &mut config, &env)?;
+
+    let pools = pools
+        .into_iter()
+        .map(|pool| {
+            let precision = get_precision(deps.storage, &pool.info)?;
+            pool.to_decimal_asset(precision)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let price = calc_new_price_a_per_b(deps.as_ref(), &env, &config, &pools)?;
+    dex::oracle::store_oracle_price(deps.storage, &env, price)?;
+
+    if accumulate_prices(deps.as_ref(), &env, &mut config, &pools)? || save_config {
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_oracle"),
+        attr("price", price.to_string()),
+    ]))
+}
+
+/// Adds the given assets to the pool reserves without minting LP tokens.
+/// The donated value is distributed to all existing LPs through the increased share value.
+///
+/// * **assets** are the assets to donate. All of them must be part of the pool.
+pub fn donate(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+) -> Result<Response, ContractError> {
+    let assets = check_assets(deps.api, &assets)?;
+    check_if_frozen(&deps)?;
+
+    if assets.is_empty() || assets.iter().any(|a| a.amount.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let (mut pools, total_share) = pool_info(deps.as_ref(), &config)?;
+    // without any LPs there is nobody to donate to
+    if total_share.is_zero() {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let save_config = update_target_rate(deps.querier, &mut config, &env)?;
+
+    let mut messages: Vec<CosmosMsg<CoreumMsg>> = vec![];
+    let mut new_pools = pools.clone();
+    for asset in &assets {
+        let index = pools
+            .iter()
+            .position(|pool| pool.info == asset.info)
+            .ok_or_else(|| ContractError::InvalidAsset(asset.info.to_string()))?;
+
+        // take asset / make sure it actually got sent
+        asset.receive(&env, &info, &mut messages)?;
+        if asset.info.is_native_token() {
+            // the pool balance already includes the sent native tokens
+            pools[index].amount = pools[index].amount.checked_sub(asset.amount)?;
+        } else {
+            new_pools[index].amount = new_pools[index].amount.checked_add(asset.amount)?;
+        }
+    }
+
+    let to_decimal_assets = |pools: Vec<AssetValidated>| {
+        pools
+            .into_iter()
+            .map(|pool| {
+                let precision = get_precision(deps.storage, &pool.info)?;
+                pool.to_decimal_asset(precision)
+            })
+            .collect::<StdResult<Vec<_>>>()
+    };
+    let old_pools = to_decimal_assets(pools)?;
+    let new_pools = to_decimal_assets(new_pools)?;
+
+    let price = calc_new_price_a_per_b(deps.as_ref(), &env, &config, &new_pools)?;
+    dex::oracle::store_oracle_price(deps.storage, &env, price)?;
+
+    if accumulate_prices(deps.as_ref(), &env, &mut config, &old_pools)? || save_config {
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "donate"),
+        attr("sender", info.sender),
+        attr("assets", assets.iter().join(", ")),
+    ]))
+}
+
+/// Re-queries the precision of one of the pool's assets and updates the stored precisions.
+/// This is needed if the precision of a token changed after the pool was created,
+/// e.g. because it was re-issued. This is only possible while the pool has no liquidity.
+///
+/// * **asset** is the pool asset to refresh the precision for.
+///
+/// ## Executor
+/// Only the factory or the pool owner can execute this.
+pub fn refresh_precision(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.factory_addr && Some(&info.sender) != config.owner.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let asset = asset.validate(deps.api)?;
+    if !config.pool_info.asset_infos.contains(&asset) {
+        return Err(ContractError::InvalidAsset(asset.to_string()));
+    }
+
+    // the reserves and LP shares are accounted in the old precision, so it cannot change once they exist
+    if !LP_SHARE_AMOUNT.load(deps.storage)?.is_zero() {
+        return Err(ContractError::RefreshPrecisionWithLiquidity {});
+    }
+
+    let old_precision = get_precision(deps.storage, &asset)?;
+    let new_precision = query_token_precision(&deps.querier, &asset)?;
+
+    let mut greatest_precision = new_precision;
+    for asset_info in config.pool_info.asset_infos.iter().filter(|a| **a != asset) {
+        greatest_precision = greatest_precision.max(get_precision(deps.storage, asset_info)?);
+    }
+
+    store_precision(deps.storage, &asset, new_precision)?;
+    config.greatest_precision = greatest_precision;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "refresh_precision"),
+        attr("asset", asset.to_string()),
+        attr("old_precision", old_precision.to_string()),
+        attr("new_precision", new_precision.to_string()),
+    ]))
+}
+
 /// Performs an swap operation with the specified parameters. The trader must approve the
 /// pool contract to transfer offer assets from their wallet.
 ///
@@ -682,6 +1152,9 @@ pub fn swap(
 ) -> Result<Response, ContractError> {
     offer_asset.assert_sent_native_token_balance(&info)?;
     let ask_asset_info = ask_asset_info.map(|a| a.validate(deps.api)).transpose()?;
+    if ask_asset_info.as_ref() == Some(&offer_asset.info) {
+        return Err(ContractError::SameAssetSwap {});
+    }
 
     check_if_frozen(&deps)?;
 
@@ -755,10 +1228,18 @@ pub fn swap(
         .checked_mul_uint128(return_amount)?;
     let return_amount = return_amount.saturating_sub(commission_amount);
 
-    // Check the max spread limit (if it was specified)
+    if return_amount < config.dust_threshold {
+        return Err(ContractError::OutputBelowDustThreshold {});
+    }
+
+    // Check the max spread limit, which defaults to `DEFAULT_SLIPPAGE` if it was not specified
+    let max_spread = max_spread.unwrap_or(Decimal::from_str(DEFAULT_SLIPPAGE)?);
+    if max_spread > config.max_allowed_spread {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
     assert_max_spread(
         belief_price,
-        max_spread,
+        Some(max_spread),
         offer_asset.amount,
         return_amount,
         spread_amount + commission_amount,
@@ -783,6 +1264,9 @@ pub fn swap(
             config.pool_info.fee_config.protocol_fee_rate(),
         ) {
             protocol_fee_amount = f.amount;
+            PROTOCOL_FEES_COLLECTED.update(deps.storage, &f.info, |total| -> StdResult<_> {
+                Ok(total.unwrap_or_default() + f.amount)
+            })?;
             messages.push(f.into_msg(fee_address)?);
         }
     }
@@ -882,13 +1366,52 @@ pub fn calculate_protocol_fee(
 /// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation  using
 /// a [`ReverseSimulationResponse`] object.
 ///
+/// * **QueryMsg::SwapFee { offer_asset, ask_asset_info }** Returns the fee breakdown of a prospective swap
+/// using a [`SwapFeeResponse`] object.
+///
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
-/// * **QueryMsg::HistoricalPrices { duration }** Returns historical price information for the assets in the
-/// pool using a [`HistoricalPricesResponse`] object.
+/// * **QueryMsg::HistoricalPrices { duration }** Returns the average price of every full sample period
+/// within the last `duration` seconds using a [`dex::oracle::HistoricalPricesResponse`] object.
+///
+/// * **QueryMsg::TwapStandard { offer, ask }** Returns the average price of `offer` in `ask` over the
+/// last 30 minutes, 1 hour and 24 hours using a [`dex::oracle::TwapStandardResponse`] object.
 ///
 /// * **QueryMsg::Config {}** Returns the configuration for the pool contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::TradingStarted {}** Returns whether trading in the pool has started using a
+/// [`TradingStartedResponse`] object.
+///
+/// * **QueryMsg::RebalanceTo { target_price }** Returns the arbitrage trade that moves the pool to
+/// the given price using a [`RebalanceToResponse`] object.
+///
+/// * **QueryMsg::Depth { ask_info }** Returns the liquidity depth for trading into the given asset
+/// using a [`DepthResponse`] object.
+///
+/// * **QueryMsg::OfferToMovePrice { offer_info, ask_info, bps }** Returns the trade that lowers the
+/// price of the offer asset by the given basis points using an [`OfferToMovePriceResponse`] object.
+///
+/// * **QueryMsg::Invariant {}** Returns the StableSwap invariant `D` of the pool using an
+/// [`InvariantResponse`] object.
+///
+/// * **QueryMsg::SpotPrice { offer, ask }** Returns the price of the offer asset in terms of the
+/// ask asset, ignoring fees, using a [`SpotPriceResponse`] object.
+///
+/// * **QueryMsg::Ticker {}** Returns the reserves, spot prices, fee and LP supply of the pool
+/// using a [`TickerResponse`] object.
+///
+/// * **QueryMsg::Precisions {}** Returns the precision stored for every asset in the pool and the
+/// greatest one using a [`PrecisionsResponse`] object.
+///
+/// * **QueryMsg::ImbalancedWithdrawSimulation { assets, share }** Returns the LP tokens an imbalanced
+/// withdraw would burn using an [`ImbalancedWithdrawSimulationResponse`] object.
+///
+/// * **QueryMsg::Amp {}** Returns the current amplification and the amplification it is changing
+/// towards using an [`AmpResponse`] object.
+///
+/// * **QueryMsg::CollectedProtocolFees {}** Returns the total protocol fees collected by the pool
+/// using a [`CollectedProtocolFeesResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -923,6 +1446,10 @@ pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Bi
             referral,
             referral_commission,
         )?),
+        QueryMsg::SwapFee {
+            offer_asset,
+            ask_asset_info,
+        } => to_json_binary(&query_swap_fee(deps, env, offer_asset, ask_asset_info)?),
         QueryMsg::CumulativePrices {} => to_json_binary(&query_cumulative_prices(deps, env)?),
         QueryMsg::Twap {
             duration,
@@ -936,7 +1463,49 @@ pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Bi
             start_age,
             end_age,
         )?),
-        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::HistoricalPrices { duration } => {
+            to_json_binary(&dex::oracle::query_oracle_history(
+                deps.storage,
+                &env,
+                &CONFIG.load(deps.storage)?.pool_info.asset_infos,
+                duration,
+            )?)
+        }
+        QueryMsg::TwapStandard { offer, ask } => {
+            to_json_binary(&dex::oracle::query_oracle_standard(
+                deps.storage,
+                &env,
+                &CONFIG.load(deps.storage)?.pool_info.asset_infos,
+                &offer.validate(deps.api)?,
+                &ask.validate(deps.api)?,
+            )?)
+        }
+        QueryMsg::Config {} => to_json_binary(&query_config(deps, env)?),
+        QueryMsg::TradingStarted {} => to_json_binary(&query_trading_started(deps, env)?),
+        QueryMsg::RebalanceTo { target_price } => {
+            to_json_binary(&query_rebalance_to(deps, env, target_price)?)
+        }
+        QueryMsg::Depth { ask_info } => to_json_binary(&query_depth(deps, env, ask_info)?),
+        QueryMsg::OfferToMovePrice {
+            offer_info,
+            ask_info,
+            bps,
+        } => to_json_binary(&query_offer_to_move_price(
+            deps, env, offer_info, ask_info, bps,
+        )?),
+        QueryMsg::Invariant {} => to_json_binary(&InvariantResponse {
+            value: query_compute_d(deps, env)?.into(),
+        }),
+        QueryMsg::SpotPrice { offer, ask } => {
+            to_json_binary(&query_spot_price(deps, env, offer, ask)?)
+        }
+        QueryMsg::Ticker {} => to_json_binary(&query_ticker(deps, env)?),
+        QueryMsg::Precisions {} => to_json_binary(&query_precisions(deps)?),
+        QueryMsg::ImbalancedWithdrawSimulation { assets, share } => to_json_binary(
+            &query_imbalanced_withdraw_simulation(deps, env, assets, share)?,
+        ),
+        QueryMsg::Amp {} => to_json_binary(&query_amp(deps, env)?),
+        QueryMsg::CollectedProtocolFees {} => to_json_binary(&query_collected_protocol_fees(deps)?),
         _ => Err(StdError::generic_err("Query is not supported")),
     }
 }
@@ -980,6 +1549,11 @@ pub fn query_simulation(
 ) -> StdResult<SimulationResponse> {
     let mut offer_asset = offer_asset.validate(deps.api)?;
     let ask_asset_info = ask_asset_info.map(|a| a.validate(deps.api)).transpose()?;
+    if ask_asset_info.as_ref() == Some(&offer_asset.info) {
+        return Err(StdError::generic_err(
+            ContractError::SameAssetSwap {}.to_string(),
+        ));
+    }
     let mut config = CONFIG.load(deps.storage)?;
     let pools = config
         .pool_info
@@ -1048,6 +1622,197 @@ pub fn query_simulation(
     })
 }
 
+/// Returns the arbitrage trade that moves the pool's spot price of the second asset (in terms of
+/// the first one) to `target_price` in a [`RebalanceToResponse`] object.
+///
+/// * **target_price** the price the pool should be moved to.
+pub fn query_rebalance_to(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    target_price: Decimal,
+) -> StdResult<RebalanceToResponse> {
+    if target_price.is_zero() {
+        return Err(StdError::generic_err(
+            "Target price must be greater than zero",
+        ));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools_decimal(&deps.querier, &config.pool_info.contract_addr)?;
+
+    update_target_rate(deps.querier, &mut config, &env)?;
+    let (offer_pool, ask_pool, offer_amount) =
+        find_rebalance_trade(deps, &env, &config, &pools, target_price)
+            .map_err(|err| StdError::generic_err(format!("{err}")))?;
+
+    let offer_precision = get_precision(deps.storage, &offer_pool.info)?;
+    let offer_amount = offer_amount.to_uint128_with_precision(offer_precision)?;
+    let return_amount = if offer_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        let SwapResult { return_amount, .. } = compute_swap(
+            deps.storage,
+            &env,
+            &config,
+            &DecimalAsset {
+                info: offer_pool.info.clone(),
+                amount: Decimal256::with_precision(offer_amount, offer_precision)?,
+            },
+            &offer_pool,
+            &ask_pool,
+            &pools,
+        )
+        .map_err(|err| StdError::generic_err(format!("{err}")))?;
+        let commission_amount = config
+            .pool_info
+            .fee_config
+            .total_fee_rate()
+            .checked_mul_uint128(return_amount)?;
+        return_amount.saturating_sub(commission_amount)
+    };
+
+    Ok(RebalanceToResponse {
+        offer_asset: AssetValidated {
+            info: offer_pool.info,
+            amount: offer_amount,
+        },
+        return_asset: AssetValidated {
+            info: ask_pool.info,
+            amount: return_amount,
+        },
+    })
+}
+
+/// Returns the largest trade into `ask_info` whose price impact stays within
+/// [`dex::pool::DEPTH_MAX_PRICE_IMPACT`] in a [`DepthResponse`] object.
+///
+/// * **ask_info** the asset to trade into.
+pub fn query_depth(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    ask_info: AssetInfo,
+) -> StdResult<DepthResponse> {
+    let ask_info = ask_info.validate(deps.api)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools_decimal(&deps.querier, &config.pool_info.contract_addr)?;
+
+    let (offer_pool, ask_pool) = select_pools(None, Some(&ask_info), &pools)
+        .map_err(|err| StdError::generic_err(format!("{err}")))?;
+
+    update_target_rate(deps.querier, &mut config, &env)?;
+    let offer_amount = find_depth_trade(deps, &env, &config, &offer_pool, &ask_pool, &pools)
+        .map_err(|err| StdError::generic_err(format!("{err}")))?;
+
+    let offer_precision = get_precision(deps.storage, &offer_pool.info)?;
+    let offer_amount = offer_amount.to_uint128_with_precision(offer_precision)?;
+    let return_amount = if offer_amount.is_zero() {
+        Uint128::zero()
+    } else {
+        let SwapResult { return_amount, .. } = compute_swap(
+            deps.storage,
+            &env,
+            &config,
+            &DecimalAsset {
+                info: offer_pool.info.clone(),
+                amount: Decimal256::with_precision(offer_amount, offer_precision)?,
+            },
+            &offer_pool,
+            &ask_pool,
+            &pools,
+        )
+        .map_err(|err| StdError::generic_err(format!("{err}")))?;
+        let commission_amount = config
+            .pool_info
+            .fee_config
+            .total_fee_rate()
+            .checked_mul_uint128(return_amount)?;
+        return_amount.saturating_sub(commission_amount)
+    };
+
+    Ok(DepthResponse {
+        offer_asset: AssetValidated {
+            info: offer_pool.info,
+            amount: offer_amount,
+        },
+        return_asset: AssetValidated {
+            info: ask_pool.info,
+            amount: return_amount,
+        },
+    })
+}
+
+/// Returns the smallest trade from `offer_info` into `ask_info` that lowers the spot price of the
+/// offer asset by `bps` basis points (ignoring fees) in an [`OfferToMovePriceResponse`] object.
+///
+/// * **offer_info** the asset to offer.
+///
+/// * **ask_info** the asset to trade into.
+///
+/// * **bps** the price move in basis points, between 1 and 9999.
+pub fn query_offer_to_move_price(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    offer_info: AssetInfo,
+    ask_info: AssetInfo,
+    bps: u16,
+) -> StdResult<OfferToMovePriceResponse> {
+    let offer_info = offer_info.validate(deps.api)?;
+    let ask_info = ask_info.validate(deps.api)?;
+    if offer_info == ask_info {
+        return Err(StdError::generic_err(
+            ContractError::SameAssetSwap {}.to_string(),
+        ));
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    let pools = config
+        .pool_info
+        .query_pools_decimal(&deps.querier, &config.pool_info.contract_addr)?;
+
+    let (offer_pool, ask_pool) = select_pools(Some(&offer_info), Some(&ask_info), &pools)
+        .map_err(|err| StdError::generic_err(format!("{err}")))?;
+
+    update_target_rate(deps.querier, &mut config, &env)?;
+    let offer_amount =
+        find_price_move_trade(deps, &env, &config, &offer_pool, &ask_pool, &pools, bps)
+            .map_err(|err| StdError::generic_err(format!("{err}")))?;
+
+    let offer_precision = get_precision(deps.storage, &offer_pool.info)?;
+    let offer_amount = offer_amount.to_uint128_with_precision(offer_precision)?;
+    let SwapResult { return_amount, .. } = compute_swap(
+        deps.storage,
+        &env,
+        &config,
+        &DecimalAsset {
+            info: offer_pool.info.clone(),
+            amount: Decimal256::with_precision(offer_amount, offer_precision)?,
+        },
+        &offer_pool,
+        &ask_pool,
+        &pools,
+    )
+    .map_err(|err| StdError::generic_err(format!("{err}")))?;
+    let commission_amount = config
+        .pool_info
+        .fee_config
+        .total_fee_rate()
+        .checked_mul_uint128(return_amount)?;
+
+    Ok(OfferToMovePriceResponse {
+        offer_asset: AssetValidated {
+            info: offer_pool.info,
+            amount: offer_amount,
+        },
+        return_asset: AssetValidated {
+            info: ask_pool.info,
+            amount: return_amount.saturating_sub(commission_amount),
+        },
+    })
+}
+
 /// Returns information about a reverse swap simulation in a [`ReverseSimulationResponse`] object.
 ///
 /// * **ask_asset** is the asset to swap to as well as the desired amount of ask
@@ -1062,6 +1827,11 @@ pub fn query_reverse_simulation(
 ) -> StdResult<ReverseSimulationResponse> {
     let ask_asset = ask_asset.validate(deps.api)?;
     let offer_asset_info = offer_asset_info.map(|a| a.validate(deps.api)).transpose()?;
+    if offer_asset_info.as_ref() == Some(&ask_asset.info) {
+        return Err(StdError::generic_err(
+            ContractError::SameAssetSwap {}.to_string(),
+        ));
+    }
 
     let mut config = CONFIG.load(deps.storage)?;
     let pools = config
@@ -1149,6 +1919,38 @@ pub fn query_reverse_simulation(
     })
 }
 
+/// Returns the fees a swap of `offer_asset` would pay in a [`SwapFeeResponse`] object.
+///
+/// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+///
+/// * **ask_asset_info** is the asset to swap to.
+pub fn query_swap_fee(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<SwapFeeResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let SimulationResponse {
+        commission_amount: total_commission,
+        ..
+    } = query_simulation(deps, env, offer_asset, ask_asset_info, false, None)?;
+
+    // The protocol fee is only taken if the factory has a fee address configured
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let protocol_fee = if factory_config.fee_address.is_some() {
+        total_commission * config.pool_info.fee_config.protocol_fee_rate()
+    } else {
+        Uint128::zero()
+    };
+
+    Ok(SwapFeeResponse {
+        total_commission,
+        protocol_fee,
+        lp_fee: total_commission - protocol_fee,
+    })
+}
+
 /// Returns information about cumulative prices for the assets in the pool using a [`CumulativePricesResponse`] object.
 pub fn query_cumulative_prices(
     deps: Deps<CoreumQueries>,
@@ -1176,12 +1978,36 @@ pub fn query_cumulative_prices(
 }
 
 /// Returns the pool contract configuration in a [`ConfigResponse`] object.
-pub fn query_config(deps: Deps<CoreumQueries>) -> StdResult<ConfigResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
+/// The amplification and target rate are returned as [`StablePoolConfig`] in `params`.
+pub fn query_config(deps: Deps<CoreumQueries>, env: Env) -> StdResult<ConfigResponse> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    update_target_rate(deps.querier, &mut config, &env)?;
+    let amp = compute_current_amp(&config, &env)?;
     Ok(ConfigResponse {
         block_time_last: config.block_time_last,
-        params: None,
-        owner: None,
+        params: Some(to_json_binary(&StablePoolConfig {
+            amp: Decimal::from_ratio(amp, AMP_PRECISION),
+            init_amp: Decimal::from_ratio(config.init_amp, AMP_PRECISION),
+            init_amp_time: config.init_amp_time,
+            next_amp: Decimal::from_ratio(config.next_amp, AMP_PRECISION),
+            next_amp_time: config.next_amp_time,
+            target_rate: config.target_rate,
+            max_allowed_spread: config.max_allowed_spread,
+        })?),
+        owner: config.owner,
+        factory_addr: config.factory_addr,
+    })
+}
+
+/// Returns whether trading in the pool has started in a [`TradingStartedResponse`] object.
+pub fn query_trading_started(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+) -> StdResult<TradingStartedResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(TradingStartedResponse {
+        started: env.block.time.seconds() >= config.trading_starts,
+        starts_at: config.trading_starts,
     })
 }
 
@@ -1191,12 +2017,15 @@ pub fn query_config(deps: Deps<CoreumQueries>) -> StdResult<ConfigResponse> {
 /// * **provided_amount** amount of provided LP tokens to withdraw liquidity with.
 ///
 /// * **assets** specifies the assets amount to withdraw.
+///
+/// * **total_fee_rate** is the total fee rate of the pool type, as configured in the factory.
 fn imbalanced_withdraw(
     deps: Deps<CoreumQueries>,
     env: &Env,
     config: &Config,
     provided_amount: Uint128,
     assets: &[AssetValidated],
+    total_fee_rate: Decimal,
 ) -> Result<Uint128, ContractError> {
     if assets.len() > config.pool_info.asset_infos.len() {
         return Err(ContractError::TooManyAssets {
@@ -1255,7 +2084,7 @@ fn imbalanced_withdraw(
     // Initial invariant (D)
     let old_balances = assets_collection
         .iter()
-        .map(|(_, pool)| *pool)
+        .map(|(withdraw, pool)| apply_rate_decimal(&withdraw.info, *pool, config))
         .collect_vec();
     let init_d = compute_d(amp, &old_balances, config.greatest_precision)?;
 
@@ -1263,26 +2092,17 @@ fn imbalanced_withdraw(
     let mut new_balances = assets_collection
         .iter()
         .cloned()
-        .map(|(withdraw, pool)| Ok(pool - withdraw.amount))
+        .map(|(withdraw, pool)| {
+            Ok(apply_rate_decimal(
+                &withdraw.info,
+                pool - withdraw.amount,
+                config,
+            ))
+        })
         .collect::<StdResult<Vec<Decimal256>>>()?;
     let withdraw_d = compute_d(amp, &new_balances, config.greatest_precision)?;
 
-    // Get fee info from the factory
-    // Get fee info from the factory
-    // let fee_info = query_fee_info(
-    //     &deps.querier,
-    //     &config.factory_addr,
-    //     config.pool_info.pair_type.clone(),
-    // )?;
-
-    // FIXME: Bring this back when factory is ready
-    // total_fee_rate * N_COINS / (4 * (N_COINS - 1))
-    let fee = /*fee_info
-            .total_fee_rate*/
-            Decimal::percent(3)
-            .checked_mul(Decimal::from_ratio(n_coins, 4 * (n_coins - 1)))?;
-
-    let fee = Decimal256::new(fee.atomics().into());
+    let fee = imbalance_fee_rate(total_fee_rate, n_coins)?;
 
     for i in 0..n_coins as usize {
         let ideal_balance = withdraw_d.checked_multiply_ratio(old_balances[i], init_d)?;
@@ -1427,21 +2247,9 @@ fn start_changing_amp(
     next_amp: u64,
     next_amp_time: u64,
 ) -> Result<(), ContractError> {
-    if next_amp == 0 || next_amp > MAX_AMP {
-        return Err(ContractError::IncorrectAmp { max_amp: MAX_AMP });
-    }
-
     let current_amp = compute_current_amp(&config, &env)?.u64();
 
-    let next_amp_with_precision = next_amp * AMP_PRECISION;
-
-    if next_amp_with_precision * MAX_AMP_CHANGE < current_amp
-        || next_amp_with_precision > current_amp * MAX_AMP_CHANGE
-    {
-        return Err(ContractError::MaxAmpChangeAssertion {
-            max_amp_change: MAX_AMP_CHANGE,
-        });
-    }
+    let next_amp_with_precision = validate_amp_change(current_amp, next_amp)?;
 
     let block_time = env.block.time.seconds();
 
@@ -1463,6 +2271,30 @@ fn start_changing_amp(
     Ok(())
 }
 
+/// Checks that `next_amp` is a valid AMP value that does not differ too much from `current_amp`.
+/// Returns `next_amp` with [`AMP_PRECISION`] applied.
+///
+/// * **current_amp** the current AMP value (with [`AMP_PRECISION`]).
+///
+/// * **next_amp** the new AMP value (without precision).
+fn validate_amp_change(current_amp: u64, next_amp: u64) -> Result<u64, ContractError> {
+    if next_amp == 0 || next_amp > MAX_AMP {
+        return Err(ContractError::IncorrectAmp { max_amp: MAX_AMP });
+    }
+
+    let next_amp_with_precision = next_amp * AMP_PRECISION;
+
+    if next_amp_with_precision * MAX_AMP_CHANGE < current_amp
+        || next_amp_with_precision > current_amp * MAX_AMP_CHANGE
+    {
+        return Err(ContractError::MaxAmpChangeAssertion {
+            max_amp_change: MAX_AMP_CHANGE,
+        });
+    }
+
+    Ok(next_amp_with_precision)
+}
+
 /// Stop changing the AMP value.
 fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<()> {
     let current_amp = compute_current_amp(&config, &env)?;
@@ -1479,17 +2311,185 @@ fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<(
     Ok(())
 }
 
+/// Returns the price of `offer` in terms of `ask`, ignoring fees, in a [`SpotPriceResponse`] object.
+pub fn query_spot_price(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    offer: AssetInfo,
+    ask: AssetInfo,
+) -> StdResult<SpotPriceResponse> {
+    let offer = offer.validate(deps.api)?;
+    let ask = ask.validate(deps.api)?;
+    if offer == ask {
+        return Err(StdError::generic_err(
+            ContractError::SameAssetSwap {}.to_string(),
+        ));
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    for asset in [&offer, &ask] {
+        if !config.pool_info.asset_infos.contains(asset) {
+            return Err(StdError::generic_err(
+                ContractError::InvalidAsset(asset.to_string()).to_string(),
+            ));
+        }
+    }
+    update_target_rate(deps.querier, &mut config, &env)?;
+    let pools = config
+        .pool_info
+        .query_pools_decimal(&deps.querier, &config.pool_info.contract_addr)?;
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(StdError::generic_err(
+            ContractError::NoLiquidity {}.to_string(),
+        ));
+    }
+
+    let amp = compute_current_amp(&config, &env)?;
+    let price = spot_price(&offer, &ask, &pools, amp, &config)?;
+
+    Ok(SpotPriceResponse { price })
+}
+
+/// Computes the marginal price of `offer` in terms of `ask`, ignoring fees.
+fn spot_price(
+    offer: &AssetInfoValidated,
+    ask: &AssetInfoValidated,
+    pools: &[DecimalAsset],
+    amp: Uint64,
+    config: &Config,
+) -> StdResult<Decimal> {
+    let price = calc_marginal_price(offer, ask, pools, amp, config)?;
+    Decimal::try_from(price).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// Returns the reserves, spot prices, fee and LP supply of the pool in a [`TickerResponse`] object.
+pub fn query_ticker(deps: Deps<CoreumQueries>, env: Env) -> StdResult<TickerResponse> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let (reserves, total_share) = pool_info(deps, &config)?;
+    update_target_rate(deps.querier, &mut config, &env)?;
+
+    let mut spot_prices = vec![];
+    if reserves.iter().all(|pool| !pool.amount.is_zero()) {
+        let pools = config
+            .pool_info
+            .query_pools_decimal(&deps.querier, &config.pool_info.contract_addr)?;
+        let amp = compute_current_amp(&config, &env)?;
+        let asset_infos = &config.pool_info.asset_infos;
+        for offer in asset_infos {
+            for ask in asset_infos.iter().filter(|ask| *ask != offer) {
+                let price = spot_price(offer, ask, &pools, amp, &config)?;
+                spot_prices.push((offer.clone(), ask.clone(), price));
+            }
+        }
+    }
+
+    Ok(TickerResponse {
+        reserves,
+        spot_prices,
+        total_fee_bps: config.pool_info.fee_config.total_fee_bps,
+        total_share,
+    })
+}
+
+/// Returns the amount of LP tokens an imbalanced withdraw of `assets` would burn, and how many of
+/// the given `share` would be returned, in an [`ImbalancedWithdrawSimulationResponse`] object.
+pub fn query_imbalanced_withdraw_simulation(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    assets: Vec<Asset>,
+    share: Option<Uint128>,
+) -> StdResult<ImbalancedWithdrawSimulationResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.allow_imbalanced {
+        return Err(StdError::generic_err(
+            ContractError::ImbalancedNotAllowed {}.to_string(),
+        ));
+    }
+    let assets =
+        check_assets(deps.api, &assets).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pool_info.pool_type.clone(),
+    )?;
+    let burn_amount = imbalanced_withdraw(
+        deps,
+        &env,
+        &config,
+        share.unwrap_or(Uint128::MAX),
+        &assets,
+        fee_info.total_fee_rate,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(ImbalancedWithdrawSimulationResponse {
+        burn_amount,
+        refund_share: share.map(|share| share - burn_amount).unwrap_or_default(),
+    })
+}
+
+/// Returns the current amplification and the amplification it is changing towards
+/// in an [`AmpResponse`] object.
+pub fn query_amp(deps: Deps<CoreumQueries>, env: Env) -> StdResult<AmpResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let amp = compute_current_amp(&config, &env)?;
+
+    Ok(AmpResponse {
+        amp: Decimal::from_ratio(amp, AMP_PRECISION),
+        next_amp: Decimal::from_ratio(config.next_amp, AMP_PRECISION),
+        next_amp_time: config.next_amp_time,
+    })
+}
+
+/// Returns the total protocol fees collected by the pool for each of its assets
+/// in a [`CollectedProtocolFeesResponse`] object.
+pub fn query_collected_protocol_fees(
+    deps: Deps<CoreumQueries>,
+) -> StdResult<CollectedProtocolFeesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let fees = config
+        .pool_info
+        .asset_infos
+        .into_iter()
+        .map(|info| {
+            let amount = PROTOCOL_FEES_COLLECTED
+                .may_load(deps.storage, &info)?
+                .unwrap_or_default();
+            Ok(AssetValidated { info, amount })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(CollectedProtocolFeesResponse { fees })
+}
+
+/// Returns the precision stored for every asset in the pool in a [`PrecisionsResponse`] object.
+pub fn query_precisions(deps: Deps<CoreumQueries>) -> StdResult<PrecisionsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let precisions = config
+        .pool_info
+        .asset_infos
+        .iter()
+        .map(|asset_info| Ok((asset_info.clone(), get_precision(deps.storage, asset_info)?)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PrecisionsResponse {
+        precisions,
+        greatest_precision: config.greatest_precision,
+    })
+}
+
 /// Compute the current pool D value.
-#[allow(dead_code)]
 fn query_compute_d(deps: Deps<CoreumQueries>, env: Env) -> StdResult<Uint128> {
-    let config = CONFIG.load(deps.storage)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    update_target_rate(deps.querier, &mut config, &env)?;
 
     let amp = compute_current_amp(&config, &env)?;
     let pools = config
         .pool_info
-        .query_pools_decimal(&deps.querier, env.contract.address)?
+        .query_pools_decimal(&deps.querier, &env.contract.address)?
         .into_iter()
-        .map(|pool| pool.amount)
+        .map(|pool| apply_rate_decimal(&pool.info, pool.amount, &config))
         .collect::<Vec<_>>();
 
     compute_d(amp, &pools, config.greatest_precision)
@@ -1500,9 +2500,22 @@ fn query_compute_d(deps: Deps<CoreumQueries>, env: Env) -> StdResult<Uint128> {
 /// Updates the config's target rate from the configured lsd hub contract if it is outdated.
 /// Returns `true` if the target rate was updated, `false` otherwise.
 fn update_target_rate(
-    _querier: QuerierWrapper<CoreumQueries>,
-    _config: &mut Config,
-    _env: &Env,
+    querier: QuerierWrapper<CoreumQueries>,
+    config: &mut Config,
+    env: &Env,
 ) -> StdResult<bool> {
-    Ok(false)
+    let Some(hub) = &config.target_rate_provider else {
+        return Ok(false);
+    };
+    let now = env.block.time.seconds();
+    if config.last_target_query != 0 && now < config.last_target_query + config.target_rate_epoch {
+        return Ok(false);
+    }
+
+    let TargetValueResponse { target_value } =
+        querier.query_wasm_smart(hub, &LsdHubQueryMsg::TargetValue {})?;
+    config.target_rate = target_value;
+    config.last_target_query = now;
+
+    Ok(true)
 }
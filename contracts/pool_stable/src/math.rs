@@ -1,5 +1,5 @@
 use crate::state::Config;
-use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256, Uint64};
+use cosmwasm_std::{Decimal256, Fraction, StdError, StdResult, Uint128, Uint256, Uint64};
 use dex::asset::{AssetInfoValidated, Decimal256Ext, DecimalAsset};
 use itertools::Itertools;
 
@@ -108,7 +108,7 @@ pub(crate) fn calc_y(
 
     for (pool_info, pool_amount) in pools {
         let pool_amount: Decimal256 = if pool_info == &from_asset.info {
-            new_amount
+            apply_rate_decimal(pool_info, new_amount, config)
         } else if pool_info != to {
             pool_amount
         } else {
@@ -143,26 +143,84 @@ pub(crate) fn calc_y(
     Err(StdError::generic_err("y is not converging"))
 }
 
+/// Computes the marginal price of `offer` in terms of `ask` at the current pool balances,
+/// i.e. how much of `ask` is returned for an infinitesimally small amount of `offer`, ignoring fees.
+///
+/// Along the invariant `A * n**n * sum(x_i) + D = A * n**n * D + D**(n+1) / (n**n * prod(x_i))`
+/// with fixed `D`, the price is the ratio of the partial derivatives
+/// `(A * n**n + D_P / x_offer) / (A * n**n + D_P / x_ask)` where `D_P = D**(n+1) / (n**n * prod(x_i))`.
+pub(crate) fn calc_marginal_price(
+    offer: &AssetInfoValidated,
+    ask: &AssetInfoValidated,
+    pools: &[DecimalAsset],
+    amp: Uint64,
+    config: &Config,
+) -> StdResult<Decimal256> {
+    let balances = pools
+        .iter()
+        .map(|pool| apply_rate_decimal(&pool.info, pool.amount, config))
+        .collect_vec();
+    let balance_of = |info: &AssetInfoValidated| -> StdResult<Decimal256> {
+        pools
+            .iter()
+            .position(|pool| pool.info == *info)
+            .map(|i| balances[i])
+            .filter(|balance| !balance.is_zero())
+            .ok_or_else(|| StdError::generic_err(format!("No liquidity for {info}")))
+    };
+    let offer_balance = balance_of(offer)?;
+    let ask_balance = balance_of(ask)?;
+
+    let d = compute_d(amp, &balances, config.greatest_precision)?;
+    let n_coins = balances.len() as u8;
+    let ann = Decimal256::from_ratio(amp.checked_mul(n_coins.into())?.u64(), AMP_PRECISION);
+    let n_coins = Decimal256::from_integer(n_coins);
+    let d_p = balances
+        .iter()
+        .try_fold::<_, _, StdResult<_>>(d, |acc, balance| {
+            acc.checked_multiply_ratio(d, balance.checked_mul(n_coins)?)
+        })?;
+
+    // price in terms of the rate-scaled amounts
+    let price = (ann + d_p / offer_balance) / (ann + d_p / ask_balance);
+
+    // scale back to the actual amounts of the assets
+    let rate = |info: &AssetInfoValidated| apply_rate_decimal(info, Decimal256::one(), config);
+    Ok(price * rate(offer) / rate(ask))
+}
+
 /// Applies the target rate to the amount if the asset is the LSD token.
-pub(crate) fn apply_rate(
-    _asset: &AssetInfoValidated,
-    amount: Uint128,
-    _config: &Config,
-) -> Uint128 {
-    amount
+pub(crate) fn apply_rate(asset: &AssetInfoValidated, amount: Uint128, config: &Config) -> Uint128 {
+    if config.lsd_asset.as_ref() == Some(asset) {
+        amount * config.target_rate
+    } else {
+        amount
+    }
 }
 
 /// Applies the target rate to the amount if the asset is the LSD token.
 pub(crate) fn apply_rate_decimal(
-    _asset: &AssetInfoValidated,
+    asset: &AssetInfoValidated,
     amount: Decimal256,
-    _config: &Config,
+    config: &Config,
 ) -> Decimal256 {
-    amount
+    if config.lsd_asset.as_ref() == Some(asset) {
+        amount * Decimal256::from(config.target_rate)
+    } else {
+        amount
+    }
 }
 
-fn inverse_rate(_to: &AssetInfoValidated, y: Uint128, _config: &Config) -> Uint128 {
-    y
+/// Reverts [`apply_rate`] for the given asset, converting a scaled amount back to the actual amount.
+fn inverse_rate(to: &AssetInfoValidated, y: Uint128, config: &Config) -> Uint128 {
+    if config.lsd_asset.as_ref() == Some(to) && !config.target_rate.is_zero() {
+        y.multiply_ratio(
+            config.target_rate.denominator(),
+            config.target_rate.numerator(),
+        )
+    } else {
+        y
+    }
 }
 
 #[cfg(test)]
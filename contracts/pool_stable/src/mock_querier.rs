@@ -9,10 +9,12 @@ use cosmwasm_std::{
 };
 
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use dex::asset::{Asset, AssetInfo};
 use dex::factory::{
-    ConfigResponse, FeeInfoResponse,
+    ConfigResponse, DefaultStakeConfig, FeeInfoResponse,
     QueryMsg::{Config, FeeInfo},
 };
+use dex::pool::{LsdHubQueryMsg, TargetValueResponse};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
 /// This uses the Dex CustomQuerier.
@@ -33,6 +35,9 @@ pub fn mock_dependencies(
 pub struct WasmMockQuerier {
     base: MockQuerier<CoreumQueries>,
     token_querier: TokenQuerier,
+    token_decimals: HashMap<String, u8>,
+    target_rate: Decimal,
+    total_fee_bps: u16,
 }
 
 #[derive(Clone, Default)]
@@ -89,7 +94,7 @@ impl WasmMockQuerier {
                         FeeInfo { .. } => SystemResult::Ok(
                             to_json_binary(&FeeInfoResponse {
                                 fee_address: Some(Addr::unchecked("fee_address")),
-                                total_fee_bps: 30,
+                                total_fee_bps: self.total_fee_bps,
                                 protocol_fee_bps: 1660,
                             })
                             .into(),
@@ -99,14 +104,40 @@ impl WasmMockQuerier {
                                 owner: Addr::unchecked("owner"),
                                 pool_configs: vec![],
                                 fee_address: Some(Addr::unchecked("fee_address")),
+                                creation_fee_address: None,
                                 max_referral_commission: Decimal::one(),
+                                default_stake_config: DefaultStakeConfig {
+                                    staking_code_id: 0,
+                                    tokens_per_power: Uint128::new(1000),
+                                    min_bond: Uint128::new(1000),
+                                    unbonding_periods: vec![],
+                                    max_distributions: 6,
+                                    auto_claim_on_delegate: false,
+                                },
                                 only_owner_can_create_pools: true,
+                                pool_creation_fee: Asset {
+                                    info: AssetInfo::SmartToken("coreum".to_string()),
+                                    amount: Uint128::zero(),
+                                },
                                 trading_starts: None,
+                                require_trading_starts: false,
+                                guardian: None,
+                                pool_creation_paused: false,
+                                max_pools: None,
                             })
                             .into(),
                         ),
                         _ => panic!("DO NOT ENTER HERE"),
                     }
+                } else if contract_addr == "lsd_hub" {
+                    match from_json(msg).unwrap() {
+                        LsdHubQueryMsg::TargetValue {} => SystemResult::Ok(
+                            to_json_binary(&TargetValueResponse {
+                                target_value: self.target_rate,
+                            })
+                            .into(),
+                        ),
+                    }
                 } else {
                     match from_json(msg).unwrap() {
                         Cw20QueryMsg::TokenInfo {} => {
@@ -128,7 +159,11 @@ impl WasmMockQuerier {
                                 to_json_binary(&TokenInfoResponse {
                                     name: "mAPPL".to_string(),
                                     symbol: "mAPPL".to_string(),
-                                    decimals: 6,
+                                    decimals: self
+                                        .token_decimals
+                                        .get(contract_addr)
+                                        .copied()
+                                        .unwrap_or(6),
                                     total_supply,
                                 })
                                 .into(),
@@ -175,6 +210,9 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
+            token_decimals: HashMap::new(),
+            target_rate: Decimal::one(),
+            total_fee_bps: 30,
         }
     }
 
@@ -183,6 +221,22 @@ impl WasmMockQuerier {
         self.token_querier = TokenQuerier::new(balances);
     }
 
+    /// Overrides the decimals returned for the given cw20 token (default is 6)
+    pub fn with_token_decimals(&mut self, contract_addr: &str, decimals: u8) {
+        self.token_decimals
+            .insert(contract_addr.to_string(), decimals);
+    }
+
+    /// Sets the target rate returned by the `lsd_hub` contract
+    pub fn with_target_rate(&mut self, target_rate: Decimal) {
+        self.target_rate = target_rate;
+    }
+
+    /// Sets the total fee returned by the `factory` contract's fee info
+    pub fn with_total_fee_bps(&mut self, total_fee_bps: u16) {
+        self.total_fee_bps = total_fee_bps;
+    }
+
     pub fn with_balance(&mut self, balances: &[(&String, &[Coin])]) {
         for (addr, balance) in balances {
             self.base.update_balance(addr.to_string(), balance.to_vec());
@@ -1,8 +1,14 @@
+use std::str::FromStr;
+
 use coreum_wasm_sdk::core::CoreumQueries;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, DepsMut, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Decimal, DepsMut, StdResult, Storage, Uint128, Uint256};
 use cw_storage_plus::{Item, Map};
-use dex::{asset::AssetInfoValidated, common::OwnershipProposal, pool::PairInfo};
+use dex::{
+    asset::AssetInfoValidated,
+    common::OwnershipProposal,
+    pool::{PairInfo, MAX_ALLOWED_SLIPPAGE},
+};
 
 /// This structure stores the main stableswap pair parameters.
 #[cw_serde]
@@ -26,9 +32,45 @@ pub struct Config {
     /// The greatest precision of assets in the pool
     pub greatest_precision: u8,
     /// The vector contains cumulative prices for each pair of assets in the pool
-    pub cumulative_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Uint128)>,
+    pub cumulative_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Uint256)>,
     /// The block time until which trading is disabled
     pub trading_starts: u64,
+    /// Whether liquidity may be provided or withdrawn in a ratio different from the pool's
+    #[serde(default = "default_allow_imbalanced")]
+    pub allow_imbalanced: bool,
+    /// Swaps returning less than this amount of the ask asset are rejected
+    #[serde(default)]
+    pub dust_threshold: Uint128,
+    /// The LSD token of the pool, whose balance is scaled by the target rate
+    #[serde(default)]
+    pub lsd_asset: Option<AssetInfoValidated>,
+    /// The liquid staking hub contract to query the target rate from
+    #[serde(default)]
+    pub target_rate_provider: Option<Addr>,
+    /// The minimum amount of time in seconds between two target rate queries
+    #[serde(default)]
+    pub target_rate_epoch: u64,
+    /// The last target rate queried from the `target_rate_provider`
+    #[serde(default = "default_target_rate")]
+    pub target_rate: Decimal,
+    /// The timestamp of the last target rate query
+    #[serde(default)]
+    pub last_target_query: u64,
+    /// Swaps with a `max_spread` above this value are rejected
+    #[serde(default = "default_max_allowed_spread")]
+    pub max_allowed_spread: Decimal,
+}
+
+fn default_allow_imbalanced() -> bool {
+    true
+}
+
+fn default_target_rate() -> Decimal {
+    Decimal::one()
+}
+
+fn default_max_allowed_spread() -> Decimal {
+    Decimal::from_str(MAX_ALLOWED_SLIPPAGE).unwrap()
 }
 
 /// Stores the config struct at the given key
@@ -41,6 +83,10 @@ pub const FROZEN: Item<bool> = Item::new("frozen");
 /// Stores the total amount of LP share tokens minted (workaround)
 pub const LP_SHARE_AMOUNT: Item<Uint128> = Item::new("lp_share_amount");
 
+/// Stores the total protocol fees sent to the factory's `fee_address`, per asset
+pub const PROTOCOL_FEES_COLLECTED: Map<&AssetInfoValidated, Uint128> =
+    Map::new("protocol_fees_collected");
+
 /// Stores map of AssetInfo (as String) -> precision
 const PRECISIONS: Map<String, u8> = Map::new("precisions");
 
@@ -63,6 +109,15 @@ pub(crate) fn store_precisions(
     Ok(max)
 }
 
+/// Stores the precision of the given asset info.
+pub(crate) fn store_precision(
+    storage: &mut dyn Storage,
+    asset_info: &AssetInfoValidated,
+    precision: u8,
+) -> StdResult<()> {
+    PRECISIONS.save(storage, asset_info.to_string(), &precision)
+}
+
 /// Loads precision of the given asset info.
 pub(crate) fn get_precision(
     storage: &dyn Storage,
@@ -1,23 +1,39 @@
-use coreum_wasm_sdk::{assetft, core::CoreumMsg};
+use coreum_wasm_sdk::{
+    assetft,
+    core::{CoreumMsg, CoreumQueries},
+};
 use cosmwasm_std::{
-    testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR},
-    {coin, to_json_binary, Addr, BlockInfo, Coin, Decimal, Env, ReplyOn, Timestamp, Uint128},
+    testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR},
+    {
+        assert_approx_eq, attr, coin, from_json, to_json_binary, Addr, BlockInfo, Coin, CosmosMsg,
+        Decimal, Deps, Env, Fraction, OwnedDeps, ReplyOn, StdError, Timestamp, Uint128,
+    },
 };
 use cw20::Cw20ReceiveMsg;
 
 use dex::{
     asset::{Asset, AssetInfo, AssetInfoValidated, MINIMUM_LIQUIDITY_AMOUNT},
     fee_config::FeeConfig,
+    oracle::{HistoricalPricesResponse, TwapStandardResponse},
     pool::{
-        ContractError, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, StablePoolParams,
-        StakeConfig, LP_TOKEN_PRECISION,
+        AmpRamp, AmpResponse, ConfigResponse, ContractError, Cw20HookMsg, DepthResponse,
+        ExecuteMsg, ImbalancedWithdrawSimulationResponse, InstantiateMsg, InvariantResponse,
+        MigrateMsg, OfferToMovePriceResponse, PairInfo, PoolResponse, PrecisionsResponse, QueryMsg,
+        RebalanceToResponse, SpotPriceResponse, StablePoolConfig, StablePoolParams,
+        StablePoolUpdateParams, StakeConfig, TickerResponse, DEPTH_MAX_PRICE_IMPACT,
+        LP_TOKEN_PRECISION,
     },
 };
 
 use crate::{
-    contract::{execute, instantiate, migrate},
-    mock_querier::mock_dependencies,
-    state::CONFIG,
+    contract::{
+        execute, instantiate, migrate, query, query_reverse_simulation, query_share,
+        query_simulation,
+    },
+    math::{AMP_PRECISION, MIN_AMP_CHANGING_TIME},
+    mock_querier::{mock_dependencies, WasmMockQuerier},
+    state::{get_precision, CONFIG, LP_SHARE_AMOUNT},
+    utils::compute_current_amp,
 };
 
 pub type SubMsg = cosmwasm_std::SubMsg<CoreumMsg>;
@@ -29,6 +45,7 @@ fn default_stake_config() -> StakeConfig {
         min_bond: Uint128::new(1000),
         unbonding_periods: vec![60 * 60 * 24 * 7],
         max_distributions: 6,
+        auto_claim_on_delegate: false,
     }
 }
 
@@ -52,6 +69,10 @@ fn proper_initialization() {
                 amp: 100,
                 owner: None,
                 lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread: None,
             })
             .unwrap(),
         ),
@@ -62,6 +83,8 @@ fn proper_initialization() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -141,6 +164,10 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
                 amp: 100,
                 owner: None,
                 lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread: None,
             })
             .unwrap(),
         ),
@@ -151,6 +178,8 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
             protocol_fee_bps: 0,
         },
         circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
         verified: true,
     };
 
@@ -311,7 +340,10 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
     // But we can withdraw liquidity
 
     // Withdraw liquidity
-    let msg = ExecuteMsg::WithdrawLiquidity { assets: vec![] };
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![],
+        max_burn: None,
+    };
 
     let info = mock_info("addr0000", &[coin(100, "uuusdmapplp-cosmos2contract")]);
     // We just want to ensure it doesn't fail with a ContractFrozen error
@@ -401,6 +433,591 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
     execute(deps.as_mut(), env, info, msg).unwrap();
 }
 
+/// Instantiates a uusd/asset0000 pool and provides the initial 100/100 liquidity.
+fn setup_balanced_pool(
+    allow_imbalanced: Option<bool>,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, CoreumQueries> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100_000000000000000000u128),
+    }]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+                lsd: None,
+                allow_imbalanced,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: Uint128::from(100_000000000000000000u128),
+            },
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::from(100_000000000000000000u128),
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[coin(100_000000000000000000u128, "uusd".to_string())],
+    );
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(100_000000000000000000),
+        )],
+    )]);
+
+    deps
+}
+
+/// Sets the pool's uusd balance as it would be after the given deposit was sent along.
+fn with_uusd_deposit(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, CoreumQueries>,
+    deposit: u128,
+) {
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin(100_000000000000000000u128 + deposit, "uusd")],
+    )]);
+}
+
+fn provide_msg(asset_amount: u128, uusd_amount: u128) -> ExecuteMsg {
+    ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: Uint128::new(asset_amount),
+            },
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(uusd_amount),
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+    }
+}
+
+#[test]
+fn imbalanced_liquidity_allowed_by_default() {
+    let mut deps = setup_balanced_pool(None);
+    assert!(CONFIG.load(deps.as_ref().storage).unwrap().allow_imbalanced);
+
+    // Providing in a different ratio than the pool works, given a wide enough tolerance
+    with_uusd_deposit(&mut deps, 50_000000000000000000);
+    let info = mock_info("addr0001", &[coin(50_000000000000000000u128, "uusd")]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide_msg(100_000000000000000000, 50_000000000000000000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MaxSlippageAssertion {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::ProvideLiquidity {
+            assets: vec![
+                Asset {
+                    info: AssetInfo::Cw20Token("asset0000".to_string()),
+                    amount: Uint128::new(100_000000000000000000),
+                },
+                Asset {
+                    info: AssetInfo::SmartToken("uusd".to_string()),
+                    amount: Uint128::new(50_000000000000000000),
+                },
+            ],
+            slippage_tolerance: Some(Decimal::percent(50)),
+            receiver: None,
+        },
+    )
+    .unwrap();
+
+    // As does withdrawing only one of the assets
+    with_uusd_deposit(&mut deps, 0);
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![Asset {
+            info: AssetInfo::SmartToken("uusd".to_string()),
+            amount: Uint128::new(1_000000000000000000),
+        }],
+        max_burn: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[coin(
+            10_000000000000000000u128,
+            "uuusdmapplp-cosmos2contract",
+        )],
+    );
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+#[test]
+fn imbalanced_liquidity_disallowed() {
+    let mut deps = setup_balanced_pool(Some(false));
+
+    // Providing in a different ratio than the pool fails
+    with_uusd_deposit(&mut deps, 50_000000000000000000);
+    let info = mock_info("addr0001", &[coin(50_000000000000000000u128, "uusd")]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(100_000000000000000000, 50_000000000000000000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ImbalancedNotAllowed {});
+
+    // So does providing a single asset
+    with_uusd_deposit(&mut deps, 0);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        provide_msg(100_000000000000000000, 0),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ImbalancedNotAllowed {});
+
+    // A deposit within the tolerance of the current ratio is accepted
+    with_uusd_deposit(&mut deps, 99_500000000000000000);
+    let info = mock_info("addr0001", &[coin(99_500000000000000000u128, "uusd")]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide_msg(100_000000000000000000, 99_500000000000000000),
+    )
+    .unwrap();
+
+    // Withdrawing specific assets fails
+    with_uusd_deposit(&mut deps, 0);
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![Asset {
+            info: AssetInfo::SmartToken("uusd".to_string()),
+            amount: Uint128::new(1_000000000000000000),
+        }],
+        max_burn: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[coin(
+            10_000000000000000000u128,
+            "uuusdmapplp-cosmos2contract",
+        )],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::ImbalancedNotAllowed {});
+
+    // But a proportional withdrawal works
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![],
+        max_burn: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+#[test]
+fn provide_liquidity_slippage_tolerance() {
+    let mut deps = setup_balanced_pool(None);
+    let provide = |slippage_tolerance| ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: Uint128::new(10_000000000000000000),
+            },
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(10_000000000000000000),
+            },
+        ],
+        slippage_tolerance,
+        receiver: None,
+    };
+    let info = mock_info("addr0001", &[coin(10_000000000000000000u128, "uusd")]);
+
+    // A provide in the current pool ratio is within a tight tolerance
+    with_uusd_deposit(&mut deps, 10_000000000000000000);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide(Some(Decimal::percent(1))),
+    )
+    .unwrap();
+
+    // Someone front-runs the provide with a swap of 15 uusd for 15 asset0000
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(85_000000000000000000),
+        )],
+    )]);
+    with_uusd_deposit(&mut deps, 15_000000000000000000 + 10_000000000000000000);
+
+    // Now the deposit ratio is off by more than the tolerance
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide(Some(Decimal::percent(1))),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MaxSlippageAssertion {});
+
+    // A tolerance above the maximum is rejected
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        provide(Some(Decimal::percent(51))),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AllowedSpreadAssertion {});
+
+    // Without a tolerance, the default one applies
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), provide(None)).unwrap_err();
+    assert_eq!(err, ContractError::MaxSlippageAssertion {});
+
+    // A loose enough tolerance still accepts the provide
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        provide(Some(Decimal::percent(30))),
+    )
+    .unwrap();
+}
+
+#[test]
+fn zap_in() {
+    let zap = |min_share| ExecuteMsg::ZapIn {
+        offer_asset: Asset {
+            info: AssetInfo::SmartToken("uusd".to_string()),
+            amount: Uint128::new(10_000000000000000000),
+        },
+        min_share,
+        receiver: None,
+    };
+    let info = mock_info("addr0001", &[coin(10_000000000000000000u128, "uusd")]);
+
+    let mut deps = setup_balanced_pool(None);
+    with_uusd_deposit(&mut deps, 10_000000000000000000);
+    let total_share = LP_SHARE_AMOUNT.load(deps.as_ref().storage).unwrap();
+
+    // Asking for more LP tokens than the zap mints fails
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        zap(Some(total_share)),
+    )
+    .unwrap_err();
+    let share = match err {
+        ContractError::MinShareAssertion { share, min_share } => {
+            assert_eq!(min_share, total_share);
+            share
+        }
+        err => panic!("unexpected error: {err}"),
+    };
+
+    // A single asset deposit of 5% of the pool mints almost 5% of the shares, minus the imbalance fee
+    let ideal_share = total_share.multiply_ratio(10u128, 200u128);
+    assert!(share < ideal_share);
+    assert!(share > ideal_share * Decimal::percent(99));
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), zap(Some(share))).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "zap_in"));
+    assert_eq!(res.attributes[4], attr("share", share));
+
+    // Zapping in is an imbalanced provide, so it is rejected if those are disabled
+    let mut deps = setup_balanced_pool(Some(false));
+    with_uusd_deposit(&mut deps, 10_000000000000000000);
+    let err = execute(deps.as_mut(), mock_env(), info, zap(None)).unwrap_err();
+    assert_eq!(err, ContractError::ImbalancedNotAllowed {});
+}
+
+#[test]
+fn imbalanced_provide_charges_factory_fee() {
+    // provides only asset0000 into the balanced pool and returns the minted share
+    let provided_share = |total_fee_bps: u16| -> Uint128 {
+        let mut deps = setup_balanced_pool(None);
+        deps.querier.with_total_fee_bps(total_fee_bps);
+        with_uusd_deposit(&mut deps, 0);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0001", &[]),
+            provide_msg(10_000000000000000000, 0),
+        )
+        .unwrap();
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == "share")
+            .unwrap()
+            .value
+            .parse()
+            .unwrap()
+    };
+
+    let no_fee = provided_share(0);
+    let configured = provided_share(30);
+    let three_percent = provided_share(300);
+    assert!(no_fee > configured);
+    assert!(configured > three_percent);
+
+    // the fee scales with the factory's fee, so 0.3% charges a tenth of what 3% would
+    assert_approx_eq!(
+        no_fee - configured,
+        (no_fee - three_percent) / Uint128::new(10),
+        "0.01"
+    );
+}
+
+#[test]
+fn single_asset_provide_into_seeded_pool() {
+    let mut deps = setup_balanced_pool(None);
+    let total_share = LP_SHARE_AMOUNT.load(deps.as_ref().storage).unwrap();
+
+    // only uusd is listed, the missing asset counts as a zero deposit
+    with_uusd_deposit(&mut deps, 10_000000000000000000);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(10_000000000000000000u128, "uusd")]),
+        ExecuteMsg::ProvideLiquidity {
+            assets: vec![Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(10_000000000000000000),
+            }],
+            slippage_tolerance: None,
+            receiver: None,
+        },
+    )
+    .unwrap();
+    let share: Uint128 = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "share")
+        .unwrap()
+        .value
+        .parse()
+        .unwrap();
+
+    // the share follows the increase of D, which is slightly less than 5% of the pool value
+    // because of the imbalance fee, but there is no minimum liquidity deducted like on the first deposit
+    let ideal_share = total_share.multiply_ratio(10u128, 200u128);
+    assert!(share < ideal_share);
+    assert!(share > ideal_share * Decimal::percent(99));
+    assert_eq!(
+        LP_SHARE_AMOUNT.load(deps.as_ref().storage).unwrap(),
+        total_share + share
+    );
+}
+
+fn withdraw_uusd(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, CoreumQueries>,
+    max_burn: Option<u128>,
+) -> Result<Uint128, ContractError> {
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(1_000000000000000000),
+            },
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: Uint128::zero(),
+            },
+        ],
+        max_burn: max_burn.map(Uint128::new),
+    };
+    let info = mock_info(
+        "addr0000",
+        &[coin(
+            10_000000000000000000u128,
+            "uuusdmapplp-cosmos2contract",
+        )],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg)?;
+
+    let burn_amount = res
+        .messages
+        .into_iter()
+        .find_map(|sub_msg| match sub_msg.msg {
+            CosmosMsg::Custom(CoreumMsg::AssetFT(assetft::Msg::Burn { coin })) => Some(coin.amount),
+            _ => None,
+        })
+        .unwrap();
+    Ok(burn_amount)
+}
+
+#[test]
+fn imbalanced_withdraw_simulation_matches_withdraw() {
+    let mut deps = setup_balanced_pool(None);
+
+    let simulate = |deps: Deps<CoreumQueries>, share| {
+        query(
+            deps,
+            mock_env(),
+            QueryMsg::ImbalancedWithdrawSimulation {
+                assets: vec![
+                    Asset {
+                        info: AssetInfo::SmartToken("uusd".to_string()),
+                        amount: Uint128::new(1_000000000000000000),
+                    },
+                    Asset {
+                        info: AssetInfo::Cw20Token("asset0000".to_string()),
+                        amount: Uint128::zero(),
+                    },
+                ],
+                share,
+            },
+        )
+        .and_then(|res| from_json::<ImbalancedWithdrawSimulationResponse>(&res))
+    };
+
+    let simulation = simulate(deps.as_ref(), Some(Uint128::new(10_000000000000000000))).unwrap();
+    assert_eq!(
+        simulation.refund_share,
+        Uint128::new(10_000000000000000000) - simulation.burn_amount
+    );
+    // without a share, nothing is refunded
+    let without_share = simulate(deps.as_ref(), None).unwrap();
+    assert_eq!(without_share.burn_amount, simulation.burn_amount);
+    assert_eq!(without_share.refund_share, Uint128::zero());
+    // too few LP tokens fail like the withdrawal would
+    simulate(deps.as_ref(), Some(simulation.burn_amount - Uint128::one())).unwrap_err();
+
+    let burned = withdraw_uusd(&mut deps, None).unwrap();
+    assert_eq!(burned, simulation.burn_amount);
+
+    // the simulation is rejected if imbalanced withdrawals are disabled
+    let deps = setup_balanced_pool(Some(false));
+    let err = simulate(deps.as_ref(), None).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(ContractError::ImbalancedNotAllowed {}.to_string())
+    );
+}
+
+#[test]
+fn withdraw_requires_lp_funds() {
+    let mut deps = setup_balanced_pool(None);
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![],
+        max_burn: None,
+    };
+    let lp_funds_err = ContractError::InvalidLpFunds("uuusdmapplp-cosmos2contract".to_string());
+
+    // No funds
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, lp_funds_err);
+
+    // Wrong denom
+    let info = mock_info("addr0000", &[coin(100, "uusd")]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, lp_funds_err);
+
+    // Extra coins alongside the LP token
+    let info = mock_info(
+        "addr0000",
+        &[coin(100, "uuusdmapplp-cosmos2contract"), coin(100, "uusd")],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, lp_funds_err);
+
+    // Only the LP token works
+    let info = mock_info("addr0000", &[coin(100, "uuusdmapplp-cosmos2contract")]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+#[test]
+fn imbalanced_withdraw_max_burn() {
+    // Quote how many LP tokens the withdrawal needs in the balanced pool
+    let mut deps = setup_balanced_pool(None);
+    let quoted = withdraw_uusd(&mut deps, None).unwrap();
+
+    // The quoted amount is enough as long as the pool does not change
+    let mut deps = setup_balanced_pool(None);
+    let burned = withdraw_uusd(&mut deps, Some(quoted.u128())).unwrap();
+    assert_eq!(burned, quoted);
+
+    // A swap of asset0000 into uusd lands before the withdrawal,
+    // making uusd scarcer and raising the burn requirement
+    let mut deps = setup_balanced_pool(None);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin(80_000000000000000000u128, "uusd")],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(120_000000000000000000),
+        )],
+    )]);
+
+    let err = withdraw_uusd(&mut deps, Some(quoted.u128())).unwrap_err();
+    match err {
+        ContractError::MaxBurnAssertion {
+            burn_amount,
+            max_burn,
+        } => {
+            assert_eq!(max_burn, quoted);
+            assert!(burn_amount > quoted);
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+
+    // Without the cap the withdrawal goes through at the higher price
+    let burned = withdraw_uusd(&mut deps, None).unwrap();
+    assert!(burned > quoted);
+}
+
 // #[test]
 // fn provide_liquidity() {
 //     let mut deps = mock_dependencies(&[Coin {
@@ -1866,6 +2483,1627 @@ fn test_freezing_a_pool_blocking_actions_then_unfreeze() {
 //     }
 // }
 
+#[test]
+fn refresh_precision() {
+    let mut deps = setup_balanced_pool(None);
+    let asset = AssetInfo::Cw20Token("asset0000".to_string());
+    let simulate = |deps: Deps<CoreumQueries>| {
+        query_simulation(
+            deps,
+            mock_env(),
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(1_000000),
+            },
+            Some(asset.clone()),
+            false,
+            None,
+        )
+        .unwrap()
+        .return_amount
+        .u128()
+    };
+
+    // both assets have 6 decimals, so the swap is roughly 1:1
+    let before = simulate(deps.as_ref());
+    assert!((900_000..=1_000_000).contains(&before), "{before}");
+
+    // the token is re-issued with less decimals
+    deps.querier.with_token_decimals("asset0000", 5);
+
+    // only the factory or the owner can refresh the precision
+    let msg = ExecuteMsg::RefreshPrecision {
+        asset: asset.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // the asset has to belong to the pool
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("factory", &[]),
+        ExecuteMsg::RefreshPrecision {
+            asset: AssetInfo::Cw20Token("asset0001".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAsset("asset0001".to_string()));
+
+    // the pool still has liquidity
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("factory", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::RefreshPrecisionWithLiquidity {});
+
+    // all LP shares are withdrawn
+    LP_SHARE_AMOUNT
+        .save(deps.as_mut().storage, &Uint128::zero())
+        .unwrap();
+
+    execute(deps.as_mut(), mock_env(), mock_info("factory", &[]), msg).unwrap();
+    assert_eq!(
+        get_precision(
+            deps.as_ref().storage,
+            &AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+        )
+        .unwrap(),
+        5
+    );
+    assert_eq!(
+        CONFIG
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .greatest_precision,
+        6
+    );
+
+    // the pool now holds 10 times more asset0000 than uusd by value,
+    // so a uusd gets more than one asset0000 (which is worth 10 times less in raw units now)
+    let after = simulate(deps.as_ref());
+    assert!((100_000..150_000).contains(&after), "{after}");
+
+    // raising the greatest precision of an empty pool is fine as well
+    deps.querier.with_token_decimals("asset0000", 8);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("factory", &[]),
+        ExecuteMsg::RefreshPrecision { asset },
+    )
+    .unwrap();
+    assert_eq!(
+        CONFIG
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .greatest_precision,
+        8
+    );
+}
+
+#[test]
+fn simulation_with_asset_not_in_pool() {
+    let deps = setup_balanced_pool(None);
+    let cny = AssetInfo::SmartToken("cny".to_string());
+    let uusd = AssetInfo::SmartToken("uusd".to_string());
+
+    // offer asset not in the pool
+    let err = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: cny.clone(),
+            amount: Uint128::new(1_000000),
+        },
+        None,
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: The asset cny does not belong to the pool"
+    );
+
+    // ask asset not in the pool
+    let err = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: uusd.clone(),
+            amount: Uint128::new(1_000000),
+        },
+        Some(cny.clone()),
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: The asset cny does not belong to the pool"
+    );
+
+    // same for the reverse simulation
+    let err = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: cny.clone(),
+            amount: Uint128::new(1_000000),
+        },
+        None,
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: The asset cny does not belong to the pool"
+    );
+    let err = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: uusd,
+            amount: Uint128::new(1_000000),
+        },
+        Some(cny),
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: The asset cny does not belong to the pool"
+    );
+}
+
+#[test]
+fn swap_asset_for_itself() {
+    let mut deps = setup_balanced_pool(None);
+    let uusd = AssetInfo::SmartToken("uusd".to_string());
+    let cw20 = AssetInfo::Cw20Token("asset0000".to_string());
+
+    // native asset for itself
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(1_000000, "uusd")]),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: uusd.clone(),
+                amount: Uint128::new(1_000000),
+            },
+            ask_asset_info: Some(uusd.clone()),
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SameAssetSwap {});
+
+    // cw20 asset for itself
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::new(1_000000),
+            msg: to_json_binary(&Cw20HookMsg::Swap {
+                ask_asset_info: Some(cw20.clone()),
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SameAssetSwap {});
+
+    // simulations are rejected as well
+    for info in [uusd, cw20] {
+        let err = query_simulation(
+            deps.as_ref(),
+            mock_env(),
+            Asset {
+                info: info.clone(),
+                amount: Uint128::new(1_000000),
+            },
+            Some(info.clone()),
+            false,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Cannot swap an asset for itself"
+        );
+
+        let err = query_reverse_simulation(
+            deps.as_ref(),
+            mock_env(),
+            Asset {
+                info: info.clone(),
+                amount: Uint128::new(1_000000),
+            },
+            Some(info),
+            false,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Cannot swap an asset for itself"
+        );
+    }
+}
+
+#[test]
+fn swap_below_dust_threshold() {
+    let mut deps = setup_balanced_pool(None);
+    let offer_asset = Asset {
+        info: AssetInfo::SmartToken("uusd".to_string()),
+        amount: Uint128::new(1_000000),
+    };
+    let expected = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        offer_asset.clone(),
+        None,
+        false,
+        None,
+    )
+    .unwrap()
+    .return_amount;
+
+    with_uusd_deposit(&mut deps, 1_000000);
+    let swap = |deps: &mut OwnedDeps<_, _, _, _>| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[coin(1_000000, "uusd")]),
+            ExecuteMsg::Swap {
+                offer_asset: offer_asset.clone(),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+        )
+    };
+
+    // a return amount just below the threshold is rejected
+    let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+    config.dust_threshold = expected + Uint128::one();
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    let err = swap(&mut deps).unwrap_err();
+    assert_eq!(err, ContractError::OutputBelowDustThreshold {});
+
+    // a return amount exactly at the threshold goes through
+    config.dust_threshold = expected;
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    swap(&mut deps).unwrap();
+}
+
+#[test]
+fn swap_max_spread_default_and_ceiling() {
+    let mut deps = setup_balanced_pool(None);
+    with_uusd_deposit(&mut deps, 1_000000);
+
+    let swap = |deps: &mut OwnedDeps<_, _, _, _>, max_spread| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[coin(1_000000, "uusd")]),
+            ExecuteMsg::Swap {
+                offer_asset: Asset {
+                    info: AssetInfo::SmartToken("uusd".to_string()),
+                    amount: Uint128::new(1_000000),
+                },
+                ask_asset_info: None,
+                // expects about 11% more than the pool returns
+                belief_price: Some(Decimal::percent(90)),
+                max_spread,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+        )
+    };
+
+    // without a max spread, the default of 0.5% applies
+    let err = swap(&mut deps, None).unwrap_err();
+    assert_eq!(err, ContractError::MaxSpreadAssertion {});
+    swap(&mut deps, Some(Decimal::percent(20))).unwrap();
+
+    // a max spread above the pool's ceiling is rejected
+    let err = swap(&mut deps, Some(Decimal::percent(51))).unwrap_err();
+    assert_eq!(err, ContractError::AllowedSpreadAssertion {});
+
+    let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+    config.max_allowed_spread = Decimal::percent(10);
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+    let err = swap(&mut deps, Some(Decimal::percent(20))).unwrap_err();
+    assert_eq!(err, ContractError::AllowedSpreadAssertion {});
+}
+
+#[test]
+fn instantiate_with_max_allowed_spread() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+    )]);
+
+    let msg = |max_allowed_spread| InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+                lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    // the ceiling cannot be below the default max spread or above the global limit
+    for invalid in [Decimal::permille(4), Decimal::percent(51)] {
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            msg(Some(invalid)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidMaxAllowedSpread {});
+    }
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg(Some(Decimal::percent(5))),
+    )
+    .unwrap();
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.max_allowed_spread, Decimal::percent(5));
+}
+
+#[test]
+fn instantiate_with_amp_ramp() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+    )]);
+
+    let start = 1_000_000;
+    let msg = |amp_ramp| InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+                lsd: None,
+                allow_imbalanced: None,
+                amp_ramp,
+                dust_threshold: None,
+                max_allowed_spread: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    // ramp has to last at least a day
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(start),
+        mock_info("addr0000", &[]),
+        msg(Some(AmpRamp {
+            next_amp: 200,
+            next_amp_time: start + 60,
+        })),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinAmpChangingTimeAssertion {
+            min_amp_changing_time: 86400
+        }
+    );
+
+    // ramp cannot exceed the max amp change
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(start),
+        mock_info("addr0000", &[]),
+        msg(Some(AmpRamp {
+            next_amp: 2000,
+            next_amp_time: start + 86400 * 10,
+        })),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxAmpChangeAssertion { max_amp_change: 10 }
+    );
+
+    instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(start),
+        mock_info("addr0000", &[]),
+        msg(Some(AmpRamp {
+            next_amp: 200,
+            next_amp_time: start + 86400 * 10,
+        })),
+    )
+    .unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    let amp_at = |time| {
+        compute_current_amp(&config, &mock_env_with_block_time(time))
+            .unwrap()
+            .u64()
+    };
+    assert_eq!(amp_at(start), 100 * AMP_PRECISION);
+    assert_eq!(amp_at(start + 86400 * 5), 150 * AMP_PRECISION);
+    assert_eq!(amp_at(start + 86400 * 10), 200 * AMP_PRECISION);
+    assert_eq!(amp_at(start + 86400 * 20), 200 * AMP_PRECISION);
+}
+
+#[test]
+fn query_config_reflects_amp_change() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+    )]);
+
+    let start = 1_000_000;
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: Some("owner".to_string()),
+                lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: Some(AmpRamp {
+                    next_amp: 200,
+                    next_amp_time: start + 86400 * 10,
+                }),
+                dust_threshold: None,
+                max_allowed_spread: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(start),
+        mock_info("addr0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let query_config = |time| -> (ConfigResponse, StablePoolConfig) {
+        let res: ConfigResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env_with_block_time(time),
+                QueryMsg::Config {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let params = from_json(res.params.as_ref().unwrap()).unwrap();
+        (res, params)
+    };
+
+    // halfway through the amp change
+    let (res, params) = query_config(start + 86400 * 5);
+    assert_eq!(res.owner, Some(Addr::unchecked("owner")));
+    assert_eq!(
+        params,
+        StablePoolConfig {
+            amp: Decimal::from_ratio(150u128, 1u128),
+            init_amp: Decimal::from_ratio(100u128, 1u128),
+            init_amp_time: start,
+            next_amp: Decimal::from_ratio(200u128, 1u128),
+            next_amp_time: start + 86400 * 10,
+            target_rate: Decimal::one(),
+            max_allowed_spread: Decimal::percent(50),
+        }
+    );
+
+    // after the change, the current amp is the target amp
+    let (_, params) = query_config(start + 86400 * 20);
+    assert_eq!(params.amp, Decimal::from_ratio(200u128, 1u128));
+}
+
+#[test]
+fn query_amp_during_ramp() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(0))],
+    )]);
+
+    let start = 1_000_000;
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: Some("owner".to_string()),
+                lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(
+        deps.as_mut(),
+        mock_env_with_block_time(start),
+        mock_info("addr0000", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let query_amp = |deps: Deps<CoreumQueries>, time| -> AmpResponse {
+        from_json(query(deps, mock_env_with_block_time(time), QueryMsg::Amp {}).unwrap()).unwrap()
+    };
+
+    // no ramp yet
+    assert_eq!(
+        query_amp(deps.as_ref(), start),
+        AmpResponse {
+            amp: Decimal::from_ratio(100u128, 1u128),
+            next_amp: Decimal::from_ratio(100u128, 1u128),
+            next_amp_time: start,
+        }
+    );
+
+    // the owner ramps the amp up to 200 over 10 days
+    let ramp_start = start + MIN_AMP_CHANGING_TIME;
+    let ramp_end = ramp_start + 86400 * 10;
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(ramp_start),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            params: to_json_binary(&StablePoolUpdateParams::StartChangingAmp {
+                next_amp: 200,
+                next_amp_time: ramp_end,
+            })
+            .unwrap(),
+        },
+    )
+    .unwrap();
+
+    // halfway through the ramp, the amp is interpolated linearly
+    assert_eq!(
+        query_amp(deps.as_ref(), ramp_start + 86400 * 5),
+        AmpResponse {
+            amp: Decimal::from_ratio(150u128, 1u128),
+            next_amp: Decimal::from_ratio(200u128, 1u128),
+            next_amp_time: ramp_end,
+        }
+    );
+    assert_eq!(
+        query_amp(deps.as_ref(), ramp_start + 86400 * 2).amp,
+        Decimal::from_ratio(120u128, 1u128)
+    );
+
+    // and stays at the target after the ramp
+    assert_eq!(
+        query_amp(deps.as_ref(), ramp_end + 1).amp,
+        Decimal::from_ratio(200u128, 1u128)
+    );
+}
+
+#[test]
+fn query_rebalance_to() {
+    let mut deps = setup_balanced_pool(None);
+    let pool_amount = 100_000000000000000000u128;
+
+    let rebalance_to = |deps: Deps<CoreumQueries>, target_price| -> RebalanceToResponse {
+        from_json(query(deps, mock_env(), QueryMsg::RebalanceTo { target_price }).unwrap()).unwrap()
+    };
+    // price of one asset0000 in uusd
+    let spot_price = |deps: Deps<CoreumQueries>| {
+        let return_amount = query_simulation(
+            deps,
+            mock_env(),
+            Asset {
+                info: AssetInfo::Cw20Token("asset0000".to_string()),
+                amount: Uint128::new(1_000000),
+            },
+            Some(AssetInfo::SmartToken("uusd".to_string())),
+            false,
+            None,
+        )
+        .unwrap()
+        .return_amount;
+        Decimal::from_ratio(return_amount, 1_000000u128)
+    };
+
+    // moving the price of asset0000 up means buying it with uusd
+    let target_price = Decimal::percent(105);
+    let res = rebalance_to(deps.as_ref(), target_price);
+    assert_eq!(
+        res.offer_asset.info,
+        AssetInfoValidated::SmartToken("uusd".to_string())
+    );
+    assert_eq!(
+        res.return_asset.info,
+        AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+    );
+
+    // apply the trade to the pool balances (the pool has no fees)
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin(pool_amount + res.offer_asset.amount.u128(), "uusd")],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &(Uint128::new(pool_amount) - res.return_asset.amount),
+        )],
+    )]);
+    let price = spot_price(deps.as_ref());
+    assert!(
+        price.abs_diff(target_price) < Decimal::permille(1),
+        "{price} != {target_price}"
+    );
+
+    // the pool is at the target now, so there is nothing to do
+    let res = rebalance_to(deps.as_ref(), price);
+    assert_eq!(res.offer_asset.amount, Uint128::zero());
+
+    // moving back down means selling asset0000
+    let res = rebalance_to(deps.as_ref(), Decimal::one());
+    assert_eq!(
+        res.offer_asset.info,
+        AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+    );
+}
+
+#[test]
+fn query_depth() {
+    let mut deps = setup_balanced_pool(None);
+    let pool_amount = 100_000000000000000000u128;
+
+    let depth = |deps: Deps<CoreumQueries>| -> DepthResponse {
+        from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::Depth {
+                    ask_info: AssetInfo::Cw20Token("asset0000".to_string()),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let price_impact = |deps: Deps<CoreumQueries>, amount: Uint128| {
+        let simulation = query_simulation(
+            deps,
+            mock_env(),
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount,
+            },
+            Some(AssetInfo::Cw20Token("asset0000".to_string())),
+            false,
+            None,
+        )
+        .unwrap();
+        Decimal::from_ratio(
+            simulation.spread_amount,
+            simulation.return_amount + simulation.spread_amount,
+        )
+    };
+
+    let shallow = depth(deps.as_ref());
+    assert_eq!(
+        shallow.offer_asset.info,
+        AssetInfoValidated::SmartToken("uusd".to_string())
+    );
+    assert_eq!(
+        shallow.return_asset.info,
+        AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+    );
+    // the depth trade stays within the max price impact, a bigger trade does not
+    assert!(price_impact(deps.as_ref(), shallow.offer_asset.amount) <= DEPTH_MAX_PRICE_IMPACT);
+    assert!(
+        price_impact(deps.as_ref(), shallow.offer_asset.amount * Uint128::new(2))
+            > DEPTH_MAX_PRICE_IMPACT
+    );
+
+    // a deeper pool at the same price has a higher depth
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin(pool_amount * 10, "uusd")],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &Uint128::new(pool_amount * 10),
+        )],
+    )]);
+    let deep = depth(deps.as_ref());
+    assert!(deep.offer_asset.amount > shallow.offer_asset.amount);
+    assert!(deep.return_asset.amount > shallow.return_asset.amount);
+}
+
+#[test]
+fn query_offer_to_move_price() {
+    let mut deps = setup_balanced_pool(None);
+    let pool_amount = 100_000000000000000000u128;
+
+    let offer_to_move_price = |deps: Deps<CoreumQueries>, bps| {
+        query(
+            deps,
+            mock_env(),
+            QueryMsg::OfferToMovePrice {
+                offer_info: AssetInfo::SmartToken("uusd".to_string()),
+                ask_info: AssetInfo::Cw20Token("asset0000".to_string()),
+                bps,
+            },
+        )
+        .map(|res| from_json::<OfferToMovePriceResponse>(&res).unwrap())
+    };
+    // price of one uusd in asset0000
+    let spot_price = |deps: Deps<CoreumQueries>| {
+        let return_amount = query_simulation(
+            deps,
+            mock_env(),
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(1_000000),
+            },
+            Some(AssetInfo::Cw20Token("asset0000".to_string())),
+            false,
+            None,
+        )
+        .unwrap()
+        .return_amount;
+        Decimal::from_ratio(return_amount, 1_000000u128)
+    };
+    let initial_price = spot_price(deps.as_ref());
+
+    for bps in [10u16, 100, 500] {
+        let res = offer_to_move_price(deps.as_ref(), bps).unwrap();
+        assert_eq!(
+            res.offer_asset.info,
+            AssetInfoValidated::SmartToken("uusd".to_string())
+        );
+        assert_eq!(
+            res.return_asset.info,
+            AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"))
+        );
+
+        // apply the trade to the pool balances (the pool has no fees)
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[coin(pool_amount + res.offer_asset.amount.u128(), "uusd")],
+        )]);
+        deps.querier.with_token_balances(&[(
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(Uint128::new(pool_amount) - res.return_asset.amount),
+            )],
+        )]);
+        let price = spot_price(deps.as_ref());
+        let target = initial_price * Decimal::from_ratio(10_000 - bps, 10_000u16);
+        assert!(
+            price.abs_diff(target) < Decimal::from_ratio(1u128, 100_000u128),
+            "{price} != {target}"
+        );
+
+        // reset the pool balances
+        deps.querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[coin(pool_amount, "uusd")],
+        )]);
+        deps.querier.with_token_balances(&[(
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(pool_amount),
+            )],
+        )]);
+    }
+
+    // invalid price moves are rejected
+    for bps in [0, 10_000] {
+        assert_eq!(
+            offer_to_move_price(deps.as_ref(), bps)
+                .unwrap_err()
+                .to_string(),
+            "Generic error: Price move must be between 1 and 9999 bps"
+        );
+    }
+}
+
+#[test]
+fn query_invariant_after_swap_and_liquidity_round_trip() {
+    let mut deps = setup_balanced_pool(None);
+    let pool_amount = 100_000000000000000000u128;
+
+    // the swap below should pay fees
+    let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+    config.pool_info.fee_config = FeeConfig {
+        total_fee_bps: 30,
+        protocol_fee_bps: 1660,
+    };
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+
+    let set_pool_balances = |querier: &mut WasmMockQuerier, uusd: Uint128, asset: Uint128| {
+        querier.with_balance(&[(
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[coin(uusd.u128(), "uusd")],
+        )]);
+        querier.with_token_balances(&[(
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset)],
+        )]);
+    };
+    let invariant = |deps: Deps<CoreumQueries>| -> Uint128 {
+        from_json::<InvariantResponse>(&query(deps, mock_env(), QueryMsg::Invariant {}).unwrap())
+            .unwrap()
+            .value
+            .try_into()
+            .unwrap()
+    };
+    let attr_value = |res: &cosmwasm_std::Response<CoreumMsg>, key: &str| -> Uint128 {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .unwrap()
+            .value
+            .parse()
+            .unwrap()
+    };
+    let initial = invariant(deps.as_ref());
+    assert!(!initial.is_zero());
+
+    // a balanced provide and withdrawing the minted share again leaves the invariant as it was
+    let deposit = 10_000000000000000000u128;
+    with_uusd_deposit(&mut deps, deposit);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(deposit, "uusd")]),
+        provide_msg(deposit, deposit),
+    )
+    .unwrap();
+    let share = attr_value(&res, "share");
+    set_pool_balances(
+        &mut deps.querier,
+        Uint128::new(pool_amount + deposit),
+        Uint128::new(pool_amount + deposit),
+    );
+    assert!(invariant(deps.as_ref()) > initial);
+
+    let refund = query_share(deps.as_ref(), share).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0001",
+            &[coin(share.u128(), "uuusdmapplp-cosmos2contract")],
+        ),
+        ExecuteMsg::WithdrawLiquidity {
+            assets: vec![],
+            max_burn: None,
+        },
+    )
+    .unwrap();
+    set_pool_balances(
+        &mut deps.querier,
+        Uint128::new(pool_amount + deposit) - refund[0].amount,
+        Uint128::new(pool_amount + deposit) - refund[1].amount,
+    );
+    // rounding only ever favors the pool
+    let after_round_trip = invariant(deps.as_ref());
+    assert!(after_round_trip >= initial);
+    assert_approx_eq!(after_round_trip, initial, "0.000000001");
+
+    // the fee that stays in the pool after a swap increases the invariant
+    let offer_amount = 1_000000000000000000u128;
+    with_uusd_deposit(&mut deps, offer_amount);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(offer_amount, "uusd")]),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(offer_amount),
+            },
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+    )
+    .unwrap();
+    assert!(!attr_value(&res, "commission_amount").is_zero());
+    let paid_out = attr_value(&res, "return_amount") + attr_value(&res, "protocol_fee_amount");
+    set_pool_balances(
+        &mut deps.querier,
+        Uint128::new(pool_amount + offer_amount),
+        Uint128::new(pool_amount) - paid_out,
+    );
+    assert!(invariant(deps.as_ref()) > after_round_trip);
+}
+
+#[test]
+fn update_staking_addr() {
+    let mut deps = setup_balanced_pool(None);
+
+    let staking_addr = |deps: Deps<CoreumQueries>| {
+        from_json::<PairInfo>(&query(deps, mock_env(), QueryMsg::Pair {}).unwrap())
+            .unwrap()
+            .staking_addr
+    };
+    let old_addr = staking_addr(deps.as_ref());
+
+    let msg = ExecuteMsg::UpdateStakingAddr {
+        new_addr: "new_stake".to_string(),
+    };
+
+    // only the factory can update the staking address
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    assert_eq!(staking_addr(deps.as_ref()), old_addr);
+
+    execute(deps.as_mut(), mock_env(), mock_info("factory", &[]), msg).unwrap();
+    assert_eq!(staking_addr(deps.as_ref()), "new_stake");
+}
+
+#[test]
+fn lp_share_amount_after_partial_withdraw() {
+    let mut deps = setup_balanced_pool(None);
+    let pool_amount = Uint128::new(100_000000000000000000u128);
+
+    let total_share = |deps: Deps<CoreumQueries>| -> Uint128 {
+        from_json::<PoolResponse>(&query(deps, mock_env(), QueryMsg::Pool {}).unwrap())
+            .unwrap()
+            .total_share
+    };
+    let initial_share = total_share(deps.as_ref());
+
+    // withdraw a quarter of the liquidity
+    let withdrawn = initial_share.multiply_ratio(1u128, 4u128);
+    let refund = query_share(deps.as_ref(), withdrawn).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[coin(withdrawn.u128(), "uuusdmapplp-cosmos2contract")],
+        ),
+        ExecuteMsg::WithdrawLiquidity {
+            assets: vec![],
+            max_burn: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(total_share(deps.as_ref()), initial_share - withdrawn);
+
+    let uusd_pool = pool_amount - refund[0].amount;
+    let asset_pool = pool_amount - refund[1].amount;
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool)],
+    )]);
+
+    // provide again in the pool ratio
+    let deposit = 10_000000000000000000u128;
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[coin(uusd_pool.u128() + deposit, "uusd")],
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(deposit, "uusd")]),
+        provide_msg(deposit, deposit),
+    )
+    .unwrap();
+    let minted: Uint128 = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "share")
+        .unwrap()
+        .value
+        .parse()
+        .unwrap();
+
+    // the supply matches the sum of all mints and burns
+    assert_eq!(
+        total_share(deps.as_ref()),
+        initial_share - withdrawn + minted
+    );
+    // and the new share is proportional to the remaining supply
+    assert_approx_eq!(
+        minted,
+        (initial_share - withdrawn).multiply_ratio(deposit, uusd_pool),
+        "0.000001"
+    );
+}
+
+#[test]
+fn donate_increases_share_value() {
+    let mut deps = setup_balanced_pool(None);
+    let donation = 10_000000000000000000u128;
+
+    let share = 1_000000u128.into();
+    let share_before = query_share(deps.as_ref(), share).unwrap();
+
+    // only pool assets can be donated
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("donor", &[coin(donation, "ujuno")]),
+        ExecuteMsg::Donate {
+            assets: vec![Asset {
+                info: AssetInfo::SmartToken("ujuno".to_string()),
+                amount: donation.into(),
+            }],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAsset("ujuno".to_string()));
+
+    with_uusd_deposit(&mut deps, donation);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("donor", &[coin(donation, "uusd")]),
+        ExecuteMsg::Donate {
+            assets: vec![Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: donation.into(),
+            }],
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "donate"));
+    // no LP tokens are minted
+    assert!(res.messages.is_empty());
+
+    // existing LP shares are worth more now
+    let share_after = query_share(deps.as_ref(), share).unwrap();
+    assert_approx_eq!(
+        share_after[0].amount,
+        share_before[0].amount.multiply_ratio(110u128, 100u128),
+        "0.000001"
+    );
+    assert_eq!(share_after[1].amount, share_before[1].amount);
+}
+
+#[test]
+fn swaps_follow_lsd_target_rate() {
+    let mut deps = setup_balanced_pool(None);
+    let lsd_asset = AssetInfo::Cw20Token("asset0000".to_string());
+
+    // make asset0000 the LSD token of the pool
+    let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+    config.lsd_asset = Some(AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000")));
+    config.target_rate_provider = Some(Addr::unchecked("lsd_hub"));
+    config.target_rate_epoch = 60;
+    CONFIG.save(deps.as_mut().storage, &config).unwrap();
+
+    let simulate = |deps: Deps<CoreumQueries>, env: Env| {
+        query_simulation(
+            deps,
+            env,
+            Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(1_000000),
+            },
+            Some(lsd_asset.clone()),
+            false,
+            None,
+        )
+        .unwrap()
+        .return_amount
+        .u128()
+    };
+
+    // at a target rate of one, the pool is balanced and swaps are roughly 1:1
+    let res = simulate(deps.as_ref(), mock_env());
+    assert!((990_000..=1_000_000).contains(&res), "{res}");
+
+    // one LSD token is worth 1.1 uusd now, so a uusd buys less of it
+    deps.querier.with_target_rate(Decimal::percent(110));
+    let res = simulate(deps.as_ref(), mock_env());
+    assert!((909_000..=920_000).contains(&res), "{res}");
+
+    // a swap stores the queried target rate
+    with_uusd_deposit(&mut deps, 1_000000);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(1_000000, "uusd")]),
+        ExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::SmartToken("uusd".to_string()),
+                amount: Uint128::new(1_000000),
+            },
+            ask_asset_info: Some(lsd_asset.clone()),
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+    )
+    .unwrap();
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.target_rate, Decimal::percent(110));
+    assert_eq!(config.last_target_query, mock_env().block.time.seconds());
+
+    // the hub is not queried again until the epoch is over
+    deps.querier.with_target_rate(Decimal::percent(120));
+    let res = simulate(deps.as_ref(), mock_env());
+    assert!((909_000..=920_000).contains(&res), "{res}");
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = simulate(deps.as_ref(), env);
+    assert!((833_000..=850_000).contains(&res), "{res}");
+}
+
+#[test]
+fn instantiate_with_invalid_fee_bps() {
+    let msg = |total_fee_bps, protocol_fee_bps| InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+                lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps,
+            protocol_fee_bps,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+
+    for (total_fee_bps, protocol_fee_bps) in [(10_001, 0), (30, 10_001), (u16::MAX, u16::MAX)] {
+        let err = instantiate(
+            mock_dependencies(&[]).as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            msg(total_fee_bps, protocol_fee_bps),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidFeeBps {});
+    }
+
+    // the bounds themselves are valid
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg(10_000, 10_000),
+    )
+    .unwrap();
+}
+
+#[test]
+fn query_ticker_matches_dedicated_queries() {
+    let mut deps = setup_balanced_pool(None);
+    let uusd = AssetInfoValidated::SmartToken("uusd".to_string());
+    let asset = AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000"));
+
+    // someone swapped uusd into the pool
+    with_uusd_deposit(&mut deps, 50_000000000000000000);
+
+    let ticker: TickerResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ticker {}).unwrap()).unwrap();
+
+    let pool: PoolResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Pool {}).unwrap()).unwrap();
+    assert_eq!(ticker.reserves, pool.assets);
+    assert_eq!(ticker.total_share, pool.total_share);
+
+    let pair: PairInfo =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Pair {}).unwrap()).unwrap();
+    assert_eq!(ticker.total_fee_bps, pair.fee_config.total_fee_bps);
+
+    let spot_price = |offer: &AssetInfoValidated, ask: &AssetInfoValidated| -> Decimal {
+        let res: SpotPriceResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SpotPrice {
+                    offer: offer.clone().into(),
+                    ask: ask.clone().into(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.price
+    };
+    assert_eq!(
+        ticker.spot_prices,
+        vec![
+            (uusd.clone(), asset.clone(), spot_price(&uusd, &asset)),
+            (asset.clone(), uusd.clone(), spot_price(&asset, &uusd)),
+        ]
+    );
+    // uusd is the more abundant asset, so it is cheaper
+    assert!(spot_price(&uusd, &asset) < Decimal::one());
+    assert!(spot_price(&asset, &uusd) > Decimal::one());
+}
+
+#[test]
+fn query_precisions() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+    )]);
+    deps.querier.with_token_decimals("asset0000", 8);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::SmartToken("uusd".to_string()),
+            AssetInfo::Cw20Token("asset0000".to_string()),
+        ],
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_json_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+                lsd: None,
+                allow_imbalanced: None,
+                amp_ramp: None,
+                dust_threshold: None,
+                max_allowed_spread: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+        circuit_breaker: None,
+        existing_staking_addr: None,
+        initial_oracle_price: None,
+        verified: true,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res: PrecisionsResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Precisions {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        PrecisionsResponse {
+            precisions: vec![
+                (AssetInfoValidated::SmartToken("uusd".to_string()), 6),
+                (
+                    AssetInfoValidated::Cw20Token(Addr::unchecked("asset0000")),
+                    8
+                ),
+            ],
+            greatest_precision: 8,
+        }
+    );
+}
+
+#[test]
+fn spot_price_is_limit_of_simulation() {
+    let mut deps = setup_balanced_pool(None);
+    let uusd = AssetInfo::SmartToken("uusd".to_string());
+    let asset = AssetInfo::Cw20Token("asset0000".to_string());
+
+    // imbalance the pool, so the price is not trivially 1
+    with_uusd_deposit(&mut deps, 50_000000000000000000);
+
+    for (offer, ask) in [(&uusd, &asset), (&asset, &uusd)] {
+        let res: SpotPriceResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SpotPrice {
+                    offer: offer.clone(),
+                    ask: ask.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let spot_price = res.price;
+
+        // the fee is zero, so the average price of a swap converges to the spot price
+        let mut last_error = Decimal::MAX;
+        for offer_amount in [
+            10_000000000000000000u128,
+            10_000000000000000,
+            10_000000000000,
+        ] {
+            let simulation = query_simulation(
+                deps.as_ref(),
+                mock_env(),
+                Asset {
+                    info: offer.clone(),
+                    amount: Uint128::new(offer_amount),
+                },
+                Some(ask.clone()),
+                false,
+                None,
+            )
+            .unwrap();
+            let swap_price = Decimal::from_ratio(simulation.return_amount, offer_amount);
+            // the average price of a swap is always worse than the spot price
+            assert!(swap_price <= spot_price);
+            let error = spot_price - swap_price;
+            assert!(error < last_error);
+            last_error = error;
+        }
+        assert!(last_error < Decimal::from_ratio(1u128, 1_000_000u128));
+    }
+
+    let err = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SpotPrice {
+            offer: uusd,
+            ask: AssetInfo::Cw20Token("asset0001".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Generic error: {}",
+            ContractError::InvalidAsset("asset0001".to_string())
+        )
+    );
+}
+
+#[test]
+fn historical_prices() {
+    let mut deps = setup_balanced_pool(None);
+    let start = mock_env().block.time.seconds();
+
+    let historical_prices = |deps: Deps<CoreumQueries>, time, duration| {
+        let res: HistoricalPricesResponse = from_json(
+            query(
+                deps,
+                mock_env_with_block_time(time),
+                QueryMsg::HistoricalPrices { duration },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.prices
+    };
+
+    // no full sample period has passed yet
+    assert_eq!(historical_prices(deps.as_ref(), start + 30, 600), vec![]);
+
+    // the price changes every minute, as someone keeps swapping uusd into the pool
+    let mut prices = vec![];
+    for minute in 1..=5 {
+        with_uusd_deposit(&mut deps, minute * 5_000000000000000000);
+        let res = execute(
+            deps.as_mut(),
+            mock_env_with_block_time(start + minute as u64 * 60),
+            mock_info("anyone", &[]),
+            ExecuteMsg::UpdateOracle {},
+        )
+        .unwrap();
+        let price: Decimal = res.attributes[1].value.parse().unwrap();
+        prices.push(price);
+    }
+    // more uusd in the pool makes the uusd per asset price rise
+    assert!(prices.windows(2).all(|w| w[0] < w[1]));
+
+    // the pool is younger than the requested window, so only the recorded samples are returned
+    let history = historical_prices(deps.as_ref(), start + 6 * 60, 600);
+    assert_eq!(history.len(), 5);
+    for (i, ((time, price), expected)) in history.into_iter().zip(prices).enumerate() {
+        // each sample ends one minute after the price was set
+        assert_eq!(time, start + (i as u64 + 2) * 60);
+        assert_approx_eq!(price.numerator(), expected.numerator(), "0.000001");
+    }
+
+    // only the requested window is returned
+    let history = historical_prices(deps.as_ref(), start + 6 * 60, 120);
+    assert_eq!(
+        history.iter().map(|(time, _)| *time).collect::<Vec<_>>(),
+        vec![start + 5 * 60, start + 6 * 60]
+    );
+}
+
+#[test]
+fn twap_standard() {
+    let mut deps = setup_balanced_pool(None);
+    let start = mock_env().block.time.seconds();
+    let uusd = AssetInfo::SmartToken("uusd".to_string());
+    let token = AssetInfo::Cw20Token("asset0000".to_string());
+
+    let twap_standard = |deps: Deps<CoreumQueries>, time, offer: &AssetInfo, ask: &AssetInfo| {
+        query(
+            deps,
+            mock_env_with_block_time(time),
+            QueryMsg::TwapStandard {
+                offer: offer.clone(),
+                ask: ask.clone(),
+            },
+        )
+        .and_then(|res| from_json::<TwapStandardResponse>(&res))
+    };
+
+    // no full half hour has passed yet
+    let res = twap_standard(deps.as_ref(), start + 30 * 60, &token, &uusd).unwrap();
+    assert_eq!(
+        (res.twap_30m, res.twap_1h, res.twap_24h),
+        (None, None, None)
+    );
+
+    // after an hour, only the 30 minute window is covered
+    let res = twap_standard(deps.as_ref(), start + 60 * 60, &token, &uusd).unwrap();
+    assert_eq!(res.offer, token);
+    assert_eq!(res.ask, uusd);
+    assert_approx_eq!(
+        res.twap_30m.unwrap().numerator(),
+        Decimal::one().numerator(),
+        "0.000001"
+    );
+    assert_eq!((res.twap_1h, res.twap_24h), (None, None));
+
+    // after 30 hours, all windows are covered
+    let res = twap_standard(deps.as_ref(), start + 30 * 60 * 60, &uusd, &token).unwrap();
+    for twap in [res.twap_30m, res.twap_1h, res.twap_24h] {
+        assert_approx_eq!(
+            twap.unwrap().numerator(),
+            Decimal::one().numerator(),
+            "0.000001"
+        );
+    }
+
+    // only the assets of the pool can be queried
+    let err = twap_standard(
+        deps.as_ref(),
+        start + 60 * 60,
+        &uusd,
+        &AssetInfo::Cw20Token("asset0001".to_string()),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Generic error: {}", ContractError::AssetMismatch {})
+    );
+}
+
 fn mock_env_with_block_time(time: u64) -> Env {
     let mut env = mock_env();
     env.block = BlockInfo {
@@ -5,15 +5,19 @@ use std::cmp::Ordering;
 
 use dex::{
     asset::{AssetInfoValidated, Decimal256Ext, DecimalAsset},
-    pool::{ContractError, TWAP_PRECISION},
+    pool::{ContractError, DEPTH_MAX_PRICE_IMPACT, TWAP_PRECISION},
 };
 
 use crate::math::{apply_rate, calc_y};
 use crate::state::{get_precision, Config};
 
+/// The maximum number of times the trade size is doubled when searching for a trade size.
+const SEARCH_MAX_DOUBLINGS: u8 = 32;
+
 /// Select offer and ask pools based on given offer and ask infos.
 /// This function works with pools with up to 5 assets. Returns (offer_pool, ask_pool) in case of success.
 /// If it is impossible to define offer and ask pools, returns [`ContractError`].
+/// If one of the given assets does not belong to the pool, returns [`ContractError::InvalidAsset`].
 ///
 /// * **offer_asset_info** - asset info of the offer asset.
 ///
@@ -25,6 +29,12 @@ pub(crate) fn select_pools(
     ask_asset_info: Option<&AssetInfoValidated>,
     pools: &[DecimalAsset],
 ) -> Result<(DecimalAsset, DecimalAsset), ContractError> {
+    for asset_info in offer_asset_info.into_iter().chain(ask_asset_info) {
+        if !pools.iter().any(|pool| pool.info.eq(asset_info)) {
+            return Err(ContractError::InvalidAsset(asset_info.to_string()));
+        }
+    }
+
     if pools.len() == 2 {
         match (offer_asset_info, ask_asset_info) {
             (Some(offer_asset_info), _) => {
@@ -171,6 +181,8 @@ pub(crate) fn compute_swap(
 ///
 /// *Important*: Make sure to update the target rate before calling this function.
 ///
+/// The accumulators are [`Uint256`] and wrap around, so long periods without updates cannot make this fail.
+///
 /// * **pools** array with assets available in the pool *before* the operation.
 pub fn accumulate_prices(
     deps: Deps<CoreumQueries>,
@@ -183,7 +195,7 @@ pub fn accumulate_prices(
         return Ok(false);
     }
 
-    let time_elapsed = Uint128::from(block_time - config.block_time_last);
+    let time_elapsed = Uint256::from(block_time - config.block_time_last);
 
     if pools.iter().all(|pool| !pool.amount.is_zero()) {
         let immut_config = config.clone();
@@ -204,11 +216,13 @@ pub fn accumulate_prices(
                 pools,
             )?;
 
-            *value = value.wrapping_add(time_elapsed.checked_mul(adjust_precision(
+            // u64 seconds * u128 price always fits into a Uint256
+            let price = adjust_precision(
                 return_amount,
                 get_precision(deps.storage, &ask_pool.info)?,
                 TWAP_PRECISION,
-            )?)?);
+            )?;
+            *value = value.wrapping_add(time_elapsed * Uint256::from(price));
         }
     }
 
@@ -357,6 +371,246 @@ pub fn recurse_bisect_spot_price(
     )
 }
 
+/// Finds the trade that moves the spot price of the pool's second asset (in terms of the first one)
+/// to `target_price`. Returns the offer pool, the ask pool and the amount to offer.
+/// The amount is zero if the pool is already at the target price.
+/// Fails if the target is not reached after doubling the trade [`SEARCH_MAX_DOUBLINGS`] times.
+pub fn find_rebalance_trade(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    config: &Config,
+    pools: &[DecimalAsset],
+    target_price: Decimal,
+) -> Result<(DecimalAsset, DecimalAsset, Decimal256), ContractError> {
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let first = &config.pool_info.asset_infos[0];
+    let second = &config.pool_info.asset_infos[1];
+    let current = calc_spot_price(deps, env, config, second, first, pools)?;
+
+    // selling the second asset lowers its price, selling the first one raises it
+    let (offer, ask) = match current.cmp(&target_price) {
+        Ordering::Equal => {
+            let (offer_pool, ask_pool) = select_pools(Some(first), Some(second), pools)?;
+            return Ok((offer_pool, ask_pool, Decimal256::zero()));
+        }
+        Ordering::Greater => (second, first),
+        Ordering::Less => (first, second),
+    };
+    let (offer_pool, ask_pool) = select_pools(Some(offer), Some(ask), pools)?;
+    let ask_precision = get_precision(deps.storage, ask)?;
+
+    // checks whether offering `amount` moves the price to or beyond the target
+    let reaches_target = |amount: Decimal256| -> Result<bool, ContractError> {
+        let offer_asset = DecimalAsset {
+            info: offer.clone(),
+            amount,
+        };
+        let SwapResult { return_amount, .. } = compute_swap(
+            deps.storage,
+            env,
+            config,
+            &offer_asset,
+            &offer_pool,
+            &ask_pool,
+            pools,
+        )?;
+        let return_amount = Decimal256::with_precision(return_amount, ask_precision)?;
+
+        let mut new_pools = pools.to_vec();
+        for pool in new_pools.iter_mut() {
+            if &pool.info == offer {
+                pool.amount += amount;
+            } else if &pool.info == ask {
+                pool.amount -= return_amount;
+            }
+        }
+
+        let price = calc_spot_price(deps, env, config, second, first, &new_pools)?;
+        Ok(if offer == second {
+            price <= target_price
+        } else {
+            price >= target_price
+        })
+    };
+
+    // find an upper bound by doubling the trade until it overshoots
+    let two = Decimal256::from_integer(2u8);
+    let mut max_trade = offer_pool.amount;
+    let mut doublings = 0;
+    while !reaches_target(max_trade)? {
+        if doublings == SEARCH_MAX_DOUBLINGS {
+            return Err(ContractError::RebalanceTargetUnreachable {});
+        }
+        max_trade *= two;
+        doublings += 1;
+    }
+
+    // bisect down to a single unit of the offer asset
+    let unit = Decimal256::with_precision(1u8, get_precision(deps.storage, offer)?)?;
+    let mut min_trade = Decimal256::zero();
+    while max_trade - min_trade > unit {
+        let mid = (min_trade + max_trade) / two;
+        if reaches_target(mid)? {
+            max_trade = mid;
+        } else {
+            min_trade = mid;
+        }
+    }
+
+    Ok((offer_pool, ask_pool, max_trade))
+}
+
+/// Finds the largest trade from `offer_pool` into `ask_pool` whose price impact stays within
+/// [`DEPTH_MAX_PRICE_IMPACT`]. Returns the amount to offer.
+/// Fails if the price impact is still within the limit after doubling the trade
+/// [`SEARCH_MAX_DOUBLINGS`] times.
+pub fn find_depth_trade(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    config: &Config,
+    offer_pool: &DecimalAsset,
+    ask_pool: &DecimalAsset,
+    pools: &[DecimalAsset],
+) -> Result<Decimal256, ContractError> {
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    // checks whether offering `amount` stays within the max price impact, ignoring fees
+    let within_impact = |amount: Decimal256| -> Result<bool, ContractError> {
+        let offer_asset = DecimalAsset {
+            info: offer_pool.info.clone(),
+            amount,
+        };
+        let SwapResult {
+            return_amount,
+            spread_amount,
+        } = compute_swap(
+            deps.storage,
+            env,
+            config,
+            &offer_asset,
+            offer_pool,
+            ask_pool,
+            pools,
+        )?;
+        let ideal_amount = apply_rate(&ask_pool.info, return_amount, config) + spread_amount;
+        Ok(ideal_amount.is_zero()
+            || Decimal::from_ratio(spread_amount, ideal_amount) <= DEPTH_MAX_PRICE_IMPACT)
+    };
+
+    // find an upper bound by doubling the trade until the price impact is too big
+    let two = Decimal256::from_integer(2u8);
+    let mut max_trade = offer_pool.amount;
+    let mut doublings = 0;
+    while within_impact(max_trade)? {
+        if doublings == SEARCH_MAX_DOUBLINGS {
+            return Err(ContractError::RebalanceTargetUnreachable {});
+        }
+        max_trade *= two;
+        doublings += 1;
+    }
+
+    // bisect down to a single unit of the offer asset
+    let unit = Decimal256::with_precision(1u8, get_precision(deps.storage, &offer_pool.info)?)?;
+    let mut min_trade = Decimal256::zero();
+    while max_trade - min_trade > unit {
+        let mid = (min_trade + max_trade) / two;
+        if within_impact(mid)? {
+            min_trade = mid;
+        } else {
+            max_trade = mid;
+        }
+    }
+
+    Ok(min_trade)
+}
+
+/// Finds the smallest trade from `offer_pool` into `ask_pool` that lowers the spot price of the
+/// offer asset (in terms of the ask asset) by `bps` basis points, ignoring fees.
+/// Returns the amount to offer.
+/// Fails if the target is not reached after doubling the trade [`SEARCH_MAX_DOUBLINGS`] times.
+pub fn find_price_move_trade(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    config: &Config,
+    offer_pool: &DecimalAsset,
+    ask_pool: &DecimalAsset,
+    pools: &[DecimalAsset],
+    bps: u16,
+) -> Result<Decimal256, ContractError> {
+    if bps == 0 || bps >= 10_000 {
+        return Err(ContractError::InvalidPriceMoveBps {});
+    }
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let offer = &offer_pool.info;
+    let ask = &ask_pool.info;
+    let current = calc_spot_price(deps, env, config, offer, ask, pools)?;
+    let target_price = current * Decimal::from_ratio(10_000 - bps, 10_000u16);
+    let ask_precision = get_precision(deps.storage, ask)?;
+
+    // checks whether offering `amount` moves the price to or below the target
+    let reaches_target = |amount: Decimal256| -> Result<bool, ContractError> {
+        let offer_asset = DecimalAsset {
+            info: offer.clone(),
+            amount,
+        };
+        let SwapResult { return_amount, .. } = compute_swap(
+            deps.storage,
+            env,
+            config,
+            &offer_asset,
+            offer_pool,
+            ask_pool,
+            pools,
+        )?;
+        let return_amount = Decimal256::with_precision(return_amount, ask_precision)?;
+
+        let mut new_pools = pools.to_vec();
+        for pool in new_pools.iter_mut() {
+            if &pool.info == offer {
+                pool.amount += amount;
+            } else if &pool.info == ask {
+                pool.amount -= return_amount;
+            }
+        }
+
+        Ok(calc_spot_price(deps, env, config, offer, ask, &new_pools)? <= target_price)
+    };
+
+    // find an upper bound by doubling the trade until it reaches the target
+    let two = Decimal256::from_integer(2u8);
+    let mut max_trade = offer_pool.amount;
+    let mut doublings = 0;
+    while !reaches_target(max_trade)? {
+        if doublings == SEARCH_MAX_DOUBLINGS {
+            return Err(ContractError::RebalanceTargetUnreachable {});
+        }
+        max_trade *= two;
+        doublings += 1;
+    }
+
+    // bisect down to a single unit of the offer asset
+    let unit = Decimal256::with_precision(1u8, get_precision(deps.storage, offer)?)?;
+    let mut min_trade = Decimal256::zero();
+    while max_trade - min_trade > unit {
+        let mid = (min_trade + max_trade) / two;
+        if reaches_target(mid)? {
+            max_trade = mid;
+        } else {
+            min_trade = mid;
+        }
+    }
+
+    Ok(max_trade)
+}
+
 /// Pretend we swapped amount from token into to token.
 /// Return the pools value as if this happened to use for future calculations
 fn pools_after_swap(
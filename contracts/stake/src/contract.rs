@@ -4,34 +4,42 @@ use coreum_wasm_sdk::core::{CoreumMsg, CoreumQueries};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure_eq, from_json, to_json_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Env,
-    MessageInfo, Order, StdError, StdResult, Storage, Uint128, WasmMsg,
+    ensure, from_json, to_json_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, StdError, StdResult, Storage, Timestamp, Uint128, Uint256, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use cw_controllers::Claim;
-use cw_storage_plus::Map;
-use dex::asset::{addr_opt_validate, AssetInfo, AssetInfoValidated};
+use cw_storage_plus::Bound;
+use dex::asset::{addr_opt_validate, AssetInfo, AssetInfoExt, AssetInfoValidated};
 use dex::common::validate_addresses;
 use dex::stake::{FundingInfo, InstantiateMsg, ReceiveMsg, UnbondingPeriod};
 
 use crate::distribution::{
-    apply_points_correction, execute_delegate_withdrawal, execute_distribute_rewards,
-    execute_withdraw_rewards, query_delegated, query_distributed_rewards, query_distribution_data,
-    query_undistributed_rewards, query_withdraw_adjustment_data, query_withdrawable_rewards,
+    active_distributions, apply_points_correction, execute_authorize_withdrawer,
+    execute_delegate_withdrawal, execute_distribute_rewards, execute_revoke_withdrawer,
+    execute_withdraw_rewards, execute_withdraw_rewards_split, query_delegated,
+    query_distributed_rewards, query_distribution_data, query_lifetime_rewards,
+    query_total_distributed, query_undistributed_rewards, query_withdraw_adjustment_data,
+    query_withdrawable_rewards, update_locked_rewards,
 };
-use crate::utils::{create_undelegate_msg, CurveExt};
+use crate::utils::{calc_power, create_undelegate_msg, CurveExt};
 use cw2::{ensure_from_older_version, set_contract_version};
 use cw_utils::{maybe_addr, Expiration};
 
 use crate::error::ContractError;
 use crate::msg::{
-    AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse, BondingInfoResponse,
-    BondingPeriodInfo, ExecuteMsg, MigrateMsg, QueryMsg, RewardsPowerResponse, StakedResponse,
-    TotalStakedResponse, TotalUnbondingResponse, UnbondAllResponse,
+    AllClaimsResponse, AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse,
+    AssetEmissionRate, BalanceReconciliationResponse, BondingInfoResponse, BondingPeriodInfo,
+    CanUnbondResponse, EmissionRateResponse, ExecuteMsg, MigrateMsg, QueryMsg, RewardRateResponse,
+    RewardsPowerResponse, ScheduledEmission, StakedResponse, TotalStakedResponse,
+    TotalUnbondingResponse, UnbondAllPreviewResponse, UnbondAllResponse,
+    UnbondRewardImpactResponse, UnbondableAmountResponse, UnbondingScheduleEntry,
+    MAX_CLAIM_FOR_ADDRESSES,
 };
 use crate::state::{
-    Config, Distribution, TokenInfo, TotalStake, ADMIN, CLAIMS, CONFIG, DISTRIBUTION, REWARD_CURVE,
-    STAKE, TOTAL_PER_PERIOD, TOTAL_STAKED, UNBOND_ALL,
+    checkpoint_stake, cumulative_stake_at, seed_stake_checkpoint, Config, Distribution, TokenInfo,
+    TotalStake, ADMIN, CLAIMS, CLAIMS_MAP, CLAIM_UNBONDING_PERIODS, CONFIG, DISTRIBUTION,
+    DISTRIBUTION_GENERATION, LAST_DELEGATION, REWARD_CURVE, STAKE, STAKE_CHECKPOINTS_START,
+    TOTAL_DISTRIBUTED, TOTAL_PER_PERIOD, TOTAL_STAKED, UNBOND_ALL,
 };
 use wynd_curve_utils::Curve;
 
@@ -40,6 +48,9 @@ pub type SubMsg = cosmwasm_std::SubMsg<CoreumMsg>;
 
 const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 // version info for migration info
 const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_CRATE_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -87,6 +98,10 @@ pub fn instantiate(
         unbonding_periods: msg.unbonding_periods,
         max_distributions: msg.max_distributions,
         unbonder: addr_opt_validate(deps.api, &msg.unbonder)?,
+        backup_unbonders: validate_addresses(deps.api, &msg.backup_unbonders)?,
+        unbond_grace_period: msg.unbond_grace_period,
+        min_distribution_interval: msg.min_distribution_interval,
+        auto_claim_on_delegate: msg.auto_claim_on_delegate,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -103,11 +118,14 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     let api = deps.api;
     match msg {
-        ExecuteMsg::Delegate { unbonding_period } => {
+        ExecuteMsg::Delegate {
+            unbonding_period,
+            delegate_as,
+        } => {
             if UNBOND_ALL.load(deps.storage)? {
                 return Err(ContractError::CannotDelegateIfUnbondAll {});
             }
-            execute_bond(deps, info, unbonding_period)
+            execute_bond(deps, env, info, unbonding_period, delegate_as)
         }
         ExecuteMsg::UpdateAdmin { admin } => {
             Ok(ADMIN.execute_update_admin(deps, info, maybe_addr(api, admin)?)?)
@@ -116,7 +134,24 @@ pub fn execute(
             manager,
             asset,
             rewards,
-        } => execute_create_distribution_flow(deps, info, manager, asset, rewards),
+            rewards_locked_until_unbond,
+        } => execute_create_distribution_flow(
+            deps,
+            info,
+            manager,
+            asset,
+            rewards,
+            rewards_locked_until_unbond,
+        ),
+        ExecuteMsg::UpdateDistributionManager { asset, manager } => {
+            execute_update_distribution_manager(deps, info, asset, manager)
+        }
+        ExecuteMsg::RemoveDistributionFlow { asset, receiver } => {
+            execute_remove_distribution_flow(deps, env, info, asset, receiver)
+        }
+        ExecuteMsg::SweepRemovedDistribution { asset } => {
+            execute_sweep_removed_distribution(deps, env, info, asset)
+        }
         ExecuteMsg::Rebond {
             tokens,
             bond_from,
@@ -126,20 +161,53 @@ pub fn execute(
             tokens: amount,
             unbonding_period,
         } => execute_unbond(deps, env, info, amount, unbonding_period),
+        ExecuteMsg::CancelUnbond {
+            release_at,
+            unbonding_period,
+        } => {
+            if UNBOND_ALL.load(deps.storage)? {
+                return Err(ContractError::CannotCancelUnbondIfUnbondAll {});
+            }
+            execute_cancel_unbond(deps, env, info, release_at, unbonding_period)
+        }
         ExecuteMsg::QuickUnbond { stakers } => execute_quick_unbond(deps, env, info, stakers),
         ExecuteMsg::UnbondAll {} => execute_unbond_all(deps, info),
         ExecuteMsg::StopUnbondAll {} => execute_stop_unbond_all(deps, info),
-        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::UpdateUnbonders {
+            unbonder,
+            backup_unbonders,
+        } => execute_update_unbonders(deps, info, unbonder, backup_unbonders),
+        ExecuteMsg::Slash {
+            address,
+            unbonding_period,
+            amount,
+            recipient,
+        } => execute_slash(
+            deps,
+            env,
+            info,
+            address,
+            unbonding_period,
+            amount,
+            recipient,
+        ),
+        ExecuteMsg::Claim { receiver } => execute_claim(deps, env, info, receiver),
+        ExecuteMsg::ClaimFor { addresses } => execute_claim_for(deps, env, addresses),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
         ExecuteMsg::DistributeRewards { sender } => {
             execute_distribute_rewards(deps, env, info, sender)
         }
         ExecuteMsg::WithdrawRewards { owner, receiver } => {
-            execute_withdraw_rewards(deps, info, owner, receiver)
+            execute_withdraw_rewards(deps, env, info, owner, receiver)
+        }
+        ExecuteMsg::WithdrawRewardsSplit { splits } => {
+            execute_withdraw_rewards_split(deps, env, info, splits)
         }
         ExecuteMsg::DelegateWithdrawal { delegated } => {
             execute_delegate_withdrawal(deps, info, delegated)
         }
+        ExecuteMsg::AuthorizeWithdrawer { addr } => execute_authorize_withdrawer(deps, info, addr),
+        ExecuteMsg::RevokeWithdrawer { addr } => execute_revoke_withdrawer(deps, info, addr),
         ExecuteMsg::FundDistribution { funding_info } => {
             execute_fund_distribution(env, deps, info, funding_info)
         }
@@ -213,6 +281,7 @@ pub fn execute_create_distribution_flow(
     manager: String,
     asset: AssetInfo,
     rewards: Vec<(UnbondingPeriod, Decimal)>,
+    rewards_locked_until_unbond: bool,
 ) -> Result<Response, ContractError> {
     // only admin can create distribution flow
     ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
@@ -244,21 +313,25 @@ pub fn execute_create_distribution_flow(
         return Err(ContractError::InvalidRewards {});
     }
 
+    // make sure the distribution does not exist already
+    // a removed distribution of the same asset has to be fully withdrawn or swept first
+    if let Some(existing) = DISTRIBUTION.may_load(deps.storage, &asset)? {
+        return Err(if existing.removed {
+            ContractError::RemovedDistributionNotWithdrawn(asset)
+        } else {
+            ContractError::DistributionAlreadyExists(asset)
+        });
+    }
+
     // make sure to respect the distribution count limit to create an upper bound for all the staking operations
-    let keys = DISTRIBUTION
-        .keys(deps.storage, None, None, Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?;
-    if keys.len() >= (config.max_distributions as usize) {
+    // removed distributions do not count, since they are deleted once their rewards are withdrawn
+    let active_count = active_distributions(deps.storage).count();
+    if active_count >= (config.max_distributions as usize) {
         return Err(ContractError::TooManyDistributions(
             config.max_distributions,
         ));
     }
 
-    // make sure the distribution does not exist already
-    if keys.contains(&asset) {
-        return Err(ContractError::DistributionAlreadyExists(asset));
-    }
-
     REWARD_CURVE.save(deps.storage, &asset, &Curve::constant(0))?;
 
     DISTRIBUTION.save(
@@ -267,20 +340,135 @@ pub fn execute_create_distribution_flow(
         &Distribution {
             manager,
             reward_multipliers: rewards,
+            rewards_locked_until_unbond,
             shares_per_point: Uint128::zero(),
             shares_leftover: 0,
             distributed_total: Uint128::zero(),
             withdrawable_total: Uint128::zero(),
+            remainder: Uint128::zero(),
+            last_distributed: 0,
+            removed: false,
+            generation: DISTRIBUTION_GENERATION
+                .may_load(deps.storage, &asset)?
+                .unwrap_or_default(),
         },
     )?;
 
     Ok(Response::default())
 }
 
-pub fn execute_rebond(
+/// Hand over an existing distribution flow to a new manager
+pub fn execute_update_distribution_manager(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    asset: AssetInfo,
+    manager: String,
+) -> Result<Response, ContractError> {
+    let asset = asset.validate(deps.api)?;
+    let manager = deps.api.addr_validate(&manager)?;
+
+    let mut distribution = DISTRIBUTION.load(deps.storage, &asset)?;
+    // only the current manager can change the distribution
+    if distribution.manager != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    distribution.manager = manager.clone();
+    DISTRIBUTION.save(deps.storage, &asset, &distribution)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_distribution_manager")
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("manager", manager))
+}
+
+/// Remove an existing distribution flow, freeing its slot for a new one.
+/// All rewards that were not distributed to the stakers yet are sent to `receiver`.
+/// Rewards that were already distributed stay withdrawable, the distribution is deleted
+/// once all of them were withdrawn.
+pub fn execute_remove_distribution_flow(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let asset = asset.validate(deps.api)?;
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+
+    let mut distribution = match DISTRIBUTION.may_load(deps.storage, &asset)? {
+        Some(distribution) if !distribution.removed => distribution,
+        _ => return Err(ContractError::DistributionNotFound(asset)),
+    };
+    // only the manager can remove the distribution
+    if distribution.manager != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // everything the stakers cannot withdraw yet is sent back, including the remainder
+    // and funding that is still locked in the reward curve
+    let undistributed = asset
+        .query_balance(&deps.querier, env.contract.address)?
+        .saturating_sub(distribution.withdrawable_total);
+
+    REWARD_CURVE.remove(deps.storage, &asset);
+    // the next distribution of this asset must not pick up the withdraw adjustments of this one
+    DISTRIBUTION_GENERATION.save(deps.storage, &asset, &(distribution.generation + 1))?;
+    if distribution.withdrawable_total.is_zero() {
+        DISTRIBUTION.remove(deps.storage, &asset);
+    } else {
+        distribution.removed = true;
+        distribution.remainder = Uint128::zero();
+        distribution.shares_leftover = 0;
+        DISTRIBUTION.save(deps.storage, &asset, &distribution)?;
+    }
+
+    let mut resp = Response::new()
+        .add_attribute("action", "remove_distribution_flow")
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("undistributed", undistributed)
+        .add_attribute("receiver", receiver.as_str());
+    if !undistributed.is_zero() {
+        resp = resp.add_message(asset.with_balance(undistributed).into_msg(receiver)?);
+    }
+    Ok(resp)
+}
+
+/// Delete a removed distribution flow that still has rewards left to withdraw and send them to the admin.
+/// Rounding leaves some dust in every distribution that nobody can withdraw, so without this
+/// a removed distribution could block a new flow for the same asset forever.
+pub fn execute_sweep_removed_distribution(
     deps: DepsMut<CoreumQueries>,
     env: Env,
     info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let asset = asset.validate(deps.api)?;
+    let distribution = match DISTRIBUTION.may_load(deps.storage, &asset)? {
+        Some(distribution) if distribution.removed => distribution,
+        _ => return Err(ContractError::DistributionNotFound(asset)),
+    };
+    DISTRIBUTION.remove(deps.storage, &asset);
+
+    let swept = asset
+        .query_balance(&deps.querier, env.contract.address)?
+        .min(distribution.withdrawable_total);
+
+    let mut resp = Response::new()
+        .add_attribute("action", "sweep_removed_distribution")
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("swept", swept);
+    if !swept.is_zero() {
+        resp = resp.add_message(asset.with_balance(swept).into_msg(info.sender)?);
+    }
+    Ok(resp)
+}
+
+pub fn execute_rebond(
+    mut deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
     amount: Uint128,
     bond_from: u64,
     bond_to: u64,
@@ -307,6 +495,31 @@ pub fn execute_rebond(
         return Err(ContractError::NoUnbondingPeriodFound(bond_to));
     }
 
+    // moving to a shorter period unbonds the tokens with a claim for the difference of the periods
+    if bond_from > bond_to {
+        remove_stake_without_total(deps.branch(), &env, &cfg, &info.sender, bond_from, amount)?;
+        TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
+            Ok(TokenInfo {
+                staked: token_info.staked.checked_sub(amount)?,
+                unbonding: token_info.unbonding + amount,
+            })
+        })?;
+        let release_at = env.block.time.plus_seconds(bond_from - bond_to);
+        CLAIMS.create_claim(
+            deps.storage,
+            &info.sender,
+            amount,
+            Expiration::AtTime(release_at),
+        )?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "rebond")
+            .add_attribute("amount", amount)
+            .add_attribute("bond_from", bond_from.to_string())
+            .add_attribute("bond_to", bond_to.to_string())
+            .add_attribute("release_at", release_at.to_string()));
+    }
+
     let distributions: Vec<_> = DISTRIBUTION
         .range(deps.storage, None, None, Order::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
@@ -339,15 +552,7 @@ pub fn execute_rebond(
             |bonding_info| -> StdResult<_> {
                 let mut bonding_info = bonding_info.unwrap_or_default();
                 old_stake_to = bonding_info.total_stake();
-
-                if bond_from > bond_to {
-                    bonding_info.add_locked_tokens(
-                        env.block.time.plus_seconds(bond_from - bond_to),
-                        amount,
-                    );
-                } else {
-                    bonding_info.add_unlocked_tokens(amount);
-                };
+                bonding_info.add_unlocked_tokens(amount);
                 Ok(bonding_info)
             },
         )?
@@ -361,6 +566,28 @@ pub fn execute_rebond(
         new_stake_from,
     )?;
     update_total_stake(deps.storage, &cfg, bond_to, old_stake_to, new_stake_to)?;
+    // the rebonded stake is not unbonded, so its rewards stay locked in the old period
+    update_locked_rewards(
+        deps.storage,
+        &env,
+        &cfg,
+        &info.sender,
+        bond_from,
+        old_stake_from,
+        new_stake_from,
+        Uint128::zero(),
+    )?;
+    update_locked_rewards(
+        deps.storage,
+        &env,
+        &cfg,
+        &info.sender,
+        bond_to,
+        old_stake_to,
+        new_stake_to,
+        Uint128::zero(),
+    )?;
+    LAST_DELEGATION.save(deps.storage, (&info.sender, bond_to), &env.block.time)?;
 
     // update the adjustment data for all distributions
     for ((asset_info, mut distribution), old_reward_power) in
@@ -389,19 +616,53 @@ pub fn execute_rebond(
 
 pub fn execute_bond(
     deps: DepsMut<CoreumQueries>,
+    env: Env,
     info: MessageInfo,
     unbonding_period: u64,
+    delegate_as: Option<String>,
 ) -> Result<Response, ContractError> {
     if info.funds.len() != 1 {
         return Err(ContractError::NoFunds {});
     }
     let coin = info.funds[0].clone();
-    let res = execute_mass_bond(deps, info.sender.clone(), coin, unbonding_period)?;
-    Ok(res.add_attribute("sender", info.sender))
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // only trusted contracts can bond on behalf of someone else
+    let staker = match delegate_as {
+        Some(delegate_as) => {
+            if cfg.instantiator != info.sender && !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            deps.api.addr_validate(&delegate_as)?
+        }
+        None => info.sender.clone(),
+    };
+
+    // pay out matured claims first, if configured
+    let auto_claimed = if cfg.auto_claim_on_delegate {
+        release_matured_claims(deps.storage, &env, &staker)?
+    } else {
+        Uint128::zero()
+    };
+
+    let mut res = execute_mass_bond(deps, env, staker.clone(), coin, unbonding_period)?;
+    if !auto_claimed.is_zero() {
+        res = res
+            .add_submessage(create_undelegate_msg(
+                staker.clone(),
+                auto_claimed,
+                cfg.lp_share_denom,
+            )?)
+            .add_attribute("auto_claimed", auto_claimed);
+    }
+    Ok(res
+        .add_attribute("sender", info.sender)
+        .add_attribute("staker", staker))
 }
 
 pub fn execute_mass_bond(
     deps: DepsMut<CoreumQueries>,
+    env: Env,
     sender: Addr,
     lp_share: Coin,
     unbonding_period: u64,
@@ -431,6 +692,7 @@ pub fn execute_mass_bond(
 
     // calculate rewards power before updating the stake
     let old_rewards = calc_rewards_powers(deps.storage, &cfg, &sender, distributions.iter())?;
+    seed_stake_checkpoint(deps.storage, &sender)?;
 
     // add to the sender's stake
     let mut old_stake = Uint128::zero();
@@ -448,6 +710,18 @@ pub fn execute_mass_bond(
         .total_stake();
 
     update_total_stake(deps.storage, &cfg, unbonding_period, old_stake, new_stake)?;
+    update_locked_rewards(
+        deps.storage,
+        &env,
+        &cfg,
+        &sender,
+        unbonding_period,
+        old_stake,
+        new_stake,
+        Uint128::zero(),
+    )?;
+    LAST_DELEGATION.save(deps.storage, (&sender, unbonding_period), &env.block.time)?;
+    checkpoint_stake(deps.storage, &env, &sender)?;
 
     // update the adjustment data for all distributions
     distributions = distributions
@@ -611,17 +885,148 @@ pub fn execute_unbond(
         Ok(resp.add_submessage(msg))
     } else {
         // provide them a claim
+        let release_at = env.block.time.plus_seconds(unbonding_period);
         CLAIMS.create_claim(
             deps.storage,
             &info.sender,
             amount,
             // If unbond all flag set to true the claim has no delay.
-            Expiration::AtTime(env.block.time.plus_seconds(unbonding_period)),
+            Expiration::AtTime(release_at),
         )?;
+        // remember where the claim came from, so it can be cancelled
+        if cfg.unbond_grace_period > 0 {
+            CLAIM_UNBONDING_PERIODS.update(
+                deps.storage,
+                (&info.sender, release_at.nanos()),
+                |periods| -> StdResult<_> {
+                    let mut periods = periods.unwrap_or_default();
+                    periods.push(unbonding_period);
+                    Ok(periods)
+                },
+            )?;
+        }
         Ok(resp)
     }
 }
 
+/// Removes `amount` from the unlocked stake of `address` in the given unbonding period and sends it
+/// to `recipient`. Rewards power is updated just like when unbonding, but no claim is created.
+/// Can only be called by the admin.
+pub fn execute_slash(
+    mut deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    unbonding_period: u64,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let staker = deps.api.addr_validate(&address)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    ensure!(!amount.is_zero(), ContractError::NoSlashAmount {});
+
+    let cfg = CONFIG.load(deps.storage)?;
+    remove_stake_without_total(deps.branch(), &env, &cfg, &staker, unbonding_period, amount)?;
+
+    TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
+        Ok(TokenInfo {
+            staked: token_info.staked.checked_sub(amount)?,
+            unbonding: token_info.unbonding,
+        })
+    })?;
+
+    let msg = create_undelegate_msg(recipient.clone(), amount, cfg.lp_share_denom)?;
+    Ok(Response::new()
+        .add_submessage(msg)
+        .add_attribute("action", "slash")
+        .add_attribute("sender", info.sender)
+        .add_attribute("staker", staker)
+        .add_attribute("unbonding_period", unbonding_period.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("recipient", recipient))
+}
+
+pub fn execute_cancel_unbond(
+    mut deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    release_at: Timestamp,
+    unbonding_period: UnbondingPeriod,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let not_found = || ContractError::ClaimNotFound {
+        release_at,
+        unbonding_period,
+    };
+
+    // claims releasing at the same time are told apart by their order
+    let mut unbonding_periods = CLAIM_UNBONDING_PERIODS
+        .may_load(deps.storage, (&info.sender, release_at.nanos()))?
+        .unwrap_or_default();
+    let position = unbonding_periods
+        .iter()
+        .position(|period| *period == unbonding_period)
+        .ok_or_else(not_found)?;
+
+    let mut claims = CLAIMS.query_claims(deps.as_ref(), &info.sender)?.claims;
+    let claim_idx = claims
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.release_at == Expiration::AtTime(release_at))
+        .nth(position)
+        .map(|(idx, _)| idx)
+        .ok_or_else(not_found)?;
+    let claim = claims.remove(claim_idx);
+
+    let grace_period_end = release_at
+        .minus_seconds(unbonding_period)
+        .plus_seconds(cfg.unbond_grace_period);
+    ensure!(
+        env.block.time < grace_period_end && env.block.time < release_at,
+        ContractError::UnbondNotCancellable {}
+    );
+
+    // remove the claim
+    CLAIMS_MAP.save(deps.storage, &info.sender, &claims)?;
+    unbonding_periods.remove(position);
+    if unbonding_periods.is_empty() {
+        CLAIM_UNBONDING_PERIODS.remove(deps.storage, (&info.sender, release_at.nanos()));
+    } else {
+        CLAIM_UNBONDING_PERIODS.save(
+            deps.storage,
+            (&info.sender, release_at.nanos()),
+            &unbonding_periods,
+        )?;
+    }
+
+    TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
+        Ok(TokenInfo {
+            staked: token_info.staked,
+            unbonding: token_info.unbonding.checked_sub(claim.amount)?,
+        })
+    })?;
+
+    // put the tokens back into stake
+    execute_mass_bond(
+        deps.branch(),
+        env,
+        info.sender.clone(),
+        Coin {
+            denom: cfg.lp_share_denom,
+            amount: claim.amount,
+        },
+        unbonding_period,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_unbond")
+        .add_attribute("amount", claim.amount)
+        .add_attribute("unbonding_period", unbonding_period.to_string())
+        .add_attribute("sender", info.sender))
+}
+
 pub fn execute_quick_unbond(
     deps: DepsMut<CoreumQueries>,
     env: Env,
@@ -631,7 +1036,7 @@ pub fn execute_quick_unbond(
     let cfg = CONFIG.load(deps.storage)?;
 
     // this can only be called if unbonder is set
-    if cfg.unbonder != Some(info.sender) {
+    if !cfg.is_unbonder(&info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -658,6 +1063,7 @@ pub fn execute_quick_unbond(
     for staker in staker_addresses {
         // calculate rewards power before updating the stake
         let old_rewards = calc_rewards_powers(deps.storage, &cfg, &staker, distributions.iter())?;
+        seed_stake_checkpoint(deps.storage, &staker)?;
 
         // the amount the staker unbonds in this call
         let mut staker_unbonds = Uint128::zero();
@@ -675,7 +1081,18 @@ pub fn execute_quick_unbond(
             bonding_info.force_unlock_all()?;
             bonding_info.release_stake(&env, old_stake)?;
             STAKE.save(deps.storage, (&staker, unbonding_period), &bonding_info)?;
+            update_locked_rewards(
+                deps.storage,
+                &env,
+                &cfg,
+                &staker,
+                unbonding_period,
+                old_stake,
+                Uint128::zero(),
+                old_stake,
+            )?;
         }
+        checkpoint_stake(deps.storage, &env, &staker)?;
 
         // update the adjustment data for all distributions
         for ((asset_info, distribution), old_reward_power) in
@@ -701,10 +1118,15 @@ pub fn execute_quick_unbond(
             .into_iter()
             .map(|c| c.amount)
             .sum();
-        // in order to delete the claims, we need to create a new Map with the same key,
-        // because the `Claims` API does not provide a way to delete unmature claims.
-        const CLAIMS_MAP: Map<&Addr, Vec<Claim>> = Map::new("claims");
+        // delete the claims, they are paid out below
         CLAIMS_MAP.save(deps.storage, &staker, &vec![])?;
+        let release_times = CLAIM_UNBONDING_PERIODS
+            .prefix(&staker)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for release_at in release_times {
+            CLAIM_UNBONDING_PERIODS.remove(deps.storage, (&staker, release_at));
+        }
         claimed_total += open_claims;
 
         let amount = staker_unbonds + open_claims;
@@ -751,10 +1173,9 @@ pub fn execute_unbond_all(
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
-    // Only unbonder can execute unbond all and set state variable to true.
-    ensure_eq!(
-        cfg.unbonder,
-        Some(info.sender),
+    // Only unbonders can execute unbond all and set state variable to true.
+    ensure!(
+        cfg.is_unbonder(&info.sender),
         ContractError::Unauthorized {}
     );
 
@@ -775,7 +1196,7 @@ pub fn execute_stop_unbond_all(
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
 
-    if cfg.unbonder != Some(info.sender.clone()) && !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
+    if !cfg.is_unbonder(&info.sender) && !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -790,6 +1211,22 @@ pub fn execute_stop_unbond_all(
     Ok(Response::default().add_attribute("action", "stop unbond all"))
 }
 
+pub fn execute_update_unbonders(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    unbonder: Option<String>,
+    backup_unbonders: Vec<String>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+    cfg.unbonder = addr_opt_validate(deps.api, &unbonder)?;
+    cfg.backup_unbonders = validate_addresses(deps.api, &backup_unbonders)?;
+    CONFIG.save(deps.storage, &cfg)?;
+
+    Ok(Response::default().add_attribute("action", "update unbonders"))
+}
+
 /// Calculates rewards power of the user for all given distributions (for all unbonding periods).
 /// They are returned in the same order as the distributions.
 fn calc_rewards_powers<'a>(
@@ -823,9 +1260,8 @@ fn update_rewards(
     }
 
     // update their share of the distribution
-    let ppw = distribution.shares_per_point.u128();
     let diff = new_reward_power.u128() as i128 - old_reward_power.u128() as i128;
-    apply_points_correction(storage, sender, asset_info, ppw, diff)?;
+    apply_points_correction(storage, sender, asset_info, distribution, diff)?;
 
     Ok(())
 }
@@ -853,6 +1289,7 @@ fn remove_stake_without_total(
         .collect::<StdResult<Vec<_>>>()?;
     // calculate rewards power before updating the stake
     let old_rewards = calc_rewards_powers(deps.storage, cfg, staker, distributions.iter())?;
+    seed_stake_checkpoint(deps.storage, staker)?;
 
     // reduce the sender's stake - aborting if insufficient
     let mut old_stake = Uint128::zero();
@@ -870,6 +1307,17 @@ fn remove_stake_without_total(
         .total_stake();
 
     update_total_stake(deps.storage, cfg, unbonding_period, old_stake, new_stake)?;
+    update_locked_rewards(
+        deps.storage,
+        env,
+        cfg,
+        staker,
+        unbonding_period,
+        old_stake,
+        new_stake,
+        amount,
+    )?;
+    checkpoint_stake(deps.storage, env, staker)?;
 
     // update the adjustment data for all distributions
     for ((asset_info, mut distribution), old_reward_power) in
@@ -895,29 +1343,102 @@ pub fn execute_claim(
     deps: DepsMut<CoreumQueries>,
     env: Env,
     info: MessageInfo,
+    receiver: Option<String>,
 ) -> Result<Response, ContractError> {
-    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+
+    let release = release_matured_claims(deps.storage, &env, &info.sender)?;
     if release.is_zero() {
         return Err(ContractError::NothingToClaim {});
     }
 
     let config = CONFIG.load(deps.storage)?;
     let amount_str = coin_to_string(release, config.lp_share_denom.as_str());
-    let undelegate_msg =
-        create_undelegate_msg(info.sender.clone(), release, config.lp_share_denom)?;
+    let undelegate_msg = create_undelegate_msg(receiver.clone(), release, config.lp_share_denom)?;
 
-    TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
+    Ok(Response::new()
+        .add_submessage(undelegate_msg)
+        .add_attribute("action", "claim")
+        .add_attribute("tokens", amount_str)
+        .add_attribute("sender", info.sender)
+        .add_attribute("receiver", receiver))
+}
+
+/// Releases the matured claims of all `addresses` and sends the tokens to their owners.
+/// Fails with [`ContractError::NothingToClaim`] if none of them had matured claims.
+pub fn execute_claim_for(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    if addresses.len() > MAX_CLAIM_FOR_ADDRESSES {
+        return Err(ContractError::TooManyClaimAddresses(
+            MAX_CLAIM_FOR_ADDRESSES,
+        ));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut response = Response::new().add_attribute("action", "claim_for");
+    let mut total = Uint128::zero();
+    for address in addresses {
+        let owner = deps.api.addr_validate(&address)?;
+        let release = release_matured_claims(deps.storage, &env, &owner)?;
+        if release.is_zero() {
+            continue;
+        }
+        total += release;
+        response = response
+            .add_submessage(create_undelegate_msg(
+                owner.clone(),
+                release,
+                config.lp_share_denom.clone(),
+            )?)
+            .add_attribute("owner", owner);
+    }
+    if total.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    Ok(response.add_attribute(
+        "tokens",
+        coin_to_string(total, config.lp_share_denom.as_str()),
+    ))
+}
+
+/// Releases all matured claims of `owner` and returns the released amount.
+/// The caller is responsible for sending the released tokens.
+fn release_matured_claims(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+) -> StdResult<Uint128> {
+    let release = CLAIMS.claim_tokens(storage, owner, &env.block, None)?;
+    if release.is_zero() {
+        return Ok(release);
+    }
+
+    // released claims can no longer be cancelled
+    let released = CLAIM_UNBONDING_PERIODS
+        .prefix(owner)
+        .keys(
+            storage,
+            None,
+            Some(Bound::inclusive(env.block.time.nanos())),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    for release_at in released {
+        CLAIM_UNBONDING_PERIODS.remove(storage, (owner, release_at));
+    }
+
+    TOTAL_STAKED.update::<_, StdError>(storage, |token_info| {
         Ok(TokenInfo {
             staked: token_info.staked,
             unbonding: token_info.unbonding.saturating_sub(release),
         })
     })?;
 
-    Ok(Response::new()
-        .add_submessage(undelegate_msg)
-        .add_attribute("action", "claim")
-        .add_attribute("tokens", amount_str)
-        .add_attribute("sender", info.sender))
+    Ok(release)
 }
 
 #[inline]
@@ -935,18 +1456,69 @@ pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Bi
             address,
             unbonding_period,
         } => to_json_binary(&query_staked(deps, &env, address, unbonding_period)?),
+        QueryMsg::UnbondableAmount {
+            address,
+            unbonding_period,
+        } => to_json_binary(&query_unbondable_amount(
+            deps,
+            &env,
+            address,
+            unbonding_period,
+        )?),
+        QueryMsg::CanUnbond {
+            address,
+            amount,
+            unbonding_period,
+        } => to_json_binary(&query_can_unbond(
+            deps,
+            &env,
+            address,
+            amount,
+            unbonding_period,
+        )?),
+        QueryMsg::UnbondRewardImpact {
+            address,
+            amount,
+            unbonding_period,
+        } => to_json_binary(&query_unbond_reward_impact(
+            deps,
+            &env,
+            address,
+            amount,
+            unbonding_period,
+        )?),
         QueryMsg::AnnualizedRewards {} => to_json_binary(&query_annualized_rewards(deps, env)?),
+        QueryMsg::RewardRate { asset } => to_json_binary(&query_reward_rate(deps, env, asset)?),
+        QueryMsg::EmissionRate {} => to_json_binary(&query_emission_rate(deps, env)?),
         QueryMsg::BondingInfo {} => to_json_binary(&query_bonding_info(deps)?),
         QueryMsg::AllStaked { address } => to_json_binary(&query_all_staked(deps, env, address)?),
+        QueryMsg::AverageLock { address } => to_json_binary(&query_average_lock(deps, address)?),
+        QueryMsg::TimeWeightedStake { address, since } => {
+            to_json_binary(&query_time_weighted_stake(deps, &env, address, since)?)
+        }
         QueryMsg::TotalStaked {} => to_json_binary(&query_total_staked(deps)?),
         QueryMsg::TotalUnbonding {} => to_json_binary(&query_total_unbonding(deps)?),
         QueryMsg::Admin {} => to_json_binary(&ADMIN.query_admin(deps)?),
         QueryMsg::TotalRewardsPower {} => to_json_binary(&query_total_rewards(deps)?),
         QueryMsg::RewardsPower { address } => to_json_binary(&query_rewards(deps, address)?),
+        QueryMsg::MarginalPower {
+            amount,
+            unbonding_period,
+            address,
+        } => to_json_binary(&query_marginal_power(
+            deps,
+            amount,
+            unbonding_period,
+            address,
+        )?),
         QueryMsg::WithdrawableRewards { owner } => {
-            to_json_binary(&query_withdrawable_rewards(deps, owner)?)
+            to_json_binary(&query_withdrawable_rewards(deps, env, owner)?)
+        }
+        QueryMsg::LifetimeRewards { address } => {
+            to_json_binary(&query_lifetime_rewards(deps, address)?)
         }
         QueryMsg::DistributedRewards {} => to_json_binary(&query_distributed_rewards(deps)?),
+        QueryMsg::TotalDistributed {} => to_json_binary(&query_total_distributed(deps)?),
         QueryMsg::UndistributedRewards {} => {
             to_json_binary(&query_undistributed_rewards(deps, env)?)
         }
@@ -956,6 +1528,16 @@ pub fn query(deps: Deps<CoreumQueries>, env: Env, msg: QueryMsg) -> StdResult<Bi
             to_json_binary(&query_withdraw_adjustment_data(deps, addr, asset)?)
         }
         QueryMsg::UnbondAll {} => to_json_binary(&query_unbond_all(deps)?),
+        QueryMsg::UnbondAllPreview {} => to_json_binary(&query_unbond_all_preview(deps)?),
+        QueryMsg::BalanceReconciliation {} => {
+            to_json_binary(&query_balance_reconciliation(deps, env)?)
+        }
+        QueryMsg::AllClaims { start_after, limit } => {
+            to_json_binary(&query_all_claims(deps, start_after, limit)?)
+        }
+        QueryMsg::UnbondingSchedule { address } => {
+            to_json_binary(&query_unbonding_schedule(deps, address)?)
+        }
     }
 }
 
@@ -978,8 +1560,7 @@ fn query_annualized_rewards(
 
     // reward info for each distribution flow... do all the heavy calcs per distribution once.
     // we can then just read this for each unbonding period
-    let distributions = DISTRIBUTION
-        .range(deps.storage, None, None, Order::Ascending)
+    let distributions = active_distributions(deps.storage)
         .map(|r| {
             let (asset, d) = r?;
             let total_rewards = d.total_rewards_power(deps.storage, &config);
@@ -1034,6 +1615,8 @@ fn query_annualized_rewards(
 
 fn calculate_annualized_payout(reward_curve: Option<Curve>, now: u64) -> Decimal {
     match reward_curve {
+        // distributions that are not paying out yet do not count
+        Some(c) if c.decline_rate(now).is_zero() => Decimal::zero(),
         Some(c) => {
             // look at the last timestamp in the rewards curve and extrapolate
             match c.end() {
@@ -1076,10 +1659,77 @@ fn calculate_annualized_payout(reward_curve: Option<Curve>, now: u64) -> Decimal
     }
 }
 
+fn query_reward_rate(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+    asset: AssetInfo,
+) -> StdResult<RewardRateResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let asset = asset.validate(deps.api)?;
+    let distribution = DISTRIBUTION.load(deps.storage, &asset)?;
+
+    Ok(RewardRateResponse {
+        per_power_per_second: reward_rate(
+            deps.storage,
+            &config,
+            env.block.time.seconds(),
+            &asset,
+            &distribution,
+        )?,
+    })
+}
+
+/// Returns how many tokens of the given asset each unit of rewards power earns per second at time `now`.
+fn reward_rate(
+    storage: &dyn Storage,
+    config: &Config,
+    now: u64,
+    asset: &AssetInfoValidated,
+    distribution: &Distribution,
+) -> StdResult<Decimal> {
+    let total_rewards = distribution.total_rewards_power(storage, config);
+    // only the part of the curve that is paying out right now counts,
+    // distributions scheduled for later are not included
+    let per_second = REWARD_CURVE
+        .may_load(storage, asset)?
+        .map(|c| c.decline_rate(now))
+        .unwrap_or_default();
+
+    Ok(if total_rewards.is_zero() {
+        Decimal::zero()
+    } else {
+        per_second / Decimal::from_ratio(total_rewards, 1u128)
+    })
+}
+
+/// Returns the current per-second emission of every asset whose funding curves are still paying out,
+/// as well as the assets whose funding only starts paying out in the future.
+fn query_emission_rate(deps: Deps<CoreumQueries>, env: Env) -> StdResult<EmissionRateResponse> {
+    let now = env.block.time.seconds();
+
+    let mut rates = vec![];
+    let mut scheduled = vec![];
+    for item in REWARD_CURVE.range(deps.storage, None, None, Order::Ascending) {
+        // all fundings of an asset are combined into a single curve
+        let (info, curve) = item?;
+        let per_second = curve.decline_rate(now);
+        if !per_second.is_zero() {
+            rates.push(AssetEmissionRate { info, per_second });
+        } else if let Some(start_time) = curve.next_decline(now) {
+            scheduled.push(ScheduledEmission {
+                info,
+                start_time,
+                per_second: curve.decline_rate(start_time),
+            });
+        }
+    }
+
+    Ok(EmissionRateResponse { rates, scheduled })
+}
+
 fn query_rewards(deps: Deps<CoreumQueries>, addr: String) -> StdResult<RewardsPowerResponse> {
     let addr = deps.api.addr_validate(&addr)?;
-    let rewards = DISTRIBUTION
-        .range(deps.storage, None, None, Order::Ascending)
+    let rewards = active_distributions(deps.storage)
         .map(|dist| {
             let (asset_info, distribution) = dist?;
             let cfg = CONFIG.load(deps.storage)?;
@@ -1096,8 +1746,7 @@ fn query_rewards(deps: Deps<CoreumQueries>, addr: String) -> StdResult<RewardsPo
 
 fn query_total_rewards(deps: Deps<CoreumQueries>) -> StdResult<RewardsPowerResponse> {
     Ok(RewardsPowerResponse {
-        rewards: DISTRIBUTION
-            .range(deps.storage, None, None, Order::Ascending)
+        rewards: active_distributions(deps.storage)
             .map(|distr| {
                 let (asset_info, distribution) = distr?;
 
@@ -1111,6 +1760,45 @@ fn query_total_rewards(deps: Deps<CoreumQueries>) -> StdResult<RewardsPowerRespo
     })
 }
 
+fn query_marginal_power(
+    deps: Deps<CoreumQueries>,
+    amount: Uint128,
+    unbonding_period: u64,
+    address: Option<String>,
+) -> StdResult<RewardsPowerResponse> {
+    // sanity check if such unbonding period exists
+    let totals = TOTAL_PER_PERIOD.load(deps.storage)?;
+    totals
+        .binary_search_by_key(&unbonding_period, |&(entry, _)| entry)
+        .map_err(|_| {
+            StdError::generic_err(format!("No unbonding period found: {}", unbonding_period))
+        })?;
+
+    let current_stake = match maybe_addr(deps.api, address)? {
+        Some(addr) => STAKE
+            .may_load(deps.storage, (&addr, unbonding_period))?
+            .unwrap_or_default()
+            .total_stake(),
+        None => Uint128::zero(),
+    };
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let rewards = active_distributions(deps.storage)
+        .map(|distr| {
+            let (asset_info, distribution) = distr?;
+            let multiplier = distribution
+                .rewards_multiplier(unbonding_period)
+                .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+            let old_power = calc_power(&cfg, current_stake, multiplier);
+            let new_power = calc_power(&cfg, current_stake + amount, multiplier);
+            Ok((asset_info, new_power - old_power))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RewardsPowerResponse { rewards })
+}
+
 fn query_bonding_info(deps: Deps<CoreumQueries>) -> StdResult<BondingInfoResponse> {
     let total_stakes = TOTAL_PER_PERIOD.load(deps.storage)?;
 
@@ -1153,6 +1841,132 @@ pub fn query_staked(
     })
 }
 
+pub fn query_unbondable_amount(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    addr: String,
+    unbonding_period: u64,
+) -> StdResult<UnbondableAmountResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    // sanity check if such unbonding period exists
+    let totals = TOTAL_PER_PERIOD.load(deps.storage)?;
+    totals
+        .binary_search_by_key(&unbonding_period, |&(entry, _)| entry)
+        .map_err(|_| {
+            StdError::generic_err(format!("No unbonding period found: {}", unbonding_period))
+        })?;
+
+    let stake = STAKE
+        .may_load(deps.storage, (&addr, unbonding_period))?
+        .unwrap_or_default();
+    Ok(UnbondableAmountResponse {
+        amount: stake.total_unlocked(env),
+        unbonding_period,
+    })
+}
+
+/// Checks whether `addr` could unbond `amount` tokens from the given unbonding period right now.
+/// The unbond all flag does not prevent unbonding, it only skips the unbonding period.
+pub fn query_can_unbond(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    addr: String,
+    amount: Uint128,
+    unbonding_period: u64,
+) -> StdResult<CanUnbondResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let stake = STAKE
+        .may_load(deps.storage, (&addr, unbonding_period))?
+        .unwrap_or_default();
+    let staked = stake.total_stake();
+    let unlocked = stake.total_unlocked(env);
+
+    let reason = if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        Some(ContractError::NoUnbondingPeriodFound(unbonding_period).to_string())
+    } else if amount > staked {
+        Some(format!(
+            "Insufficient stake: {} staked, {} requested",
+            staked, amount
+        ))
+    } else if amount > unlocked {
+        Some(format!(
+            "Stake is locked: only {} of {} staked tokens can be unbonded now",
+            unlocked, staked
+        ))
+    } else {
+        None
+    };
+
+    Ok(CanUnbondResponse {
+        allowed: reason.is_none(),
+        reason,
+    })
+}
+
+/// Returns the rewards power that unbonding `amount` from the given unbonding period would remove,
+/// as well as the rewards that power would earn during the unbonding period at the current reward rates.
+pub fn query_unbond_reward_impact(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    addr: String,
+    amount: Uint128,
+    unbonding_period: u64,
+) -> StdResult<UnbondRewardImpactResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        return Err(StdError::generic_err(
+            ContractError::NoUnbondingPeriodFound(unbonding_period).to_string(),
+        ));
+    }
+
+    let staked = STAKE
+        .may_load(deps.storage, (&addr, unbonding_period))?
+        .unwrap_or_default()
+        .total_stake();
+    if amount > staked {
+        return Err(StdError::generic_err(format!(
+            "Insufficient stake: {} staked, {} requested",
+            staked, amount
+        )));
+    }
+
+    let now = env.block.time.seconds();
+    let mut power_reduction = vec![];
+    let mut rewards_foregone = vec![];
+    for item in active_distributions(deps.storage) {
+        let (asset_info, distribution) = item?;
+        let multiplier = distribution
+            .rewards_multiplier(unbonding_period)
+            .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+        let reduction =
+            calc_power(&cfg, staked, multiplier) - calc_power(&cfg, staked - amount, multiplier);
+        let per_power_per_second =
+            reward_rate(deps.storage, &cfg, now, &asset_info, &distribution)?;
+        let foregone =
+            reduction.checked_mul(Uint128::from(unbonding_period))? * per_power_per_second;
+
+        rewards_foregone.push(asset_info.with_balance(foregone));
+        power_reduction.push((asset_info, reduction));
+    }
+
+    Ok(UnbondRewardImpactResponse {
+        power_reduction,
+        rewards_foregone,
+    })
+}
+
 pub fn query_all_staked(
     deps: Deps<CoreumQueries>,
     env: Env,
@@ -1180,6 +1994,53 @@ pub fn query_all_staked(
     Ok(AllStakedResponse { stakes })
 }
 
+/// Returns the stake-weighted average unbonding period of the given address.
+pub fn query_average_lock(deps: Deps<CoreumQueries>, addr: String) -> StdResult<u64> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut total_stake = Uint128::zero();
+    let mut weighted_periods = Uint256::zero();
+    for up in config.unbonding_periods {
+        if let Some(stake) = STAKE.may_load(deps.storage, (&addr, up))? {
+            let stake = stake.total_stake();
+            total_stake += stake;
+            weighted_periods += stake.full_mul(up);
+        }
+    }
+
+    if total_stake.is_zero() {
+        return Ok(0);
+    }
+    // the average is at most the longest unbonding period, so it always fits into a u64
+    let average = Uint128::try_from(weighted_periods / Uint256::from(total_stake))?;
+    Ok(average.u128() as u64)
+}
+
+/// Returns the average total stake of the given address from `since` (in seconds) until now.
+pub fn query_time_weighted_stake(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    addr: String,
+    since: u64,
+) -> StdResult<Uint128> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let now = env.block.time.seconds();
+    if since > now {
+        return Err(StdError::generic_err("`since` cannot be in the future"));
+    }
+
+    let (stake, cumulative) = cumulative_stake_at(deps.storage, &addr, now)?;
+    if since == now {
+        return Ok(stake);
+    }
+    let (_, cumulative_since) = cumulative_stake_at(deps.storage, &addr, since)?;
+    // the average is at most the highest stake in the period, so it always fits into a Uint128
+    Ok(Uint128::try_from(
+        (cumulative - cumulative_since) / Uint256::from(now - since),
+    )?)
+}
+
 pub fn query_total_staked(deps: Deps<CoreumQueries>) -> StdResult<TotalStakedResponse> {
     Ok(TotalStakedResponse {
         total_staked: TOTAL_STAKED.load(deps.storage).unwrap_or_default().staked,
@@ -1201,11 +2062,98 @@ pub fn query_unbond_all(deps: Deps<CoreumQueries>) -> StdResult<UnbondAllRespons
     })
 }
 
+pub fn query_unbond_all_preview(deps: Deps<CoreumQueries>) -> StdResult<UnbondAllPreviewResponse> {
+    let total = TOTAL_STAKED.load(deps.storage).unwrap_or_default();
+    let halted_distributions = active_distributions(deps.storage)
+        .map(|item| item.map(|(asset, _)| asset))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(UnbondAllPreviewResponse {
+        already_set: UNBOND_ALL.load(deps.storage)?,
+        releasable: total.staked,
+        unbonding: total.unbonding,
+        halted_distributions,
+    })
+}
+
+pub fn query_balance_reconciliation(
+    deps: Deps<CoreumQueries>,
+    env: Env,
+) -> StdResult<BalanceReconciliationResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let held_lp = deps
+        .querier
+        .query_balance(env.contract.address, cfg.lp_share_denom)?
+        .amount;
+    let total = TOTAL_STAKED.load(deps.storage)?;
+
+    Ok(BalanceReconciliationResponse {
+        held_lp,
+        tracked_staked: total.staked,
+        in_claims: total.unbonding,
+        matches: held_lp == total.total(),
+    })
+}
+
+/// Returns the open claims of all addresses, ordered by address and paginated.
+/// Addresses whose claims were all released or cancelled are skipped.
+pub fn query_all_claims(
+    deps: Deps<CoreumQueries>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllClaimsResponse> {
+    let start_after = maybe_addr(deps.api, start_after)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let claims = CLAIMS_MAP
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| !matches!(item, Ok((_, claims)) if claims.is_empty()))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllClaimsResponse { claims })
+}
+
+/// Returns the open claims of the given address ordered by release time,
+/// with the running total of tokens released up to each claim.
+pub fn query_unbonding_schedule(
+    deps: Deps<CoreumQueries>,
+    address: String,
+) -> StdResult<Vec<UnbondingScheduleEntry>> {
+    let address = deps.api.addr_validate(&address)?;
+    let mut claims = CLAIMS.query_claims(deps, &address)?.claims;
+    // claims are stored in the order they were created, which differs from the release order
+    // if different unbonding periods were used
+    claims.sort_by(|a, b| {
+        a.release_at
+            .partial_cmp(&b.release_at)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut cumulative = Uint128::zero();
+    Ok(claims
+        .into_iter()
+        .map(|claim| {
+            cumulative += claim.amount;
+            UnbondingScheduleEntry {
+                release_at: claim.release_at,
+                amount: claim.amount,
+                cumulative,
+            }
+        })
+        .collect())
+}
+
 /// Manages the contract migration.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut<CoreumQueries>,
-    _env: Env,
+    env: Env,
     msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
     ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -1218,6 +2166,21 @@ pub fn migrate(
     // set unbond all flag
     UNBOND_ALL.save(deps.storage, &msg.unbond_all)?;
 
+    // start the lifetime totals with what the existing distributions already distributed
+    let distributions = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (asset_info, distribution) in distributions {
+        if !TOTAL_DISTRIBUTED.has(deps.storage, &asset_info) {
+            TOTAL_DISTRIBUTED.save(deps.storage, &asset_info, &distribution.distributed_total)?;
+        }
+    }
+
+    // stakers from before the stake checkpoints are seeded on their next stake change
+    if !STAKE_CHECKPOINTS_START.exists(deps.storage) {
+        STAKE_CHECKPOINTS_START.save(deps.storage, &env.block.time.seconds())?;
+    }
+
     Ok(Response::new())
 }
 
@@ -1233,7 +2196,7 @@ mod tests {
 
     use crate::error::ContractError;
     use crate::msg::{DistributionDataResponse, WithdrawAdjustmentDataResponse};
-    use crate::state::{Distribution, WithdrawAdjustment};
+    use crate::state::{Distribution, WithdrawAdjustment, STAKE_CHECKPOINTS};
 
     use super::*;
 
@@ -1288,6 +2251,10 @@ mod tests {
             admin: Some(INIT_ADMIN.into()),
             max_distributions: 6,
             unbonder: None,
+            backup_unbonders: vec![],
+            unbond_grace_period: 0,
+            min_distribution_interval: 0,
+            auto_claim_on_delegate: false,
         };
         let info = mock_info("creator", &[]);
         instantiate(deps, env, info, msg).unwrap();
@@ -1306,7 +2273,10 @@ mod tests {
 
         for (addr, stake) in &[(USER1, user1), (USER2, user2), (USER3, user3)] {
             if *stake != 0 {
-                let msg = ExecuteMsg::Delegate { unbonding_period };
+                let msg = ExecuteMsg::Delegate {
+                    unbonding_period,
+                    delegate_as: None,
+                };
                 let info = mock_info(addr, &[coin(*stake, SMART_TOKEN_DENOM)]);
                 execute(deps.branch(), env.clone(), info, msg).unwrap();
             }
@@ -1396,6 +2366,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::percent(1))],
+            false,
         )
         .unwrap();
 
@@ -1413,6 +2384,11 @@ mod tests {
                     withdrawable_total: Uint128::zero(),
                     manager: Addr::unchecked(INIT_ADMIN),
                     reward_multipliers: vec![(UNBONDING_PERIOD, Decimal::percent(1))],
+                    rewards_locked_until_unbond: false,
+                    remainder: Uint128::zero(),
+                    last_distributed: 0,
+                    removed: false,
+                    generation: 0,
                 }
             )]
         );
@@ -1432,6 +2408,7 @@ mod tests {
             WithdrawAdjustment {
                 shares_correction: 0,
                 withdrawn_rewards: Uint128::zero(),
+                generation: 0,
             }
         );
     }
@@ -1555,7 +2532,7 @@ mod tests {
             deps.as_mut(),
             env.clone(),
             mock_info(USER1, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { receiver: None },
         )
         .unwrap();
         assert_eq!(res.messages.len(), 1);
@@ -1616,7 +2593,7 @@ mod tests {
             deps.as_mut(),
             env2,
             mock_info(USER1, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { receiver: None },
         )
         .unwrap_err();
         assert_eq!(err, ContractError::NothingToClaim {});
@@ -1629,7 +2606,7 @@ mod tests {
             deps.as_mut(),
             env3.clone(),
             mock_info(USER1, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { receiver: None },
         )
         .unwrap();
         assert_st_undelegate(res, USER1, 4_500);
@@ -1639,7 +2616,7 @@ mod tests {
             deps.as_mut(),
             env3.clone(),
             mock_info(USER2, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { receiver: None },
         )
         .unwrap();
         assert_st_undelegate(res, USER2, 2_600);
@@ -1649,7 +2626,7 @@ mod tests {
             deps.as_mut(),
             env3,
             mock_info(USER3, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { receiver: None },
         )
         .unwrap_err();
         assert_eq!(err, ContractError::NothingToClaim {});
@@ -1676,7 +2653,7 @@ mod tests {
             deps.as_mut(),
             env4,
             mock_info(USER2, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { receiver: None },
         )
         .unwrap();
         assert_st_undelegate(res, USER2, 2_950); // 1_345 + 600 + 1_005
@@ -1706,6 +2683,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::percent(1))],
+            false,
         )
         .unwrap();
 
@@ -1777,6 +2755,7 @@ mod tests {
                 (UNBONDING_PERIOD, Decimal::percent(1)),
                 (UNBONDING_PERIOD_2, Decimal::percent(10)),
             ],
+            false,
         )
         .unwrap();
 
@@ -1871,6 +2850,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unbondable_amount_excludes_locked_tokens() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        st_instantiate(
+            deps.as_mut(),
+            env.clone(),
+            TOKENS_PER_POWER,
+            MIN_BOND,
+            vec![UNBONDING_PERIOD, UNBONDING_PERIOD_2],
+        );
+
+        let unbondable = |deps: Deps<CoreumQueries>, env: &Env, unbonding_period| {
+            query_unbondable_amount(deps, env, USER1.to_string(), unbonding_period)
+                .unwrap()
+                .amount
+                .u128()
+        };
+
+        bond_with_period(deps.as_mut(), 10_000, 0, 0, UNBONDING_PERIOD, 0);
+        bond_with_period(deps.as_mut(), 6_000, 0, 0, UNBONDING_PERIOD_2, 0);
+        // lock some more tokens for the difference of the periods
+        STAKE
+            .update(
+                &mut deps.storage,
+                (&Addr::unchecked(USER1), UNBONDING_PERIOD),
+                |bonding_info| -> StdResult<_> {
+                    let mut bonding_info = bonding_info.unwrap_or_default();
+                    bonding_info.add_locked_tokens(
+                        env.block
+                            .time
+                            .plus_seconds(UNBONDING_PERIOD_2 - UNBONDING_PERIOD),
+                        Uint128::new(4_000),
+                    );
+                    Ok(bonding_info)
+                },
+            )
+            .unwrap();
+
+        assert_eq!(unbondable(deps.as_ref(), &env, UNBONDING_PERIOD), 10_000);
+        assert_eq!(unbondable(deps.as_ref(), &env, UNBONDING_PERIOD_2), 6_000);
+        // nobody else has anything to unbond
+        assert_eq!(
+            query_unbondable_amount(deps.as_ref(), &env, USER2.to_string(), UNBONDING_PERIOD)
+                .unwrap()
+                .amount,
+            Uint128::zero()
+        );
+
+        // unbonding more than the reported amount fails
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::Unbond {
+                tokens: Uint128::new(10_001),
+                unbonding_period: UNBONDING_PERIOD,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // once the lock expires, the full stake can be unbonded
+        let mut later = env.clone();
+        later.block.time = later
+            .block
+            .time
+            .plus_seconds(UNBONDING_PERIOD_2 - UNBONDING_PERIOD);
+        assert_eq!(unbondable(deps.as_ref(), &later, UNBONDING_PERIOD), 14_000);
+
+        // unknown unbonding periods are rejected
+        query_unbondable_amount(deps.as_ref(), &env, USER1.to_string(), 1).unwrap_err();
+    }
+
     #[test]
     fn ensure_bonding_edge_cases() {
         // use min_bond 0, tokens_per_power 500
@@ -1922,6 +2975,7 @@ mod tests {
                 INIT_ADMIN.to_string(),
                 native_asset_info(denom),
                 vec![(UNBONDING_PERIOD, Decimal::one())],
+                false,
             )
             .unwrap();
         }
@@ -1932,6 +2986,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::one())],
+            false,
         )
         .unwrap_err();
         assert_eq!(err, ContractError::TooManyDistributions(6));
@@ -1949,6 +3004,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::one())],
+            false,
         )
         .unwrap();
 
@@ -1959,6 +3015,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::one())],
+            false,
         )
         .unwrap_err();
 
@@ -1982,6 +3039,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::one())],
+            false,
         )
         .unwrap();
 
@@ -2013,6 +3071,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             token_asset_info(SMART_TOKEN_DENOM),
             vec![(UNBONDING_PERIOD, Decimal::one())],
+            false,
         )
         .unwrap_err();
 
@@ -2031,6 +3090,7 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD, Decimal::one())],
+            false,
         )
         .unwrap();
         let err = execute_fund_distribution(
@@ -2066,8 +3126,52 @@ mod tests {
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
             vec![(UNBONDING_PERIOD + 1, Decimal::one())],
+            false,
         )
         .unwrap_err();
         assert_eq!(err, ContractError::InvalidRewards {});
     }
+
+    #[test]
+    fn migrate_seeds_stake_checkpoints_lazily() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut(), mock_env());
+        bond(deps.as_mut(), 12_000, 7_500, 0, 0);
+
+        // user1 staked before the stake checkpoints were introduced
+        let start = mock_env().block.time.seconds();
+        STAKE_CHECKPOINTS.remove(&mut deps.storage, (&Addr::unchecked(USER1), start));
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.0.0").unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                unbonder: None,
+                unbond_all: false,
+            },
+        )
+        .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(100);
+        // the stake from before the migration is only counted from the migration on
+        assert_eq!(
+            query_time_weighted_stake(deps.as_ref(), &env, USER1.to_owned(), start).unwrap(),
+            Uint128::new(6_000)
+        );
+        // existing checkpoints are kept
+        assert_eq!(
+            query_time_weighted_stake(deps.as_ref(), &env, USER2.to_owned(), start).unwrap(),
+            Uint128::new(7_500)
+        );
+
+        // the first stake change keeps the stake held since the migration
+        bond(deps.as_mut(), 12_000, 0, 0, 200);
+        env.block.time = env.block.time.plus_seconds(100);
+        assert_eq!(
+            query_time_weighted_stake(deps.as_ref(), &env, USER1.to_owned(), start + 100).unwrap(),
+            Uint128::new(18_000)
+        );
+    }
 }
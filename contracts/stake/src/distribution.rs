@@ -1,20 +1,26 @@
 use std::collections::HashSet;
 
 use coreum_wasm_sdk::core::CoreumQueries;
-use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, StdResult, Storage, Uint128};
-use dex::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated};
+use cosmwasm_std::{
+    Addr, Decimal, Deps, DepsMut, Env, MessageInfo, Order, StdResult, Storage, Uint128,
+};
+use dex::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated};
+use dex::stake::UnbondingPeriod;
 
 use crate::{
     contract::Response,
     error::ContractError,
     msg::{
         DelegatedResponse, DistributedRewardsResponse, DistributionDataResponse,
-        UndistributedRewardsResponse, WithdrawAdjustmentDataResponse, WithdrawableRewardsResponse,
+        LifetimeRewardsResponse, UndistributedRewardsResponse, WithdrawAdjustmentDataResponse,
+        WithdrawableRewardsResponse,
     },
     state::{
-        Config, Distribution, WithdrawAdjustment, CONFIG, DELEGATED, DISTRIBUTION, REWARD_CURVE,
-        SHARES_SHIFT, UNBOND_ALL, WITHDRAW_ADJUSTMENT,
+        Config, Distribution, LockedRewards, WithdrawAdjustment, AUTHORIZED_WITHDRAWERS, CONFIG,
+        DELEGATED, DISTRIBUTION, LAST_DELEGATION, LIFETIME_REWARDS, LOCKED_REWARDS, REWARD_CURVE,
+        SHARES_SHIFT, STAKE, TOTAL_DISTRIBUTED, UNBOND_ALL, WITHDRAW_ADJUSTMENT,
     },
+    utils::calc_power,
 };
 
 pub fn execute_distribute_rewards(
@@ -34,9 +40,8 @@ pub fn execute_distribute_rewards(
         .transpose()?
         .unwrap_or(info.sender);
 
-    let distributions = DISTRIBUTION
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?;
+    // removed distributions do not receive any new rewards
+    let distributions = active_distributions(deps.storage).collect::<StdResult<Vec<_>>>()?;
 
     // do not accept unsupported funds
     // we can only check the ones that were sent with the message (so only native assets)
@@ -65,6 +70,7 @@ pub fn execute_distribute_rewards(
         }
 
         let withdrawable: u128 = distribution.withdrawable_total.into();
+        let remainder: u128 = distribution.remainder.into();
 
         // Query current reward balance
         let balance =
@@ -74,27 +80,46 @@ pub fn execute_distribute_rewards(
 
         // Calculate how much we have received since the last time Distributed was called,
         // including only the reward config amount that is eligible for distribution.
-        // This is the amount we will distribute to all members.
-        let amount = balance - withdrawable - curve.value(env.block.time.seconds()).u128();
+        // The remainder of previous distributions is already part of the leftover shares.
+        // If the balance does not cover all of it, there is nothing new to distribute.
+        let amount = balance
+            .checked_sub(withdrawable)
+            .and_then(|amount| amount.checked_sub(remainder))
+            .and_then(|amount| amount.checked_sub(curve.value(env.block.time.seconds()).u128()))
+            .unwrap_or_default();
 
         if amount == 0 {
             continue;
         }
 
+        let now = env.block.time.seconds();
+        if now < distribution.last_distributed + cfg.min_distribution_interval {
+            return Err(ContractError::DistributionTooSoon {});
+        }
+        distribution.last_distributed = now;
+
         let leftover: u128 = distribution.shares_leftover.into();
         let points = (amount << SHARES_SHIFT) + leftover;
         let points_per_share = points / total_rewards.u128();
-        distribution.shares_leftover = (points % total_rewards.u128()) as u64;
+        let leftover = points % total_rewards.u128();
+        distribution.shares_leftover = leftover as u64;
+
+        // Whole tokens still contained in the leftover are not assigned to anyone yet,
+        // so they are kept as remainder until a later distribution pays them out.
+        // This way `distributed_total + remainder` always matches the funded amount.
+        let new_remainder = leftover >> SHARES_SHIFT;
+        let distributed = amount + remainder - new_remainder;
 
         // Everything goes back to 128-bits/16-bytes
-        // Full amount is added here to total withdrawable, as it should not be considered on its own
-        // on future distributions - even if because of calculation offsets it is not fully
-        // distributed, the error is handled by leftover.
         distribution.shares_per_point += Uint128::new(points_per_share);
-        distribution.distributed_total += Uint128::new(amount);
-        distribution.withdrawable_total += Uint128::new(amount);
+        distribution.distributed_total += Uint128::new(distributed);
+        distribution.withdrawable_total += Uint128::new(distributed);
+        distribution.remainder = Uint128::new(new_remainder);
 
         DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+        TOTAL_DISTRIBUTED.update(deps.storage, &asset_info, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default() + Uint128::new(distributed))
+        })?;
 
         resp = resp.add_attribute(format!("amount_{}", asset_info), amount.to_string());
     }
@@ -102,6 +127,17 @@ pub fn execute_distribute_rewards(
     Ok(resp)
 }
 
+/// Iterates over all distributions that were not removed.
+/// Removed distributions are only kept until their rewards are withdrawn and do not take part in
+/// distributing rewards or in the rewards power.
+pub fn active_distributions(
+    storage: &dyn Storage,
+) -> impl Iterator<Item = StdResult<(AssetInfoValidated, Distribution)>> + '_ {
+    DISTRIBUTION
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, distribution)) if distribution.removed))
+}
+
 /// Query current reward balance of the given asset.
 /// Make sure not to call this for the staking token
 fn undistributed_rewards(
@@ -114,6 +150,7 @@ fn undistributed_rewards(
 
 pub fn execute_withdraw_rewards(
     deps: DepsMut<CoreumQueries>,
+    env: Env,
     info: MessageInfo,
     owner: Option<String>,
     receiver: Option<String>,
@@ -124,8 +161,22 @@ pub fn execute_withdraw_rewards(
     )?;
     let receiver = receiver
         .map(|receiver| deps.api.addr_validate(&receiver))
-        .transpose()?
-        .unwrap_or_else(|| info.sender.clone());
+        .transpose()?;
+
+    let delegated = DELEGATED
+        .may_load(deps.storage, &owner)?
+        .unwrap_or_else(|| owner.clone());
+    let receiver = if [&owner, &delegated].contains(&&info.sender) {
+        receiver.unwrap_or_else(|| info.sender.clone())
+    } else if AUTHORIZED_WITHDRAWERS.has(deps.storage, (&owner, &info.sender)) {
+        // authorized withdrawers can only trigger the withdrawal, the rewards go to the owner
+        match receiver {
+            Some(receiver) if receiver != owner => return Err(ContractError::Unauthorized {}),
+            _ => owner.clone(),
+        }
+    } else {
+        return Err(ContractError::Unauthorized {});
+    };
 
     let mut resp = Response::new()
         .add_attribute("action", "withdraw_rewards")
@@ -133,43 +184,111 @@ pub fn execute_withdraw_rewards(
         .add_attribute("owner", owner.as_str())
         .add_attribute("receiver", receiver.as_str());
 
-    let distributions = DISTRIBUTION
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+    for reward in take_withdrawable_rewards(deps, &env, &owner)? {
+        resp = resp
+            .add_attribute(format!("reward_{}", reward.info), reward.amount)
+            .add_message(reward.into_msg(receiver.clone())?);
+    }
+
+    Ok(resp)
+}
+
+/// Withdraws all rewards of the sender and splits them between the given receivers according to
+/// their fractions. The last receiver gets whatever is left after rounding, so no dust stays
+/// behind.
+pub fn execute_withdraw_rewards_split(
+    deps: DepsMut<CoreumQueries>,
+    env: Env,
+    info: MessageInfo,
+    splits: Vec<(String, Decimal)>,
+) -> Result<Response, ContractError> {
+    if splits.is_empty()
+        || splits.iter().any(|(_, fraction)| fraction.is_zero())
+        || splits.iter().map(|(_, fraction)| fraction).sum::<Decimal>() != Decimal::one()
+    {
+        return Err(ContractError::InvalidRewardSplits {});
+    }
+    let splits = splits
+        .into_iter()
+        .map(|(receiver, fraction)| Ok((deps.api.addr_validate(&receiver)?, fraction)))
         .collect::<StdResult<Vec<_>>>()?;
 
-    let delegated = DELEGATED
-        .may_load(deps.storage, &owner)?
-        .unwrap_or_else(|| owner.clone());
-    if ![&owner, &delegated].contains(&&info.sender) {
-        return Err(ContractError::Unauthorized {});
+    let mut resp = Response::new()
+        .add_attribute("action", "withdraw_rewards_split")
+        .add_attribute("sender", info.sender.as_str());
+
+    for reward in take_withdrawable_rewards(deps, &env, &info.sender)? {
+        resp = resp.add_attribute(format!("reward_{}", reward.info), reward.amount);
+
+        let mut remaining = reward.amount;
+        for (i, (receiver, fraction)) in splits.iter().enumerate() {
+            let share = if i == splits.len() - 1 {
+                remaining
+            } else {
+                reward.amount * *fraction
+            };
+            if share.is_zero() {
+                continue;
+            }
+            remaining -= share;
+            resp = resp.add_message(reward.info.with_balance(share).into_msg(receiver.clone())?);
+        }
     }
 
+    Ok(resp)
+}
+
+/// Marks all currently withdrawable rewards of `owner` as withdrawn and returns them.
+/// Rewards of distributions that are still locked for the owner are skipped.
+fn take_withdrawable_rewards(
+    deps: DepsMut<CoreumQueries>,
+    env: &Env,
+    owner: &Addr,
+) -> Result<Vec<AssetValidated>, ContractError> {
+    let distributions = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
     let cfg = CONFIG.load(deps.storage)?;
+    let mut rewards = vec![];
     for (asset_info, mut distribution) in distributions {
         // get adjustment data
-        let mut adjustment = WITHDRAW_ADJUSTMENT
-            .may_load(deps.storage, (&owner, &asset_info))?
-            .unwrap_or_default();
-
-        let reward = withdrawable_rewards(deps.as_ref(), &cfg, &owner, &distribution, &adjustment)?;
+        let mut adjustment =
+            load_withdraw_adjustment(deps.storage, owner, &asset_info, &distribution)?;
+
+        let reward = available_rewards(
+            deps.as_ref(),
+            env,
+            &cfg,
+            owner,
+            &asset_info,
+            &distribution,
+            &adjustment,
+        )?;
 
         if reward.is_zero() {
             // Just do nothing
             continue;
         }
         adjustment.withdrawn_rewards += reward;
-        WITHDRAW_ADJUSTMENT.save(deps.storage, (&owner, &asset_info), &adjustment)?;
+        WITHDRAW_ADJUSTMENT.save(deps.storage, (owner, &asset_info), &adjustment)?;
         distribution.withdrawable_total -= reward;
-        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
-        // send rewards to receiver
-        let msg = asset_info.with_balance(reward).into_msg(receiver.clone())?;
+        if distribution.removed && distribution.withdrawable_total.is_zero() {
+            // the last rewards of a removed distribution were withdrawn
+            DISTRIBUTION.remove(deps.storage, &asset_info);
+        } else {
+            DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+        }
+        LIFETIME_REWARDS.update(
+            deps.storage,
+            (owner, &asset_info),
+            |total| -> StdResult<_> { Ok(total.unwrap_or_default() + reward) },
+        )?;
 
-        resp = resp
-            .add_message(msg)
-            .add_attribute(format!("reward_{}", asset_info), reward);
+        rewards.push(asset_info.with_balance(reward));
     }
 
-    Ok(resp)
+    Ok(rewards)
 }
 
 pub fn execute_delegate_withdrawal(
@@ -188,8 +307,41 @@ pub fn execute_delegate_withdrawal(
     Ok(resp)
 }
 
+pub fn execute_authorize_withdrawer(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let addr = deps.api.addr_validate(&addr)?;
+
+    AUTHORIZED_WITHDRAWERS.save(deps.storage, (&info.sender, &addr), &())?;
+    let resp = Response::new()
+        .add_attribute("action", "authorize_withdrawer")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("withdrawer", &addr);
+
+    Ok(resp)
+}
+
+pub fn execute_revoke_withdrawer(
+    deps: DepsMut<CoreumQueries>,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let addr = deps.api.addr_validate(&addr)?;
+
+    AUTHORIZED_WITHDRAWERS.remove(deps.storage, (&info.sender, &addr));
+    let resp = Response::new()
+        .add_attribute("action", "revoke_withdrawer")
+        .add_attribute("sender", info.sender.as_str())
+        .add_attribute("withdrawer", &addr);
+
+    Ok(resp)
+}
+
 pub fn query_withdrawable_rewards(
     deps: Deps<CoreumQueries>,
+    env: Env,
     owner: String,
 ) -> StdResult<WithdrawableRewardsResponse> {
     // Not checking address, as if it is invalid it is guaranteed not to appear in maps, so
@@ -197,16 +349,22 @@ pub fn query_withdrawable_rewards(
     let owner = Addr::unchecked(owner);
 
     let cfg = CONFIG.load(deps.storage)?;
-    let distributions =
-        DISTRIBUTION.range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+    let distributions = DISTRIBUTION.range(deps.storage, None, None, Order::Ascending);
 
     let rewards = distributions
         .map(|distr| -> StdResult<_> {
             let (asset_info, distribution) = distr?;
-            let adjustment = WITHDRAW_ADJUSTMENT
-                .may_load(deps.storage, (&owner, &asset_info))?
-                .unwrap_or_default();
-            let rewards = withdrawable_rewards(deps, &cfg, &owner, &distribution, &adjustment)?;
+            let adjustment =
+                load_withdraw_adjustment(deps.storage, &owner, &asset_info, &distribution)?;
+            let rewards = available_rewards(
+                deps,
+                &env,
+                &cfg,
+                &owner,
+                &asset_info,
+                &distribution,
+                &adjustment,
+            )?;
 
             Ok(asset_info.with_balance(rewards))
         })
@@ -214,12 +372,39 @@ pub fn query_withdrawable_rewards(
     Ok(WithdrawableRewardsResponse { rewards })
 }
 
+pub fn query_lifetime_rewards(
+    deps: Deps<CoreumQueries>,
+    address: String,
+) -> StdResult<LifetimeRewardsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    let rewards = LIFETIME_REWARDS
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| -> StdResult<_> {
+            let (asset_info, amount) = item?;
+            Ok(asset_info.with_balance(amount))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(LifetimeRewardsResponse { rewards })
+}
+
+pub fn query_total_distributed(deps: Deps<CoreumQueries>) -> StdResult<Vec<AssetValidated>> {
+    TOTAL_DISTRIBUTED
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| -> StdResult<_> {
+            let (asset_info, amount) = item?;
+            Ok(asset_info.with_balance(amount))
+        })
+        .collect()
+}
+
 pub fn query_undistributed_rewards(
     deps: Deps<CoreumQueries>,
     env: Env,
 ) -> StdResult<UndistributedRewardsResponse> {
-    let distributions =
-        DISTRIBUTION.range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+    let distributions = active_distributions(deps.storage);
 
     let rewards = distributions
         .map(|distribution| -> StdResult<_> {
@@ -237,7 +422,7 @@ pub fn query_distributed_rewards(
     deps: Deps<CoreumQueries>,
 ) -> StdResult<DistributedRewardsResponse> {
     let distributions = DISTRIBUTION
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .range(deps.storage, None, None, Order::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
 
     Ok(DistributedRewardsResponse {
@@ -263,7 +448,7 @@ pub fn query_delegated(deps: Deps<CoreumQueries>, owner: String) -> StdResult<De
 pub fn query_distribution_data(deps: Deps<CoreumQueries>) -> StdResult<DistributionDataResponse> {
     Ok(DistributionDataResponse {
         distributions: DISTRIBUTION
-            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .range(deps.storage, None, None, Order::Ascending)
             .collect::<StdResult<Vec<_>>>()?,
     })
 }
@@ -277,33 +462,208 @@ pub fn query_withdraw_adjustment_data(
     let asset = asset.validate(deps.api)?;
     let adjust = WITHDRAW_ADJUSTMENT
         .may_load(deps.storage, (&addr, &asset))?
-        .unwrap_or(WithdrawAdjustmentDataResponse {
-            shares_correction: 0,
-            withdrawn_rewards: Uint128::zero(),
-        });
+        .unwrap_or_default();
     Ok(adjust)
 }
 
+/// Loads the withdraw adjustment of `owner` for the given distribution.
+/// Adjustments left over from an earlier, removed distribution of the same asset are ignored.
+pub fn load_withdraw_adjustment(
+    storage: &dyn Storage,
+    owner: &Addr,
+    asset_info: &AssetInfoValidated,
+    distribution: &Distribution,
+) -> StdResult<WithdrawAdjustment> {
+    Ok(WITHDRAW_ADJUSTMENT
+        .may_load(storage, (owner, asset_info))?
+        .filter(|adjustment| adjustment.generation == distribution.generation)
+        .unwrap_or_else(|| WithdrawAdjustment {
+            generation: distribution.generation,
+            ..WithdrawAdjustment::default()
+        }))
+}
+
 /// Applies points correction for given address.
-/// `shares_per_point` is current value from `SHARES_PER_POINT` - not loaded in function, to
-/// avoid multiple queries on bulk updates.
+/// `distribution` is passed in rather than loaded in the function, to avoid multiple queries
+/// on bulk updates.
 /// `diff` is the points change
 pub fn apply_points_correction(
     storage: &mut dyn Storage,
     addr: &Addr,
     asset_info: &AssetInfoValidated,
-    shares_per_point: u128,
+    distribution: &Distribution,
     diff: i128,
 ) -> StdResult<()> {
-    WITHDRAW_ADJUSTMENT.update(storage, (addr, asset_info), |old| -> StdResult<_> {
-        let mut old = old.unwrap_or_default();
-        let shares_correction: i128 = old.shares_correction;
-        old.shares_correction = shares_correction - shares_per_point as i128 * diff;
-        Ok(old)
-    })?;
+    let mut adjustment = load_withdraw_adjustment(storage, addr, asset_info, distribution)?;
+    adjustment.shares_correction -= distribution.shares_per_point.u128() as i128 * diff;
+    WITHDRAW_ADJUSTMENT.save(storage, (addr, asset_info), &adjustment)
+}
+
+/// Loads the locked rewards of `staker` in one unbonding period of the given distribution.
+/// Locked rewards left over from an earlier, removed distribution of the same asset are ignored.
+fn load_locked_rewards(
+    storage: &dyn Storage,
+    staker: &Addr,
+    asset_info: &AssetInfoValidated,
+    distribution: &Distribution,
+    unbonding_period: UnbondingPeriod,
+) -> StdResult<LockedRewards> {
+    Ok(LOCKED_REWARDS
+        .may_load(storage, (staker, asset_info, unbonding_period))?
+        .filter(|locked| locked.generation == distribution.generation)
+        .unwrap_or_else(|| LockedRewards {
+            generation: distribution.generation,
+            ..LockedRewards::default()
+        }))
+}
+
+/// Returns all rewards `staker` accrued with `stake` in one unbonding period, locked or not.
+fn period_rewards(
+    cfg: &Config,
+    distribution: &Distribution,
+    locked: &LockedRewards,
+    stake: Uint128,
+    multiplier: Decimal,
+) -> Uint128 {
+    let power = calc_power(cfg, stake, multiplier).u128();
+    let points = (distribution.shares_per_point.u128() * power) as i128 + locked.shares_correction;
+    Uint128::new(points as u128 >> SHARES_SHIFT)
+}
+
+/// Checks whether the stake of `staker` in the unbonding period was bonded for at least the full
+/// unbonding period since the staker last delegated into it.
+fn bonded_for_full_period(
+    storage: &dyn Storage,
+    env: &Env,
+    staker: &Addr,
+    unbonding_period: UnbondingPeriod,
+) -> StdResult<bool> {
+    Ok(LAST_DELEGATION
+        .may_load(storage, (staker, unbonding_period))?
+        .map_or(true, |last_delegation| {
+            last_delegation.plus_seconds(unbonding_period) <= env.block.time
+        }))
+}
+
+/// Updates the locked rewards of `staker` in all distributions with `rewards_locked_until_unbond`
+/// set after the stake in `unbonding_period` changed from `old_stake` to `new_stake`.
+/// Make sure to call this after every change of the staker's [`STAKE`], but before a new
+/// delegation updates [`LAST_DELEGATION`].
+///
+/// The rewards accrued so far are unlocked if the stake was bonded for the full unbonding period,
+/// so a new delegation does not lock them again. Otherwise, `released` is the part of the old stake
+/// that is unbonded, unlocking the same part of the locked rewards.
+#[allow(clippy::too_many_arguments)]
+pub fn update_locked_rewards(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    staker: &Addr,
+    unbonding_period: UnbondingPeriod,
+    old_stake: Uint128,
+    new_stake: Uint128,
+    released: Uint128,
+) -> StdResult<()> {
+    let distributions = DISTRIBUTION
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, distribution)| {
+                distribution.rewards_locked_until_unbond
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    if distributions.is_empty() {
+        return Ok(());
+    }
+    let matured = bonded_for_full_period(storage, env, staker, unbonding_period)?;
+
+    for (asset_info, distribution) in distributions {
+        let Ok(multiplier) = distribution.rewards_multiplier(unbonding_period) else {
+            continue;
+        };
+        let mut locked = load_locked_rewards(
+            storage,
+            staker,
+            &asset_info,
+            &distribution,
+            unbonding_period,
+        )?;
+
+        let accrued = period_rewards(cfg, &distribution, &locked, old_stake, multiplier);
+        if matured {
+            locked.unlocked = accrued;
+        } else if !released.is_zero() && !old_stake.is_zero() {
+            let still_locked = accrued.saturating_sub(locked.unlocked);
+            locked.unlocked += still_locked.multiply_ratio(released, old_stake);
+        }
+
+        // keep the accrued rewards the same after the stake change
+        let diff = calc_power(cfg, new_stake, multiplier).u128() as i128
+            - calc_power(cfg, old_stake, multiplier).u128() as i128;
+        locked.shares_correction -= distribution.shares_per_point.u128() as i128 * diff;
+
+        LOCKED_REWARDS.save(storage, (staker, &asset_info, unbonding_period), &locked)?;
+    }
     Ok(())
 }
 
+/// Returns the rewards of a distribution with `rewards_locked_until_unbond` set that `staker` can
+/// withdraw. These are the rewards of the unbonding periods whose stake was bonded for the full
+/// unbonding period, and the rewards that were unlocked before, minus what was already withdrawn.
+pub fn unlocked_rewards(
+    storage: &dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    staker: &Addr,
+    asset_info: &AssetInfoValidated,
+    distribution: &Distribution,
+    adjustment: &WithdrawAdjustment,
+) -> StdResult<Uint128> {
+    let mut unlocked = Uint128::zero();
+    for &(unbonding_period, multiplier) in &distribution.reward_multipliers {
+        let locked =
+            load_locked_rewards(storage, staker, asset_info, distribution, unbonding_period)?;
+        unlocked += if bonded_for_full_period(storage, env, staker, unbonding_period)? {
+            let stake = STAKE
+                .may_load(storage, (staker, unbonding_period))?
+                .unwrap_or_default()
+                .total_stake();
+            period_rewards(cfg, distribution, &locked, stake, multiplier)
+        } else {
+            locked.unlocked
+        };
+    }
+    Ok(unlocked.saturating_sub(adjustment.withdrawn_rewards))
+}
+
+/// Returns the rewards `owner` can withdraw from the distribution right now.
+/// Rewards of distributions with `rewards_locked_until_unbond` set are only included once unlocked.
+fn available_rewards(
+    deps: Deps<CoreumQueries>,
+    env: &Env,
+    cfg: &Config,
+    owner: &Addr,
+    asset_info: &AssetInfoValidated,
+    distribution: &Distribution,
+    adjustment: &WithdrawAdjustment,
+) -> StdResult<Uint128> {
+    let reward = withdrawable_rewards(deps, cfg, owner, distribution, adjustment)?;
+    if !distribution.rewards_locked_until_unbond {
+        return Ok(reward);
+    }
+    // locked rewards stay assigned to the owner until the stake is bonded long enough or unbonded
+    let unlocked = unlocked_rewards(
+        deps.storage,
+        env,
+        cfg,
+        owner,
+        asset_info,
+        distribution,
+        adjustment,
+    )?;
+    Ok(reward.min(unlocked))
+}
+
 /// This is customized for the use case of the contract
 /// Since asset is clear from the distribution, we just return the number
 pub fn withdrawable_rewards(
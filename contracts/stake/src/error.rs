@@ -1,4 +1,4 @@
-use cosmwasm_std::{Coin, OverflowError, StdError};
+use cosmwasm_std::{Coin, OverflowError, StdError, Timestamp};
 use thiserror::Error;
 
 use cw_controllers::{AdminError, HookError};
@@ -28,9 +28,24 @@ pub enum ContractError {
     #[error("Rebond amount is invalid")]
     NoRebondAmount {},
 
+    #[error("Slash amount must not be zero")]
+    NoSlashAmount {},
+
     #[error("No claims that can be released currently")]
     NothingToClaim {},
 
+    #[error("Cannot claim for more than {0} addresses at once")]
+    TooManyClaimAddresses(usize),
+
+    #[error("No claim releasing at {release_at} from unbonding period {unbonding_period}")]
+    ClaimNotFound {
+        release_at: Timestamp,
+        unbonding_period: u64,
+    },
+
+    #[error("The unbond grace period has passed, the claim can no longer be cancelled")]
+    UnbondNotCancellable {},
+
     #[error("Sender's smart token denom {got} does not match one from config {expected}")]
     DenomNotMatch { got: String, expected: String },
 
@@ -49,6 +64,14 @@ pub enum ContractError {
     #[error("There already is a distribution for {0}")]
     DistributionAlreadyExists(AssetInfoValidated),
 
+    #[error("There is no distribution for {0}")]
+    DistributionNotFound(AssetInfoValidated),
+
+    #[error(
+        "The removed distribution for {0} still has rewards to withdraw, the admin can sweep them"
+    )]
+    RemovedDistributionNotWithdrawn(AssetInfoValidated),
+
     #[error("Cannot distribute the staked token")]
     InvalidAsset {},
 
@@ -64,6 +87,12 @@ pub enum ContractError {
     #[error("Invalid distribution rewards")]
     InvalidRewards {},
 
+    #[error("Reward splits must be non-zero and sum up to one")]
+    InvalidRewardSplits {},
+
+    #[error("Too soon since the last distribution of this asset")]
+    DistributionTooSoon {},
+
     #[error("No reward duration provided for rewards distribution")]
     ZeroRewardDuration {},
 
@@ -81,6 +110,9 @@ pub enum ContractError {
 
     #[error("Cannot rebond when unbond all flag is set to true, unbond instead")]
     CannotRebondIfUnbondAll {},
+
+    #[error("Cannot cancel an unbond when unbond all flag is set to true")]
+    CannotCancelUnbondIfUnbondAll {},
 }
 
 impl From<OverflowError> for ContractError {
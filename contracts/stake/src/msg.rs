@@ -1,18 +1,28 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cw20::Cw20ReceiveMsg;
 
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
+use cw_controllers::Claim;
+use cw_utils::Expiration;
 use dex::asset::{AssetInfo, AssetInfoValidated, AssetValidated};
 
 use dex::stake::{FundingInfo, UnbondingPeriod};
 
+/// The maximum number of addresses that can be settled in one [`ExecuteMsg::ClaimFor`]
+pub const MAX_CLAIM_FOR_ADDRESSES: usize = 30;
+
 #[cw_serde]
 pub enum ExecuteMsg {
     Delegate {
         /// Unbonding period in seconds
         unbonding_period: u64,
+        /// Credits the stake to this address instead of the sender.
+        /// Only the admin and the instantiator of the contract (usually the pool) can set this.
+        delegate_as: Option<String>,
     },
-    /// Rebond will update an amount of bonded tokens from one bond period to the other
+    /// Rebond will update an amount of bonded tokens from one bond period to the other.
+    /// Moving to a shorter period unbonds the tokens instead, with a claim that releases them
+    /// after the difference of the periods.
     Rebond {
         tokens: Uint128,
         // these must be valid time periods
@@ -28,21 +38,58 @@ pub enum ExecuteMsg {
         /// multiplier, unbonding_period needs to be passed in unbond as well
         unbonding_period: u64,
     },
+    /// Cancels a pending claim and puts the tokens back into stake for the unbonding period
+    /// they were unbonded from. Only possible within the unbond grace period after unbonding.
+    CancelUnbond {
+        /// Release time of the claim, as returned by [`QueryMsg::Claims`]
+        release_at: Timestamp,
+        /// Unbonding period the claim was unbonded from
+        unbonding_period: u64,
+    },
     /// Will immediately unbond all tokens for the given addresses.
-    /// Can only be called by the `unbonder` account.
+    /// Can only be called by the `unbonder` or one of the `backup_unbonders`.
     QuickUnbond {
         /// The addresses of the stakers that should be unbonded
         stakers: Vec<String>,
     },
     /// UnbondAll is used to allow instant unbond of tokens in emergency cases.
-    /// Can only be called by the `unbonder` account.
+    /// Can only be called by the `unbonder` or one of the `backup_unbonders`.
     UnbondAll {},
     /// Allows to revert the unbond all flag to false.
-    /// Can only be called by the `unbonder` account or the ADMIN.
+    /// Can only be called by the `unbonder`, one of the `backup_unbonders` or the ADMIN.
     StopUnbondAll {},
+    /// Replaces the unbonder and the backup unbonders.
+    /// Can only be called by the ADMIN.
+    UpdateUnbonders {
+        unbonder: Option<String>,
+        backup_unbonders: Vec<String>,
+    },
+    /// Removes the given amount of tokens from a staker's unlocked stake in the given unbonding period
+    /// and sends them to `recipient`, e.g. a treasury. Used to penalize misbehaving stakers.
+    /// Can only be called by the ADMIN.
+    Slash {
+        /// The staker to slash
+        address: String,
+        /// The unbonding period to slash the stake from
+        unbonding_period: u64,
+        /// The amount of tokens to slash
+        amount: Uint128,
+        /// The address receiving the slashed tokens
+        recipient: String,
+    },
     /// Claim is used to claim your native tokens that you previously "unbonded"
     /// after the contract-defined waiting period (eg. 1 week)
-    Claim {},
+    Claim {
+        /// Address that receives the claimed tokens. If not present, they are sent to `sender`.
+        receiver: Option<String>,
+    },
+    /// Releases the matured claims of each of the given addresses and sends the tokens to them.
+    /// Can be called by anyone, e.g. a keeper cleaning up storage.
+    /// Addresses without matured claims are skipped.
+    ClaimFor {
+        /// The owners of the claims, at most [`MAX_CLAIM_FOR_ADDRESSES`]
+        addresses: Vec<String>,
+    },
 
     /// Change the admin
     UpdateAdmin { admin: Option<String> },
@@ -57,6 +104,37 @@ pub enum ExecuteMsg {
         /// Rewards multiplier by unbonding period for this distribution
         /// Only periods that are defined in the contract can be used here
         rewards: Vec<(UnbondingPeriod, Decimal)>,
+
+        /// If set, rewards of this distribution can only be withdrawn once the stake that accrued
+        /// them has been bonded for at least its full unbonding period, or was unbonded.
+        /// This is tracked separately for each unbonding period.
+        #[serde(default)]
+        rewards_locked_until_unbond: bool,
+    },
+    /// Change the manager of an existing distribution flow.
+    /// Can only be called by the current manager of that distribution flow.
+    UpdateDistributionManager {
+        /// The asset of the distribution flow
+        asset: AssetInfo,
+        /// The address of the new manager
+        manager: String,
+    },
+    /// Remove an existing distribution flow, so it no longer counts towards `max_distributions`.
+    /// Can only be called by the manager of that distribution flow.
+    /// Rewards that were not distributed yet are sent to `receiver`, while rewards that were
+    /// already distributed stay withdrawable by the stakers.
+    RemoveDistributionFlow {
+        /// The asset of the distribution flow
+        asset: AssetInfo,
+        /// The address to send the undistributed rewards to. Defaults to the sender.
+        receiver: Option<String>,
+    },
+    /// Delete a removed distribution flow whose rewards were not fully withdrawn yet, so a new flow
+    /// for the same asset can be created. The rewards left in it, e.g. rounding dust, are sent to the admin.
+    /// Can only be called by the admin.
+    SweepRemovedDistribution {
+        /// The asset of the removed distribution flow
+        asset: AssetInfo,
     },
 
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
@@ -65,6 +143,7 @@ pub enum ExecuteMsg {
     /// Distributes rewards sent with this message, and all rewards transferred since last call of this
     /// to members, proportionally to their points. Rewards are not immediately send to members, but
     /// assigned to them for later withdrawal (see: `ExecuteMsg::WithdrawFunds`)
+    /// Fails if an asset was already distributed within the configured `min_distribution_interval`.
     DistributeRewards {
         /// Original source of rewards, informational. If present overwrites "sender" field on
         /// propagated event.
@@ -74,11 +153,17 @@ pub enum ExecuteMsg {
     WithdrawRewards {
         /// Account from which assigned rewards would be withdrawn; `sender` by default. `sender` has
         /// to be eligible for withdrawal from `owner` address to perform this call (`owner` has to
-        /// call `DelegateWithdrawal { delegated: sender }` before)
+        /// call `DelegateWithdrawal { delegated: sender }` or `AuthorizeWithdrawer { addr: sender }`
+        /// before)
         owner: Option<String>,
         /// Address where to transfer funds. If not present, funds would be sent to `sender`.
+        /// Withdrawers authorized with `AuthorizeWithdrawer` can only send funds to `owner`, which is
+        /// also the default for them.
         receiver: Option<String>,
     },
+    /// Withdraws all rewards assigned to sender and splits every reward asset between the given
+    /// receivers. Fractions have to be non-zero and sum up to one.
+    WithdrawRewardsSplit { splits: Vec<(String, Decimal)> },
     /// Sets given address as allowed for senders funds withdrawal. Funds still can be withdrawn by
     /// sender himself, but this additional account is allowed to perform it as well. There can be only
     /// one account delegated for withdrawal for any owner at any single time.
@@ -87,6 +172,12 @@ pub enum ExecuteMsg {
         /// to own address.
         delegated: String,
     },
+    /// Allows the given address to withdraw the sender's rewards on their behalf, e.g. for a
+    /// compounding keeper. Unlike `DelegateWithdrawal`, any number of addresses can be authorized
+    /// and the rewards are always sent to the sender.
+    AuthorizeWithdrawer { addr: String },
+    /// Removes an authorization given with `AuthorizeWithdrawer`.
+    RevokeWithdrawer { addr: String },
     /// Fund a distribution flow with 1 or more native tokens, updating each provided native token's reward config appropriately.
     /// Funds to be provided are included in `info.funds`
     FundDistribution { funding_info: FundingInfo },
@@ -105,9 +196,45 @@ pub enum QueryMsg {
         /// Unbonding period in seconds
         unbonding_period: u64,
     },
+    /// Show the number of tokens this address could unbond right now from the given unbonding period.
+    /// Tokens which are still locked because of a rebond to a shorter period are not included.
+    #[returns(UnbondableAmountResponse)]
+    UnbondableAmount {
+        address: String,
+        /// Unbonding period in seconds
+        unbonding_period: u64,
+    },
+    /// Check whether this address could unbond the given amount from the given unbonding period
+    /// right now, taking its stake and locked tokens into account.
+    /// The unbond all flag does not prevent unbonding, so it never makes this fail.
+    #[returns(CanUnbondResponse)]
+    CanUnbond {
+        address: String,
+        amount: Uint128,
+        /// Unbonding period in seconds
+        unbonding_period: u64,
+    },
+    /// Show the rewards power this address would lose by unbonding the given amount from the given
+    /// unbonding period, and the rewards that power would earn during the unbonding period at the current
+    /// reward rates. The unbonding tokens do not earn rewards, so these are foregone by unbonding now.
+    #[returns(UnbondRewardImpactResponse)]
+    UnbondRewardImpact {
+        address: String,
+        amount: Uint128,
+        /// Unbonding period in seconds
+        unbonding_period: u64,
+    },
     /// Show the number of tokens currently staked by this address for all unbonding periods
     #[returns(AllStakedResponse)]
     AllStaked { address: String },
+    /// Show the average unbonding period (in seconds) of the tokens staked by this address,
+    /// weighted by the amount staked in each period. Zero if the address has no stake.
+    #[returns(u64)]
+    AverageLock { address: String },
+    /// Show the average number of tokens staked by this address for all unbonding periods
+    /// from `since` (in seconds) until now, weighted by how long each amount was staked.
+    #[returns(Uint128)]
+    TimeWeightedStake { address: String, since: u64 },
     /// Show the number of all, not unbonded tokens delegated by all users for all unbonding periods
     #[returns(TotalStakedResponse)]
     TotalStaked {},
@@ -120,6 +247,16 @@ pub enum QueryMsg {
     /// Show the outstanding rewards for this address
     #[returns(RewardsPowerResponse)]
     RewardsPower { address: String },
+    /// Show the rewards power that delegating `amount` to the given unbonding period would add.
+    /// If `address` is given, its current stake in that period is taken into account
+    /// (relevant for `min_bond`), otherwise a fresh delegation is assumed.
+    #[returns(RewardsPowerResponse)]
+    MarginalPower {
+        amount: Uint128,
+        /// Unbonding period in seconds
+        unbonding_period: u64,
+        address: Option<String>,
+    },
     /// Return AdminResponse
     #[returns(cw_controllers::AdminResponse)]
     Admin {},
@@ -129,14 +266,25 @@ pub enum QueryMsg {
     /// Return how many rewards will be received per token in each unbonding period in one year
     #[returns(AnnualizedRewardsResponse)]
     AnnualizedRewards {},
+    /// Return how many tokens of the given asset each unit of rewards power currently earns per second,
+    /// based on the remaining funding curve of its distribution.
+    #[returns(RewardRateResponse)]
+    RewardRate { asset: AssetInfo },
     /// Return how many rewards are assigned for withdrawal from the given address. Returns
-    /// `RewardsResponse`.
+    /// `RewardsResponse`. Locked rewards are not included.
     #[returns(WithdrawableRewardsResponse)]
     WithdrawableRewards { owner: String },
+    /// Return how many rewards were withdrawn from the given address over all time.
+    #[returns(LifetimeRewardsResponse)]
+    LifetimeRewards { address: String },
     /// Return how many rewards were distributed in total by this contract. Returns
     /// `RewardsResponse`.
     #[returns(DistributedRewardsResponse)]
     DistributedRewards {},
+    /// Return how many rewards were distributed per asset over the whole lifetime of the contract,
+    /// including distribution flows that were removed since.
+    #[returns(Vec<AssetValidated>)]
+    TotalDistributed {},
     /// Return how many funds were sent to this contract since last `ExecuteMsg::DistributeFunds`,
     /// and await for distribution. Returns `RewardsResponse`.
     #[returns(UndistributedRewardsResponse)]
@@ -153,6 +301,26 @@ pub enum QueryMsg {
     /// Returns the value of unbond all flag
     #[returns(UnbondAllResponse)]
     UnbondAll {},
+    /// Returns what [`ExecuteMsg::UnbondAll`] would do given the current state
+    #[returns(UnbondAllPreviewResponse)]
+    UnbondAllPreview {},
+    /// Compares the staked token balance held by the contract with the tracked totals
+    #[returns(BalanceReconciliationResponse)]
+    BalanceReconciliation {},
+    /// Lists the open claims of all addresses, ordered by address
+    #[returns(AllClaimsResponse)]
+    AllClaims {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the open claims of the given address ordered by release time,
+    /// along with the total amount released up to each of them
+    #[returns(Vec<UnbondingScheduleEntry>)]
+    UnbondingSchedule { address: String },
+    /// Returns how many tokens of each asset are currently emitted per second by the funding curves,
+    /// as well as the fundings that only start in the future
+    #[returns(EmissionRateResponse)]
+    EmissionRate {},
 }
 
 #[cw_serde]
@@ -172,6 +340,28 @@ pub struct StakedResponse {
     pub lp_share_denom: String,
 }
 
+#[cw_serde]
+pub struct UnbondableAmountResponse {
+    pub amount: Uint128,
+    pub unbonding_period: u64,
+}
+
+#[cw_serde]
+pub struct CanUnbondResponse {
+    pub allowed: bool,
+    /// Why unbonding would fail, if it is not allowed
+    pub reason: Option<String>,
+}
+
+#[cw_serde]
+pub struct UnbondRewardImpactResponse {
+    /// The rewards power the address would lose per asset
+    pub power_reduction: Vec<(AssetInfoValidated, Uint128)>,
+    /// The rewards the lost power would earn over the unbonding period at the current reward rates.
+    /// This is an estimate, since the rates change with the total rewards power and the funding curves.
+    pub rewards_foregone: Vec<AssetValidated>,
+}
+
 #[cw_serde]
 pub struct AllStakedResponse {
     pub stakes: Vec<StakedResponse>,
@@ -212,6 +402,36 @@ pub struct AnnualizedRewardsResponse {
     pub rewards: Vec<(UnbondingPeriod, Vec<AnnualizedReward>)>,
 }
 
+#[cw_serde]
+pub struct RewardRateResponse {
+    /// The amount of tokens paid out per second for each unit of rewards power.
+    pub per_power_per_second: Decimal,
+}
+
+#[cw_serde]
+pub struct EmissionRateResponse {
+    /// The assets that are currently emitted, along with their emission rate.
+    pub rates: Vec<AssetEmissionRate>,
+    /// The assets that are funded, but only start being emitted in the future.
+    pub scheduled: Vec<ScheduledEmission>,
+}
+
+#[cw_serde]
+pub struct AssetEmissionRate {
+    pub info: AssetInfoValidated,
+    /// The amount of tokens paid out per second to all stakers together.
+    pub per_second: Decimal,
+}
+
+#[cw_serde]
+pub struct ScheduledEmission {
+    pub info: AssetInfoValidated,
+    /// Epoch in seconds when the emission starts.
+    pub start_time: u64,
+    /// The amount of tokens paid out per second to all stakers together, once the emission started.
+    pub per_second: Decimal,
+}
+
 #[cw_serde]
 pub struct AnnualizedReward {
     pub info: AssetInfoValidated,
@@ -231,6 +451,12 @@ pub struct WithdrawableRewardsResponse {
     pub rewards: Vec<AssetValidated>,
 }
 
+#[cw_serde]
+pub struct LifetimeRewardsResponse {
+    /// Total amount of rewards withdrawn from the given address, per asset.
+    pub rewards: Vec<AssetValidated>,
+}
+
 #[cw_serde]
 pub struct DelegatedResponse {
     pub delegated: Addr,
@@ -256,3 +482,44 @@ pub struct UnbondAllResponse {
     /// Value of unbond all flag.
     pub unbond_all: bool,
 }
+
+#[cw_serde]
+pub struct UnbondAllPreviewResponse {
+    /// Whether the unbond all flag is already set, in which case `UnbondAll` would fail.
+    pub already_set: bool,
+    /// Staked tokens that can be unbonded immediately once the flag is set.
+    /// Unbonding them creates no claims, the tokens are sent out right away.
+    pub releasable: Uint128,
+    /// Tokens in already existing claims. These keep their original release time.
+    pub unbonding: Uint128,
+    /// Distributions that can no longer be funded or distributed while the flag is set.
+    pub halted_distributions: Vec<AssetInfoValidated>,
+}
+
+#[cw_serde]
+pub struct BalanceReconciliationResponse {
+    /// Amount of the staked token held by the contract.
+    pub held_lp: Uint128,
+    /// Tokens tracked as staked.
+    pub tracked_staked: Uint128,
+    /// Tokens tracked as unbonding, waiting to be claimed.
+    pub in_claims: Uint128,
+    /// Whether the held balance equals the sum of staked and unbonding tokens.
+    pub matches: bool,
+}
+
+#[cw_serde]
+pub struct AllClaimsResponse {
+    /// Addresses with at least one open claim, along with those claims.
+    pub claims: Vec<(Addr, Vec<Claim>)>,
+}
+
+#[cw_serde]
+pub struct UnbondingScheduleEntry {
+    /// When the tokens of this claim are released.
+    pub release_at: Expiration,
+    /// Amount of tokens released by this claim.
+    pub amount: Uint128,
+    /// Total amount released by this and all earlier claims.
+    pub cumulative: Uint128,
+}
@@ -1,6 +1,7 @@
 mod delegate;
 mod distribution;
 mod quick_unbond;
+mod slash;
 mod staking_rewards;
 mod suite;
 mod unbond_all;
@@ -1,6 +1,10 @@
-use super::suite::{SuiteBuilder, SEVEN_DAYS};
-use cosmwasm_std::Uint128;
+use super::suite::{juno, SuiteBuilder, SEVEN_DAYS};
+use crate::msg::MAX_CLAIM_FOR_ADDRESSES;
+use crate::ContractError;
+use cosmwasm_std::{Decimal, Uint128};
 use cw_controllers::Claim;
+use cw_utils::Expiration;
+use dex::asset::{AssetInfo, AssetInfoValidated};
 
 const DENOM: &str = "VEST";
 const USER: &str = "user_addr_0000";
@@ -118,3 +122,668 @@ fn mixed_vested_liquid_delegate_and_transfer_remaining() {
         20_000u128
     );
 }
+
+#[test]
+fn claim_to_receiver() {
+    const VAULT: &str = "vault";
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .build();
+
+    suite.delegate(USER, 50_000u128, None).unwrap();
+    suite.unbond(USER, 50_000u128, None).unwrap();
+    suite.update_time(SEVEN_DAYS);
+
+    // invalid receiver is rejected
+    suite.claim_to(USER, "").unwrap_err();
+
+    suite.claim_to(USER, VAULT).unwrap();
+    assert_eq!(suite.query_claims(USER).unwrap(), vec![]);
+    // tokens land at the receiver, not the claimer
+    assert_eq!(
+        suite.query_balance_vesting_contract(VAULT).unwrap(),
+        50_000u128
+    );
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        50_000u128
+    );
+
+    // nothing left to claim for the sender
+    suite.claim_to(USER, VAULT).unwrap_err();
+}
+
+#[test]
+fn keeper_claims_for_multiple_users() {
+    const USER2: &str = "user_addr_0001";
+    const USER3: &str = "user_addr_0002";
+    const KEEPER: &str = "keeper";
+    let balances = vec![
+        (USER, 100_000u128),
+        (USER2, 100_000u128),
+        (USER3, 100_000u128),
+    ];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .build();
+
+    for user in [USER, USER2, USER3] {
+        suite.delegate(user, 100_000u128, None).unwrap();
+    }
+    suite.unbond(USER, 30_000u128, None).unwrap();
+    suite.unbond(USER2, 20_000u128, None).unwrap();
+    suite.unbond(USER2, 10_000u128, None).unwrap();
+
+    // nothing has matured yet
+    let err = suite.claim_for(KEEPER, &[USER, USER2]).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    suite.update_time(SEVEN_DAYS);
+    // this claim only matures in another week
+    suite.unbond(USER3, 50_000u128, None).unwrap();
+
+    suite.claim_for(KEEPER, &[USER, USER2, USER3]).unwrap();
+    assert_eq!(suite.query_claims(USER).unwrap(), vec![]);
+    assert_eq!(suite.query_claims(USER2).unwrap(), vec![]);
+    assert_eq!(suite.query_claims(USER3).unwrap().len(), 1);
+    // the tokens go to the owners, not the keeper
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        30_000u128
+    );
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER2).unwrap(),
+        30_000u128
+    );
+    assert_eq!(suite.query_balance_vesting_contract(USER3).unwrap(), 0u128);
+    assert_eq!(suite.query_balance_vesting_contract(KEEPER).unwrap(), 0u128);
+
+    // the batch size is capped
+    let addresses = vec![USER; MAX_CLAIM_FOR_ADDRESSES + 1];
+    let err = suite.claim_for(KEEPER, &addresses).unwrap_err();
+    assert_eq!(
+        ContractError::TooManyClaimAddresses(MAX_CLAIM_FOR_ADDRESSES),
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn balance_reconciliation() {
+    const USER2: &str = "user_addr_0001";
+    let balances = vec![(USER, 100_000u128), (USER2, 50_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .build();
+
+    let assert_reconciles = |suite: &super::suite::Suite, staked: u128, in_claims: u128| {
+        let resp = suite.query_balance_reconciliation().unwrap();
+        assert_eq!(resp.held_lp.u128(), staked + in_claims);
+        assert_eq!(resp.tracked_staked.u128(), staked);
+        assert_eq!(resp.in_claims.u128(), in_claims);
+        assert!(resp.matches);
+    };
+    assert_reconciles(&suite, 0, 0);
+
+    suite.delegate(USER, 100_000u128, None).unwrap();
+    suite.delegate(USER2, 50_000u128, None).unwrap();
+    assert_reconciles(&suite, 150_000, 0);
+
+    suite.unbond(USER, 40_000u128, None).unwrap();
+    suite.unbond(USER2, 10_000u128, None).unwrap();
+    assert_reconciles(&suite, 100_000, 50_000);
+
+    suite.update_time(SEVEN_DAYS);
+    suite.claim(USER).unwrap();
+    assert_reconciles(&suite, 100_000, 10_000);
+
+    suite.claim(USER2).unwrap();
+    suite.unbond(USER, 60_000u128, None).unwrap();
+    assert_reconciles(&suite, 40_000, 60_000);
+}
+
+#[test]
+fn all_claims() {
+    const USER2: &str = "user_addr_0001";
+    const USER3: &str = "user_addr_0002";
+    let balances = vec![
+        (USER, 100_000u128),
+        (USER2, 100_000u128),
+        (USER3, 100_000u128),
+    ];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .build();
+
+    assert_eq!(suite.query_all_claims(None, None).unwrap(), vec![]);
+
+    for user in [USER3, USER, USER2] {
+        suite.delegate(user, 100_000u128, None).unwrap();
+    }
+    suite.unbond(USER3, 10_000u128, None).unwrap();
+    suite.unbond(USER, 20_000u128, None).unwrap();
+    suite.update_time(100);
+    suite.unbond(USER, 5_000u128, None).unwrap();
+    suite.unbond(USER2, 30_000u128, None).unwrap();
+
+    // ordered by address
+    let all = suite.query_all_claims(None, None).unwrap();
+    let amounts: Vec<(String, Vec<u128>)> = all
+        .iter()
+        .map(|(addr, claims)| {
+            (
+                addr.to_string(),
+                claims.iter().map(|c| c.amount.u128()).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        amounts,
+        vec![
+            (USER.to_string(), vec![20_000, 5_000]),
+            (USER2.to_string(), vec![30_000]),
+            (USER3.to_string(), vec![10_000]),
+        ]
+    );
+    for (addr, claims) in &all {
+        assert_eq!(claims, &suite.query_claims(addr.as_str()).unwrap());
+    }
+
+    // paginated
+    let page = suite.query_all_claims(None, Some(2)).unwrap();
+    assert_eq!(page, all[..2]);
+    let page = suite.query_all_claims(Some(USER2), Some(2)).unwrap();
+    assert_eq!(page, all[2..]);
+    let page = suite.query_all_claims(Some(USER3), None).unwrap();
+    assert_eq!(page, vec![]);
+
+    // fully claimed addresses are no longer listed
+    suite.update_time(SEVEN_DAYS);
+    suite.claim(USER3).unwrap();
+    assert_eq!(suite.query_all_claims(None, None).unwrap(), all[..2]);
+}
+
+#[test]
+fn cancel_unbond_within_grace_period() {
+    let grace_period = 86400;
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, vec![(USER, 100_000u128)])
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbond_grace_period(grace_period)
+        .build();
+
+    suite.delegate(USER, 50_000u128, None).unwrap();
+    suite.unbond(USER, 20_000u128, None).unwrap();
+    assert_eq!(suite.query_staked(USER, None).unwrap(), 30_000u128);
+    let claims = suite.query_claims(USER).unwrap();
+    assert_eq!(claims.len(), 1);
+
+    // unknown claims cannot be cancelled
+    let err = suite
+        .cancel_unbond(USER, claims[0].release_at, 2 * SEVEN_DAYS)
+        .unwrap_err();
+    let Expiration::AtTime(release_at) = claims[0].release_at else {
+        unreachable!()
+    };
+    assert_eq!(
+        ContractError::ClaimNotFound {
+            release_at,
+            unbonding_period: 2 * SEVEN_DAYS
+        },
+        err.downcast().unwrap()
+    );
+
+    // cancelling within the grace period restores the stake
+    suite.update_time(grace_period / 2);
+    suite
+        .cancel_unbond(USER, claims[0].release_at, None)
+        .unwrap();
+    assert_eq!(suite.query_staked(USER, None).unwrap(), 50_000u128);
+    assert_eq!(suite.query_total_staked().unwrap(), 50_000u128);
+    assert_eq!(suite.query_claims(USER).unwrap(), vec![]);
+    // the tokens never left the contract
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        50_000u128
+    );
+
+    // after the grace period the claim has to run its course
+    suite.unbond(USER, 10_000u128, None).unwrap();
+    let claims = suite.query_claims(USER).unwrap();
+    suite.update_time(grace_period);
+    let err = suite
+        .cancel_unbond(USER, claims[0].release_at, None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::UnbondNotCancellable {},
+        err.downcast().unwrap()
+    );
+    assert_eq!(suite.query_staked(USER, None).unwrap(), 40_000u128);
+    assert_eq!(suite.query_claims(USER).unwrap().len(), 1);
+
+    suite.update_time(SEVEN_DAYS);
+    suite.claim(USER).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        60_000u128
+    );
+}
+
+#[test]
+fn cancel_unbond_of_claims_releasing_at_the_same_time() {
+    let grace_period = 86400;
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, vec![(USER, 100_000u128)])
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbonding_periods(vec![SEVEN_DAYS, 2 * SEVEN_DAYS])
+        .with_unbond_grace_period(grace_period)
+        .build();
+
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS).unwrap();
+    suite.delegate(USER, 50_000u128, 2 * SEVEN_DAYS).unwrap();
+
+    // both claims release at the same time, but come from different unbonding periods
+    suite.unbond(USER, 10_000u128, 2 * SEVEN_DAYS).unwrap();
+    suite.update_time(SEVEN_DAYS);
+    suite.unbond(USER, 20_000u128, SEVEN_DAYS).unwrap();
+    let claims = suite.query_claims(USER).unwrap();
+    assert_eq!(claims.len(), 2);
+    assert_eq!(claims[0].release_at, claims[1].release_at);
+
+    // the grace period of the first claim is over
+    suite.update_time(grace_period / 2);
+    let err = suite
+        .cancel_unbond(USER, claims[0].release_at, 2 * SEVEN_DAYS)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::UnbondNotCancellable {},
+        err.downcast().unwrap()
+    );
+
+    // the second one is still cancellable and goes back into its own unbonding period
+    suite
+        .cancel_unbond(USER, claims[1].release_at, SEVEN_DAYS)
+        .unwrap();
+    assert_eq!(suite.query_staked(USER, SEVEN_DAYS).unwrap(), 50_000u128);
+    assert_eq!(
+        suite.query_staked(USER, 2 * SEVEN_DAYS).unwrap(),
+        40_000u128
+    );
+    assert_eq!(
+        suite.query_claims(USER).unwrap(),
+        vec![Claim::new(10_000, claims[0].release_at)]
+    );
+}
+
+#[test]
+fn cancel_unbond_after_earlier_claims_were_released() {
+    let grace_period = 86400;
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, vec![(USER, 100_000u128)])
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbonding_periods(vec![SEVEN_DAYS, 2 * SEVEN_DAYS])
+        .with_unbond_grace_period(grace_period)
+        .build();
+
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS).unwrap();
+    suite.delegate(USER, 50_000u128, 2 * SEVEN_DAYS).unwrap();
+
+    suite.unbond(USER, 10_000u128, SEVEN_DAYS).unwrap();
+    suite.update_time(SEVEN_DAYS - 3600);
+    suite.unbond(USER, 20_000u128, 2 * SEVEN_DAYS).unwrap();
+    let claims = suite.query_claims(USER).unwrap();
+    assert_eq!(claims.len(), 2);
+
+    // releasing the first claim does not change which claim is cancelled
+    suite.update_time(3600);
+    suite.claim(USER).unwrap();
+    suite
+        .cancel_unbond(USER, claims[1].release_at, 2 * SEVEN_DAYS)
+        .unwrap();
+    assert_eq!(
+        suite.query_staked(USER, 2 * SEVEN_DAYS).unwrap(),
+        50_000u128
+    );
+    assert_eq!(suite.query_claims(USER).unwrap(), vec![]);
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        10_000u128
+    );
+}
+
+#[test]
+fn unbonding_schedule() {
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbonding_periods(vec![SEVEN_DAYS, SEVEN_DAYS * 2])
+        .build();
+
+    assert_eq!(suite.query_unbonding_schedule(USER).unwrap(), vec![]);
+
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS).unwrap();
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS * 2).unwrap();
+
+    // claims are created in a different order than they are released
+    suite.unbond(USER, 10_000u128, SEVEN_DAYS * 2).unwrap();
+    suite.update_time(100);
+    suite.unbond(USER, 20_000u128, SEVEN_DAYS).unwrap();
+    suite.update_time(200);
+    suite.unbond(USER, 5_000u128, SEVEN_DAYS * 2).unwrap();
+    suite.unbond(USER, 7_000u128, SEVEN_DAYS).unwrap();
+
+    let claims = suite.query_claims(USER).unwrap();
+    let schedule = suite.query_unbonding_schedule(USER).unwrap();
+    // ordered by release time, which is the order 2, 4, 1, 3 of creation
+    assert_eq!(
+        schedule
+            .iter()
+            .map(|entry| entry.release_at)
+            .collect::<Vec<_>>(),
+        [1, 3, 0, 2]
+            .iter()
+            .map(|&i| claims[i].release_at)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        schedule
+            .iter()
+            .map(|entry| (entry.amount.u128(), entry.cumulative.u128()))
+            .collect::<Vec<_>>(),
+        vec![
+            (20_000, 20_000),
+            (7_000, 27_000),
+            (10_000, 37_000),
+            (5_000, 42_000)
+        ]
+    );
+
+    // released claims disappear from the schedule once claimed
+    suite.update_time(SEVEN_DAYS);
+    suite.claim(USER).unwrap();
+    let schedule = suite.query_unbonding_schedule(USER).unwrap();
+    assert_eq!(
+        schedule
+            .iter()
+            .map(|entry| (entry.amount.u128(), entry.cumulative.u128()))
+            .collect::<Vec<_>>(),
+        vec![(10_000, 10_000), (5_000, 15_000)]
+    );
+}
+
+#[test]
+fn average_lock() {
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbonding_periods(vec![SEVEN_DAYS, SEVEN_DAYS * 2, SEVEN_DAYS * 4])
+        .build();
+
+    assert_eq!(suite.query_average_lock(USER).unwrap(), 0);
+
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS).unwrap();
+    assert_eq!(suite.query_average_lock(USER).unwrap(), SEVEN_DAYS);
+
+    suite.delegate(USER, 30_000u128, SEVEN_DAYS * 2).unwrap();
+    suite.delegate(USER, 20_000u128, SEVEN_DAYS * 4).unwrap();
+    // (50_000 * 1 + 30_000 * 2 + 20_000 * 4) / 100_000 = 1.9 times seven days
+    assert_eq!(
+        suite.query_average_lock(USER).unwrap(),
+        SEVEN_DAYS * 19 / 10
+    );
+
+    // unbonding tokens no longer count
+    suite.unbond(USER, 20_000u128, SEVEN_DAYS * 4).unwrap();
+    // (50_000 * 1 + 30_000 * 2) / 80_000 = 1.375 times seven days
+    assert_eq!(suite.query_average_lock(USER).unwrap(), SEVEN_DAYS * 11 / 8);
+}
+
+#[test]
+fn time_weighted_stake() {
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbonding_periods(vec![SEVEN_DAYS, SEVEN_DAYS * 2])
+        .build();
+
+    let start = suite.app.block_info().time.seconds();
+    assert_eq!(suite.query_time_weighted_stake(USER, start).unwrap(), 0);
+
+    suite.delegate(USER, 10_000u128, SEVEN_DAYS).unwrap();
+    suite.update_time(100);
+    // stakes in all unbonding periods count
+    suite.delegate(USER, 30_000u128, SEVEN_DAYS * 2).unwrap();
+    suite.update_time(100);
+    suite.unbond(USER, 20_000u128, SEVEN_DAYS * 2).unwrap();
+    suite.update_time(200);
+
+    // (10_000 * 100 + 40_000 * 100 + 20_000 * 200) / 400
+    assert_eq!(
+        suite.query_time_weighted_stake(USER, start).unwrap(),
+        22_500
+    );
+    // (40_000 * 100 + 20_000 * 200) / 300
+    assert_eq!(
+        suite.query_time_weighted_stake(USER, start + 100).unwrap(),
+        26_666
+    );
+    // there was no stake before the first delegation
+    // (10_000 * 100 + 40_000 * 100 + 20_000 * 200) / 500
+    assert_eq!(
+        suite.query_time_weighted_stake(USER, start - 100).unwrap(),
+        18_000
+    );
+    // the average since now is the current stake
+    let now = start + 400;
+    assert_eq!(suite.query_time_weighted_stake(USER, now).unwrap(), 20_000);
+
+    suite.query_time_weighted_stake(USER, now + 1).unwrap_err();
+}
+
+#[test]
+fn can_unbond() {
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .with_unbonding_periods(vec![SEVEN_DAYS, SEVEN_DAYS * 2])
+        .build();
+
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS).unwrap();
+    suite.delegate(USER, 50_000u128, SEVEN_DAYS * 2).unwrap();
+
+    // sufficient stake
+    let res = suite
+        .query_can_unbond(USER, 50_000u128, SEVEN_DAYS)
+        .unwrap();
+    assert!(res.allowed);
+    assert_eq!(res.reason, None);
+
+    // insufficient stake
+    let res = suite
+        .query_can_unbond(USER, 50_001u128, SEVEN_DAYS)
+        .unwrap();
+    assert!(!res.allowed);
+    assert_eq!(
+        res.reason.unwrap(),
+        "Insufficient stake: 50000 staked, 50001 requested"
+    );
+    suite.unbond(USER, 50_001u128, SEVEN_DAYS).unwrap_err();
+
+    // unknown unbonding period
+    let res = suite.query_can_unbond(USER, 1u128, SEVEN_DAYS * 3).unwrap();
+    assert!(!res.allowed);
+    assert_eq!(
+        res.reason.unwrap(),
+        ContractError::NoUnbondingPeriodFound(SEVEN_DAYS * 3).to_string()
+    );
+
+    // rebonding to the shorter period unbonds the tokens instead of adding them to it
+    suite
+        .rebond(USER, 20_000u128, SEVEN_DAYS * 2, SEVEN_DAYS)
+        .unwrap();
+    let res = suite
+        .query_can_unbond(USER, 30_000u128, SEVEN_DAYS * 2)
+        .unwrap();
+    assert!(res.allowed);
+    let res = suite
+        .query_can_unbond(USER, 50_001u128, SEVEN_DAYS)
+        .unwrap();
+    assert!(!res.allowed);
+    assert_eq!(
+        res.reason.unwrap(),
+        "Insufficient stake: 50000 staked, 50001 requested"
+    );
+    suite.unbond(USER, 50_000u128, SEVEN_DAYS).unwrap();
+}
+
+#[test]
+fn delegate_as() {
+    // the suite instantiates the contract from this address, e.g. the pool
+    let instantiator = "admin";
+    let admin = "owner";
+    let user2 = "user_addr_0001";
+    let mut suite = SuiteBuilder::new()
+        .with_admin(admin)
+        .with_native_balances(
+            DENOM,
+            vec![(instantiator, 100_000), (admin, 100_000), (USER, 100_000)],
+        )
+        .with_native_balances("juno", vec![(admin, 800)])
+        .with_lp_share_denom(DENOM.to_string())
+        .build();
+
+    suite
+        .create_distribution_flow(
+            admin,
+            admin,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(SEVEN_DAYS, Decimal::one())],
+        )
+        .unwrap();
+
+    // a normal staker cannot delegate on behalf of someone else
+    let err = suite
+        .delegate_as(USER, 50_000u128, None, Some(user2))
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // the instantiator and the admin can
+    suite
+        .delegate_as(instantiator, 50_000u128, None, Some(USER))
+        .unwrap();
+    suite
+        .delegate_as(admin, 30_000u128, None, Some(user2))
+        .unwrap();
+
+    // the stake and rewards power are credited to the targets, not the senders
+    assert_eq!(suite.query_staked(USER, None).unwrap(), 50_000u128);
+    assert_eq!(suite.query_staked(user2, None).unwrap(), 30_000u128);
+    assert_eq!(suite.query_staked(instantiator, None).unwrap(), 0u128);
+    assert_eq!(suite.query_staked(admin, None).unwrap(), 0u128);
+    assert_eq!(
+        suite.query_balance_vesting_contract(instantiator).unwrap(),
+        50_000u128
+    );
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        100_000u128
+    );
+
+    let juno_info = AssetInfoValidated::SmartToken("juno".to_string());
+    assert_eq!(
+        suite.query_rewards_power(USER).unwrap(),
+        vec![(juno_info.clone(), 50)]
+    );
+    assert_eq!(
+        suite.query_rewards_power(user2).unwrap(),
+        vec![(juno_info, 30)]
+    );
+    assert_eq!(suite.query_rewards_power(instantiator).unwrap(), vec![]);
+
+    // so are the rewards
+    suite
+        .distribute_funds(admin, None, Some(juno(800)))
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(USER).unwrap(), vec![juno(500)]);
+    assert_eq!(suite.withdrawable_rewards(user2).unwrap(), vec![juno(300)]);
+
+    // and only the target can unbond the stake
+    suite.unbond(instantiator, 50_000u128, None).unwrap_err();
+    suite.unbond(USER, 50_000u128, None).unwrap();
+    assert_eq!(suite.query_claims(USER).unwrap().len(), 1);
+}
+
+#[test]
+fn auto_claim_on_delegate() {
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .with_auto_claim_on_delegate()
+        .build();
+
+    suite.delegate(USER, 50_000u128, None).unwrap();
+    suite.unbond(USER, 30_000u128, None).unwrap();
+    suite.update_time(SEVEN_DAYS);
+    // a second claim that has not matured yet
+    suite.unbond(USER, 10_000u128, None).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        50_000u128
+    );
+
+    // delegating pays out the matured claim in the same transaction
+    suite.delegate(USER, 20_000u128, None).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        60_000u128
+    );
+    assert_eq!(suite.query_staked(USER, None).unwrap(), 30_000u128);
+    let claims = suite.query_claims(USER).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount, Uint128::new(10_000));
+    assert_eq!(
+        suite
+            .query_balance_vesting_contract(&suite.stake_contract())
+            .unwrap(),
+        40_000u128
+    );
+
+    // the matured claim is gone, so claiming now fails
+    let err = suite.claim(USER).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+}
+
+#[test]
+fn no_auto_claim_on_delegate_by_default() {
+    let balances = vec![(USER, 100_000u128)];
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances(DENOM, balances)
+        .with_lp_share_denom(DENOM.to_string())
+        .build();
+
+    suite.delegate(USER, 50_000u128, None).unwrap();
+    suite.unbond(USER, 30_000u128, None).unwrap();
+    suite.update_time(SEVEN_DAYS);
+
+    // the matured claim stays until it is claimed explicitly
+    suite.delegate(USER, 20_000u128, None).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        30_000u128
+    );
+    assert_eq!(suite.query_claims(USER).unwrap().len(), 1);
+    suite.claim(USER).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(USER).unwrap(),
+        60_000u128
+    );
+}
@@ -1,7 +1,7 @@
 use std::vec;
 
 use cosmwasm_std::{assert_approx_eq, Decimal, Uint128};
-use dex::asset::{native_asset, AssetInfo};
+use dex::asset::{native_asset, AssetInfo, AssetInfoExt, AssetInfoValidated};
 
 use super::suite::SuiteBuilder;
 use crate::multitest::suite::COREUM_DENOM;
@@ -753,6 +753,206 @@ fn divisible_amount_distributed_with_rate() {
     assert_eq!(suite.query_balance(&members[3], "juno").unwrap(), 0);
 }
 
+#[test]
+fn withdraw_rewards_split_between_receivers() {
+    let members = ["member1".to_owned(), "member2".to_owned()];
+    let distributor = "distributor";
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(&members[0], 10_000), (&members[1], 30_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(distributor, 401)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite
+        .delegate(&members[0], 10_000, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(&members[1], 30_000, unbonding_period)
+        .unwrap();
+
+    suite
+        .execute_fund_distribution(distributor, None, juno(400))
+        .unwrap();
+    suite.update_time(100);
+    suite
+        .distribute_funds(distributor, None, Some(juno(1)))
+        .unwrap();
+
+    assert_eq!(
+        suite.withdrawable_rewards(&members[0]).unwrap(),
+        vec![juno(100)]
+    );
+
+    // fractions have to sum up to one
+    let err = suite
+        .withdraw_funds_split(
+            &members[0],
+            &[
+                ("dao1", Decimal::percent(30)),
+                ("dao2", Decimal::percent(60)),
+            ],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidRewardSplits {},
+        err.downcast().unwrap()
+    );
+
+    // zero fractions are rejected
+    let err = suite
+        .withdraw_funds_split(
+            &members[0],
+            &[("dao1", Decimal::one()), ("dao2", Decimal::zero())],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidRewardSplits {},
+        err.downcast().unwrap()
+    );
+
+    suite
+        .withdraw_funds_split(
+            &members[0],
+            &[
+                ("dao1", Decimal::percent(30)),
+                ("dao2", Decimal::percent(70)),
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(suite.query_balance("dao1", "juno").unwrap(), 30);
+    assert_eq!(suite.query_balance("dao2", "juno").unwrap(), 70);
+    assert_eq!(suite.query_balance(&members[0], "juno").unwrap(), 0);
+    assert_eq!(
+        suite.withdrawable_rewards(&members[0]).unwrap(),
+        vec![juno(0)]
+    );
+    // rewards of other members are untouched
+    assert_eq!(
+        suite.withdrawable_rewards(&members[1]).unwrap(),
+        vec![juno(300)]
+    );
+}
+
+#[test]
+fn distributions_are_rate_limited() {
+    let member = "member";
+    let distributor = "distributor";
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 10_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(distributor, 300)])
+        .with_min_distribution_interval(60)
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 10_000, unbonding_period).unwrap();
+
+    suite
+        .distribute_funds(distributor, None, Some(juno(100)))
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(100)]);
+
+    // distributing again right away is rejected
+    suite.update_time(10);
+    let err = suite
+        .distribute_funds(distributor, None, Some(juno(100)))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DistributionTooSoon {},
+        err.downcast().unwrap()
+    );
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(100)]);
+
+    // after the interval, the funds sent in the meantime are distributed
+    suite.update_time(50);
+    suite
+        .distribute_funds(distributor, None, Some(juno(100)))
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(300)]);
+}
+
+#[test]
+fn authorized_withdrawers() {
+    let member = "member";
+    let keeper = "keeper";
+    let distributor = "distributor";
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 10_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(distributor, 200)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 10_000, unbonding_period).unwrap();
+    suite
+        .distribute_funds(distributor, None, Some(juno(100)))
+        .unwrap();
+
+    // unauthorized third parties cannot withdraw
+    let err = suite.withdraw_funds(keeper, member, None).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    suite.authorize_withdrawer(member, keeper).unwrap();
+
+    // authorized withdrawers cannot send the rewards anywhere but to the owner
+    let err = suite.withdraw_funds(keeper, member, keeper).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    suite.withdraw_funds(keeper, member, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 100);
+    assert_eq!(suite.query_balance(keeper, "juno").unwrap(), 0);
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(0)]);
+
+    // the authorization does not allow withdrawing for anyone else
+    let err = suite.withdraw_funds(keeper, "other", None).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // once revoked, the keeper cannot withdraw anymore
+    suite.revoke_withdrawer(member, keeper).unwrap();
+    suite
+        .distribute_funds(distributor, None, Some(juno(100)))
+        .unwrap();
+    let err = suite.withdraw_funds(keeper, member, member).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(100)]);
+}
+
 #[test]
 fn calculate_apr() {
     let distributor = "distributor";
@@ -2123,3 +2323,922 @@ fn withdraw_adjustment_handled_lazily() {
     // member should get rewards
     assert_eq!(suite.query_balance(member, "juno").unwrap(), 500);
 }
+
+#[test]
+fn rewards_locked_until_unbonding_period_elapsed() {
+    let executor = "executor";
+    let member = "member";
+    let unbonding_period = 1000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_min_bond(0)
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 1_000)])
+        .with_native_balances("juno", vec![(executor, 1_000)])
+        .build();
+
+    suite
+        .create_distribution_flow_with_lock(
+            "admin",
+            executor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+            true,
+        )
+        .unwrap();
+
+    suite.delegate(member, 1_000, unbonding_period).unwrap();
+
+    suite
+        .distribute_funds(executor, None, Some(juno(500)))
+        .unwrap();
+    // rewards are accrued, but locked
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(0)]);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 0);
+
+    // still locked one second before the unbonding period elapsed
+    suite.update_time(unbonding_period - 1);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 0);
+
+    // unlocked once the stake was bonded for the full unbonding period
+    suite.update_time(1);
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(500)]);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 500);
+}
+
+#[test]
+fn rewards_lock_applies_per_unbonding_period() {
+    let executor = "executor";
+    let member = "member";
+    let unbonder = "unbonder";
+    let short_period = 1000u64;
+    let long_period = 2000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![short_period, long_period])
+        .with_min_bond(0)
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 3_000), (unbonder, 1_000)])
+        .with_native_balances("juno", vec![(executor, 1_500)])
+        .build();
+
+    suite
+        .create_distribution_flow_with_lock(
+            "admin",
+            executor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![
+                (short_period, Decimal::one()),
+                (long_period, Decimal::one()),
+            ],
+            true,
+        )
+        .unwrap();
+
+    suite.delegate(member, 1_000, short_period).unwrap();
+    suite.update_time(short_period);
+    // the short period stake is mature, a fresh bond into the long period is not
+    suite.delegate(member, 1_000, long_period).unwrap();
+    suite.delegate(unbonder, 1_000, short_period).unwrap();
+
+    suite
+        .distribute_funds(executor, None, Some(juno(900)))
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(300)]);
+
+    // only the rewards of the mature stake can be withdrawn
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 300);
+    // withdrawing again does not unlock more
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(0)]);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 300);
+
+    // unbonding unlocks the rewards of the unbonded stake
+    assert_eq!(suite.withdrawable_rewards(unbonder).unwrap(), vec![juno(0)]);
+    suite.unbond(unbonder, 1_000, short_period).unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(unbonder).unwrap(),
+        vec![juno(300)]
+    );
+    suite.withdraw_funds(unbonder, None, None).unwrap();
+    assert_eq!(suite.query_balance(unbonder, "juno").unwrap(), 300);
+
+    suite
+        .distribute_funds(executor, None, Some(juno(600)))
+        .unwrap();
+    // a new delegation into the mature period does not lock its rewards again
+    suite.delegate(member, 1_000, short_period).unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(300)]);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 600);
+
+    // the rest is unlocked once the stake of both periods is mature
+    suite.update_time(long_period);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 1_200);
+}
+
+#[test]
+fn lifetime_rewards_accumulate_over_withdrawals() {
+    let executor = "executor";
+    let member = "member";
+    let unbonding_period = 1000u64;
+    let luna = |amount: u128| native_token("luna".to_string(), amount);
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_min_bond(0)
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 1_000)])
+        .with_native_balances("juno", vec![(executor, 1_000)])
+        .with_native_balances("luna", vec![(executor, 1_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            executor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite
+        .create_distribution_flow(
+            "admin",
+            executor,
+            AssetInfo::SmartToken("luna".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite.delegate(member, 1_000, unbonding_period).unwrap();
+
+    // nothing withdrawn yet
+    assert_eq!(suite.lifetime_rewards(member).unwrap(), vec![]);
+
+    suite
+        .distribute_funds(executor, None, Some(juno(500)))
+        .unwrap();
+    suite
+        .distribute_funds(executor, None, Some(luna(300)))
+        .unwrap();
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(
+        suite.lifetime_rewards(member).unwrap(),
+        vec![juno(500), luna(300)]
+    );
+
+    // accrued rewards are not counted until they are withdrawn
+    suite
+        .distribute_funds(executor, None, Some(juno(200)))
+        .unwrap();
+    assert_eq!(
+        suite.lifetime_rewards(member).unwrap(),
+        vec![juno(500), luna(300)]
+    );
+
+    // withdrawing to a different receiver still counts for the owner
+    suite.withdraw_funds(member, None, executor).unwrap();
+    assert_eq!(
+        suite.lifetime_rewards(member).unwrap(),
+        vec![juno(700), luna(300)]
+    );
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 500);
+    assert_eq!(suite.lifetime_rewards(executor).unwrap(), vec![]);
+}
+
+#[test]
+fn reward_rate_follows_funding_curve() {
+    let distributor = "distributor";
+    let member = "member";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 100_000_000)])
+        .with_native_balances(COREUM_DENOM, vec![(distributor, 60_480_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    // no funding yet
+    assert_eq!(
+        suite.query_reward_rate(juno_info.clone()).unwrap(),
+        Decimal::zero()
+    );
+
+    // 100_000_000 / 1000 tokens per power = 100_000 rewards power
+    suite
+        .delegate(member, 100_000_000, unbonding_period)
+        .unwrap();
+
+    // 60.48 JUNO over 1 week is 100 ujuno per second
+    suite
+        .execute_fund_distribution_curve(distributor, COREUM_DENOM, 60_480_000, 86400 * 7)
+        .unwrap();
+
+    // 100 ujuno per second split over 100_000 rewards power
+    assert_eq!(
+        suite.query_reward_rate(juno_info.clone()).unwrap(),
+        Decimal::permille(1)
+    );
+
+    // the curve pays out linearly, so the rate stays the same halfway through
+    suite.update_time(86400 * 7 / 2);
+    assert_eq!(
+        suite.query_reward_rate(juno_info.clone()).unwrap(),
+        Decimal::permille(1)
+    );
+
+    // and drops to zero once the funding is over
+    suite.update_time(86400 * 4);
+    assert_eq!(suite.query_reward_rate(juno_info).unwrap(), Decimal::zero());
+}
+
+#[test]
+fn unbond_reward_impact_scales_with_amount_and_period() {
+    let distributor = "distributor";
+    let member = "member";
+    let short_period = 1000u64;
+    let long_period = 2000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+    let juno_validated = AssetInfoValidated::SmartToken(COREUM_DENOM.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![short_period, long_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 200_000_000)])
+        .with_native_balances(COREUM_DENOM, vec![(distributor, 60_480_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            juno_info,
+            vec![
+                (short_period, Decimal::one()),
+                (long_period, Decimal::one()),
+            ],
+        )
+        .unwrap();
+
+    // 100_000 rewards power in each period
+    suite.delegate(member, 100_000_000, short_period).unwrap();
+    suite.delegate(member, 100_000_000, long_period).unwrap();
+
+    // nothing is lost before there is any funding
+    let impact = suite
+        .query_unbond_reward_impact(member, 10_000_000, short_period)
+        .unwrap();
+    assert_eq!(
+        impact.power_reduction,
+        vec![(juno_validated.clone(), Uint128::new(10_000))]
+    );
+    assert_eq!(
+        impact.rewards_foregone,
+        vec![juno_validated.with_balance(0u128)]
+    );
+
+    // 100 ujuno per second split over 200_000 rewards power
+    suite
+        .execute_fund_distribution_curve(distributor, COREUM_DENOM, 60_480_000, 86400 * 7)
+        .unwrap();
+
+    // 10_000 power * 1000 seconds * 0.0005 per power per second
+    let impact = suite
+        .query_unbond_reward_impact(member, 10_000_000, short_period)
+        .unwrap();
+    assert_eq!(
+        impact.rewards_foregone,
+        vec![juno_validated.with_balance(5_000u128)]
+    );
+
+    // doubling the amount doubles the impact
+    let impact = suite
+        .query_unbond_reward_impact(member, 20_000_000, short_period)
+        .unwrap();
+    assert_eq!(
+        impact.power_reduction,
+        vec![(juno_validated.clone(), Uint128::new(20_000))]
+    );
+    assert_eq!(
+        impact.rewards_foregone,
+        vec![juno_validated.with_balance(10_000u128)]
+    );
+
+    // so does doubling the unbonding period
+    let impact = suite
+        .query_unbond_reward_impact(member, 10_000_000, long_period)
+        .unwrap();
+    assert_eq!(
+        impact.power_reduction,
+        vec![(juno_validated.clone(), Uint128::new(10_000))]
+    );
+    assert_eq!(
+        impact.rewards_foregone,
+        vec![juno_validated.with_balance(10_000u128)]
+    );
+
+    // cannot unbond more than staked or from an unknown period
+    suite
+        .query_unbond_reward_impact(member, 100_000_001, short_period)
+        .unwrap_err();
+    suite
+        .query_unbond_reward_impact(member, 10_000_000, 1500)
+        .unwrap_err();
+}
+
+#[test]
+fn remainder_is_carried_over_to_next_distribution() {
+    let executor = "executor";
+    let member = "member";
+    let unbonding_period = 1000u64;
+    // more rewards power than fits into the leftover shares, so rounding leaves whole tokens behind
+    let stake = 25_000_000_000u128;
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_min_bond(0)
+        .with_tokens_per_power(1)
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, stake)])
+        .with_native_balances("juno", vec![(executor, 10_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            executor,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite.delegate(member, stake, unbonding_period).unwrap();
+
+    let mut funded = 0u128;
+    let mut remainders = vec![];
+    for amount in [7, 13, 29, 1000, 3] {
+        suite
+            .distribute_funds(executor, None, Some(juno(amount)))
+            .unwrap();
+        funded += amount;
+
+        let (_, distribution) = suite.distribution_data().unwrap().remove(0);
+        assert_eq!(
+            distribution.distributed_total + distribution.remainder,
+            Uint128::new(funded)
+        );
+        remainders.push(distribution.remainder.u128());
+    }
+    // some tokens were actually held back and paid out later
+    assert_eq!(remainders, vec![1, 2, 2, 1, 4]);
+
+    // the member never gets more than what was distributed, the remainder stays in the contract
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert!(suite.query_balance(member, "juno").unwrap() <= funded - 4);
+    assert_eq!(suite.undistributed_funds().unwrap(), vec![juno(4)]);
+}
+
+#[test]
+fn marginal_power_matches_delegation() {
+    let member = "member";
+    let unbonding_periods = vec![100u64, 1000u64];
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_min_bond(1_000)
+        .with_admin("admin")
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 10_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            "distributor",
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![
+                (unbonding_periods[0], Decimal::one()),
+                (unbonding_periods[1], Decimal::percent(200)),
+            ],
+        )
+        .unwrap();
+
+    // below min_bond, no power is added
+    assert_eq!(
+        suite
+            .query_marginal_power(500, unbonding_periods[1], None)
+            .unwrap(),
+        juno_power(0)
+    );
+    // 2_000 * 2 / 1_000 = 4
+    let marginal = suite
+        .query_marginal_power(2_000, unbonding_periods[1], Some(member))
+        .unwrap();
+    assert_eq!(marginal, juno_power(4));
+
+    suite.delegate(member, 2_000, unbonding_periods[1]).unwrap();
+    assert_eq!(suite.query_rewards_power(member).unwrap(), marginal);
+
+    // existing stake is taken into account, so now even a small amount adds power
+    let marginal = suite
+        .query_marginal_power(500, unbonding_periods[1], Some(member))
+        .unwrap();
+    assert_eq!(marginal, juno_power(1));
+
+    suite.delegate(member, 500, unbonding_periods[1]).unwrap();
+    assert_eq!(suite.query_rewards_power(member).unwrap(), juno_power(5));
+}
+
+#[test]
+fn emission_rate_combines_funding_curves() {
+    let distributor = "distributor";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken("juno".to_string());
+    let luna_info = AssetInfo::SmartToken("luna".to_string());
+    let juno = AssetInfoValidated::SmartToken("juno".to_string());
+    let luna = AssetInfoValidated::SmartToken("luna".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("juno", vec![(distributor, 90_720_000)])
+        .with_native_balances("luna", vec![(distributor, 8_640_000)])
+        .build();
+
+    for info in [&juno_info, &luna_info] {
+        suite
+            .create_distribution_flow(
+                "admin",
+                distributor,
+                info.clone(),
+                vec![(unbonding_period, Decimal::one())],
+            )
+            .unwrap();
+    }
+
+    // nothing is funded yet
+    assert_eq!(suite.query_emission_rate().unwrap(), vec![]);
+
+    // 100 juno per second for a week, plus another 100 juno per second for half a week
+    suite
+        .execute_fund_distribution_curve(distributor, "juno", 60_480_000, 86400 * 7)
+        .unwrap();
+    suite
+        .execute_fund_distribution_curve(distributor, "juno", 30_240_000, 86400 * 7 / 2)
+        .unwrap();
+    // 10 luna per second for ten days
+    suite
+        .execute_fund_distribution_curve(distributor, "luna", 8_640_000, 86400 * 10)
+        .unwrap();
+
+    assert_eq!(
+        suite.query_emission_rate().unwrap(),
+        vec![
+            (juno.clone(), Decimal::percent(20_000)),
+            (luna.clone(), Decimal::percent(1_000)),
+        ]
+    );
+
+    // the shorter juno funding is over
+    suite.update_time(86400 * 4);
+    assert_eq!(
+        suite.query_emission_rate().unwrap(),
+        vec![
+            (juno.clone(), Decimal::percent(10_000)),
+            (luna.clone(), Decimal::percent(1_000)),
+        ]
+    );
+
+    // and then the longer one
+    suite.update_time(86400 * 3);
+    assert_eq!(
+        suite.query_emission_rate().unwrap(),
+        vec![(luna, Decimal::percent(1_000))]
+    );
+
+    // only finished curves are left
+    suite.update_time(86400 * 3);
+    assert_eq!(suite.query_emission_rate().unwrap(), vec![]);
+}
+
+#[test]
+fn future_funding_is_inert_until_start() {
+    let distributor = "distributor";
+    let member = "member";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 100_000_000)])
+        .with_native_balances(COREUM_DENOM, vec![(distributor, 60_480_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    // 100_000_000 / 1000 tokens per power = 100_000 rewards power
+    suite
+        .delegate(member, 100_000_000, unbonding_period)
+        .unwrap();
+
+    // 60.48 JUNO over 1 week, starting in a day, is 100 ujuno per second
+    let start_time = suite.app.block_info().time.seconds() + 86400;
+    suite
+        .execute_fund_distribution_at(distributor, COREUM_DENOM, 60_480_000, start_time, 86400 * 7)
+        .unwrap();
+
+    // the funding is only scheduled, so nothing is paid out yet
+    assert_eq!(suite.query_emission_rate().unwrap(), vec![]);
+    assert_eq!(
+        suite.query_scheduled_emissions().unwrap(),
+        vec![(juno(0).info, start_time, Decimal::percent(10_000))]
+    );
+    assert_eq!(
+        suite.query_reward_rate(juno_info.clone()).unwrap(),
+        Decimal::zero()
+    );
+    let annual_rewards = suite.query_annualized_rewards().unwrap();
+    assert_eq!(annual_rewards[0].1[0].amount, Some(Decimal::zero()));
+
+    // no rewards accrue before the start time
+    suite.update_time(86400 / 2);
+    suite.distribute_funds(distributor, None, None).unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(0)]);
+
+    suite.update_time(86400 / 2 - 1);
+    suite.distribute_funds(distributor, None, None).unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(0)]);
+
+    // once started, the funding pays out like any other
+    suite.update_time(1001);
+    assert_eq!(
+        suite.query_emission_rate().unwrap(),
+        vec![(juno(0).info, Decimal::percent(10_000))]
+    );
+    assert_eq!(suite.query_scheduled_emissions().unwrap(), vec![]);
+    assert_eq!(
+        suite.query_reward_rate(juno_info).unwrap(),
+        Decimal::permille(1)
+    );
+
+    suite.distribute_funds(distributor, None, None).unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(member).unwrap(),
+        vec![juno(100_000)]
+    );
+}
+
+#[test]
+fn remove_distribution_flow() {
+    let members = ["member1", "member2"];
+    let manager = "manager";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+    let luna_info = AssetInfo::SmartToken("luna".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_max_distributions(1)
+        .with_native_balances("tia", vec![(members[0], 20_000), (members[1], 30_000)])
+        .with_native_balances(COREUM_DENOM, vec![(manager, 550)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    // the only slot is taken
+    let err = suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            luna_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TooManyDistributions(1),
+        err.downcast().unwrap()
+    );
+
+    suite
+        .delegate(members[0], 10_000, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(members[1], 30_000, unbonding_period)
+        .unwrap();
+
+    // 400 are distributed, another 150 are funded but not distributed yet
+    suite
+        .distribute_funds(manager, None, Some(juno(400)))
+        .unwrap();
+    suite
+        .execute_fund_distribution_curve(manager, COREUM_DENOM, 150, 100)
+        .unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(members[0]).unwrap(),
+        vec![juno(100)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(members[1]).unwrap(),
+        vec![juno(300)]
+    );
+
+    // only the manager can remove the flow
+    let err = suite
+        .remove_distribution_flow("admin", juno_info.clone(), None)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // the undistributed rewards go to the receiver
+    suite
+        .remove_distribution_flow(manager, juno_info.clone(), "receiver")
+        .unwrap();
+    assert_eq!(suite.query_balance("receiver", COREUM_DENOM).unwrap(), 150);
+    let err = suite
+        .remove_distribution_flow(manager, juno_info.clone(), None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DistributionNotFound(juno(0).info),
+        err.downcast().unwrap()
+    );
+
+    // the slot can be used for a new flow
+    suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            luna_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    // but the removed asset cannot be reused while its rewards are not withdrawn
+    let err = suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::RemovedDistributionNotWithdrawn(juno(0).info),
+        err.downcast().unwrap()
+    );
+
+    // changing the stake does not change the rewards that were already distributed
+    suite
+        .delegate(members[0], 10_000, unbonding_period)
+        .unwrap();
+    suite.unbond(members[1], 10_000, unbonding_period).unwrap();
+
+    suite.withdraw_funds(members[0], None, None).unwrap();
+    assert_eq!(suite.query_balance(members[0], COREUM_DENOM).unwrap(), 100);
+    // the removed flow is kept until all of its rewards are withdrawn
+    assert_eq!(suite.distribution_data().unwrap().len(), 2);
+
+    suite.withdraw_funds(members[1], None, None).unwrap();
+    assert_eq!(suite.query_balance(members[1], COREUM_DENOM).unwrap(), 300);
+    let distributions = suite.distribution_data().unwrap();
+    assert_eq!(distributions.len(), 1);
+    assert_eq!(
+        distributions[0].0,
+        AssetInfoValidated::SmartToken("luna".to_string())
+    );
+}
+
+#[test]
+fn sweep_dust_of_removed_distribution_flow() {
+    let members = ["member1", "member2", "member3"];
+    let manager = "manager";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances(
+            "tia",
+            vec![
+                (members[0], 10_000),
+                (members[1], 10_000),
+                (members[2], 10_000),
+            ],
+        )
+        .with_native_balances(COREUM_DENOM, vec![(manager, 100)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    for member in members {
+        suite.delegate(member, 10_000, unbonding_period).unwrap();
+    }
+    suite
+        .distribute_funds(manager, None, Some(juno(100)))
+        .unwrap();
+    suite
+        .remove_distribution_flow(manager, juno_info.clone(), None)
+        .unwrap();
+
+    // every member withdraws 33, the last token is rounding dust nobody can withdraw
+    for member in members {
+        suite.withdraw_funds(member, None, None).unwrap();
+        assert_eq!(suite.query_balance(member, COREUM_DENOM).unwrap(), 33);
+    }
+    assert_eq!(suite.distribution_data().unwrap().len(), 1);
+    let err = suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::RemovedDistributionNotWithdrawn(juno(0).info),
+        err.downcast().unwrap()
+    );
+
+    // only the admin can sweep
+    let err = suite
+        .sweep_removed_distribution(manager, juno_info.clone())
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Admin(cw_controllers::AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    suite
+        .sweep_removed_distribution("admin", juno_info.clone())
+        .unwrap();
+    assert_eq!(suite.query_balance("admin", COREUM_DENOM).unwrap(), 1);
+    assert_eq!(suite.distribution_data().unwrap(), vec![]);
+
+    // only removed distributions can be swept
+    let err = suite
+        .sweep_removed_distribution("admin", juno_info.clone())
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DistributionNotFound(juno(0).info),
+        err.downcast().unwrap()
+    );
+
+    // the asset can be used for a new flow again
+    suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+}
+
+#[test]
+fn remove_distribution_flow_and_recreate_it() {
+    let member = "member";
+    let manager = "manager";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 10_000)])
+        .with_native_balances(COREUM_DENOM, vec![(manager, 150)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 10_000, unbonding_period).unwrap();
+    suite
+        .distribute_funds(manager, None, Some(juno(100)))
+        .unwrap();
+    suite.withdraw_funds(member, None, None).unwrap();
+
+    // nothing is left to withdraw, so the flow is deleted right away
+    suite
+        .remove_distribution_flow(manager, juno_info.clone(), None)
+        .unwrap();
+    assert_eq!(suite.distribution_data().unwrap(), vec![]);
+
+    // a new flow for the same asset does not inherit anything from the removed one
+    suite
+        .create_distribution_flow(
+            "admin",
+            manager,
+            juno_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite
+        .distribute_funds(manager, None, Some(juno(50)))
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(50)]);
+
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, COREUM_DENOM).unwrap(), 150);
+}
+
+#[test]
+fn total_distributed_accumulates() {
+    let member = "member";
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::SmartToken(COREUM_DENOM.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(member, 10_000)])
+        .with_native_balances(COREUM_DENOM, vec![(funder, 600)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 10_000, unbonding_period).unwrap();
+    assert_eq!(suite.total_distributed().unwrap(), vec![]);
+
+    // fund and distribute twice
+    suite
+        .execute_fund_distribution(funder, None, juno(400))
+        .unwrap();
+    suite.update_time(100);
+    suite.distribute_funds(funder, None, None).unwrap();
+    assert_eq!(suite.total_distributed().unwrap(), vec![juno(400)]);
+
+    suite
+        .execute_fund_distribution(funder, None, juno(200))
+        .unwrap();
+    suite.update_time(100);
+    suite.distribute_funds(funder, None, None).unwrap();
+    assert_eq!(suite.total_distributed().unwrap(), vec![juno(600)]);
+
+    // withdrawing does not change the total
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, COREUM_DENOM).unwrap(), 600);
+    assert_eq!(suite.total_distributed().unwrap(), vec![juno(600)]);
+
+    // and it is kept after the distribution flow is removed
+    suite
+        .remove_distribution_flow(funder, juno_info, None)
+        .unwrap();
+    assert_eq!(suite.distributed_funds().unwrap(), vec![]);
+    assert_eq!(suite.total_distributed().unwrap(), vec![juno(600)]);
+}
@@ -0,0 +1,109 @@
+use cosmwasm_std::Decimal;
+use dex::asset::AssetInfoValidated;
+
+use crate::error::ContractError;
+use crate::multitest::suite::SuiteBuilder;
+
+const DAY: u64 = 24 * 60 * 60;
+const UNBONDING_PERIODS: &[u64; 2] = &[DAY, 2 * DAY];
+const ADMIN: &str = "owner";
+const TREASURY: &str = "treasury";
+const VOTER1: &str = "voter1";
+const VOTER2: &str = "voter2";
+
+fn cash() -> AssetInfoValidated {
+    AssetInfoValidated::SmartToken("cash".to_string())
+}
+
+#[test]
+fn slash_moves_stake_to_recipient() {
+    let mut suite = SuiteBuilder::new()
+        .with_admin(ADMIN)
+        .with_min_bond(0)
+        .with_tokens_per_power(1)
+        .with_unbonding_periods(UNBONDING_PERIODS.to_vec())
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(VOTER1, 500), (VOTER2, 600)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            ADMIN,
+            ADMIN,
+            cash().into(),
+            vec![
+                (UNBONDING_PERIODS[0], Decimal::percent(100)),
+                (UNBONDING_PERIODS[1], Decimal::percent(200)),
+            ],
+        )
+        .unwrap();
+
+    suite.delegate(VOTER1, 500, UNBONDING_PERIODS[0]).unwrap();
+    suite.delegate(VOTER2, 600, UNBONDING_PERIODS[1]).unwrap();
+    assert_eq!(suite.query_total_rewards_power().unwrap()[0].1, 1700);
+
+    suite
+        .slash(ADMIN, VOTER1, 200, UNBONDING_PERIODS[0], TREASURY)
+        .unwrap();
+
+    // the slashed stake is gone, together with its rewards power
+    assert_eq!(
+        suite.query_staked(VOTER1, UNBONDING_PERIODS[0]).unwrap(),
+        300
+    );
+    assert_eq!(suite.query_rewards_power(VOTER1).unwrap()[0].1, 300);
+    assert_eq!(suite.query_total_rewards_power().unwrap()[0].1, 1500);
+    assert_eq!(suite.query_total_staked().unwrap(), 900);
+    // it does not turn into a claim
+    assert!(suite.query_claims(VOTER1).unwrap().is_empty());
+    // but is sent to the recipient instead
+    assert_eq!(suite.query_balance(TREASURY, "tia").unwrap(), 200);
+    assert_eq!(suite.query_balance(VOTER1, "tia").unwrap(), 0);
+    assert_eq!(suite.query_balance_staking_contract().unwrap(), 900);
+
+    // other stakers are not affected
+    assert_eq!(
+        suite.query_staked(VOTER2, UNBONDING_PERIODS[1]).unwrap(),
+        600
+    );
+    assert_eq!(suite.query_rewards_power(VOTER2).unwrap()[0].1, 1200);
+}
+
+#[test]
+fn slash_is_admin_only() {
+    let mut suite = SuiteBuilder::new()
+        .with_admin(ADMIN)
+        .with_unbonding_periods(UNBONDING_PERIODS.to_vec())
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(VOTER1, 5_000)])
+        .build();
+
+    suite.delegate(VOTER1, 5_000, UNBONDING_PERIODS[0]).unwrap();
+
+    let err = suite
+        .slash(VOTER2, VOTER1, 1_000, UNBONDING_PERIODS[0], VOTER2)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Admin(cw_controllers::AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    let err = suite
+        .slash(ADMIN, VOTER1, 0, UNBONDING_PERIODS[0], TREASURY)
+        .unwrap_err();
+    assert_eq!(ContractError::NoSlashAmount {}, err.downcast().unwrap());
+
+    // cannot slash more than the staker has in that period
+    suite
+        .slash(ADMIN, VOTER1, 5_001, UNBONDING_PERIODS[0], TREASURY)
+        .unwrap_err();
+    suite
+        .slash(ADMIN, VOTER1, 1_000, UNBONDING_PERIODS[1], TREASURY)
+        .unwrap_err();
+
+    assert_eq!(
+        suite.query_staked(VOTER1, UNBONDING_PERIODS[0]).unwrap(),
+        5_000
+    );
+    assert_eq!(suite.query_balance(TREASURY, "tia").unwrap(), 0);
+}
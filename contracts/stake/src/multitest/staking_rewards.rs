@@ -488,6 +488,159 @@ fn unbond_then_unbond_again() {
     );
 }
 
+#[test]
+fn rebond_to_longer_period() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let unbonding_period3 = 8000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![
+            unbonding_period1,
+            unbonding_period2,
+            unbonding_period3,
+        ])
+        .with_admin("admin")
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(user, 100_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            user,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![
+                (unbonding_period1, Decimal::percent(25)),
+                (unbonding_period2, Decimal::percent(60)),
+                (unbonding_period3, Decimal::percent(80)),
+            ],
+        )
+        .unwrap();
+
+    suite
+        .delegate(user, 100_000u128, unbonding_period1)
+        .unwrap();
+
+    // the same moves as in `unbond_then_unbond_again`, but without waiting
+    suite
+        .rebond(user, 40_000u128, unbonding_period1, unbonding_period2)
+        .unwrap();
+    suite
+        .rebond(user, 20_000u128, unbonding_period2, unbonding_period3)
+        .unwrap();
+
+    assert_eq!(suite.query_claims(user).unwrap(), vec![]);
+    assert_eq!(
+        suite.query_all_staked(user).unwrap(),
+        AllStakedResponse {
+            stakes: vec![
+                StakedResponse {
+                    stake: Uint128::new(60_000),
+                    total_locked: Uint128::zero(),
+                    unbonding_period: 1000,
+                    lp_share_denom: "tia".to_string(),
+                },
+                StakedResponse {
+                    stake: Uint128::new(20_000),
+                    total_locked: Uint128::zero(),
+                    unbonding_period: 4000,
+                    lp_share_denom: "tia".to_string(),
+                },
+                StakedResponse {
+                    stake: Uint128::new(20_000),
+                    total_locked: Uint128::zero(),
+                    unbonding_period: 8000,
+                    lp_share_denom: "tia".to_string(),
+                },
+            ]
+        }
+    );
+
+    // 0.25 * 60_000 + 0.6 * 20_000 + 0.8 * 20_000
+    assert_eq!(
+        suite.query_rewards_power(user).unwrap(),
+        vec![(AssetInfoValidated::SmartToken("juno".to_string()), 43u128)]
+    );
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(43));
+
+    // the rebonded tokens can be unbonded right away
+    suite.unbond(user, 20_000u128, unbonding_period3).unwrap();
+}
+
+#[test]
+fn rebond_to_shorter_period() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period3 = 8000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period3])
+        .with_admin("admin")
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(user, 100_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            user,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![
+                (unbonding_period1, Decimal::percent(25)),
+                (unbonding_period3, Decimal::percent(80)),
+            ],
+        )
+        .unwrap();
+
+    suite
+        .delegate(user, 100_000u128, unbonding_period3)
+        .unwrap();
+    suite
+        .rebond(user, 40_000u128, unbonding_period3, unbonding_period1)
+        .unwrap();
+
+    // the tokens are unbonded with a claim for the difference of the periods
+    let claims = suite.query_claims(user).unwrap();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].amount, Uint128::new(40_000));
+    assert_eq!(
+        suite.query_all_staked(user).unwrap(),
+        AllStakedResponse {
+            stakes: vec![StakedResponse {
+                stake: Uint128::new(60_000),
+                total_locked: Uint128::zero(),
+                unbonding_period: 8000,
+                lp_share_denom: "tia".to_string(),
+            }]
+        }
+    );
+    assert_eq!(suite.query_total_staked().unwrap(), 60_000u128);
+
+    // unbonding tokens do not earn rewards: 0.8 * 60_000
+    assert_eq!(
+        suite.query_rewards_power(user).unwrap(),
+        vec![(AssetInfoValidated::SmartToken("juno".to_string()), 48u128)]
+    );
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(48));
+
+    // the claim cannot be released before the difference of the periods has passed
+    let err = suite.claim(user).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    // afterwards the tokens can be claimed and delegated to the shorter period
+    suite.update_time(unbonding_period3 - unbonding_period1);
+    suite.claim(user).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(user).unwrap(),
+        40_000u128
+    );
+    suite.delegate(user, 40_000u128, unbonding_period1).unwrap();
+    assert_eq!(
+        suite.query_staked(user, unbonding_period1).unwrap(),
+        40_000u128
+    );
+}
+
 #[test]
 fn one_user_multiple_periods_delegate_or_unbond_fail() {
     let user = "user";
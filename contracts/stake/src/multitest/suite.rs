@@ -7,16 +7,21 @@ use coreum_wasm_sdk::core::{CoreumMsg, CoreumQueries};
 use cosmwasm_std::{coin, Addr, BankMsg, Coin, CosmosMsg, Decimal, StdResult, Uint128};
 use cw_controllers::{Claim, ClaimsResponse};
 use cw_multi_test::{AppResponse, Contract, ContractWrapper, Executor};
+use cw_utils::Expiration;
 use dex::{
     asset::{AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated},
     stake::{FundingInfo, InstantiateMsg, UnbondingPeriod},
 };
 
 use crate::msg::{
-    AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse, BondingInfoResponse,
-    BondingPeriodInfo, DistributedRewardsResponse, ExecuteMsg, QueryMsg, RewardsPowerResponse,
-    StakedResponse, TotalStakedResponse, UndistributedRewardsResponse, WithdrawableRewardsResponse,
+    AllClaimsResponse, AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse,
+    BalanceReconciliationResponse, BondingInfoResponse, BondingPeriodInfo, CanUnbondResponse,
+    DistributedRewardsResponse, DistributionDataResponse, EmissionRateResponse, ExecuteMsg,
+    LifetimeRewardsResponse, QueryMsg, RewardRateResponse, RewardsPowerResponse, StakedResponse,
+    TotalStakedResponse, UnbondAllPreviewResponse, UnbondRewardImpactResponse,
+    UnbondingScheduleEntry, UndistributedRewardsResponse, WithdrawableRewardsResponse,
 };
+use crate::state::Distribution;
 
 pub const SEVEN_DAYS: u64 = 604800;
 pub const VESTING_DENOM: &str = "VEST";
@@ -56,6 +61,11 @@ pub struct SuiteBuilder {
     pub unbonding_periods: Vec<UnbondingPeriod>,
     pub admin: Option<String>,
     pub unbonder: Option<String>,
+    pub backup_unbonders: Vec<String>,
+    pub unbond_grace_period: u64,
+    pub min_distribution_interval: u64,
+    pub auto_claim_on_delegate: bool,
+    pub max_distributions: u32,
     pub native_balances: Vec<(Addr, Coin)>,
 }
 
@@ -68,6 +78,11 @@ impl SuiteBuilder {
             unbonding_periods: vec![SEVEN_DAYS],
             admin: None,
             unbonder: None,
+            backup_unbonders: vec![],
+            unbond_grace_period: 0,
+            min_distribution_interval: 0,
+            auto_claim_on_delegate: false,
+            max_distributions: 6,
             native_balances: vec![],
         }
     }
@@ -111,6 +126,31 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_backup_unbonders(mut self, backup_unbonders: &[&str]) -> Self {
+        self.backup_unbonders = backup_unbonders.iter().map(|&u| u.to_owned()).collect();
+        self
+    }
+
+    pub fn with_unbond_grace_period(mut self, unbond_grace_period: u64) -> Self {
+        self.unbond_grace_period = unbond_grace_period;
+        self
+    }
+
+    pub fn with_min_distribution_interval(mut self, min_distribution_interval: u64) -> Self {
+        self.min_distribution_interval = min_distribution_interval;
+        self
+    }
+
+    pub fn with_auto_claim_on_delegate(mut self) -> Self {
+        self.auto_claim_on_delegate = true;
+        self
+    }
+
+    pub fn with_max_distributions(mut self, max_distributions: u32) -> Self {
+        self.max_distributions = max_distributions;
+        self
+    }
+
     pub fn with_unbonding_periods(mut self, unbonding_periods: Vec<UnbondingPeriod>) -> Self {
         self.unbonding_periods = unbonding_periods;
         self
@@ -150,7 +190,11 @@ impl SuiteBuilder {
                     unbonding_periods: self.unbonding_periods,
                     admin: self.admin,
                     unbonder: self.unbonder,
-                    max_distributions: 6,
+                    backup_unbonders: self.backup_unbonders,
+                    unbond_grace_period: self.unbond_grace_period,
+                    min_distribution_interval: self.min_distribution_interval,
+                    auto_claim_on_delegate: self.auto_claim_on_delegate,
+                    max_distributions: self.max_distributions,
                 },
                 &[],
                 "stake",
@@ -200,6 +244,18 @@ impl Suite {
         manager: &str,
         asset: AssetInfo,
         rewards: Vec<(UnbondingPeriod, Decimal)>,
+    ) -> AnyResult<AppResponse> {
+        self.create_distribution_flow_with_lock(sender, manager, asset, rewards, false)
+    }
+
+    // create a new distribution flow for staking, optionally locking rewards until unbond
+    pub fn create_distribution_flow_with_lock(
+        &mut self,
+        sender: &str,
+        manager: &str,
+        asset: AssetInfo,
+        rewards: Vec<(UnbondingPeriod, Decimal)>,
+        rewards_locked_until_unbond: bool,
     ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(sender),
@@ -208,11 +264,42 @@ impl Suite {
                 manager: manager.to_string(),
                 asset,
                 rewards,
+                rewards_locked_until_unbond,
+            },
+            &[],
+        )
+    }
+
+    pub fn remove_distribution_flow<'s>(
+        &mut self,
+        sender: &str,
+        asset: AssetInfo,
+        receiver: impl Into<Option<&'s str>>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::RemoveDistributionFlow {
+                asset,
+                receiver: receiver.into().map(str::to_owned),
             },
             &[],
         )
     }
 
+    pub fn sweep_removed_distribution(
+        &mut self,
+        sender: &str,
+        asset: AssetInfo,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::SweepRemovedDistribution { asset },
+            &[],
+        )
+    }
+
     // call to staking contract by sender
     pub fn delegate(
         &mut self,
@@ -229,13 +316,16 @@ impl Suite {
         sender: &str,
         amount: u128,
         unbonding_period: impl Into<Option<u64>>,
-        _delegate_as: Option<&str>,
+        delegate_as: Option<&str>,
     ) -> AnyResult<AppResponse> {
         let unbonding_period = self.unbonding_period_or_default(unbonding_period);
         self.app.execute_contract(
             Addr::unchecked(sender),
             self.stake_contract.clone(),
-            &ExecuteMsg::Delegate { unbonding_period },
+            &ExecuteMsg::Delegate {
+                unbonding_period,
+                delegate_as: delegate_as.map(str::to_owned),
+            },
             &[coin(amount, self.lp_share.clone())],
         )
     }
@@ -258,11 +348,94 @@ impl Suite {
         )
     }
 
+    pub fn slash(
+        &mut self,
+        sender: &str,
+        address: &str,
+        amount: u128,
+        unbonding_period: impl Into<Option<u64>>,
+        recipient: &str,
+    ) -> AnyResult<AppResponse> {
+        let unbonding_period = self.unbonding_period_or_default(unbonding_period);
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::Slash {
+                address: address.to_owned(),
+                unbonding_period,
+                amount: amount.into(),
+                recipient: recipient.to_owned(),
+            },
+            &[],
+        )
+    }
+
+    pub fn rebond(
+        &mut self,
+        sender: &str,
+        amount: u128,
+        bond_from: u64,
+        bond_to: u64,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::Rebond {
+                tokens: amount.into(),
+                bond_from,
+                bond_to,
+            },
+            &[],
+        )
+    }
+
+    pub fn cancel_unbond(
+        &mut self,
+        sender: &str,
+        release_at: Expiration,
+        unbonding_period: impl Into<Option<u64>>,
+    ) -> AnyResult<AppResponse> {
+        let Expiration::AtTime(release_at) = release_at else {
+            panic!("claims are released at a time");
+        };
+        let unbonding_period = self.unbonding_period_or_default(unbonding_period);
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::CancelUnbond {
+                release_at,
+                unbonding_period,
+            },
+            &[],
+        )
+    }
+
     pub fn claim(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.claim_to(sender, None)
+    }
+
+    pub fn claim_for(&mut self, sender: &str, addresses: &[&str]) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(sender),
             self.stake_contract.clone(),
-            &ExecuteMsg::Claim {},
+            &ExecuteMsg::ClaimFor {
+                addresses: addresses.iter().map(|addr| addr.to_string()).collect(),
+            },
+            &[],
+        )
+    }
+
+    pub fn claim_to<'s>(
+        &mut self,
+        sender: &str,
+        receiver: impl Into<Option<&'s str>>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::Claim {
+                receiver: receiver.into().map(str::to_owned),
+            },
             &[],
         )
     }
@@ -343,12 +516,29 @@ impl Suite {
     ) -> AnyResult<AppResponse> {
         let curr_block = self.app.block_info().time;
 
+        self.execute_fund_distribution_at(
+            executor,
+            denom,
+            amount,
+            curr_block.seconds(),
+            distribution_duration,
+        )
+    }
+
+    pub fn execute_fund_distribution_at(
+        &mut self,
+        executor: &str,
+        denom: impl Into<String>,
+        amount: u128,
+        start_time: u64,
+        distribution_duration: u64,
+    ) -> AnyResult<AppResponse> {
         self.app.execute_contract(
             Addr::unchecked(executor),
             self.stake_contract.clone(),
             &ExecuteMsg::FundDistribution {
                 funding_info: FundingInfo {
-                    start_time: curr_block.seconds(),
+                    start_time,
                     distribution_duration,
                     amount: Uint128::from(amount),
                 },
@@ -377,6 +567,50 @@ impl Suite {
         )
     }
 
+    pub fn withdraw_funds_split(
+        &mut self,
+        executor: &str,
+        splits: &[(&str, Decimal)],
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(executor),
+            self.stake_contract.clone(),
+            &ExecuteMsg::WithdrawRewardsSplit {
+                splits: splits
+                    .iter()
+                    .map(|(receiver, fraction)| (receiver.to_string(), *fraction))
+                    .collect(),
+            },
+            &[],
+        )
+    }
+
+    pub fn authorize_withdrawer(
+        &mut self,
+        owner: &str,
+        withdrawer: &str,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(owner),
+            self.stake_contract.clone(),
+            &ExecuteMsg::AuthorizeWithdrawer {
+                addr: withdrawer.to_owned(),
+            },
+            &[],
+        )
+    }
+
+    pub fn revoke_withdrawer(&mut self, owner: &str, withdrawer: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(owner),
+            self.stake_contract.clone(),
+            &ExecuteMsg::RevokeWithdrawer {
+                addr: withdrawer.to_owned(),
+            },
+            &[],
+        )
+    }
+
     pub fn withdrawable_rewards(&self, owner: &str) -> StdResult<Vec<AssetValidated>> {
         let resp: WithdrawableRewardsResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -387,6 +621,16 @@ impl Suite {
         Ok(resp.rewards)
     }
 
+    pub fn lifetime_rewards(&self, address: &str) -> StdResult<Vec<AssetValidated>> {
+        let resp: LifetimeRewardsResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::LifetimeRewards {
+                address: address.to_owned(),
+            },
+        )?;
+        Ok(resp.rewards)
+    }
+
     pub fn distributed_funds(&self) -> StdResult<Vec<AssetValidated>> {
         let resp: DistributedRewardsResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -395,6 +639,20 @@ impl Suite {
         Ok(resp.distributed)
     }
 
+    pub fn total_distributed(&self) -> StdResult<Vec<AssetValidated>> {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::TotalDistributed {})
+    }
+
+    pub fn distribution_data(&self) -> StdResult<Vec<(AssetInfoValidated, Distribution)>> {
+        let resp: DistributionDataResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::DistributionData {})?;
+        Ok(resp.distributions)
+    }
+
     pub fn withdrawable_funds(&self) -> StdResult<Vec<AssetValidated>> {
         let resp: DistributedRewardsResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -465,6 +723,42 @@ impl Suite {
         Ok(all_staked)
     }
 
+    pub fn query_can_unbond(
+        &self,
+        address: &str,
+        amount: u128,
+        unbonding_period: u64,
+    ) -> StdResult<CanUnbondResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::CanUnbond {
+                address: address.to_owned(),
+                amount: amount.into(),
+                unbonding_period,
+            },
+        )
+    }
+
+    pub fn query_average_lock(&self, address: &str) -> StdResult<u64> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::AverageLock {
+                address: address.to_owned(),
+            },
+        )
+    }
+
+    pub fn query_time_weighted_stake(&self, address: &str, since: u64) -> StdResult<u128> {
+        let stake: Uint128 = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::TimeWeightedStake {
+                address: address.to_owned(),
+                since,
+            },
+        )?;
+        Ok(stake.u128())
+    }
+
     pub fn query_total_staked(&self) -> StdResult<u128> {
         let total_staked: TotalStakedResponse = self
             .app
@@ -473,6 +767,19 @@ impl Suite {
         Ok(total_staked.total_staked.u128())
     }
 
+    pub fn query_unbond_all_preview(&self) -> StdResult<UnbondAllPreviewResponse> {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::UnbondAllPreview {})
+    }
+
+    pub fn query_balance_reconciliation(&self) -> StdResult<BalanceReconciliationResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::BalanceReconciliation {},
+        )
+    }
+
     pub fn query_claims(&self, address: &str) -> StdResult<Vec<Claim>> {
         let claims: ClaimsResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -483,6 +790,33 @@ impl Suite {
         Ok(claims.claims)
     }
 
+    pub fn query_all_claims(
+        &self,
+        start_after: Option<&str>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<(Addr, Vec<Claim>)>> {
+        let resp: AllClaimsResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::AllClaims {
+                start_after: start_after.map(str::to_owned),
+                limit,
+            },
+        )?;
+        Ok(resp.claims)
+    }
+
+    pub fn query_unbonding_schedule(
+        &self,
+        address: &str,
+    ) -> StdResult<Vec<UnbondingScheduleEntry>> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::UnbondingSchedule {
+                address: address.to_owned(),
+            },
+        )
+    }
+
     pub fn query_annualized_rewards(
         &self,
     ) -> StdResult<Vec<(UnbondingPeriod, Vec<AnnualizedReward>)>> {
@@ -493,6 +827,54 @@ impl Suite {
         Ok(apr.rewards)
     }
 
+    pub fn query_unbond_reward_impact(
+        &self,
+        address: &str,
+        amount: u128,
+        unbonding_period: u64,
+    ) -> StdResult<UnbondRewardImpactResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::UnbondRewardImpact {
+                address: address.to_owned(),
+                amount: amount.into(),
+                unbonding_period,
+            },
+        )
+    }
+
+    pub fn query_reward_rate(&self, asset: AssetInfo) -> StdResult<Decimal> {
+        let rate: RewardRateResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::RewardRate { asset })?;
+        Ok(rate.per_power_per_second)
+    }
+
+    pub fn query_emission_rate(&self) -> StdResult<Vec<(AssetInfoValidated, Decimal)>> {
+        let resp: EmissionRateResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::EmissionRate {})?;
+        Ok(resp
+            .rates
+            .into_iter()
+            .map(|rate| (rate.info, rate.per_second))
+            .collect())
+    }
+
+    pub fn query_scheduled_emissions(&self) -> StdResult<Vec<(AssetInfoValidated, u64, Decimal)>> {
+        let resp: EmissionRateResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::EmissionRate {})?;
+        Ok(resp
+            .scheduled
+            .into_iter()
+            .map(|emission| (emission.info, emission.start_time, emission.per_second))
+            .collect())
+    }
+
     pub fn query_rewards_power(&self, address: &str) -> StdResult<Vec<(AssetInfoValidated, u128)>> {
         let rewards: RewardsPowerResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -509,6 +891,28 @@ impl Suite {
             .collect())
     }
 
+    pub fn query_marginal_power(
+        &self,
+        amount: u128,
+        unbonding_period: u64,
+        address: Option<&str>,
+    ) -> StdResult<Vec<(AssetInfoValidated, u128)>> {
+        let rewards: RewardsPowerResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::MarginalPower {
+                amount: amount.into(),
+                unbonding_period,
+                address: address.map(str::to_owned),
+            },
+        )?;
+
+        Ok(rewards
+            .rewards
+            .into_iter()
+            .map(|(a, p)| (a, p.u128()))
+            .collect())
+    }
+
     pub fn query_total_rewards_power(&self) -> StdResult<Vec<(AssetInfoValidated, u128)>> {
         let rewards: RewardsPowerResponse = self
             .app
@@ -2,7 +2,7 @@ use std::vec;
 
 use cosmwasm_std::{Addr, Decimal};
 use cw_multi_test::Executor;
-use dex::asset::AssetInfo;
+use dex::asset::{AssetInfo, AssetInfoValidated};
 
 use crate::{
     msg::ExecuteMsg,
@@ -190,6 +190,42 @@ fn delegate_as_with_unbond_all_flag() {
     );
 }
 
+#[test]
+fn cancel_unbond_with_unbond_all_flag() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(user, 100_000)])
+        .with_unbonder(UNBONDER)
+        .with_unbond_grace_period(86400)
+        .build();
+
+    suite.delegate(user, 50_000u128, None).unwrap();
+    suite.unbond(user, 20_000u128, None).unwrap();
+    let claims = suite.query_claims(user).unwrap();
+
+    // Set unbond all flag to true.
+    let stake_contract = suite.stake_contract();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(UNBONDER),
+            Addr::unchecked(stake_contract),
+            &ExecuteMsg::UnbondAll {},
+            &[],
+        )
+        .unwrap();
+
+    // Cannot cancel an unbond if unbond all.
+    let err = suite
+        .cancel_unbond(user, claims[0].release_at, None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CannotCancelUnbondIfUnbondAll {},
+        err.downcast().unwrap()
+    );
+}
+
 #[test]
 fn multiple_distribution_flows() {
     let user = "user";
@@ -257,3 +293,170 @@ fn multiple_distribution_flows() {
         err.downcast().unwrap()
     );
 }
+
+#[test]
+fn backup_unbonders() {
+    let admin = "admin";
+    let backup = "backup";
+    let stake_contract_msg = |suite: &mut super::suite::Suite, sender: &str, msg: ExecuteMsg| {
+        let stake_contract = suite.stake_contract();
+        suite.app.execute_contract(
+            Addr::unchecked(sender),
+            Addr::unchecked(stake_contract),
+            &msg,
+            &[],
+        )
+    };
+    let mut suite = SuiteBuilder::new()
+        .with_admin(admin)
+        .with_lp_share_denom("tia".to_string())
+        .with_unbonder(UNBONDER)
+        .with_backup_unbonders(&[backup])
+        .build();
+
+    // Random user cannot set the flag.
+    let err = stake_contract_msg(&mut suite, "user", ExecuteMsg::UnbondAll {}).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // Backup unbonder can set and unset the flag.
+    stake_contract_msg(&mut suite, backup, ExecuteMsg::UnbondAll {}).unwrap();
+    stake_contract_msg(&mut suite, backup, ExecuteMsg::StopUnbondAll {}).unwrap();
+
+    // Only admin can update the unbonders.
+    let err = stake_contract_msg(
+        &mut suite,
+        UNBONDER,
+        ExecuteMsg::UpdateUnbonders {
+            unbonder: None,
+            backup_unbonders: vec![],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        ContractError::Admin(cw_controllers::AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+    stake_contract_msg(
+        &mut suite,
+        admin,
+        ExecuteMsg::UpdateUnbonders {
+            unbonder: Some(backup.to_owned()),
+            backup_unbonders: vec!["backup2".to_owned()],
+        },
+    )
+    .unwrap();
+
+    // Previous unbonder lost its permissions.
+    let err = stake_contract_msg(&mut suite, UNBONDER, ExecuteMsg::UnbondAll {}).unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // New backup unbonder can set the flag, new main unbonder can unset it.
+    stake_contract_msg(&mut suite, "backup2", ExecuteMsg::UnbondAll {}).unwrap();
+    stake_contract_msg(&mut suite, backup, ExecuteMsg::StopUnbondAll {}).unwrap();
+}
+
+#[test]
+fn unbond_all_preview_matches_effects() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonder(UNBONDER)
+        .with_unbonding_periods(vec![SEVEN_DAYS])
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(user, 100_000)])
+        .with_native_balances("juno", vec![(user, 1_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            user,
+            AssetInfo::SmartToken("juno".to_string()),
+            vec![(SEVEN_DAYS, Decimal::one())],
+        )
+        .unwrap();
+
+    suite.delegate(user, 50_000u128, SEVEN_DAYS).unwrap();
+    suite.unbond(user, 10_000u128, SEVEN_DAYS).unwrap();
+
+    let preview = suite.query_unbond_all_preview().unwrap();
+    assert!(!preview.already_set);
+    assert_eq!(preview.releasable.u128(), 40_000u128);
+    assert_eq!(preview.unbonding.u128(), 10_000u128);
+    assert_eq!(
+        preview.halted_distributions,
+        vec![AssetInfoValidated::SmartToken("juno".to_string())]
+    );
+
+    let stake_contract = suite.stake_contract();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(UNBONDER),
+            Addr::unchecked(stake_contract),
+            &ExecuteMsg::UnbondAll {},
+            &[],
+        )
+        .unwrap();
+
+    let preview = suite.query_unbond_all_preview().unwrap();
+    assert!(preview.already_set);
+
+    // Releasable tokens are sent out right away without creating a claim.
+    suite.unbond(user, 40_000u128, SEVEN_DAYS).unwrap();
+    assert_eq!(suite.query_claims(user).unwrap().len(), 1);
+    assert_eq!(suite.query_balance(user, "tia").unwrap(), 90_000u128);
+
+    // Halted distributions cannot be funded anymore.
+    let err = suite
+        .execute_fund_distribution(user, None, juno(400))
+        .unwrap_err();
+    assert_eq!(
+        ContractError::CannotDistributeIfUnbondAll {
+            what: "funds".into()
+        },
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn can_unbond_with_unbond_all_flag() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_lp_share_denom("tia".to_string())
+        .with_native_balances("tia", vec![(user, 100_000)])
+        .with_unbonder(UNBONDER)
+        .build();
+
+    suite.delegate(user, 50_000u128, None).unwrap();
+
+    let stake_contract = suite.stake_contract();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(UNBONDER),
+            Addr::unchecked(stake_contract),
+            &ExecuteMsg::UnbondAll {},
+            &[],
+        )
+        .unwrap();
+
+    // Unbond all does not block unbonding, it only skips the unbonding period.
+    let res = suite
+        .query_can_unbond(user, 50_000u128, SEVEN_DAYS)
+        .unwrap();
+    assert!(res.allowed);
+    assert_eq!(res.reason, None);
+    let res = suite
+        .query_can_unbond(user, 50_001u128, SEVEN_DAYS)
+        .unwrap();
+    assert!(!res.allowed);
+
+    suite.unbond(user, 50_000u128, None).unwrap();
+    assert!(
+        !suite
+            .query_can_unbond(user, 1u128, SEVEN_DAYS)
+            .unwrap()
+            .allowed
+    );
+}
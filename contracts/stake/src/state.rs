@@ -3,14 +3,26 @@ use serde::{Deserialize, Serialize};
 use wynd_curve_utils::Curve;
 
 use crate::{utils::calc_power, ContractError};
-use cosmwasm_std::{Addr, Decimal, Env, OverflowError, StdResult, Storage, Timestamp, Uint128};
-use cw_controllers::{Admin, Claims};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{
+    Addr, Decimal, Env, Order, OverflowError, StdResult, Storage, Timestamp, Uint128, Uint256,
+};
+use cw_controllers::{Admin, Claim, Claims};
+use cw_storage_plus::{Bound, Item, Map};
 use dex::asset::AssetInfoValidated;
 use dex::stake::UnbondingPeriod;
 
 pub const CLAIMS: Claims = Claims::new("claims");
 
+/// Raw access to the claims stored by [`CLAIMS`].
+/// The `Claims` API does not provide a way to delete unmature claims or to iterate over all addresses.
+pub(crate) const CLAIMS_MAP: Map<&Addr, Vec<Claim>> = Map::new("claims");
+
+/// Unbonding periods the claims were unbonded from, keyed by staker and the claims' release time in nanoseconds.
+/// Claims releasing at the same time are listed in the order they were created.
+/// Only tracked if an unbond grace period is configured, since it is needed to cancel an unbond.
+pub const CLAIM_UNBONDING_PERIODS: Map<(&Addr, u64), Vec<UnbondingPeriod>> =
+    Map::new("claim_unbonding_periods");
+
 #[cw_serde]
 pub struct Config {
     /// denom of smart token contract token to stake
@@ -25,6 +37,25 @@ pub struct Config {
     pub max_distributions: u32,
     /// Address of the account that can call [`ExecuteMsg::QuickUnbond`]
     pub unbonder: Option<Addr>,
+    /// Additional accounts that have the same rights as the `unbonder`
+    #[serde(default)]
+    pub backup_unbonders: Vec<Addr>,
+    /// Time in seconds after unbonding during which the unbond can be cancelled
+    #[serde(default)]
+    pub unbond_grace_period: u64,
+    /// Minimum time in seconds between two reward distributions of the same asset
+    #[serde(default)]
+    pub min_distribution_interval: u64,
+    /// When this is set to `true`, delegating also pays out the staker's matured claims
+    #[serde(default)]
+    pub auto_claim_on_delegate: bool,
+}
+
+impl Config {
+    /// Returns true if the given address is the unbonder or one of the backup unbonders
+    pub fn is_unbonder(&self, addr: &Addr) -> bool {
+        self.unbonder.as_ref() == Some(addr) || self.backup_unbonders.contains(addr)
+    }
 }
 
 #[cw_serde]
@@ -150,6 +181,129 @@ pub const TOTAL_STAKED: Item<TokenInfo> = Item::new("total_staked");
 
 pub const STAKE: Map<(&Addr, UnbondingPeriod), BondingInfo> = Map::new("stake");
 
+/// Time of the last delegation of a staker into the given unbonding period.
+/// Used to lock rewards of distributions with `rewards_locked_until_unbond` set.
+pub const LAST_DELEGATION: Map<(&Addr, UnbondingPeriod), Timestamp> = Map::new("last_delegation");
+
+/// The total stake of an address at the time of a change, used for time-weighted stake queries
+#[cw_serde]
+#[derive(Default)]
+pub struct StakeCheckpoint {
+    /// Total stake of the address over all unbonding periods from this checkpoint on
+    pub stake: Uint128,
+    /// Sum of stake times seconds from the first checkpoint until this one
+    pub cumulative: Uint256,
+}
+
+/// Stake checkpoints of each address, keyed by the time (in seconds) of the change.
+/// Changes before this was introduced are not tracked. Stakers from before that are assumed to
+/// have held their stake since [`STAKE_CHECKPOINTS_START`], see [`seed_stake_checkpoint`].
+pub const STAKE_CHECKPOINTS: Map<(&Addr, u64), StakeCheckpoint> = Map::new("stake_checkpoints");
+
+/// Time (in seconds) of the migration that introduced [`STAKE_CHECKPOINTS`].
+/// Stake before that time counts as zero.
+pub const STAKE_CHECKPOINTS_START: Item<u64> = Item::new("stake_checkpoints_start");
+
+/// Returns the checkpoint of a staker from before [`STAKE_CHECKPOINTS`] was introduced
+/// who did not change their stake since, along with its time.
+/// This is their current stake, held since [`STAKE_CHECKPOINTS_START`].
+fn legacy_checkpoint(
+    storage: &dyn Storage,
+    staker: &Addr,
+) -> StdResult<Option<(u64, StakeCheckpoint)>> {
+    let Some(start) = STAKE_CHECKPOINTS_START.may_load(storage)? else {
+        return Ok(None);
+    };
+    let has_checkpoints = STAKE_CHECKPOINTS
+        .prefix(staker)
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if has_checkpoints {
+        return Ok(None);
+    }
+    let stake = total_stake_of(storage, staker)?;
+    Ok((!stake.is_zero()).then(|| {
+        (
+            start,
+            StakeCheckpoint {
+                stake,
+                cumulative: Uint256::zero(),
+            },
+        )
+    }))
+}
+
+/// Returns the latest stake checkpoint of `staker` at or before `time`, along with its time.
+fn checkpoint_at(
+    storage: &dyn Storage,
+    staker: &Addr,
+    time: u64,
+) -> StdResult<Option<(u64, StakeCheckpoint)>> {
+    STAKE_CHECKPOINTS
+        .prefix(staker)
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive(time)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map_or_else(
+            || Ok(legacy_checkpoint(storage, staker)?.filter(|(start, _)| *start <= time)),
+            |checkpoint| Ok(Some(checkpoint)),
+        )
+}
+
+/// Returns the stake of `staker` at `time` and the sum of its stake times seconds up to `time`.
+pub fn cumulative_stake_at(
+    storage: &dyn Storage,
+    staker: &Addr,
+    time: u64,
+) -> StdResult<(Uint128, Uint256)> {
+    Ok(match checkpoint_at(storage, staker, time)? {
+        Some((checkpoint_time, checkpoint)) => (
+            checkpoint.stake,
+            checkpoint.cumulative + checkpoint.stake.full_mul(time - checkpoint_time),
+        ),
+        None => (Uint128::zero(), Uint256::zero()),
+    })
+}
+
+/// Returns the total stake of `staker` over all unbonding periods.
+fn total_stake_of(storage: &dyn Storage, staker: &Addr) -> StdResult<Uint128> {
+    STAKE
+        .prefix(staker)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1.total_stake()))
+        .sum()
+}
+
+/// Records the current total stake of `staker` in [`STAKE_CHECKPOINTS`].
+/// Make sure to call this after every change of the staker's [`STAKE`].
+pub fn checkpoint_stake(storage: &mut dyn Storage, env: &Env, staker: &Addr) -> StdResult<()> {
+    let now = env.block.time.seconds();
+    let (_, cumulative) = cumulative_stake_at(storage, staker, now)?;
+    let stake = total_stake_of(storage, staker)?;
+
+    STAKE_CHECKPOINTS.save(
+        storage,
+        (staker, now),
+        &StakeCheckpoint { stake, cumulative },
+    )
+}
+
+/// Stores the checkpoint of a staker from before [`STAKE_CHECKPOINTS`] was introduced,
+/// so their stake until now is kept when it changes.
+/// Make sure to call this before every change of the staker's [`STAKE`].
+pub fn seed_stake_checkpoint(storage: &mut dyn Storage, staker: &Addr) -> StdResult<()> {
+    if let Some((start, checkpoint)) = legacy_checkpoint(storage, staker)? {
+        STAKE_CHECKPOINTS.save(storage, (staker, start), &checkpoint)?;
+    }
+    Ok(())
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct TotalStake {
     /// Total stake
@@ -206,6 +360,26 @@ pub struct Distribution {
     pub manager: Addr,
     /// Rewards multiplier by unbonding period for this distribution
     pub reward_multipliers: Vec<(UnbondingPeriod, Decimal)>,
+    /// If set, rewards can only be withdrawn after the staker's delegations
+    /// have been bonded for their full unbonding period
+    #[serde(default)]
+    pub rewards_locked_until_unbond: bool,
+    /// Rewards which were received, but could not be assigned to any staker yet because of
+    /// rounding. They are carried over and paid out with the following distributions.
+    #[serde(default)]
+    pub remainder: Uint128,
+    /// Block time in seconds of the last distribution that assigned rewards
+    #[serde(default)]
+    pub last_distributed: u64,
+    /// Set once the distribution flow was removed. A removed distribution does not receive any new
+    /// rewards and does not count towards `max_distributions`, but is kept until its
+    /// distributed rewards are withdrawn.
+    #[serde(default)]
+    pub removed: bool,
+    /// Distinguishes this distribution from earlier, removed distributions of the same asset.
+    /// See [`DISTRIBUTION_GENERATION`].
+    #[serde(default)]
+    pub generation: u32,
 }
 
 impl Distribution {
@@ -278,18 +452,54 @@ pub struct WithdrawAdjustment {
     pub shares_correction: i128,
     /// How much funds addresses already withdrawn.
     pub withdrawn_rewards: Uint128,
+    /// The generation of the distribution this adjustment belongs to.
+    /// Adjustments of an earlier generation are left over from a removed distribution and ignored.
+    #[serde(default)]
+    pub generation: u32,
+}
+
+/// Rewards of a staker accrued by the stake in a single unbonding period, tracked for
+/// distributions with `rewards_locked_until_unbond` set.
+#[cw_serde]
+#[derive(Default)]
+pub struct LockedRewards {
+    /// Points correction of this unbonding period, like [`WithdrawAdjustment::shares_correction`]
+    pub shares_correction: i128,
+    /// Rewards of this unbonding period that are already unlocked, including withdrawn ones
+    pub unlocked: Uint128,
+    /// The generation of the distribution these rewards belong to
+    pub generation: u32,
 }
 
 /// Rewards distribution data
 pub const DISTRIBUTION: Map<&AssetInfoValidated, Distribution> = Map::new("distribution");
+/// Generation of the next distribution created for an asset.
+/// It is increased whenever a distribution flow is removed.
+pub const DISTRIBUTION_GENERATION: Map<&AssetInfoValidated, u32> =
+    Map::new("distribution_generation");
 /// Information how to exactly adjust rewards while withdrawal.
 /// This is per user, so it applies to all distributions.
 pub const WITHDRAW_ADJUSTMENT: Map<(&Addr, &AssetInfoValidated), WithdrawAdjustment> =
     Map::new("withdraw_adjustment");
 
+/// Rewards of distributions with `rewards_locked_until_unbond` set, per staker and unbonding period
+pub const LOCKED_REWARDS: Map<(&Addr, &AssetInfoValidated, UnbondingPeriod), LockedRewards> =
+    Map::new("locked_rewards");
+
+/// Total amount of rewards ever withdrawn by an address, per asset
+pub const LIFETIME_REWARDS: Map<(&Addr, &AssetInfoValidated), Uint128> =
+    Map::new("lifetime_rewards");
+
+/// Total amount of rewards ever distributed, per asset.
+/// Unlike `Distribution::distributed_total`, this is kept when a distribution flow is removed.
+pub const TOTAL_DISTRIBUTED: Map<&AssetInfoValidated, Uint128> = Map::new("total_distributed");
+
 /// User delegated for funds withdrawal
 pub const DELEGATED: Map<&Addr, Addr> = Map::new("delegated");
 
+/// Addresses allowed to withdraw rewards to the owner, keyed by (owner, withdrawer)
+pub const AUTHORIZED_WITHDRAWERS: Map<(&Addr, &Addr), ()> = Map::new("authorized_withdrawers");
+
 /// Flag to allow fast unbonding in emergency cases.
 pub const UNBOND_ALL: Item<bool> = Item::new("unbond_all");
 
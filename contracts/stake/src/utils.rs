@@ -31,6 +31,13 @@ pub trait CurveExt {
     /// Returns the last `x` value of the curve, if any.
     /// This will be `None` for infinite and empty curves.
     fn end(&self) -> Option<u64>;
+
+    /// Returns by how much the curve decreases per unit of `x` at the given `x` value.
+    /// This is zero for flat or increasing sections.
+    fn decline_rate(&self, x: u64) -> Decimal;
+
+    /// Returns the first `x` value at or after the given one from which the curve decreases, if any.
+    fn next_decline(&self, x: u64) -> Option<u64>;
 }
 
 impl CurveExt for Curve {
@@ -49,6 +56,22 @@ impl CurveExt for Curve {
             Curve::PiecewiseLinear(pl) => pl.end(),
         }
     }
+
+    fn decline_rate(&self, x: u64) -> Decimal {
+        match self {
+            Curve::Constant { .. } => Decimal::zero(),
+            Curve::SaturatingLinear(sl) => sl.decline_rate(x),
+            Curve::PiecewiseLinear(pl) => pl.decline_rate(x),
+        }
+    }
+
+    fn next_decline(&self, x: u64) -> Option<u64> {
+        match self {
+            Curve::Constant { .. } => None,
+            Curve::SaturatingLinear(sl) => sl.next_decline(x),
+            Curve::PiecewiseLinear(pl) => pl.next_decline(x),
+        }
+    }
 }
 
 impl CurveExt for SaturatingLinear {
@@ -62,6 +85,21 @@ impl CurveExt for SaturatingLinear {
     fn end(&self) -> Option<u64> {
         Some(self.max_x)
     }
+
+    fn decline_rate(&self, x: u64) -> Decimal {
+        if (self.min_x..self.max_x).contains(&x) {
+            Decimal::from_ratio(
+                self.min_y.saturating_sub(self.max_y),
+                self.max_x - self.min_x,
+            )
+        } else {
+            Decimal::zero()
+        }
+    }
+
+    fn next_decline(&self, x: u64) -> Option<u64> {
+        (x < self.max_x && self.min_y > self.max_y).then(|| self.min_x.max(x))
+    }
 }
 
 impl CurveExt for PiecewiseLinear {
@@ -75,4 +113,19 @@ impl CurveExt for PiecewiseLinear {
     fn end(&self) -> Option<u64> {
         self.steps.last().map(|(x, _)| *x)
     }
+
+    fn decline_rate(&self, x: u64) -> Decimal {
+        self.steps
+            .windows(2)
+            .find(|w| (w[0].0..w[1].0).contains(&x))
+            .map(|w| Decimal::from_ratio(w[0].1.saturating_sub(w[1].1), w[1].0 - w[0].0))
+            .unwrap_or_default()
+    }
+
+    fn next_decline(&self, x: u64) -> Option<u64> {
+        self.steps
+            .windows(2)
+            .find(|w| x < w[1].0 && w[0].1 > w[1].1)
+            .map(|w| w[0].0.max(x))
+    }
 }
@@ -7,8 +7,9 @@ use cosmwasm_std::{
     to_json_binary, Addr, Coin, Decimal, OwnedDeps, Querier, QuerierResult, QueryRequest,
     SystemError, SystemResult, Uint128, WasmQuery,
 };
+use dex::asset::{Asset, AssetInfo};
 use dex::factory::{
-    ConfigResponse, FeeInfoResponse,
+    ConfigResponse, DefaultStakeConfig, FeeInfoResponse,
     QueryMsg::{Config, FeeInfo},
 };
 
@@ -96,9 +97,26 @@ impl WhelpMockQuerier {
                                 owner: Addr::unchecked("owner"),
                                 pool_configs: vec![],
                                 fee_address: Some(Addr::unchecked("fee_address")),
+                                creation_fee_address: None,
                                 max_referral_commission: Decimal::one(),
+                                default_stake_config: DefaultStakeConfig {
+                                    staking_code_id: 0,
+                                    tokens_per_power: Uint128::new(1000),
+                                    min_bond: Uint128::new(1000),
+                                    unbonding_periods: vec![],
+                                    max_distributions: 6,
+                                    auto_claim_on_delegate: false,
+                                },
                                 only_owner_can_create_pools: true,
+                                pool_creation_fee: Asset {
+                                    info: AssetInfo::SmartToken("coreum".to_string()),
+                                    amount: Uint128::zero(),
+                                },
                                 trading_starts: None,
+                                require_trading_starts: false,
+                                guardian: None,
+                                pool_creation_paused: false,
+                                max_pools: None,
                             })
                             .into(),
                         ),
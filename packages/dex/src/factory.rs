@@ -1,5 +1,5 @@
 use crate::{
-    asset::{Asset, AssetInfo},
+    asset::{Asset, AssetInfo, AssetInfoValidated},
     fee_config::FeeConfig,
     pool::{PairInfo, StakeConfig},
     stake::UnbondingPeriod,
@@ -11,6 +11,10 @@ use cw20::Cw20ReceiveMsg;
 use cw_storage_plus::Map;
 use std::fmt::{Display, Formatter, Result};
 
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+/// The maximum amount of seconds that the trading can be delayed when a factory or pool is instantiated.
+pub const MAX_TRADING_STARTS_DELAY: u64 = 60 * SECONDS_PER_DAY;
+
 /// This enum describes available pool types.
 /// ## Available pool types
 /// ```
@@ -52,6 +56,10 @@ pub struct PoolConfig {
     /// Whether a pool type is disabled or not. If it is disabled, new pools cannot be
     /// created, but existing ones can still read the pool configuration
     pub is_disabled: bool,
+    /// Overrides for the factory's `default_stake_config` when creating pools of this type.
+    /// Staking config passed on pool creation still takes precedence over these.
+    #[serde(default)]
+    pub stake_config_override: Option<PartialStakeConfig>,
 }
 
 /// This structure stores the basic settings for creating a new factory contract.
@@ -62,6 +70,10 @@ pub struct InstantiateMsg {
     /// Contract address to send governance fees to (the protocol).
     /// If this is not specified, no protocol fees are paid out regardless of the fee configuration
     pub fee_address: Option<String>,
+    /// Address to send the collected pool creation fees to.
+    /// If this is not specified, they are sent to the owner.
+    #[serde(default)]
+    pub creation_fee_address: Option<String>,
     /// Address of owner that is allowed to change factory contract parameters.
     pub owner: String,
     /// Maximum referral commission
@@ -70,6 +82,9 @@ pub struct InstantiateMsg {
     pub default_stake_config: DefaultStakeConfig,
     /// The block time until which trading is disabled
     pub trading_starts: Option<u64>,
+    /// If set, new pools can only be created with a trading start in the future
+    #[serde(default)]
+    pub require_trading_starts: bool,
     /// Fee required to create non-verified pool
     pub pool_creation_fee: Asset,
 }
@@ -82,6 +97,9 @@ pub struct DefaultStakeConfig {
     pub min_bond: Uint128,
     pub unbonding_periods: Vec<u64>,
     pub max_distributions: u32,
+    /// If set, delegating to the staking contract also pays out the staker's matured claims
+    #[serde(default)]
+    pub auto_claim_on_delegate: bool,
 }
 
 impl DefaultStakeConfig {
@@ -101,6 +119,9 @@ impl DefaultStakeConfig {
         if let Some(max_distributions) = partial.max_distributions {
             self.max_distributions = max_distributions;
         }
+        if let Some(auto_claim_on_delegate) = partial.auto_claim_on_delegate {
+            self.auto_claim_on_delegate = auto_claim_on_delegate;
+        }
 
         self
     }
@@ -121,6 +142,9 @@ impl DefaultStakeConfig {
         if let Some(max_distributions) = partial.max_distributions {
             self.max_distributions = max_distributions;
         }
+        if let Some(auto_claim_on_delegate) = partial.auto_claim_on_delegate {
+            self.auto_claim_on_delegate = auto_claim_on_delegate;
+        }
     }
 
     pub fn to_stake_config(self) -> StakeConfig {
@@ -130,6 +154,7 @@ impl DefaultStakeConfig {
             min_bond: self.min_bond,
             unbonding_periods: self.unbonding_periods,
             max_distributions: self.max_distributions,
+            auto_claim_on_delegate: self.auto_claim_on_delegate,
         }
     }
 }
@@ -142,6 +167,8 @@ pub struct PartialDefaultStakeConfig {
     pub min_bond: Option<Uint128>,
     pub unbonding_periods: Option<Vec<u64>>,
     pub max_distributions: Option<u32>,
+    #[serde(default)]
+    pub auto_claim_on_delegate: Option<bool>,
 }
 
 /// This structure describes the execute messages of the contract.
@@ -151,10 +178,19 @@ pub enum ExecuteMsg {
     UpdateConfig {
         /// Contract address to send governance fees to (the protocol)
         fee_address: Option<String>,
+        /// Address to send the collected pool creation fees to
+        #[serde(default)]
+        creation_fee_address: Option<String>,
         /// Whether only the owner or anyone can create new pools
         only_owner_can_create_pools: Option<bool>,
         /// The default configuration for the staking contracts of new pools
         default_stake_config: Option<PartialDefaultStakeConfig>,
+        /// Whether new pools must have a trading start in the future
+        #[serde(default)]
+        require_trading_starts: Option<bool>,
+        /// The maximum referral commission, at most 1
+        #[serde(default)]
+        max_referral_commission: Option<Decimal>,
     },
     /// UpdatePoolConfig updates the config for a pool type.
     UpdatePoolConfig {
@@ -176,6 +212,15 @@ pub enum ExecuteMsg {
         /// Config for the staking contract
         #[serde(default)]
         staking_config: PartialStakeConfig,
+        /// An existing staking contract to register for the pool.
+        /// If provided, no new staking contract is instantiated. Only the owner can set this.
+        /// It must stake the LP share denom of the new pool and must not be used by another pool.
+        #[serde(default)]
+        existing_staking_addr: Option<String>,
+        /// The block time until which trading is disabled for this pool.
+        /// If not provided, the factory's `trading_starts` is used.
+        #[serde(default)]
+        trading_starts: Option<u64>,
     },
     /// UpdatePoolFees updates the fees for a pool.
     /// This just sends the corresponding message to the pool.
@@ -190,6 +235,48 @@ pub enum ExecuteMsg {
         /// The assets for which we deregister a pool
         asset_infos: Vec<AssetInfo>,
     },
+    /// ReRegisterPool adds a previously deregistered pool back to the factory.
+    /// The pool's assets, type and staking contract are read from the pool itself.
+    /// Only pools created by this factory can be re-registered.
+    ReRegisterPool {
+        /// The address of the deployed pool contract
+        pool_addr: String,
+    },
+    /// SetPoolDeprecated hides a pool from the `Pools` listing by default, without deregistering it.
+    /// The pool keeps working and can still be queried directly.
+    SetPoolDeprecated {
+        /// The assets of the pool
+        asset_infos: Vec<AssetInfo>,
+        /// Whether the pool is deprecated
+        deprecated: bool,
+    },
+    /// SetGuardian sets or removes the guardian. The guardian can pause pool creation and
+    /// disable pool types in an emergency, but cannot change any other setting.
+    /// Only the owner can execute this.
+    SetGuardian {
+        /// The new guardian address, or `None` to remove the guardian
+        guardian: Option<String>,
+    },
+    /// SetPoolCreationPaused stops or resumes the creation of new pools.
+    /// The guardian can only pause, resuming requires the owner.
+    SetPoolCreationPaused {
+        /// Whether pool creation is paused
+        paused: bool,
+    },
+    /// SetMaxPools sets or removes the maximum number of pools that can be registered in the factory.
+    /// Once it is reached, no new pools can be created. Only the owner can execute this.
+    SetMaxPools {
+        /// The maximum number of pools, or `None` to remove the limit
+        max_pools: Option<u64>,
+    },
+    /// SetPoolTypeDisabled sets the `is_disabled` flag of a pool type's [`PoolConfig`].
+    /// The guardian can only disable, enabling requires the owner.
+    SetPoolTypeDisabled {
+        /// The pool type to update
+        pool_type: PoolType,
+        /// Whether new pools of this type can no longer be created
+        disabled: bool,
+    },
     /// ProposeNewOwner creates a proposal to change contract ownership.
     /// The validity period for the proposal is set in the `expires_in` variable.
     ProposeNewOwner {
@@ -222,6 +309,10 @@ pub enum ExecuteMsg {
         staking_config: PartialStakeConfig,
         /// The distribution flows to create
         distribution_flows: Vec<DistributionFlow>,
+        /// The block time until which trading is disabled for this pool.
+        /// If not provided, the factory's `trading_starts` is used.
+        #[serde(default)]
+        trading_starts: Option<u64>,
     },
     /// Creates a distribution flow for the pool staking contract
     CreateDistributionFlow {
@@ -234,8 +325,89 @@ pub enum ExecuteMsg {
         /// Only periods that are defined in the contract can be used here
         rewards: Vec<(UnbondingPeriod, Decimal)>,
     },
-    /// Withdraws pool fee creations to the owner of the contract
+    /// Hands over all distribution flows of a pool's staking contract to a new manager.
+    /// This just sends the corresponding message to the staking contract, so it only works for
+    /// distribution flows that are currently managed by the factory.
+    UpdateStakingManager {
+        /// The assets of the pool whose staking contract should be updated
+        asset_infos: Vec<AssetInfo>,
+        /// The address of the new distribution flow manager
+        new_manager: String,
+    },
+    /// Replaces the staking contract of a pool, e.g. after it was replaced by a new one.
+    /// The new staking contract must stake the pool's LP share denom and must not be used by another pool.
+    UpdatePoolStakingAddr {
+        /// The assets of the pool whose staking contract should be replaced
+        asset_infos: Vec<AssetInfo>,
+        /// The address of the new staking contract
+        new_staking_addr: String,
+    },
+    /// Points a pool type to a new code id and migrates the existing pools of that type to it.
+    /// Pools are processed in pages, so this may have to be called multiple times,
+    /// passing the last pool of the previous page as `start_after`.
+    MigratePoolsOfType {
+        /// The pool type to migrate
+        pool_type: PoolType,
+        /// The code id to migrate the pools to
+        new_code_id: u64,
+        /// The message passed to the `migrate` entry point of each pool
+        migrate_msg: Binary,
+        /// The assets of the pool after which to start migrating
+        start_after: Option<Vec<AssetInfo>>,
+        /// The maximum number of pools to go through
+        limit: Option<u32>,
+    },
+    /// Sends the given fee config to all pools of a pool type.
+    /// Pools are processed in pages, so this may have to be called multiple times,
+    /// passing the last pool of the previous page as `start_after`.
+    UpdateFeesForType {
+        /// The pool type whose pools get the new fees
+        pool_type: PoolType,
+        /// The new fee config
+        fee_config: FeeConfig,
+        /// The assets of the pool after which to start updating
+        start_after: Option<Vec<AssetInfo>>,
+        /// The maximum number of pools to go through
+        limit: Option<u32>,
+    },
+    /// Sends [`Freeze`](crate::pool::ExecuteMsg::Freeze) to the registered pools, freezing or unfreezing them.
+    /// The guardian can only freeze, unfreezing requires the owner.
+    /// Pools are processed in pages, so this may have to be called multiple times,
+    /// passing the last pool of the previous page as `start_after`.
+    FreezeAllPools {
+        /// Whether the pools are frozen
+        frozen: bool,
+        /// The assets of the pool after which to start
+        start_after: Option<Vec<AssetInfo>>,
+        /// The maximum number of pools to go through
+        limit: Option<u32>,
+    },
+    /// Sets the factory's `trading_starts` used for new pools and sends it to the registered pools
+    /// that have not started trading yet, moving their trading start as well.
+    /// Only the owner can execute this.
+    /// Pools are processed in pages, so this may have to be called multiple times,
+    /// passing the last pool of the previous page as `start_after`.
+    UpdateTradingStarts {
+        /// The new block time until which trading is disabled
+        new_start: u64,
+        /// The assets of the pools to update. If given, only these pools are updated
+        /// and `start_after` and `limit` are ignored
+        pools: Option<Vec<Vec<AssetInfo>>>,
+        /// The assets of the pool after which to start
+        start_after: Option<Vec<AssetInfo>>,
+        /// The maximum number of pools to go through
+        limit: Option<u32>,
+    },
+    /// Sends the pool creation fees collected since the last withdrawal to the `creation_fee_address`,
+    /// or to the owner if none is configured. Only the owner can execute this.
     WithdrawPoolCreationFees {},
+    /// Sends the balance of a native token that got stuck in the factory to `recipient`,
+    /// or to the owner if not given. Only the owner can execute this.
+    /// The collected pool creation fees are kept, use [`ExecuteMsg::WithdrawPoolCreationFees`] for them instead.
+    SweepStuck {
+        denom: String,
+        recipient: Option<String>,
+    },
     /// Implements the Cw20 receiver interface.
     Receive(Cw20ReceiveMsg),
 }
@@ -257,6 +429,15 @@ pub enum ReceiveMsg {
         /// Config for the staking contract
         #[serde(default)]
         staking_config: PartialStakeConfig,
+        /// An existing staking contract to register for the pool.
+        /// If provided, no new staking contract is instantiated. Only the owner can set this.
+        /// It must stake the LP share denom of the new pool and must not be used by another pool.
+        #[serde(default)]
+        existing_staking_addr: Option<String>,
+        /// The block time until which trading is disabled for this pool.
+        /// If not provided, the factory's `trading_starts` is used.
+        #[serde(default)]
+        trading_starts: Option<u64>,
     },
     CreatePoolAndDistributionFlows {
         /// The pool type (exposed in [`PoolType`])
@@ -274,6 +455,10 @@ pub enum ReceiveMsg {
         staking_config: PartialStakeConfig,
         /// The distribution flows to create
         distribution_flows: Vec<DistributionFlow>,
+        /// The block time until which trading is disabled for this pool.
+        /// If not provided, the factory's `trading_starts` is used.
+        #[serde(default)]
+        trading_starts: Option<u64>,
     },
 }
 
@@ -299,6 +484,8 @@ pub struct PartialStakeConfig {
     pub min_bond: Option<Uint128>,
     pub unbonding_periods: Option<Vec<u64>>,
     pub max_distributions: Option<u32>,
+    #[serde(default)]
+    pub auto_claim_on_delegate: Option<bool>,
 }
 
 /// This structure describes the available query messages for the factory contract.
@@ -314,6 +501,32 @@ pub enum QueryMsg {
         /// The assets for which we return a pool
         asset_infos: Vec<AssetInfo>,
     },
+    /// StakingAddress returns the address of the LP token staking contract of the pool with the specified assets.
+    #[returns(Addr)]
+    StakingAddress {
+        /// The assets of the pool for which we return the staking contract
+        asset_infos: Vec<AssetInfo>,
+    },
+    /// PoolAddresses returns the addresses of multiple pools at once, in the same order as the given assets.
+    /// This allows resolving a whole swap route with a single query.
+    #[returns(Vec<Addr>)]
+    PoolAddresses {
+        /// The assets of each pool for which we return the address
+        asset_infos: Vec<Vec<AssetInfo>>,
+    },
+    /// Route returns the addresses of all pools that can swap `from` into `to`, in the order they were registered.
+    /// The result is empty if there is no such pool.
+    #[returns(Vec<Addr>)]
+    Route { from: AssetInfo, to: AssetInfo },
+    /// Routes returns all known routes and their pools, paginated like [`QueryMsg::Pools`].
+    #[returns(RoutesResponse)]
+    Routes {
+        /// The `(from, to)` asset pair of the route after which to start,
+        /// as returned in [`RouteInfo`]
+        start_after: Option<(String, String)>,
+        /// The number of routes to return
+        limit: Option<u32>,
+    },
     /// Pools returns an array of pools and their information according to the specified parameters in `start_after` and `limit` variables.
     #[returns(PoolsResponse)]
     Pools {
@@ -321,6 +534,18 @@ pub enum QueryMsg {
         start_after: Option<Vec<AssetInfo>>,
         /// The number of pools to read and return. It is an [`Option`] type.
         limit: Option<u32>,
+        /// Whether to include deprecated pools
+        #[serde(default)]
+        include_deprecated: bool,
+    },
+    /// PoolsByType returns the non-deprecated pools of the given type, paginated like [`QueryMsg::Pools`].
+    #[returns(PoolsResponse)]
+    PoolsByType {
+        pool_type: PoolType,
+        /// The asset infos of the pool after which to start
+        start_after: Option<Vec<AssetInfo>>,
+        /// The number of pools to go through, pools of other types are skipped
+        limit: Option<u32>,
     },
     /// FeeInfo returns default fee parameters for a specific pool type.
     /// If you want to get the fee parameters for a specific pool, use the `Pool` query.
@@ -340,6 +565,53 @@ pub enum QueryMsg {
     /// Used by the `gauge-adapter` contract
     #[returns(bool)]
     ValidateStakingAddress { address: String },
+    /// Returns the address of the pool the given LP token staking contract belongs to,
+    /// or `None` if it is not a registered staking contract
+    #[returns(Option<Addr>)]
+    PoolOfStaking { staking_addr: String },
+    /// Returns the code id the given pool was instantiated with,
+    /// or `None` if it is not a registered pool or was created before code ids were recorded
+    #[returns(Option<u64>)]
+    PoolCodeId { pool_addr: String },
+    /// Returns the address that created the given pool, or `None` if the pool is unknown
+    /// or was created before creators were tracked
+    #[returns(Option<Addr>)]
+    PoolCreator { pool_address: String },
+    /// Returns statistics about the pools created by the given address
+    #[returns(CreatorStatsResponse)]
+    CreatorStats { creator: String },
+    /// Returns the value of the reserves of the registered pools in terms of the `reference` asset,
+    /// paginated like [`QueryMsg::Pools`]. Deprecated pools are included.
+    /// Assets are valued at the spot price of the pool itself if it contains the reference asset,
+    /// otherwise at the spot price of the first pool routing them to the reference asset,
+    /// possibly via another asset of the pool.
+    #[returns(TotalValueLockedResponse)]
+    TotalValueLocked {
+        /// The asset to value the reserves in
+        reference: AssetInfo,
+        /// The assets of the pool after which to start
+        start_after: Option<Vec<AssetInfo>>,
+        /// The maximum number of pools to go through
+        limit: Option<u32>,
+    },
+    /// Returns the instantiate message the factory would use for creating a pool with the given parameters,
+    /// including the combined stake config and the resolved fee config.
+    /// The pool is not marked as verified, as that depends on the sender.
+    #[returns(crate::pool::InstantiateMsg)]
+    SimulateCreatePool {
+        /// The pool type (exposed in [`PoolType`])
+        pool_type: PoolType,
+        /// The assets to create the pool for
+        asset_infos: Vec<AssetInfo>,
+        /// Optional binary serialised parameters for custom pool types
+        init_params: Option<Binary>,
+        /// The total fees (in bps) charged by a pool of this type.
+        /// If not provided, the default is used.
+        total_fee_bps: Option<u16>,
+        /// Config for the staking contract
+        #[serde(default)]
+        staking_config: PartialStakeConfig,
+    },
 }
 
 /// A custom struct for each query response that returns general contract settings/configs.
@@ -351,12 +623,31 @@ pub struct ConfigResponse {
     pub pool_configs: Vec<PoolConfig>,
     /// Address of contract to send governance fees to (the protocol)
     pub fee_address: Option<Addr>,
+    /// Address to send the collected pool creation fees to. The owner receives them if this is not set.
+    #[serde(default)]
+    pub creation_fee_address: Option<Addr>,
     /// Maximum referral commission
     pub max_referral_commission: Decimal,
+    /// Default values for lp token staking contracts
+    pub default_stake_config: DefaultStakeConfig,
     /// When this is set to `true`, only the owner can create pools
     pub only_owner_can_create_pools: bool,
+    /// Fee required for the pool to be established by a non-admin
+    pub pool_creation_fee: Asset,
     /// The block time until which trading is disabled
     pub trading_starts: Option<u64>,
+    /// Whether new pools must have a trading start in the future
+    #[serde(default)]
+    pub require_trading_starts: bool,
+    /// Address that can pause pool creation and disable pool types in an emergency
+    #[serde(default)]
+    pub guardian: Option<Addr>,
+    /// Whether the creation of new pools is paused
+    #[serde(default)]
+    pub pool_creation_paused: bool,
+    /// The maximum number of pools that can be registered in the factory
+    #[serde(default)]
+    pub max_pools: Option<u64>,
 }
 
 /// A custom struct for each query response that returns an array of objects of type [`PairInfo`].
@@ -366,6 +657,55 @@ pub struct PoolsResponse {
     pub pools: Vec<PairInfo>,
 }
 
+/// The pools that can swap between two assets, as returned by [`QueryMsg::Routes`].
+#[cw_serde]
+pub struct RouteInfo {
+    /// The asset swapped from (denom or contract address)
+    pub from: String,
+    /// The asset swapped to (denom or contract address)
+    pub to: String,
+    /// The addresses of the pools, in the order they were registered
+    pub pools: Vec<Addr>,
+}
+
+/// A custom struct for each query response that returns an array of routes.
+#[cw_serde]
+pub struct RoutesResponse {
+    pub routes: Vec<RouteInfo>,
+}
+
+/// A custom struct for each query response that returns the pool creation statistics of an address.
+#[cw_serde]
+pub struct CreatorStatsResponse {
+    /// The address that created the pools
+    pub creator: Addr,
+    /// Number of pools created by this address
+    pub pools_created: u64,
+}
+
+/// The value of a pool's reserves, as returned by [`QueryMsg::TotalValueLocked`].
+#[cw_serde]
+pub struct PoolValue {
+    /// The address of the pool
+    pub contract_addr: Addr,
+    /// The assets of the pool
+    pub asset_infos: Vec<AssetInfoValidated>,
+    /// The value of the reserves in the reference asset,
+    /// or `None` if one of the assets could not be priced
+    pub value: Option<Uint128>,
+}
+
+/// A custom struct for each query response that returns the value locked in a page of pools.
+#[cw_serde]
+pub struct TotalValueLockedResponse {
+    /// The asset the values are expressed in
+    pub reference: AssetInfoValidated,
+    /// The sum of the values of all priced pools in this page
+    pub total_value: Uint128,
+    /// The value of each pool in this page
+    pub pools: Vec<PoolValue>,
+}
+
 /// A custom struct for each query response that returns an object of type [`FeeInfoResponse`].
 #[cw_serde]
 pub struct FeeInfoResponse {
@@ -324,22 +324,35 @@ pub fn query_oracle_range(
     // None takes the latest accumulator update
     end_index: Option<u32>,
 ) -> StdResult<TwapResponse> {
+    let updates = LAST_UPDATES.load(storage)?;
+    oracle_range(
+        storage,
+        env,
+        asset_infos,
+        &updates,
+        sample_period,
+        start_index,
+        end_index,
+    )?
+    .ok_or_else(|| {
+        StdError::generic_err("start index is earlier than earliest recorded price data")
+    })
+}
+
+/// Same as [`query_oracle_range`], but returns `None` if the start index is earlier than the
+/// recorded price data.
+fn oracle_range(
+    storage: &dyn Storage,
+    env: &Env,
+    asset_infos: &[AssetInfoValidated],
+    updates: &LastUpdates,
+    sample_period: SamplePeriod,
+    start_index: u32,
+    end_index: Option<u32>,
+) -> StdResult<Option<TwapResponse>> {
     // TODO: assert start_index > end_index
 
-    let updates = LAST_UPDATES.load(storage)?;
-    let (step, last_update, stored_prices) = match sample_period {
-        SamplePeriod::Minute => (MINUTE, updates.minutes, LAST_MINUTES_PRICES.load(storage)?),
-        SamplePeriod::HalfHour => (
-            HALF_HOUR,
-            updates.half_hours,
-            LAST_HALF_HOUR_PRICES.load(storage)?,
-        ),
-        SamplePeriod::SixHour => (
-            SIX_HOURS,
-            updates.six_hours,
-            LAST_SIX_HOUR_PRICES.load(storage)?,
-        ),
-    };
+    let (step, last_update, stored_prices) = load_prices(storage, updates, sample_period)?;
 
     // interpolate prices to the present (if they haven't been updated in a while)
     let latest_checkpoint = calc_checkpoint(last_update, env, step);
@@ -351,12 +364,9 @@ pub fn query_oracle_range(
         None => (last_update, stored_prices),
     };
 
-    let old_twap_a_per_b = prices
-        .twap_a_per_b
-        .get(start_index as usize)
-        .ok_or_else(|| {
-            StdError::generic_err("start index is earlier than earliest recorded price data")
-        })?;
+    let Some(old_twap_a_per_b) = prices.twap_a_per_b.get(start_index as usize) else {
+        return Ok(None);
+    };
     let old_twap_b_per_a = prices.twap_b_per_a[start_index as usize];
 
     // handle current accumulator (`end_index == None`)
@@ -394,11 +404,165 @@ pub fn query_oracle_range(
     let a_per_b = new_twap_a_per_b.average_price(old_twap_a_per_b, elapsed_nanos);
     let b_per_a = new_twap_b_per_a.average_price(&old_twap_b_per_a, elapsed_nanos);
 
-    Ok(TwapResponse {
+    Ok(Some(TwapResponse {
         a: asset_infos[0].clone().into(),
         b: asset_infos[1].clone().into(),
         a_per_b,
         b_per_a,
+    }))
+}
+
+#[cw_serde]
+pub struct TwapStandardResponse {
+    pub offer: AssetInfo,
+    pub ask: AssetInfo,
+    /// The average price of `offer` in `ask` over the last full 30 minutes,
+    /// `None` if not enough price data is recorded yet
+    pub twap_30m: Option<Decimal>,
+    /// The average price of `offer` in `ask` over the last full hour,
+    /// `None` if not enough price data is recorded yet
+    pub twap_1h: Option<Decimal>,
+    /// The average price of `offer` in `ask` over the last full 24 hours,
+    /// `None` if not enough price data is recorded yet
+    pub twap_24h: Option<Decimal>,
+}
+
+/// This gets the twap of `offer` in `ask` over the last 30 minutes, 1 hour and 24 hours.
+/// Each window ends at the latest checkpoint of its sample frequency. Windows reaching further back
+/// than the recorded price data are returned as `None`.
+pub fn query_oracle_standard(
+    storage: &dyn Storage,
+    env: &Env,
+    asset_infos: &[AssetInfoValidated],
+    offer: &AssetInfoValidated,
+    ask: &AssetInfoValidated,
+) -> StdResult<TwapStandardResponse> {
+    // a_per_b is the price of b in a
+    let offer_is_b = if offer == &asset_infos[1] && ask == &asset_infos[0] {
+        true
+    } else if offer == &asset_infos[0] && ask == &asset_infos[1] {
+        false
+    } else {
+        return Err(StdError::generic_err(
+            crate::pool::ContractError::AssetMismatch {}.to_string(),
+        ));
+    };
+
+    // there is no price data before the oracle is initialized
+    let updates = LAST_UPDATES.may_load(storage)?;
+    let twap = |sample_period, start_index| -> StdResult<Option<Decimal>> {
+        let Some(updates) = &updates else {
+            return Ok(None);
+        };
+        let twap = oracle_range(
+            storage,
+            env,
+            asset_infos,
+            updates,
+            sample_period,
+            start_index,
+            Some(0),
+        )?;
+        Ok(twap.map(|twap| {
+            if offer_is_b {
+                twap.a_per_b
+            } else {
+                twap.b_per_a
+            }
+        }))
+    };
+
+    Ok(TwapStandardResponse {
+        offer: offer.clone().into(),
+        ask: ask.clone().into(),
+        twap_30m: twap(SamplePeriod::HalfHour, 1)?,
+        twap_1h: twap(SamplePeriod::HalfHour, 2)?,
+        twap_24h: twap(SamplePeriod::SixHour, 4)?,
+    })
+}
+
+#[cw_serde]
+pub struct HistoricalPricesResponse {
+    pub a: AssetInfo,
+    pub b: AssetInfo,
+    /// The average `a_per_b` price of each sample period as `(end of the period in seconds, price)`,
+    /// oldest first
+    pub prices: Vec<(u64, Decimal)>,
+}
+
+/// This gets the average price of every full sample period within the last `duration` seconds.
+/// The finest sample frequency whose buffer covers `duration` is used. If less history is recorded
+/// (e.g. because the pool is younger than `duration`), only the available samples are returned.
+pub fn query_oracle_history(
+    storage: &dyn Storage,
+    env: &Env,
+    asset_infos: &[AssetInfoValidated],
+    duration: u64,
+) -> StdResult<HistoricalPricesResponse> {
+    let mut response = HistoricalPricesResponse {
+        a: asset_infos[0].clone().into(),
+        b: asset_infos[1].clone().into(),
+        prices: vec![],
+    };
+    // the oracle is only initialized once liquidity is provided
+    let Some(updates) = LAST_UPDATES.may_load(storage)? else {
+        return Ok(response);
+    };
+
+    // n buffer entries give us n - 1 samples
+    let max_samples = BUFFER_DEPTH as u64 - 1;
+    let sample_period = if duration <= MINUTE * max_samples {
+        SamplePeriod::Minute
+    } else if duration <= HALF_HOUR * max_samples {
+        SamplePeriod::HalfHour
+    } else {
+        SamplePeriod::SixHour
+    };
+    let (step, last_update, stored_prices) = load_prices(storage, &updates, sample_period)?;
+
+    // interpolate prices to the present (if they haven't been updated in a while)
+    let (checkpoint, prices) = match calc_checkpoint(last_update, env, step) {
+        Some(checkpoint) => (
+            checkpoint,
+            stored_prices.accumulate(last_update, checkpoint, &updates.accumulator, step),
+        ),
+        None => (last_update, stored_prices),
+    };
+
+    let samples = ((duration + step - 1) / step)
+        .min(prices.twap_a_per_b.len().saturating_sub(1) as u64) as usize;
+    let elapsed_nanos = step * 1_000_000_000u64;
+    response.prices = (0..samples)
+        .rev()
+        .map(|i| {
+            (
+                checkpoint - i as u64 * step,
+                prices.twap_a_per_b[i].average_price(&prices.twap_a_per_b[i + 1], elapsed_nanos),
+            )
+        })
+        .collect();
+
+    Ok(response)
+}
+
+/// Returns the step, the last update and the stored price buffer of the given sample frequency
+fn load_prices(
+    storage: &dyn Storage,
+    updates: &LastUpdates,
+    sample_period: SamplePeriod,
+) -> StdResult<(u64, u64, Prices)> {
+    Ok(match sample_period {
+        SamplePeriod::Minute => (MINUTE, updates.minutes, LAST_MINUTES_PRICES.load(storage)?),
+        SamplePeriod::HalfHour => (
+            HALF_HOUR,
+            updates.half_hours,
+            LAST_HALF_HOUR_PRICES.load(storage)?,
+        ),
+        SamplePeriod::SixHour => (
+            SIX_HOURS,
+            updates.six_hours,
+            LAST_SIX_HOUR_PRICES.load(storage)?,
+        ),
     })
 }
 
@@ -409,11 +573,15 @@ pub fn query_oracle_accumulator(storage: &dyn Storage) -> StdResult<Accumulator>
 
 #[cfg(test)]
 mod tests {
+    use crate::asset::AssetInfoValidated;
     use crate::oracle::{Accumulator, Twap, BUFFER_DEPTH};
-    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
     use cosmwasm_std::{assert_approx_eq, Decimal, Fraction, Timestamp, Uint128};
 
-    use super::{calc_checkpoint, Prices, MINUTE};
+    use super::{
+        calc_checkpoint, initialize_oracle, query_oracle_history, query_oracle_standard,
+        store_oracle_price, Prices, MINUTE,
+    };
 
     #[test]
     fn twap_accumulates() {
@@ -481,6 +649,114 @@ mod tests {
         );
     }
 
+    #[test]
+    fn price_history() {
+        let mut storage = MockStorage::new();
+        let asset_infos = [
+            AssetInfoValidated::SmartToken("a".to_string()),
+            AssetInfoValidated::SmartToken("b".to_string()),
+        ];
+        let mut env = mock_env();
+        let start = 1682155800;
+        env.block.time = Timestamp::from_seconds(start);
+
+        // no history before the oracle is initialized
+        let history = query_oracle_history(&storage, &env, &asset_infos, 600).unwrap();
+        assert_eq!(history.prices, vec![]);
+
+        // price goes 1, 2, 3, 4, changing every minute
+        initialize_oracle(&mut storage, &env, Decimal::percent(100)).unwrap();
+        for price in 2..=4 {
+            env.block.time = env.block.time.plus_seconds(MINUTE);
+            store_oracle_price(&mut storage, &env, Decimal::percent(price * 100)).unwrap();
+        }
+        env.block.time = env.block.time.plus_seconds(MINUTE);
+
+        // the pool is younger than the requested duration, so we only get the recorded samples
+        let history = query_oracle_history(&storage, &env, &asset_infos, 600).unwrap();
+        assert_eq!(history.a, asset_infos[0].clone().into());
+        assert_eq!(
+            history.prices,
+            vec![
+                (start + 2 * MINUTE, Decimal::percent(200)),
+                (start + 3 * MINUTE, Decimal::percent(300)),
+                (start + 4 * MINUTE, Decimal::percent(400)),
+            ]
+        );
+
+        // partial sample periods are rounded up
+        let history = query_oracle_history(&storage, &env, &asset_infos, MINUTE + 1).unwrap();
+        assert_eq!(
+            history.prices,
+            vec![
+                (start + 3 * MINUTE, Decimal::percent(300)),
+                (start + 4 * MINUTE, Decimal::percent(400)),
+            ]
+        );
+
+        // longer durations use half hour samples, of which there are none yet
+        let history = query_oracle_history(&storage, &env, &asset_infos, 3600).unwrap();
+        assert_eq!(history.prices, vec![]);
+    }
+
+    #[test]
+    fn standard_twaps() {
+        let mut storage = MockStorage::new();
+        let asset_infos = [
+            AssetInfoValidated::SmartToken("a".to_string()),
+            AssetInfoValidated::SmartToken("b".to_string()),
+        ];
+        let (a, b) = (&asset_infos[0], &asset_infos[1]);
+        let mut env = mock_env();
+        let start = Timestamp::from_seconds(1682155800);
+        env.block.time = start;
+
+        // no price data before the oracle is initialized
+        let twaps = query_oracle_standard(&storage, &env, &asset_infos, b, a).unwrap();
+        assert_eq!(
+            (twaps.twap_30m, twaps.twap_1h, twaps.twap_24h),
+            (None, None, None)
+        );
+
+        // price stays at 1 a per b for 29 hours
+        initialize_oracle(&mut storage, &env, Decimal::one()).unwrap();
+
+        // after one hour, only the 30 minute window is covered
+        env.block.time = start.plus_seconds(60 * MINUTE);
+        let twaps = query_oracle_standard(&storage, &env, &asset_infos, b, a).unwrap();
+        assert_eq!(twaps.offer, b.clone().into());
+        assert_eq!(twaps.ask, a.clone().into());
+        assert_eq!(
+            (twaps.twap_30m, twaps.twap_1h, twaps.twap_24h),
+            (Some(Decimal::one()), None, None)
+        );
+
+        // then it moves to 4 a per b for the last hour
+        env.block.time = start.plus_seconds(29 * 60 * MINUTE);
+        store_oracle_price(&mut storage, &env, Decimal::percent(400)).unwrap();
+        env.block.time = start.plus_seconds(30 * 60 * MINUTE);
+
+        let twaps = query_oracle_standard(&storage, &env, &asset_infos, b, a).unwrap();
+        assert_eq!(twaps.twap_30m, Some(Decimal::percent(400)));
+        assert_eq!(twaps.twap_1h, Some(Decimal::percent(400)));
+        // 23 hours at 1 and 1 hour at 4
+        assert_eq!(twaps.twap_24h, Some(Decimal::from_ratio(27u128, 24u128)));
+
+        // the other direction returns b per a
+        let twaps = query_oracle_standard(&storage, &env, &asset_infos, a, b).unwrap();
+        assert_eq!(twaps.twap_30m, Some(Decimal::percent(25)));
+        assert_eq!(twaps.twap_1h, Some(Decimal::percent(25)));
+        assert_eq!(
+            twaps.twap_24h,
+            Some(Decimal::from_ratio(2325u128, 2400u128))
+        );
+
+        // assets must be the ones of the pool
+        let c = AssetInfoValidated::SmartToken("c".to_string());
+        query_oracle_standard(&storage, &env, &asset_infos, a, &c).unwrap_err();
+        query_oracle_standard(&storage, &env, &asset_infos, a, a).unwrap_err();
+    }
+
     #[test]
     fn updating_price_buffer() {
         let mut prices = Prices::default();
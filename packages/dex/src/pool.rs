@@ -2,7 +2,10 @@ use cosmwasm_schema::{cw_serde, QueryResponses};
 
 use crate::{
     asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated, DecimalAsset},
-    factory::{ConfigResponse as FactoryConfigResponse, QueryMsg as FactoryQueryMsg},
+    factory::{
+        ConfigResponse as FactoryConfigResponse, QueryMsg as FactoryQueryMsg,
+        MAX_TRADING_STARTS_DELAY,
+    },
     fee_config::FeeConfig,
     oracle::SamplePeriod,
 };
@@ -10,7 +13,7 @@ use crate::{
 use coreum_wasm_sdk::core::CoreumQueries;
 use cosmwasm_std::{
     to_json_binary, Addr, Binary, Decimal, Decimal256, QuerierWrapper, StdError, StdResult,
-    Uint128, WasmMsg,
+    Timestamp, Uint128, Uint256, WasmMsg,
 };
 use cw20::Cw20ReceiveMsg;
 
@@ -28,6 +31,9 @@ pub use utils::*;
 /// Decimal precision for TWAP results
 pub const TWAP_PRECISION: u8 = 6;
 
+/// The maximum price impact a trade can have to count towards the pool's depth
+pub const DEPTH_MAX_PRICE_IMPACT: Decimal = Decimal::percent(1);
+
 /// This structure stores the main parameters for an Dex pool
 #[cw_serde]
 pub struct PairInfo {
@@ -113,16 +119,44 @@ pub struct InstantiateMsg {
     pub trading_starts: u64,
     /// Address which can call ExecuteMsg::Freeze
     pub circuit_breaker: Option<String>,
+    /// An existing staking contract to use for this pool.
+    /// If not provided, a new one is instantiated using `staking_config`.
+    #[serde(default)]
+    pub existing_staking_addr: Option<String>,
+    /// Price (asset 0 per asset 1) to seed the oracle with, so TWAP queries work right away.
+    /// This price is unverified: it is replaced by the real pool price on the first liquidity provision.
+    #[serde(default)]
+    pub initial_oracle_price: Option<Decimal>,
 }
 
 impl InstantiateMsg {
-    /// Returns an error if the fee config is invalid
+    /// Returns an error if the fee config is invalid.
+    /// This applies the same [`FeeConfig::valid_fee_bps`] bounds the factory checks for pool configs,
+    /// so pools instantiated directly cannot have fees the factory would reject.
     pub fn validate_fees(&self) -> Result<(), ContractError> {
         self.fee_config
             .valid_fee_bps()
             .then_some(())
             .ok_or(ContractError::InvalidFeeBps {})
     }
+
+    /// Returns an error if trading would start too far in the future
+    pub fn validate_trading_starts(&self, block_time: Timestamp) -> Result<(), ContractError> {
+        if self.trading_starts > block_time.seconds() + MAX_TRADING_STARTS_DELAY {
+            return Err(ContractError::InvalidTradingStart {
+                max_delay: MAX_TRADING_STARTS_DELAY,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the initial oracle price is zero
+    pub fn validate_initial_oracle_price(&self) -> Result<(), ContractError> {
+        if self.initial_oracle_price == Some(Decimal::zero()) {
+            return Err(ContractError::InvalidInitialOraclePrice {});
+        }
+        Ok(())
+    }
 }
 
 #[cw_serde]
@@ -133,6 +167,9 @@ pub struct StakeConfig {
     pub min_bond: Uint128,
     pub unbonding_periods: Vec<u64>,
     pub max_distributions: u32,
+    /// If set, delegating to the staking contract also pays out the staker's matured claims
+    #[serde(default)]
+    pub auto_claim_on_delegate: bool,
 }
 
 impl StakeConfig {
@@ -158,6 +195,10 @@ impl StakeConfig {
                 max_distributions: self.max_distributions,
                 admin: Some(factory_addr),
                 unbonder: None, // TODO: allow specifying unbonder
+                backup_unbonders: vec![],
+                unbond_grace_period: 0,
+                min_distribution_interval: 0,
+                auto_claim_on_delegate: self.auto_claim_on_delegate,
             })?,
             funds: vec![],
             admin: Some(factory_owner),
@@ -180,8 +221,40 @@ pub enum ExecuteMsg {
         /// The receiver of LP tokens
         receiver: Option<String>,
     },
+    /// Provides liquidity with a single asset in one step.
+    /// Xyk pools first swap the part of the `offer_asset` that leaves both deposits in the pool ratio,
+    /// stableswap pools deposit it as it is and charge the imbalance fee instead.
+    ZapIn {
+        offer_asset: Asset,
+        /// The minimum amount of LP tokens to mint. The zap fails if fewer would be minted
+        min_share: Option<Uint128>,
+        /// The receiver of LP tokens
+        receiver: Option<String>,
+    },
     /// Withdraw liquidity from the pool
-    WithdrawLiquidity { assets: Vec<Asset> },
+    WithdrawLiquidity {
+        /// The assets to withdraw. If empty, the sent LP tokens are withdrawn proportionally
+        assets: Vec<Asset>,
+        /// The maximum amount of LP tokens to burn for withdrawing the given `assets`.
+        /// The withdrawal fails if more would be needed
+        max_burn: Option<Uint128>,
+    },
+    /// Withdraws liquidity and swaps the withdrawn other asset into `ask_asset`, so the whole position
+    /// is returned as a single asset. The `share` of LP tokens has to be sent along.
+    /// Only supported by xyk pools. Stableswap pools can withdraw a single asset with
+    /// [`ExecuteMsg::WithdrawLiquidity`] instead.
+    ZapOut {
+        /// The amount of LP tokens to burn
+        share: Uint128,
+        /// The pool asset to receive
+        ask_asset: AssetInfo,
+        /// The receiver of the ask asset
+        receiver: Option<String>,
+        /// The max spread of the internal swap
+        max_spread: Option<Decimal>,
+        /// The minimum amount of the ask asset to receive in total. The zap fails if less would be returned
+        minimum_receive: Option<Uint128>,
+    },
     /// Swap performs a swap in the pool
     Swap {
         offer_asset: Asset,
@@ -211,8 +284,22 @@ pub enum ExecuteMsg {
     DropOwnershipProposal {},
     /// Used to claim contract ownership.
     ClaimOwnership {},
-    /// Freeze all but withdraw liquidity, can only be called if a circuit breaker is set through a MigrateMsg
+    /// Freeze all but withdraw liquidity. Can only be called by the factory or by the circuit breaker
+    /// set through a MigrateMsg
     Freeze { frozen: bool },
+    /// Stores the current pool price in the oracle without trading. Can be called by anyone.
+    UpdateOracle {},
+    /// Adds the given assets to the pool reserves without minting LP tokens,
+    /// increasing the value of the existing LP shares. Can be called by anyone.
+    Donate { assets: Vec<Asset> },
+    /// Re-queries the precision of the given pool asset, e.g. after the token was re-issued.
+    /// Can only be called by the factory or the pool owner, while the pool has no liquidity.
+    RefreshPrecision { asset: AssetInfo },
+    /// Updates the address of the staking contract of this pool's LP tokens,
+    /// e.g. after it was replaced by a new staking contract. Can only be called by the factory.
+    UpdateStakingAddr { new_addr: String },
+    /// Moves the block time until which trading is disabled. Can only be called by the factory.
+    UpdateTradingStarts { new_start: u64 },
 }
 
 /// This structure describes a CW20 hook message.
@@ -254,10 +341,15 @@ pub enum QueryMsg {
     /// Returns contract configuration settings in a custom [`ConfigResponse`] structure.
     #[returns(ConfigResponse)]
     Config {},
+    /// Returns whether trading in the pool has started in a [`TradingStartedResponse`] object.
+    #[returns(TradingStartedResponse)]
+    TradingStarted {},
     /// Returns information about the share of the pool in a vector that contains objects of type [`Asset`].
     #[returns(Vec<AssetValidated>)]
     Share { amount: Uint128 },
     /// Returns information about a swap simulation in a [`SimulationResponse`] object.
+    /// Fails with "The asset ... does not belong to the pool" if `offer_asset` or `ask_asset_info`
+    /// is not one of the pool's assets.
     #[returns(SimulationResponse)]
     Simulation {
         offer_asset: Asset,
@@ -269,6 +361,8 @@ pub enum QueryMsg {
         referral_commission: Option<Decimal>,
     },
     /// Returns information about cumulative prices in a [`ReverseSimulationResponse`] object.
+    /// Fails with "The asset ... does not belong to the pool" if `ask_asset` or `offer_asset_info`
+    /// is not one of the pool's assets.
     #[returns(ReverseSimulationResponse)]
     ReverseSimulation {
         offer_asset_info: Option<AssetInfo>,
@@ -279,6 +373,13 @@ pub enum QueryMsg {
         /// This is capped by and defaulting to the configured max commission
         referral_commission: Option<Decimal>,
     },
+    /// Returns the fee breakdown of a prospective swap in a [`SwapFeeResponse`] object.
+    /// Referral commissions are not taken into account.
+    #[returns(SwapFeeResponse)]
+    SwapFee {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+    },
     /// Returns information about the cumulative prices in a [`CumulativePricesResponse`] object
     #[returns(CumulativePricesResponse)]
     CumulativePrices {},
@@ -291,6 +392,12 @@ pub enum QueryMsg {
         /// end_age: None means count until the current time, end_age: Some(0) means til the last checkpoint, which would be more regular
         end_age: Option<u32>,
     },
+    /// Returns the average price of every full sample period within the last `duration` seconds
+    #[returns(crate::oracle::HistoricalPricesResponse)]
+    HistoricalPrices { duration: u64 },
+    /// Returns the average price of `offer` in `ask` over the last 30 minutes, 1 hour and 24 hours
+    #[returns(crate::oracle::TwapStandardResponse)]
+    TwapStandard { offer: AssetInfo, ask: AssetInfo },
     /// Returns current D invariant in as a [`u128`] value
     #[returns(Uint128)]
     QueryComputeD {},
@@ -312,6 +419,52 @@ pub enum QueryMsg {
         /// (higher numbers gives more accuracy at higher gas cost)
         iterations: u8,
     },
+    /// Returns the arbitrage trade that would move the pool's spot price of the second asset
+    /// (in terms of the first asset) to `target_price`, given the current liquidity.
+    /// Fails if the target price cannot be reached.
+    #[returns(RebalanceToResponse)]
+    RebalanceTo { target_price: Decimal },
+    /// Returns the largest trade into `ask_info` whose price impact stays within
+    /// [`DEPTH_MAX_PRICE_IMPACT`], as a measure of the pool's liquidity depth.
+    #[returns(DepthResponse)]
+    Depth { ask_info: AssetInfo },
+    /// Returns the smallest trade from `offer_info` into `ask_info` that lowers the spot price of
+    /// the offer asset (in terms of the ask asset) by `bps` basis points, ignoring fees.
+    #[returns(OfferToMovePriceResponse)]
+    OfferToMovePrice {
+        offer_info: AssetInfo,
+        ask_info: AssetInfo,
+        bps: u16,
+    },
+    /// Returns the pool's current invariant: the constant product `k` for xyk pools and `D`
+    /// for stableswap pools. It never decreases through swaps.
+    #[returns(InvariantResponse)]
+    Invariant {},
+    /// Returns the reserves, spot prices, swap fee and total LP share of the pool in a single
+    /// response, so exchanges and aggregators can poll the pool with one query.
+    #[returns(TickerResponse)]
+    Ticker {},
+    /// Returns the decimal precision recorded for every asset in the pool, together with the
+    /// greatest one, which is used for the stableswap math. Only supported by stableswap pools.
+    #[returns(PrecisionsResponse)]
+    Precisions {},
+    /// Returns how many LP tokens an imbalanced [`ExecuteMsg::WithdrawLiquidity`] of the given `assets`
+    /// would burn, and how many of the sent `share` would be returned. Only supported by stableswap pools.
+    #[returns(ImbalancedWithdrawSimulationResponse)]
+    ImbalancedWithdrawSimulation {
+        /// The assets to withdraw
+        assets: Vec<Asset>,
+        /// The LP tokens that would be sent along. If given, fails if they are not enough.
+        #[serde(default)]
+        share: Option<Uint128>,
+    },
+    /// Returns the current amplification of the pool, interpolated while it is changing,
+    /// and the amplification it is changing towards. Only supported by stableswap pools.
+    #[returns(AmpResponse)]
+    Amp {},
+    /// Returns the total protocol fees the pool has sent to the factory's `fee_address`, per asset.
+    #[returns(CollectedProtocolFeesResponse)]
+    CollectedProtocolFees {},
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
@@ -332,6 +485,102 @@ pub struct ConfigResponse {
     pub params: Option<Binary>,
     /// The contract owner
     pub owner: Option<Addr>,
+    /// The factory that created the pool
+    pub factory_addr: Addr,
+}
+
+/// This struct is used to return whether trading in the pool has started.
+#[cw_serde]
+pub struct TradingStartedResponse {
+    /// Whether swaps are already allowed
+    pub started: bool,
+    /// Timestamp (in seconds) from which on swaps are allowed
+    pub starts_at: u64,
+}
+
+/// This struct is used to return the trade needed to move the pool to a target price.
+#[cw_serde]
+pub struct RebalanceToResponse {
+    /// The asset that has to be offered to the pool.
+    /// The amount is zero if the pool is already at the target price.
+    pub offer_asset: AssetValidated,
+    /// The asset returned by the trade, after fees
+    pub return_asset: AssetValidated,
+}
+
+/// This struct is used to return the liquidity depth of the pool for trading into an asset.
+#[cw_serde]
+pub struct DepthResponse {
+    /// The largest amount that can be offered before the price impact exceeds [`DEPTH_MAX_PRICE_IMPACT`]
+    pub offer_asset: AssetValidated,
+    /// The asset returned by that trade, after fees
+    pub return_asset: AssetValidated,
+}
+
+/// This struct is used to return the trade that moves the pool's spot price by a given amount.
+#[cw_serde]
+pub struct OfferToMovePriceResponse {
+    /// The amount that has to be offered to move the price by the requested basis points
+    pub offer_asset: AssetValidated,
+    /// The asset returned by that trade, after fees
+    pub return_asset: AssetValidated,
+}
+
+/// This struct is used to return the pool's invariant.
+#[cw_serde]
+pub struct InvariantResponse {
+    /// `k` for constant product pools, `D` for stableswap pools
+    pub value: Uint256,
+}
+
+/// This struct is used to return a query result with the current market data of the pool.
+#[cw_serde]
+pub struct TickerResponse {
+    /// The assets in the pool together with asset amounts
+    pub reserves: Vec<AssetValidated>,
+    /// The spot price of the first asset in terms of the second one, for every ordered pair of
+    /// assets in the pool. Empty while the pool has no liquidity.
+    pub spot_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Decimal)>,
+    /// The total fee charged on swaps, in basis points
+    pub total_fee_bps: u16,
+    /// The total amount of LP tokens currently issued
+    pub total_share: Uint128,
+}
+
+/// This struct is used to return the precisions the pool uses for its assets.
+#[cw_serde]
+pub struct PrecisionsResponse {
+    /// The precision stored for every asset in the pool
+    pub precisions: Vec<(AssetInfoValidated, u8)>,
+    /// The greatest of all the asset precisions
+    pub greatest_precision: u8,
+}
+
+/// This struct is used to return the result of an imbalanced withdraw simulation.
+#[cw_serde]
+pub struct ImbalancedWithdrawSimulationResponse {
+    /// The amount of LP tokens that would be burned
+    pub burn_amount: Uint128,
+    /// The amount of the sent LP tokens that would be returned, zero if no `share` was given
+    pub refund_share: Uint128,
+}
+
+/// This struct is used to return the protocol fees collected by a pool.
+#[cw_serde]
+pub struct CollectedProtocolFeesResponse {
+    /// The total protocol fees collected for each asset of the pool
+    pub fees: Vec<AssetValidated>,
+}
+
+/// This struct is used to return the amplification of a stableswap pool.
+#[cw_serde]
+pub struct AmpResponse {
+    /// The current amplification
+    pub amp: Decimal,
+    /// The amplification to reach at `next_amp_time`
+    pub next_amp: Decimal,
+    /// The time (in seconds) at which the amplification reaches `next_amp`
+    pub next_amp_time: u64,
 }
 
 /// This structure holds the parameters that are returned from a swap simulation response
@@ -360,6 +609,17 @@ pub struct ReverseSimulationResponse {
     pub referral_amount: Uint128,
 }
 
+/// This structure holds the fees a prospective swap will pay (all denominated in the ask asset)
+#[cw_serde]
+pub struct SwapFeeResponse {
+    /// The total amount of fees charged by the swap
+    pub total_commission: Uint128,
+    /// The part of the total commission that is sent to the protocol
+    pub protocol_fee: Uint128,
+    /// The part of the total commission that stays in the pool for the liquidity providers
+    pub lp_fee: Uint128,
+}
+
 /// This structure is used to return a cumulative prices query response.
 #[cw_serde]
 pub struct CumulativePricesResponse {
@@ -368,7 +628,7 @@ pub struct CumulativePricesResponse {
     /// The total amount of LP tokens currently issued
     pub total_share: Uint128,
     /// The vector contains cumulative prices for each pool of assets in the pool
-    pub cumulative_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Uint128)>,
+    pub cumulative_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Uint256)>,
 }
 
 /// This structure holds stableswap pool parameters.
@@ -380,6 +640,29 @@ pub struct StablePoolParams {
     pub owner: Option<String>,
     /// Information on LSD, if supported (TODO: always require?)
     pub lsd: Option<LsdInfo>,
+    /// Whether liquidity may be provided or withdrawn in a ratio different from the pool's.
+    /// Defaults to `true`.
+    pub allow_imbalanced: Option<bool>,
+    /// An amp ramp that starts right at instantiation, going from `amp` to the given target.
+    /// Equivalent to calling [`StablePoolUpdateParams::StartChangingAmp`] after creation.
+    #[serde(default)]
+    pub amp_ramp: Option<AmpRamp>,
+    /// Swaps returning less than this amount of the ask asset are rejected.
+    /// Defaults to zero, which accepts any return amount.
+    #[serde(default)]
+    pub dust_threshold: Option<Uint128>,
+    /// Swaps with a `max_spread` above this value are rejected. Must be between
+    /// [`DEFAULT_SLIPPAGE`] and [`MAX_ALLOWED_SLIPPAGE`], which is also the default.
+    #[serde(default)]
+    pub max_allowed_spread: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct AmpRamp {
+    /// The amp value to ramp to
+    pub next_amp: u64,
+    /// The time (in seconds) at which the amp reaches `next_amp`
+    pub next_amp_time: u64,
 }
 
 #[cw_serde]
@@ -395,11 +678,39 @@ pub struct LsdInfo {
     pub target_rate_epoch: u64,
 }
 
+/// The query a liquid staking hub contract has to support to be used as [`LsdInfo::hub`].
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum LsdHubQueryMsg {
+    /// Returns the current redemption value of the LSD token
+    #[returns(TargetValueResponse)]
+    TargetValue {},
+}
+
+#[cw_serde]
+pub struct TargetValueResponse {
+    /// How many of the underlying tokens one LSD token is worth
+    pub target_value: Decimal,
+}
+
 /// This structure stores a stableswap pool's configuration.
+/// It is returned as the `params` of the pool's [`ConfigResponse`].
 #[cw_serde]
 pub struct StablePoolConfig {
-    /// The stableswap pool amplification
+    /// The current stableswap pool amplification
     pub amp: Decimal,
+    /// The amplification at the start of the current amp change
+    pub init_amp: Decimal,
+    /// The time (in seconds) at which the current amp change started
+    pub init_amp_time: u64,
+    /// The amplification to reach at `next_amp_time`
+    pub next_amp: Decimal,
+    /// The time (in seconds) at which the amplification reaches `next_amp`
+    pub next_amp_time: u64,
+    /// How many of the underlying tokens one LSD token is worth. Always one if the pool has no LSD.
+    pub target_rate: Decimal,
+    /// The greatest `max_spread` a swap may specify
+    pub max_allowed_spread: Decimal,
 }
 
 /// This enum stores the options available to start and stop changing a stableswap pool's amplification.
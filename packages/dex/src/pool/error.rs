@@ -1,5 +1,8 @@
+use super::{DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
 use crate::asset::MINIMUM_LIQUIDITY_AMOUNT;
-use cosmwasm_std::{CheckedMultiplyRatioError, ConversionOverflowError, OverflowError, StdError};
+use cosmwasm_std::{
+    CheckedMultiplyRatioError, ConversionOverflowError, OverflowError, StdError, Uint128,
+};
 use thiserror::Error;
 
 /// This enum describes pool contract errors
@@ -20,6 +23,12 @@ pub enum ContractError {
     #[error("Trading has not started yet")]
     TradingNotStarted {},
 
+    #[error("Trading start must not be more than {max_delay} seconds in the future")]
+    InvalidTradingStart { max_delay: u64 },
+
+    #[error("Initial oracle price must be bigger than 0")]
+    InvalidInitialOraclePrice {},
+
     #[error("The {0} address was set already and cannot be changed")]
     AddrAlreadySet(&'static str),
 
@@ -38,6 +47,23 @@ pub enum ContractError {
     #[error("Operation exceeds max splippage tolerance")]
     MaxSlippageAssertion {},
 
+    #[error("Withdrawal needs {burn_amount} LP tokens, which exceeds the max burn of {max_burn}")]
+    MaxBurnAssertion {
+        burn_amount: Uint128,
+        max_burn: Uint128,
+    },
+
+    #[error(
+        "Zapping in would mint {share} LP tokens, which is less than the min share of {min_share}"
+    )]
+    MinShareAssertion { share: Uint128, min_share: Uint128 },
+
+    #[error("Zapping out would return {return_amount}, which is less than the minimum receive of {minimum_receive}")]
+    MinimumReceiveAssertion {
+        return_amount: Uint128,
+        minimum_receive: Uint128,
+    },
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
@@ -59,6 +85,15 @@ pub enum ContractError {
     #[error("Insufficient amount of liquidity")]
     LiquidityAmountTooSmall {},
 
+    #[error("Withdrawing liquidity requires sending exactly one coin of the LP token {0}")]
+    InvalidLpFunds(String),
+
+    #[error("The pool does not have any liquidity yet")]
+    NoLiquidity {},
+
+    #[error("The target price cannot be reached by trading with the pool")]
+    RebalanceTargetUnreachable {},
+
     #[error("The target rate epoch is specified in seconds and has to be less than a week")]
     InvalidTargetRateEpoch {},
 
@@ -84,9 +119,15 @@ pub enum ContractError {
     #[error("It is not possible to provide liquidity with one token for an empty pool")]
     InvalidProvideLPsWithSingleToken {},
 
+    #[error("Imbalanced liquidity provision and withdrawal is disabled for this pool")]
+    ImbalancedNotAllowed {},
+
     #[error("The asset {0} does not belong to the pool")]
     InvalidAsset(String),
 
+    #[error("Precisions can only be refreshed while the pool has no liquidity")]
+    RefreshPrecisionWithLiquidity {},
+
     #[error("Fee bps in must be smaller than or equal to 10,000")]
     InvalidFeeBps {},
 
@@ -96,6 +137,22 @@ pub enum ContractError {
     #[error("Source and target assets are the same")]
     SameAssets {},
 
+    #[error("Cannot swap an asset for itself")]
+    SameAssetSwap {},
+
+    #[error("Swap return amount is below the pool's dust threshold")]
+    OutputBelowDustThreshold {},
+
+    #[error(
+        "The max allowed spread must be between {} and {}",
+        DEFAULT_SLIPPAGE,
+        MAX_ALLOWED_SLIPPAGE
+    )]
+    InvalidMaxAllowedSpread {},
+
+    #[error("Price move must be between 1 and 9999 bps")]
+    InvalidPriceMoveBps {},
+
     #[error(
         "Invalid number of assets. This pool supports at least {min} and at most {max} assets within a pool"
     )]
@@ -4,7 +4,9 @@ use super::error::ContractError;
 
 use crate::asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated};
 
-use cosmwasm_std::{wasm_execute, Addr, Api, CosmosMsg, Decimal, Fraction, StdError, Uint128};
+use cosmwasm_std::{
+    wasm_execute, Addr, Api, Coin, CosmosMsg, Decimal, Fraction, StdError, Uint128,
+};
 use cw20::Cw20ExecuteMsg;
 
 use itertools::Itertools;
@@ -62,6 +64,17 @@ pub fn check_cw20_in_pool(
     Err(ContractError::Unauthorized {})
 }
 
+/// Checks that exactly one coin was sent and that it is the pool's LP token.
+/// Returns the amount of LP tokens sent.
+///
+/// * **liquidity_token** is the denom of the pool's LP token.
+pub fn check_lp_funds(funds: &[Coin], liquidity_token: &str) -> Result<Uint128, ContractError> {
+    match funds {
+        [coin] if coin.denom == liquidity_token => Ok(coin.amount),
+        _ => Err(ContractError::InvalidLpFunds(liquidity_token.to_string())),
+    }
+}
+
 /// If `belief_price` and `max_spread` are both specified, we compute a new spread,
 /// otherwise we just use the swap spread to check `max_spread`.
 ///
@@ -172,6 +172,18 @@ pub fn query_pool_info(
     )
 }
 
+/// Returns the addresses of multiple pools with a single query, in the same order as `asset_infos`.
+pub fn query_pool_addresses(
+    querier: &QuerierWrapper<CoreumQueries>,
+    factory_contract: impl Into<String>,
+    asset_infos: Vec<Vec<AssetInfo>>,
+) -> StdResult<Vec<Addr>> {
+    querier.query_wasm_smart(
+        factory_contract,
+        &FactoryQueryMsg::PoolAddresses { asset_infos },
+    )
+}
+
 /// Returns a vector that contains items of type [`PairInfo`] which
 /// symbolize pools instantiated in the Dex factory
 pub fn query_pools_info(
@@ -182,7 +194,11 @@ pub fn query_pools_info(
 ) -> StdResult<PoolsResponse> {
     querier.query_wasm_smart(
         factory_contract,
-        &FactoryQueryMsg::Pools { start_after, limit },
+        &FactoryQueryMsg::Pools {
+            start_after,
+            limit,
+            include_deprecated: false,
+        },
     )
 }
 
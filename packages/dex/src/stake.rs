@@ -18,6 +18,21 @@ pub struct InstantiateMsg {
     pub admin: Option<String>,
     /// Address of the account that can call [`ExecuteMsg::QuickUnbond`]
     pub unbonder: Option<String>,
+    /// Additional accounts that have the same rights as the `unbonder`,
+    /// so unbonding does not depend on a single account
+    #[serde(default)]
+    pub backup_unbonders: Vec<String>,
+    /// Time in seconds after unbonding during which the staker can still cancel the unbond.
+    /// Zero disables cancelling.
+    #[serde(default)]
+    pub unbond_grace_period: u64,
+    /// Minimum time in seconds between two reward distributions of the same asset.
+    /// Zero disables the limit.
+    #[serde(default)]
+    pub min_distribution_interval: u64,
+    /// When this is set to `true`, delegating also pays out the staker's matured claims
+    #[serde(default)]
+    pub auto_claim_on_delegate: bool,
 }
 
 #[cw_serde]